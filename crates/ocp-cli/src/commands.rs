@@ -0,0 +1,600 @@
+//! Subcommands and the program instructions they send.
+
+use crate::output::Report;
+use anchor_lang::AccountDeserialize;
+use anyhow::{bail, Context, Result};
+use clap::{Args, Subcommand, ValueEnum};
+use ocp_solana::state::{
+    AntiDilutionType, CapTableSnapshot, CapTableSummary, Issuer, StockClass, StockClassKind,
+    StockClassTerms, WarrantExerciseTrigger,
+};
+use ocp_solana_client::instructions::*;
+use ocp_solana_client::{
+    bytes16_to_uuid, pda, string_number_to_u64, u64_to_string_number, uuid_to_bytes16, ClientError,
+    Instruction, InstructionBuilder, Pubkey,
+};
+use solana_rpc_client::rpc_client::RpcClient;
+
+fn uuid(value: &str) -> Result<[u8; 16], ClientError> {
+    uuid_to_bytes16(value)
+}
+
+fn amount(value: &str) -> Result<u64, ClientError> {
+    string_number_to_u64(value)
+}
+
+type Fields = Vec<(&'static str, String)>;
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Issuers and their authorized shares
+    #[command(subcommand)]
+    Issuer(IssuerCommand),
+    /// Stock classes and their terms
+    #[command(subcommand)]
+    StockClass(StockClassCommand),
+    /// Stakeholders
+    #[command(subcommand)]
+    Stakeholder(StakeholderCommand),
+    /// Stock issuances
+    #[command(subcommand)]
+    Stock(StockCommand),
+    /// Stock plans (option pools)
+    #[command(subcommand)]
+    Plan(PlanCommand),
+    /// Equity compensation (stock options)
+    #[command(subcommand, name = "option")]
+    Options(OptionCommand),
+    /// Warrants
+    #[command(subcommand)]
+    Warrant(WarrantCommand),
+    /// Read-only views; no keypair needed
+    #[command(subcommand)]
+    CapTable(CapTableCommand),
+}
+
+#[derive(Subcommand)]
+pub enum IssuerCommand {
+    /// Create the issuer, its cap table summary and its TxLog
+    Init {
+        #[arg(long, value_parser = uuid)]
+        id: [u8; 16],
+        #[arg(long, value_parser = amount)]
+        shares_authorized: u64,
+    },
+    /// Record the cap table summary as it stands in an immutable snapshot
+    Snapshot {
+        #[arg(long, value_parser = uuid)]
+        issuer: [u8; 16],
+        /// Snapshot id, unique per issuer
+        #[arg(long, value_parser = uuid)]
+        id: [u8; 16],
+    },
+}
+
+#[derive(Subcommand)]
+pub enum StockClassCommand {
+    /// Create a stock class; preferred terms default to none
+    Create(CreateStockClassArgs),
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Kind {
+    Common,
+    Preferred,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum AntiDilution {
+    None,
+    FullRatchet,
+    BroadBasedWeightedAverage,
+    NarrowBasedWeightedAverage,
+}
+
+#[derive(Args)]
+pub struct CreateStockClassArgs {
+    #[arg(long, value_parser = uuid)]
+    issuer: [u8; 16],
+    #[arg(long, value_parser = uuid)]
+    id: [u8; 16],
+    /// Short class label such as CS or PA
+    #[arg(long)]
+    class_type: String,
+    #[arg(long, value_parser = amount)]
+    price_per_share: u64,
+    #[arg(long, value_parser = amount)]
+    shares_authorized: u64,
+    #[arg(long, value_enum, default_value_t = Kind::Common)]
+    kind: Kind,
+    #[arg(long, default_value_t = 0)]
+    seniority: u32,
+    /// e.g. 1 for 1x
+    #[arg(long, value_parser = amount, default_value = "0")]
+    liquidation_preference_multiple: u64,
+    #[arg(long)]
+    participating: bool,
+    /// 0 for uncapped
+    #[arg(long, value_parser = amount, default_value = "0")]
+    participation_cap_multiple: u64,
+    /// Common shares received per share converted
+    #[arg(long, value_parser = amount, default_value = "0")]
+    conversion_ratio: u64,
+    #[arg(long, value_parser = uuid)]
+    converts_to_stock_class: Option<[u8; 16]>,
+    #[arg(long, value_parser = amount, default_value = "1")]
+    votes_per_share: u64,
+    #[arg(long, value_enum, default_value_t = AntiDilution::None)]
+    anti_dilution: AntiDilution,
+}
+
+#[derive(Subcommand)]
+pub enum StakeholderCommand {
+    /// Create a stakeholder
+    Create {
+        #[arg(long, value_parser = uuid)]
+        issuer: [u8; 16],
+        #[arg(long, value_parser = uuid)]
+        id: [u8; 16],
+    },
+}
+
+#[derive(Subcommand)]
+pub enum StockCommand {
+    /// Issue stock to a stakeholder
+    Issue {
+        #[arg(long, value_parser = uuid)]
+        issuer: [u8; 16],
+        #[arg(long, value_parser = uuid)]
+        security_id: [u8; 16],
+        #[arg(long, value_parser = uuid)]
+        stock_class: [u8; 16],
+        #[arg(long, value_parser = uuid)]
+        stakeholder: [u8; 16],
+        #[arg(long, value_parser = amount)]
+        quantity: u64,
+        #[arg(long, value_parser = amount)]
+        share_price: u64,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PlanCommand {
+    /// Create a plan reserving shares from one or more classes
+    Create {
+        #[arg(long, value_parser = uuid)]
+        issuer: [u8; 16],
+        #[arg(long, value_parser = uuid)]
+        id: [u8; 16],
+        /// Repeat for each class the plan can grant from
+        #[arg(long = "stock-class", value_parser = uuid, required = true)]
+        stock_classes: Vec<[u8; 16]>,
+        #[arg(long, value_parser = amount)]
+        shares_reserved: u64,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum OptionCommand {
+    /// Grant options, from a plan if one is given
+    Grant {
+        #[arg(long, value_parser = uuid)]
+        issuer: [u8; 16],
+        #[arg(long, value_parser = uuid)]
+        security_id: [u8; 16],
+        #[arg(long, value_parser = uuid)]
+        stock_class: [u8; 16],
+        #[arg(long, value_parser = uuid)]
+        stakeholder: [u8; 16],
+        /// Omit for grants made outside any plan
+        #[arg(long, value_parser = uuid)]
+        plan: Option<[u8; 16]>,
+        #[arg(long, value_parser = amount)]
+        quantity: u64,
+    },
+    /// Issue the resulting stock and exercise into it in one transaction
+    Exercise {
+        #[arg(long, value_parser = uuid)]
+        issuer: [u8; 16],
+        #[arg(long, value_parser = uuid)]
+        security_id: [u8; 16],
+        #[arg(long, value_parser = uuid)]
+        resulting_security_id: [u8; 16],
+        #[arg(long, value_parser = uuid)]
+        stock_class: [u8; 16],
+        #[arg(long, value_parser = uuid)]
+        stakeholder: [u8; 16],
+        #[arg(long, value_parser = amount)]
+        quantity: u64,
+        /// Exercise price paid per share
+        #[arg(long, value_parser = amount)]
+        share_price: u64,
+    },
+}
+
+// Named after the OCF trigger types, which all happen to be elective
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Trigger {
+    ElectiveAtWill,
+    ElectiveInRange,
+    ElectiveOnCondition,
+}
+
+#[derive(Subcommand)]
+pub enum WarrantCommand {
+    /// Issue a warrant over a stock class
+    Issue {
+        #[arg(long, value_parser = uuid)]
+        issuer: [u8; 16],
+        #[arg(long, value_parser = uuid)]
+        security_id: [u8; 16],
+        #[arg(long, value_parser = uuid)]
+        stakeholder: [u8; 16],
+        #[arg(long, value_parser = uuid)]
+        stock_class: [u8; 16],
+        #[arg(long, value_parser = amount)]
+        quantity: u64,
+        #[arg(long, value_parser = amount)]
+        exercise_price: u64,
+        /// Unix timestamp in seconds
+        #[arg(long)]
+        expiration_date: i64,
+        #[arg(long, value_enum, default_value_t = Trigger::ElectiveAtWill)]
+        exercise_trigger: Trigger,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CapTableCommand {
+    /// Issuer totals and per-class shares from the cap table summary
+    Show {
+        #[arg(long, value_parser = uuid)]
+        issuer: [u8; 16],
+        /// Show the totals recorded by `issuer snapshot` instead of the live ones
+        #[arg(long, value_parser = uuid)]
+        snapshot: Option<[u8; 16]>,
+    },
+}
+
+impl Command {
+    /// The instructions to send, plus the addresses worth reporting.
+    pub fn instructions(&self, authority: &Pubkey) -> Result<(Vec<Instruction>, Fields)> {
+        let authority = *authority;
+        let single = |instruction: Instruction, fields: Fields| Ok((vec![instruction], fields));
+
+        match self {
+            Command::Issuer(IssuerCommand::Init {
+                id,
+                shares_authorized,
+            }) => single(
+                InitializeIssuer {
+                    id: *id,
+                    initial_shares_authorized: *shares_authorized,
+                    authority,
+                }
+                .instruction(),
+                vec![
+                    ("issuer", pda::issuer(id).to_string()),
+                    ("cap_table_summary", pda::cap_table_summary(id).to_string()),
+                    ("tx_log", pda::tx_log(id).to_string()),
+                ],
+            ),
+            Command::Issuer(IssuerCommand::Snapshot { issuer, id }) => single(
+                SnapshotCapTable {
+                    issuer_id: *issuer,
+                    id: *id,
+                    authority,
+                }
+                .instruction(),
+                vec![("snapshot", pda::cap_table_snapshot(issuer, id).to_string())],
+            ),
+            Command::StockClass(StockClassCommand::Create(args)) => single(
+                CreateStockClass {
+                    issuer_id: args.issuer,
+                    id: args.id,
+                    class_type: args.class_type.clone(),
+                    price_per_share: args.price_per_share,
+                    initial_shares_authorized: args.shares_authorized,
+                    terms: args.terms(),
+                    authority,
+                }
+                .instruction(),
+                vec![("stock_class", pda::stock_class(&args.id).to_string())],
+            ),
+            Command::Stakeholder(StakeholderCommand::Create { issuer, id }) => single(
+                CreateStakeholder {
+                    issuer_id: *issuer,
+                    id: *id,
+                    authority,
+                }
+                .instruction(),
+                vec![("stakeholder", pda::stakeholder(id).to_string())],
+            ),
+            Command::Stock(StockCommand::Issue {
+                issuer,
+                security_id,
+                stock_class,
+                stakeholder,
+                quantity,
+                share_price,
+            }) => single(
+                IssueStock {
+                    issuer_id: *issuer,
+                    security_id: *security_id,
+                    stock_class_id: *stock_class,
+                    stakeholder_id: *stakeholder,
+                    quantity: *quantity,
+                    share_price: *share_price,
+                    authority,
+                }
+                .instruction(),
+                vec![(
+                    "position",
+                    pda::stock_position(stakeholder, security_id).to_string(),
+                )],
+            ),
+            Command::Plan(PlanCommand::Create {
+                issuer,
+                id,
+                stock_classes,
+                shares_reserved,
+            }) => single(
+                CreateStockPlan {
+                    issuer_id: *issuer,
+                    id: *id,
+                    stock_class_ids: stock_classes.clone(),
+                    shares_reserved: *shares_reserved,
+                    authority,
+                }
+                .instruction(),
+                vec![("stock_plan", pda::stock_plan(id).to_string())],
+            ),
+            Command::Options(OptionCommand::Grant {
+                issuer,
+                security_id,
+                stock_class,
+                stakeholder,
+                plan,
+                quantity,
+            }) => single(
+                IssueEquityCompensation {
+                    issuer_id: *issuer,
+                    security_id: *security_id,
+                    stock_class_id: *stock_class,
+                    stakeholder_id: *stakeholder,
+                    stock_plan_id: *plan,
+                    quantity: *quantity,
+                    authority,
+                }
+                .instruction(),
+                vec![(
+                    "position",
+                    pda::equity_compensation_position(security_id, stock_class, stakeholder)
+                        .to_string(),
+                )],
+            ),
+            Command::Options(OptionCommand::Exercise {
+                issuer,
+                security_id,
+                resulting_security_id,
+                stock_class,
+                stakeholder,
+                quantity,
+                share_price,
+            }) => Ok((
+                vec![
+                    IssueStock {
+                        issuer_id: *issuer,
+                        security_id: *resulting_security_id,
+                        stock_class_id: *stock_class,
+                        stakeholder_id: *stakeholder,
+                        quantity: *quantity,
+                        share_price: *share_price,
+                        authority,
+                    }
+                    .instruction(),
+                    ExerciseEquityCompensation {
+                        issuer_id: *issuer,
+                        equity_comp_security_id: *security_id,
+                        resulting_stock_security_id: *resulting_security_id,
+                        stock_class_id: *stock_class,
+                        stakeholder_id: *stakeholder,
+                        quantity: *quantity,
+                        // Options are issued by the same authority, which paid for them
+                        rent_payer: authority,
+                        authority,
+                    }
+                    .instruction(),
+                ],
+                vec![(
+                    "stock_position",
+                    pda::stock_position(stakeholder, resulting_security_id).to_string(),
+                )],
+            )),
+            Command::Warrant(WarrantCommand::Issue {
+                issuer,
+                security_id,
+                stakeholder,
+                stock_class,
+                quantity,
+                exercise_price,
+                expiration_date,
+                exercise_trigger,
+            }) => single(
+                IssueWarrant {
+                    issuer_id: *issuer,
+                    security_id: *security_id,
+                    stakeholder_id: *stakeholder,
+                    stock_class_id: *stock_class,
+                    quantity: *quantity,
+                    exercise_price: *exercise_price,
+                    expiration_date: *expiration_date,
+                    exercise_trigger: exercise_trigger.into(),
+                    authority,
+                }
+                .instruction(),
+                vec![(
+                    "position",
+                    pda::warrant_position(stakeholder, security_id).to_string(),
+                )],
+            ),
+            Command::CapTable(_) => bail!("cap-table commands do not send transactions"),
+        }
+    }
+}
+
+impl CreateStockClassArgs {
+    fn terms(&self) -> StockClassTerms {
+        StockClassTerms {
+            kind: match self.kind {
+                Kind::Common => StockClassKind::Common,
+                Kind::Preferred => StockClassKind::Preferred,
+            },
+            seniority: self.seniority,
+            liquidation_preference_multiple: self.liquidation_preference_multiple,
+            is_participating: self.participating,
+            participation_cap_multiple: self.participation_cap_multiple,
+            conversion_ratio: self.conversion_ratio,
+            converts_to_stock_class_id: self.converts_to_stock_class.unwrap_or_default(),
+            votes_per_share: self.votes_per_share,
+            anti_dilution_type: match self.anti_dilution {
+                AntiDilution::None => AntiDilutionType::None,
+                AntiDilution::FullRatchet => AntiDilutionType::FullRatchet,
+                AntiDilution::BroadBasedWeightedAverage => {
+                    AntiDilutionType::BroadBasedWeightedAverage
+                }
+                AntiDilution::NarrowBasedWeightedAverage => {
+                    AntiDilutionType::NarrowBasedWeightedAverage
+                }
+            },
+        }
+    }
+}
+
+impl From<&Trigger> for WarrantExerciseTrigger {
+    fn from(trigger: &Trigger) -> Self {
+        match trigger {
+            Trigger::ElectiveAtWill => WarrantExerciseTrigger::ElectiveAtWill,
+            Trigger::ElectiveInRange => WarrantExerciseTrigger::ElectiveInRange,
+            Trigger::ElectiveOnCondition => WarrantExerciseTrigger::ElectiveOnCondition,
+        }
+    }
+}
+
+fn fetch<T: AccountDeserialize>(rpc: &RpcClient, address: &Pubkey, name: &str) -> Result<T> {
+    let data = rpc
+        .get_account_data(address)
+        .with_context(|| format!("failed to fetch {} {}", name, address))?;
+    T::try_deserialize(&mut data.as_slice())
+        .with_context(|| format!("{} {} is not a valid account", name, address))
+}
+
+impl CapTableCommand {
+    pub fn run(&self, rpc: &RpcClient) -> Result<Report> {
+        let CapTableCommand::Show {
+            issuer: issuer_id,
+            snapshot,
+        } = self;
+        let issuer: Issuer = fetch(rpc, &pda::issuer(issuer_id), "issuer")?;
+        let mut snapshot_fields = Vec::new();
+        let summary: CapTableSummary = match snapshot {
+            Some(snapshot_id) => {
+                let snapshot: CapTableSnapshot = fetch(
+                    rpc,
+                    &pda::cap_table_snapshot(issuer_id, snapshot_id),
+                    "cap table snapshot",
+                )?;
+                snapshot_fields = vec![
+                    ("snapshot", bytes16_to_uuid(&snapshot.id)),
+                    ("unix_timestamp", snapshot.unix_timestamp.to_string()),
+                    ("tx_log_sequence", snapshot.tx_log_sequence.to_string()),
+                ];
+                CapTableSummary {
+                    issuer_id: snapshot.issuer_id,
+                    shares_authorized: snapshot.shares_authorized,
+                    stock_classes: snapshot.stock_classes,
+                    options_outstanding: snapshot.options_outstanding,
+                    warrants_outstanding: snapshot.warrants_outstanding,
+                    plan_pool_reserved: snapshot.plan_pool_reserved,
+                    plan_pool_granted: snapshot.plan_pool_granted,
+                    plan_pool_available: snapshot.plan_pool_available,
+                    convertible_principal: snapshot.convertible_principal,
+                }
+            }
+            None => fetch(rpc, &pda::cap_table_summary(issuer_id), "cap table summary")?,
+        };
+
+        let class_addresses: Vec<Pubkey> = summary
+            .stock_classes
+            .iter()
+            .map(|class| pda::stock_class(&class.stock_class_id))
+            .collect();
+        let class_accounts = rpc.get_multiple_accounts(&class_addresses)?;
+
+        let mut rows = Vec::with_capacity(summary.stock_classes.len());
+        for (class, account) in summary.stock_classes.iter().zip(class_accounts) {
+            let class_type = match account {
+                Some(account) => StockClass::try_deserialize(&mut account.data.as_slice())
+                    .map(|stock_class| stock_class.class_type)
+                    .unwrap_or_default(),
+                None => String::new(),
+            };
+            rows.push(vec![
+                bytes16_to_uuid(&class.stock_class_id),
+                class_type,
+                u64_to_string_number(class.shares_authorized),
+                u64_to_string_number(class.shares_outstanding),
+            ]);
+        }
+
+        let mut fields = vec![("issuer", bytes16_to_uuid(&issuer.id))];
+        fields.append(&mut snapshot_fields);
+        fields.extend([
+            (
+                "shares_authorized",
+                u64_to_string_number(summary.shares_authorized),
+            ),
+            (
+                "shares_outstanding",
+                u64_to_string_number(summary.shares_outstanding()),
+            ),
+            (
+                "options_outstanding",
+                u64_to_string_number(summary.options_outstanding),
+            ),
+            (
+                "warrants_outstanding",
+                u64_to_string_number(summary.warrants_outstanding),
+            ),
+            (
+                "plan_pool_reserved",
+                u64_to_string_number(summary.plan_pool_reserved),
+            ),
+            (
+                "plan_pool_available",
+                u64_to_string_number(summary.plan_pool_available),
+            ),
+            (
+                "convertible_principal",
+                u64_to_string_number(summary.convertible_principal),
+            ),
+            (
+                "fully_diluted_shares",
+                u64_to_string_number(summary.fully_diluted_shares()),
+            ),
+        ]);
+
+        Ok(Report::Table {
+            fields,
+            columns: vec![
+                "stock_class",
+                "class_type",
+                "shares_authorized",
+                "shares_outstanding",
+            ],
+            rows,
+            rows_key: "stock_classes",
+        })
+    }
+}
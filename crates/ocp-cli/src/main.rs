@@ -0,0 +1,75 @@
+//! `ocp`: manage ocp-solana cap tables from the command line.
+//!
+//! Ids are UUIDs and amounts decimal strings, as in `sdk/controllers`; every
+//! account is derived from them. Transactions are signed and paid for by the
+//! `--keypair` wallet, which must be the issuer's authority.
+
+mod commands;
+mod output;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use commands::Command;
+use output::{OutputFormat, Report};
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+
+#[derive(Parser)]
+#[command(name = "ocp", version, about = "Manage ocp-solana cap tables")]
+struct Cli {
+    /// RPC endpoint; defaults to a local test validator
+    #[arg(
+        long,
+        global = true,
+        env = "OCP_RPC_URL",
+        default_value = "http://127.0.0.1:8899"
+    )]
+    url: String,
+    /// Wallet keypair file; defaults to ~/.config/solana/id.json
+    #[arg(long, global = true, env = "OCP_KEYPAIR")]
+    keypair: Option<String>,
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+    #[command(subcommand)]
+    command: Command,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let rpc = RpcClient::new_with_commitment(cli.url.clone(), CommitmentConfig::confirmed());
+
+    let report = match &cli.command {
+        Command::CapTable(command) => command.run(&rpc)?,
+        command => {
+            let payer = load_keypair(cli.keypair.as_deref())?;
+            let (instructions, mut report) = command.instructions(&payer.pubkey())?;
+            let blockhash = rpc.get_latest_blockhash()?;
+            let transaction = Transaction::new_signed_with_payer(
+                &instructions,
+                Some(&payer.pubkey()),
+                &[&payer],
+                blockhash,
+            );
+            let signature = rpc
+                .send_and_confirm_transaction(&transaction)
+                .context("transaction failed")?;
+            report.insert(0, ("signature", signature.to_string()));
+            Report::Fields(report)
+        }
+    };
+
+    report.print(cli.output)
+}
+
+fn load_keypair(path: Option<&str>) -> Result<Keypair> {
+    let path = match path {
+        Some(path) => path.to_string(),
+        None => {
+            let home = std::env::var("HOME").context("HOME is not set; pass --keypair")?;
+            format!("{}/.config/solana/id.json", home)
+        }
+    };
+    read_keypair_file(&path).map_err(|error| anyhow::anyhow!("failed to read {}: {}", path, error))
+}
@@ -0,0 +1,121 @@
+//! Printing command results as an aligned table or as JSON.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use serde_json::{Map, Value};
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+pub enum Report {
+    /// Named values, such as a signature and the accounts it created
+    Fields(Vec<(&'static str, String)>),
+    /// Named values followed by rows under column headers
+    Table {
+        fields: Vec<(&'static str, String)>,
+        columns: Vec<&'static str>,
+        rows: Vec<Vec<String>>,
+        /// JSON key holding the rows
+        rows_key: &'static str,
+    },
+}
+
+impl Report {
+    pub fn print(&self, format: OutputFormat) -> Result<()> {
+        match format {
+            OutputFormat::Table => self.print_table(),
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&self.to_json())?),
+        }
+        Ok(())
+    }
+
+    fn fields(&self) -> &[(&'static str, String)] {
+        match self {
+            Report::Fields(fields) | Report::Table { fields, .. } => fields,
+        }
+    }
+
+    fn print_table(&self) {
+        let width = self
+            .fields()
+            .iter()
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or(0);
+        for (name, value) in self.fields() {
+            println!("{:width$}  {}", name, value, width = width);
+        }
+
+        if let Report::Table { columns, rows, .. } = self {
+            let widths: Vec<usize> = columns
+                .iter()
+                .enumerate()
+                .map(|(index, column)| {
+                    rows.iter()
+                        .map(|row| row[index].len())
+                        .chain([column.len()])
+                        .max()
+                        .unwrap_or(0)
+                })
+                .collect();
+            let line = |cells: &[String]| {
+                let cells: Vec<String> = cells
+                    .iter()
+                    .zip(&widths)
+                    .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                    .collect();
+                println!("{}", cells.join("  ").trim_end());
+            };
+
+            println!();
+            line(
+                &columns
+                    .iter()
+                    .map(|column| column.to_string())
+                    .collect::<Vec<_>>(),
+            );
+            line(
+                &widths
+                    .iter()
+                    .map(|width| "-".repeat(*width))
+                    .collect::<Vec<_>>(),
+            );
+            for row in rows {
+                line(row);
+            }
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let mut object: Map<String, Value> = self
+            .fields()
+            .iter()
+            .map(|(name, value)| (name.to_string(), Value::String(value.clone())))
+            .collect();
+        if let Report::Table {
+            columns,
+            rows,
+            rows_key,
+            ..
+        } = self
+        {
+            let rows = rows
+                .iter()
+                .map(|row| {
+                    Value::Object(
+                        columns
+                            .iter()
+                            .zip(row)
+                            .map(|(column, cell)| (column.to_string(), Value::String(cell.clone())))
+                            .collect(),
+                    )
+                })
+                .collect();
+            object.insert(rows_key.to_string(), Value::Array(rows));
+        }
+        Value::Object(object)
+    }
+}
@@ -0,0 +1,76 @@
+//! Conversions from on-chain representations to OCF primitive types.
+
+/// Formats a unix timestamp as an OCF `Date` (`YYYY-MM-DD`, UTC).
+pub fn format_date(unix_timestamp: i64) -> String {
+    // Days-to-civil conversion from Howard Hinnant's date algorithms
+    let days = unix_timestamp.div_euclid(86_400) + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Formats a unix timestamp as an RFC 3339 date-time (UTC).
+pub fn format_date_time(unix_timestamp: i64) -> String {
+    let seconds = unix_timestamp.rem_euclid(86_400);
+    format!(
+        "{}T{:02}:{:02}:{:02}Z",
+        format_date(unix_timestamp),
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ocp_solana_client::u64_to_string_number;
+
+    #[test]
+    fn formats_dates() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(1_709_164_800), "2024-02-29");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_709_251_200), "2024-03-01");
+        // 2100 is not a leap year
+        assert_eq!(format_date(4_107_456_000), "2100-02-28");
+        assert_eq!(format_date(4_107_542_400), "2100-03-01");
+        // Times within a day still format as that day
+        assert_eq!(format_date(1_709_251_199), "2024-02-29");
+    }
+
+    #[test]
+    fn formats_dates_before_the_epoch() {
+        assert_eq!(format_date(-1), "1969-12-31");
+        assert_eq!(format_date(-86_400), "1969-12-31");
+        assert_eq!(format_date(-86_401), "1969-12-30");
+        assert_eq!(format_date(-2_203_891_200), "1900-03-01");
+    }
+
+    #[test]
+    fn formats_date_times() {
+        assert_eq!(format_date_time(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_date_time(1_709_254_861), "2024-03-01T01:01:01Z");
+        assert_eq!(format_date_time(-1), "1969-12-31T23:59:59Z");
+    }
+
+    #[test]
+    fn formats_decimals_without_trailing_zeros() {
+        // Amounts go through the client's conversion; OCF requires the trimmed form
+        assert_eq!(u64_to_string_number(0), "0");
+        assert_eq!(u64_to_string_number(1_500_000), "1.5");
+        assert_eq!(u64_to_string_number(1), "0.000001");
+        assert_eq!(u64_to_string_number(123_000_000), "123");
+    }
+}
@@ -0,0 +1,213 @@
+//! Exports an ocp-solana cap table as Open Cap Format (OCF) JSON.
+//!
+//! The caller supplies the decoded program accounts and the issuer's
+//! `TxCreated` history; [`export`] turns them into an OCF manifest plus the
+//! object and transaction files it references. Ids are written as UUIDs and
+//! 6-decimal fixed-point amounts as decimal strings.
+
+pub mod format;
+pub mod objects;
+pub mod transactions;
+
+use ocp_solana::events::TxCreatedV2;
+use ocp_solana::payload::TxDecodeError;
+use ocp_solana::state::{
+    ConvertibleActivePosition, EquityCompensationActivePosition, Issuer, Stakeholder,
+    StockActivePosition, StockClass, StockPlan, WarrantActivePosition,
+};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use transactions::Position;
+
+pub const OCF_VERSION: &str = "1.2.0";
+
+pub const MANIFEST_FILE: &str = "Manifest.ocf.json";
+pub const STOCK_CLASSES_FILE: &str = "StockClasses.ocf.json";
+pub const STAKEHOLDERS_FILE: &str = "Stakeholders.ocf.json";
+pub const STOCK_PLANS_FILE: &str = "StockPlans.ocf.json";
+pub const TRANSACTIONS_FILE: &str = "Transactions.ocf.json";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error("failed to decode transaction: {0}")]
+    Decode(#[from] TxDecodeError),
+    #[error("failed to serialize OCF file: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("failed to write OCF file: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Issuer details OCF requires that are not stored on-chain.
+pub struct ExportMetadata {
+    pub issuer_legal_name: String,
+    /// OCF date (`YYYY-MM-DD`)
+    pub formation_date: String,
+    /// ISO 3166-1 alpha-2 country code
+    pub country_of_formation: String,
+    /// ISO 4217 currency code used for every monetary amount
+    pub currency: String,
+    /// Unix timestamp the export is taken at
+    pub as_of: i64,
+    /// Display names for stock classes, stakeholders and stock plans, keyed
+    /// by on-chain id. Objects without an entry fall back to a default.
+    pub names: HashMap<[u8; 16], String>,
+}
+
+impl ExportMetadata {
+    pub(crate) fn name_of(&self, id: &[u8; 16], default: &str) -> String {
+        self.names
+            .get(id)
+            .cloned()
+            .unwrap_or_else(|| default.to_string())
+    }
+}
+
+/// Decoded program accounts for one issuer.
+#[derive(Default)]
+pub struct CapTableAccounts {
+    pub issuer: Issuer,
+    pub stock_classes: Vec<StockClass>,
+    pub stakeholders: Vec<Stakeholder>,
+    pub stock_plans: Vec<StockPlan>,
+    pub stock_positions: Vec<StockActivePosition>,
+    pub warrant_positions: Vec<WarrantActivePosition>,
+    pub convertible_positions: Vec<ConvertibleActivePosition>,
+    pub equity_compensation_positions: Vec<EquityCompensationActivePosition>,
+}
+
+impl CapTableAccounts {
+    fn positions(&self) -> impl Iterator<Item = Position<'_>> {
+        self.stock_positions
+            .iter()
+            .map(Position::Stock)
+            .chain(self.warrant_positions.iter().map(Position::Warrant))
+            .chain(self.convertible_positions.iter().map(Position::Convertible))
+            .chain(
+                self.equity_compensation_positions
+                    .iter()
+                    .map(Position::EquityCompensation),
+            )
+    }
+}
+
+/// An OCF export: the manifest and the files it references, by file name.
+pub struct OcfExport {
+    pub manifest: Value,
+    pub files: Vec<(String, Value)>,
+}
+
+impl OcfExport {
+    /// Writes the manifest and every referenced file into `dir`.
+    pub fn write_to_dir(&self, dir: &Path) -> Result<(), ExportError> {
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(
+            dir.join(MANIFEST_FILE),
+            serde_json::to_vec_pretty(&self.manifest)?,
+        )?;
+        for (name, file) in &self.files {
+            std::fs::write(dir.join(name), serde_json::to_vec_pretty(file)?)?;
+        }
+        Ok(())
+    }
+}
+
+/// Builds the OCF export for `accounts`. `history` may contain events of other
+/// issuers; only those of `accounts.issuer` are exported, in `TxLog` order.
+pub fn export(
+    accounts: &CapTableAccounts,
+    history: &[TxCreatedV2],
+    metadata: &ExportMetadata,
+) -> Result<OcfExport, ExportError> {
+    let mut history: Vec<&TxCreatedV2> = history
+        .iter()
+        .filter(|event| event.envelope.issuer_id == accounts.issuer.id)
+        .collect();
+    history.sort_by_key(|event| event.sequence);
+
+    let mut items = Vec::with_capacity(history.len());
+    let mut issued = HashSet::new();
+    for event in history {
+        let (transaction, security_id) = transactions::transaction(event, metadata)?;
+        issued.extend(security_id);
+        items.push(transaction);
+    }
+
+    // Active positions whose issuance has been pruned from the history
+    items.extend(
+        accounts
+            .positions()
+            .filter(|position| !issued.contains(&position.security_id()))
+            .map(|position| transactions::position_issuance(&position, metadata)),
+    );
+
+    let files = vec![
+        ocf_file(
+            STOCK_CLASSES_FILE,
+            "OCF_STOCK_CLASSES_FILE",
+            accounts
+                .stock_classes
+                .iter()
+                .map(|stock_class| objects::stock_class(stock_class, metadata))
+                .collect(),
+        ),
+        ocf_file(
+            STAKEHOLDERS_FILE,
+            "OCF_STAKEHOLDERS_FILE",
+            accounts
+                .stakeholders
+                .iter()
+                .map(|stakeholder| objects::stakeholder(stakeholder, metadata))
+                .collect(),
+        ),
+        ocf_file(
+            STOCK_PLANS_FILE,
+            "OCF_STOCK_PLANS_FILE",
+            accounts
+                .stock_plans
+                .iter()
+                .map(|stock_plan| objects::stock_plan(stock_plan, metadata))
+                .collect(),
+        ),
+        ocf_file(TRANSACTIONS_FILE, "OCF_TRANSACTIONS_FILE", items),
+    ];
+
+    let file_reference = |name: &str| -> Result<Value, ExportError> {
+        let (_, file) = files
+            .iter()
+            .find(|(file_name, _)| file_name == name)
+            .expect("every referenced file is generated");
+        Ok(json!([{
+            "filepath": name,
+            "md5": format!("{:x}", md5::compute(serde_json::to_vec_pretty(file)?)),
+        }]))
+    };
+
+    let manifest = json!({
+        "ocf_version": OCF_VERSION,
+        "file_type": "OCF_MANIFEST_FILE",
+        "issuer": objects::issuer(&accounts.issuer, metadata),
+        "as_of_date": format::format_date(metadata.as_of),
+        "generated_at": format::format_date_time(metadata.as_of),
+        "stock_classes_files": file_reference(STOCK_CLASSES_FILE)?,
+        "stakeholders_files": file_reference(STAKEHOLDERS_FILE)?,
+        "stock_plans_files": file_reference(STOCK_PLANS_FILE)?,
+        "transactions_files": file_reference(TRANSACTIONS_FILE)?,
+        "stock_legend_templates_files": [],
+        "vesting_terms_files": [],
+        "valuations_files": [],
+    });
+
+    Ok(OcfExport { manifest, files })
+}
+
+fn ocf_file(name: &str, file_type: &str, items: Vec<Value>) -> (String, Value) {
+    (
+        name.to_string(),
+        json!({
+            "ocf_version": OCF_VERSION,
+            "file_type": file_type,
+            "items": items,
+        }),
+    )
+}
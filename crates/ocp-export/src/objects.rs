@@ -0,0 +1,105 @@
+//! OCF objects built from decoded program accounts.
+
+use crate::ExportMetadata;
+use ocp_solana::state::{
+    AntiDilutionType, Issuer, Stakeholder, StockClass, StockClassKind, StockPlan,
+};
+use ocp_solana_client::{bytes16_to_uuid, u64_to_string_number};
+use serde_json::{json, Value};
+
+pub(crate) fn monetary(amount: u64, metadata: &ExportMetadata) -> Value {
+    json!({
+        "amount": u64_to_string_number(amount),
+        "currency": metadata.currency,
+    })
+}
+
+pub fn issuer(issuer: &Issuer, metadata: &ExportMetadata) -> Value {
+    json!({
+        "object_type": "ISSUER",
+        "id": bytes16_to_uuid(&issuer.id),
+        "legal_name": metadata.issuer_legal_name,
+        "formation_date": metadata.formation_date,
+        "country_of_formation": metadata.country_of_formation,
+        "initial_shares_authorized": u64_to_string_number(issuer.shares_authorized),
+    })
+}
+
+pub fn stock_class(stock_class: &StockClass, metadata: &ExportMetadata) -> Value {
+    let terms = &stock_class.terms;
+    let mut object = json!({
+        "object_type": "STOCK_CLASS",
+        "id": bytes16_to_uuid(&stock_class.id),
+        "name": metadata.name_of(&stock_class.id, &stock_class.class_type),
+        "class_type": match terms.kind {
+            StockClassKind::Common => "COMMON",
+            StockClassKind::Preferred => "PREFERRED",
+        },
+        "default_id_prefix": &stock_class.class_type,
+        "initial_shares_authorized": u64_to_string_number(stock_class.shares_authorized),
+        "votes_per_share": u64_to_string_number(terms.votes_per_share),
+        "price_per_share": monetary(stock_class.price_per_share, metadata),
+        "seniority": terms.seniority.to_string(),
+    });
+
+    if terms.kind == StockClassKind::Preferred {
+        object["liquidation_preference_multiple"] =
+            json!(u64_to_string_number(terms.liquidation_preference_multiple));
+        if terms.is_participating {
+            object["participation_cap_multiple"] =
+                json!(u64_to_string_number(terms.participation_cap_multiple));
+        }
+        object["conversion_rights"] = json!([{
+            "type": "STOCK_CLASS_CONVERSION_RIGHT",
+            "conversion_mechanism": {
+                "type": "RATIO_CONVERSION",
+                "conversion_price": monetary(stock_class.conversion_price, metadata),
+                "ratio": {
+                    "numerator": u64_to_string_number(terms.conversion_ratio),
+                    "denominator": "1",
+                },
+                "rounding_type": "NORMAL",
+            },
+            "converts_to_stock_class_id": bytes16_to_uuid(&terms.converts_to_stock_class_id),
+        }]);
+        if let Some(provision) = anti_dilution_provision(terms.anti_dilution_type) {
+            object["conversion_rights"][0]["anti_dilution_provision"] = json!(provision);
+        }
+    }
+
+    object
+}
+
+fn anti_dilution_provision(anti_dilution_type: AntiDilutionType) -> Option<&'static str> {
+    match anti_dilution_type {
+        AntiDilutionType::None => None,
+        AntiDilutionType::FullRatchet => Some("FULL_RATCHET"),
+        AntiDilutionType::BroadBasedWeightedAverage => Some("BROAD_BASED_WEIGHTED_AVERAGE"),
+        AntiDilutionType::NarrowBasedWeightedAverage => Some("NARROW_BASED_WEIGHTED_AVERAGE"),
+    }
+}
+
+pub fn stakeholder(stakeholder: &Stakeholder, metadata: &ExportMetadata) -> Value {
+    let id = bytes16_to_uuid(&stakeholder.id);
+    json!({
+        "object_type": "STAKEHOLDER",
+        "id": id,
+        "name": { "legal_name": metadata.name_of(&stakeholder.id, &id) },
+        "stakeholder_type": "INDIVIDUAL",
+    })
+}
+
+pub fn stock_plan(stock_plan: &StockPlan, metadata: &ExportMetadata) -> Value {
+    let id = bytes16_to_uuid(&stock_plan.id);
+    json!({
+        "object_type": "STOCK_PLAN",
+        "id": id,
+        "plan_name": metadata.name_of(&stock_plan.id, &id),
+        "initial_shares_reserved": u64_to_string_number(stock_plan.shares_reserved),
+        "stock_class_ids": stock_plan
+            .stock_class_ids
+            .iter()
+            .map(bytes16_to_uuid)
+            .collect::<Vec<_>>(),
+    })
+}
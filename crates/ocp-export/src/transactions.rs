@@ -0,0 +1,375 @@
+//! OCF transactions built from the `TxCreated` history and, for securities
+//! whose issuance is no longer in that history, from their active positions.
+
+use crate::format::format_date;
+use crate::objects::monetary;
+use crate::{ExportError, ExportMetadata};
+use ocp_solana::events::TxCreatedV2;
+use ocp_solana::payload::{decode_tx_created, DecodedTx};
+use ocp_solana::state::{
+    ConvertibleActivePosition, EquityCompensationActivePosition, StockActivePosition,
+    WarrantActivePosition, WarrantExerciseTrigger,
+};
+use ocp_solana_client::{bytes16_to_uuid, u64_to_string_number};
+use serde_json::{json, Map, Value};
+
+/// Converts one `TxCreated` into its OCF transaction. Returns the transaction
+/// and, for issuances, the security id it created.
+pub fn transaction(
+    event: &TxCreatedV2,
+    metadata: &ExportMetadata,
+) -> Result<(Value, Option<[u8; 16]>), ExportError> {
+    let decoded = decode_tx_created(event)?;
+    let id = format!(
+        "{}-{}",
+        bytes16_to_uuid(&event.envelope.issuer_id),
+        event.sequence
+    );
+
+    let (object_type, issued, fields) = match decoded {
+        DecodedTx::StockIssuance(tx) => (
+            "TX_STOCK_ISSUANCE",
+            Some(tx.security_id),
+            stock_issuance(
+                &tx.security_id,
+                &tx.stakeholder_id,
+                &tx.stock_class_id,
+                tx.quantity,
+                tx.share_price,
+                metadata,
+            ),
+        ),
+        DecodedTx::StockConversion(tx) => (
+            "TX_STOCK_CONVERSION",
+            Some(tx.resulting_security_id),
+            json!({
+                "security_id": bytes16_to_uuid(&tx.security_id),
+                "quantity_converted": u64_to_string_number(tx.quantity_converted),
+                "resulting_security_ids": [bytes16_to_uuid(&tx.resulting_security_id)],
+            }),
+        ),
+        DecodedTx::StockClassSplit(tx) => (
+            "TX_STOCK_CLASS_SPLIT",
+            None,
+            json!({
+                "stock_class_id": bytes16_to_uuid(&tx.stock_class_id),
+                "split_ratio": {
+                    "numerator": tx.numerator.to_string(),
+                    "denominator": tx.denominator.to_string(),
+                },
+            }),
+        ),
+        DecodedTx::StockClassConversionRatioAdjustment(tx) => (
+            "TX_STOCK_CLASS_CONVERSION_RATIO_ADJUSTMENT",
+            None,
+            json!({
+                "stock_class_id": bytes16_to_uuid(&tx.stock_class_id),
+                "new_ratio_conversion_mechanism": {
+                    "conversion_price": monetary(tx.new_conversion_price, metadata),
+                    "ratio": {
+                        "numerator": u64_to_string_number(tx.new_conversion_ratio),
+                        "denominator": "1",
+                    },
+                    "rounding_type": "NORMAL",
+                },
+            }),
+        ),
+        DecodedTx::ConvertibleIssuance(tx) => (
+            "TX_CONVERTIBLE_ISSUANCE",
+            Some(tx.security_id),
+            convertible_issuance(
+                &tx.security_id,
+                &tx.stakeholder_id,
+                tx.investment_amount,
+                metadata,
+            ),
+        ),
+        DecodedTx::ConvertibleConversion(tx) => (
+            "TX_CONVERTIBLE_CONVERSION",
+            Some(tx.resulting_security_id),
+            json!({
+                "security_id": bytes16_to_uuid(&tx.security_id),
+                "trigger_id": trigger_id(&tx.security_id),
+                "resulting_security_ids": [bytes16_to_uuid(&tx.resulting_security_id)],
+                "reason_text": "Converted in a priced financing round",
+            }),
+        ),
+        DecodedTx::EquityCompensationIssuance(tx) => (
+            "TX_EQUITY_COMPENSATION_ISSUANCE",
+            Some(tx.security_id),
+            equity_compensation_issuance(
+                &tx.security_id,
+                &tx.stakeholder_id,
+                &tx.stock_class_id,
+                &tx.stock_plan_id,
+                tx.quantity,
+            ),
+        ),
+        DecodedTx::EquityCompensationExercise(tx) => (
+            "TX_EQUITY_COMPENSATION_EXERCISE",
+            None,
+            json!({
+                "security_id": bytes16_to_uuid(&tx.equity_comp_security_id),
+                "quantity": u64_to_string_number(tx.quantity),
+                "resulting_security_ids": [bytes16_to_uuid(&tx.resulting_stock_security_id)],
+            }),
+        ),
+        DecodedTx::WarrantIssuance(tx) => (
+            "TX_WARRANT_ISSUANCE",
+            Some(tx.security_id),
+            warrant_issuance(
+                &tx.security_id,
+                &tx.stakeholder_id,
+                &tx.stock_class_id,
+                tx.quantity,
+                tx.exercise_price,
+                tx.expiration_date,
+                tx.exercise_trigger,
+                metadata,
+            ),
+        ),
+        DecodedTx::WarrantExercise(tx) => (
+            "TX_WARRANT_EXERCISE",
+            Some(tx.resulting_stock_security_id),
+            json!({
+                "security_id": bytes16_to_uuid(&tx.warrant_security_id),
+                "trigger_id": trigger_id(&tx.warrant_security_id),
+                "quantity": u64_to_string_number(tx.quantity),
+                "resulting_security_ids": [bytes16_to_uuid(&tx.resulting_stock_security_id)],
+            }),
+        ),
+        DecodedTx::WarrantCancellation(tx) => (
+            "TX_WARRANT_CANCELLATION",
+            None,
+            json!({
+                "security_id": bytes16_to_uuid(&tx.security_id),
+                "quantity": u64_to_string_number(tx.quantity),
+                "reason_text": "Cancelled on-chain",
+            }),
+        ),
+        DecodedTx::WarrantExpiration(tx) => (
+            "TX_WARRANT_EXPIRATION",
+            None,
+            json!({
+                "security_id": bytes16_to_uuid(&tx.security_id),
+                "quantity": u64_to_string_number(tx.quantity),
+            }),
+        ),
+        DecodedTx::WarrantTransfer(tx) => (
+            "TX_WARRANT_TRANSFER",
+            Some(tx.resulting_security_id),
+            json!({
+                "security_id": bytes16_to_uuid(&tx.security_id),
+                "quantity": u64_to_string_number(tx.quantity),
+                "resulting_security_ids": [bytes16_to_uuid(&tx.resulting_security_id)],
+            }),
+        ),
+    };
+
+    Ok((
+        with_header(object_type, id, event.envelope.unix_timestamp, fields),
+        issued,
+    ))
+}
+
+fn with_header(object_type: &str, id: String, unix_timestamp: i64, fields: Value) -> Value {
+    let mut object = Map::new();
+    object.insert("object_type".into(), json!(object_type));
+    object.insert("id".into(), json!(id));
+    object.insert("date".into(), json!(format_date(unix_timestamp)));
+    if let Value::Object(fields) = fields {
+        object.extend(fields);
+    }
+    Value::Object(object)
+}
+
+fn stock_issuance(
+    security_id: &[u8; 16],
+    stakeholder_id: &[u8; 16],
+    stock_class_id: &[u8; 16],
+    quantity: u64,
+    share_price: u64,
+    metadata: &ExportMetadata,
+) -> Value {
+    json!({
+        "security_id": bytes16_to_uuid(security_id),
+        "custom_id": bytes16_to_uuid(security_id),
+        "stakeholder_id": bytes16_to_uuid(stakeholder_id),
+        "stock_class_id": bytes16_to_uuid(stock_class_id),
+        "quantity": u64_to_string_number(quantity),
+        "share_price": monetary(share_price, metadata),
+        "security_law_exemptions": [],
+        "stock_legend_ids": [],
+    })
+}
+
+fn convertible_issuance(
+    security_id: &[u8; 16],
+    stakeholder_id: &[u8; 16],
+    investment_amount: u64,
+    metadata: &ExportMetadata,
+) -> Value {
+    json!({
+        "security_id": bytes16_to_uuid(security_id),
+        "custom_id": bytes16_to_uuid(security_id),
+        "stakeholder_id": bytes16_to_uuid(stakeholder_id),
+        "investment_amount": monetary(investment_amount, metadata),
+        "convertible_type": "SAFE",
+        "conversion_triggers": [],
+        "seniority": 1,
+        "security_law_exemptions": [],
+    })
+}
+
+fn equity_compensation_issuance(
+    security_id: &[u8; 16],
+    stakeholder_id: &[u8; 16],
+    stock_class_id: &[u8; 16],
+    stock_plan_id: &[u8; 16],
+    quantity: u64,
+) -> Value {
+    let mut object = json!({
+        "security_id": bytes16_to_uuid(security_id),
+        "custom_id": bytes16_to_uuid(security_id),
+        "stakeholder_id": bytes16_to_uuid(stakeholder_id),
+        "stock_class_id": bytes16_to_uuid(stock_class_id),
+        "compensation_type": "OPTION",
+        "quantity": u64_to_string_number(quantity),
+        "expiration_date": null,
+        "termination_exercise_windows": [],
+        "security_law_exemptions": [],
+    });
+    // An all-zero plan id means the grant was made outside any plan
+    if *stock_plan_id != [0; 16] {
+        object["stock_plan_id"] = json!(bytes16_to_uuid(stock_plan_id));
+    }
+    object
+}
+
+#[allow(clippy::too_many_arguments)]
+fn warrant_issuance(
+    security_id: &[u8; 16],
+    stakeholder_id: &[u8; 16],
+    stock_class_id: &[u8; 16],
+    quantity: u64,
+    exercise_price: u64,
+    expiration_date: i64,
+    exercise_trigger: WarrantExerciseTrigger,
+    metadata: &ExportMetadata,
+) -> Value {
+    json!({
+        "security_id": bytes16_to_uuid(security_id),
+        "custom_id": bytes16_to_uuid(security_id),
+        "stakeholder_id": bytes16_to_uuid(stakeholder_id),
+        "quantity": u64_to_string_number(quantity),
+        "exercise_price": monetary(exercise_price, metadata),
+        "purchase_price": monetary(0, metadata),
+        "warrant_expiration_date": format_date(expiration_date),
+        "exercise_triggers": [{
+            "type": trigger_type(exercise_trigger),
+            "trigger_id": trigger_id(security_id),
+            "conversion_right": {
+                "type": "WARRANT_CONVERSION_RIGHT",
+                "conversion_mechanism": {
+                    "type": "FIXED_AMOUNT_CONVERSION",
+                    "converts_to_quantity": u64_to_string_number(quantity),
+                },
+                "converts_to_stock_class_id": bytes16_to_uuid(stock_class_id),
+            },
+        }],
+        "security_law_exemptions": [],
+    })
+}
+
+fn trigger_type(exercise_trigger: WarrantExerciseTrigger) -> &'static str {
+    match exercise_trigger {
+        WarrantExerciseTrigger::ElectiveAtWill => "ELECTIVE_AT_WILL",
+        WarrantExerciseTrigger::ElectiveInRange => "ELECTIVE_IN_RANGE",
+        WarrantExerciseTrigger::ElectiveOnCondition => "ELECTIVE_ON_CONDITION",
+    }
+}
+
+// Each warrant or convertible has a single trigger, so its id derives from the security
+fn trigger_id(security_id: &[u8; 16]) -> String {
+    format!("{}-trigger", bytes16_to_uuid(security_id))
+}
+
+/// Issuance for an active position whose original `TxCreated` is missing
+/// from the history, dated at the export's as-of date.
+pub fn position_issuance(position: &Position, metadata: &ExportMetadata) -> Value {
+    let (object_type, security_id, fields) = match position {
+        Position::Stock(position) => (
+            "TX_STOCK_ISSUANCE",
+            position.security_id,
+            stock_issuance(
+                &position.security_id,
+                &position.stakeholder_id,
+                &position.stock_class_id,
+                position.quantity,
+                position.share_price,
+                metadata,
+            ),
+        ),
+        Position::Warrant(position) => (
+            "TX_WARRANT_ISSUANCE",
+            position.security_id,
+            warrant_issuance(
+                &position.security_id,
+                &position.stakeholder_id,
+                &position.stock_class_id,
+                position.quantity,
+                position.exercise_price,
+                position.expiration_date,
+                position.exercise_trigger,
+                metadata,
+            ),
+        ),
+        Position::Convertible(position) => (
+            "TX_CONVERTIBLE_ISSUANCE",
+            position.security_id,
+            convertible_issuance(
+                &position.security_id,
+                &position.stakeholder_id,
+                position.investment_amount,
+                metadata,
+            ),
+        ),
+        Position::EquityCompensation(position) => (
+            "TX_EQUITY_COMPENSATION_ISSUANCE",
+            position.security_id,
+            equity_compensation_issuance(
+                &position.security_id,
+                &position.stakeholder_id,
+                &position.stock_class_id,
+                &position.stock_plan_id,
+                position.quantity,
+            ),
+        ),
+    };
+
+    with_header(
+        object_type,
+        format!("{}-position", bytes16_to_uuid(&security_id)),
+        metadata.as_of,
+        fields,
+    )
+}
+
+/// Any active position account of the program.
+pub enum Position<'a> {
+    Stock(&'a StockActivePosition),
+    Warrant(&'a WarrantActivePosition),
+    Convertible(&'a ConvertibleActivePosition),
+    EquityCompensation(&'a EquityCompensationActivePosition),
+}
+
+impl Position<'_> {
+    pub fn security_id(&self) -> [u8; 16] {
+        match self {
+            Position::Stock(position) => position.security_id,
+            Position::Warrant(position) => position.security_id,
+            Position::Convertible(position) => position.security_id,
+            Position::EquityCompensation(position) => position.security_id,
+        }
+    }
+}
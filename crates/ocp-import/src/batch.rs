@@ -0,0 +1,49 @@
+//! Packing instructions into transactions that fit Solana's size limit.
+
+use crate::ImportError;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::message::Message;
+
+/// Maximum serialized size of a transaction (IPv6 MTU minus headers)
+pub const MAX_TRANSACTION_SIZE: usize = 1232;
+
+const SIGNATURE_SIZE: usize = 64;
+
+/// Serialized size of a transaction carrying `instructions`, paid by `payer`.
+pub fn transaction_size(instructions: &[Instruction], payer: &Pubkey) -> usize {
+    let message = Message::new(instructions, Some(payer));
+    let signatures = message.header.num_required_signatures as usize;
+    // Signatures are prefixed by a compact-u16 count, one byte below 128
+    1 + signatures * SIGNATURE_SIZE + message.serialize().len()
+}
+
+/// Greedily groups `instructions`, in order, into transactions no larger than
+/// [`MAX_TRANSACTION_SIZE`] and, if given, `max_instructions` long. Order is
+/// preserved, so each transaction can be sent once the previous one lands.
+pub fn batch(
+    instructions: Vec<Instruction>,
+    payer: &Pubkey,
+    max_instructions: Option<usize>,
+) -> Result<Vec<Vec<Instruction>>, ImportError> {
+    let max_instructions = max_instructions.unwrap_or(usize::MAX).max(1);
+    let mut batches = Vec::new();
+    let mut current: Vec<Instruction> = Vec::new();
+
+    for (index, instruction) in instructions.into_iter().enumerate() {
+        if transaction_size(std::slice::from_ref(&instruction), payer) > MAX_TRANSACTION_SIZE {
+            return Err(ImportError::InstructionTooLarge { index });
+        }
+        current.push(instruction);
+        if current.len() > max_instructions
+            || transaction_size(&current, payer) > MAX_TRANSACTION_SIZE
+        {
+            let overflow = current.pop().expect("just pushed");
+            batches.push(std::mem::replace(&mut current, vec![overflow]));
+        }
+    }
+    if !current.is_empty() {
+        batches.push(current);
+    }
+    Ok(batches)
+}
@@ -0,0 +1,633 @@
+//! Replaying an import plan against an in-memory model of the program.
+//!
+//! The model is the program's own [`CapTable`], which applies the same rules
+//! as the instruction handlers, so a plan that passes here only fails
+//! on-chain for reasons outside the program (fees, rent, compute). On top of
+//! the rules it checks the account space the program allocates. Each step's
+//! instruction is built and decoded back first, so a builder that sends the
+//! wrong arguments or accounts fails here rather than on-chain. It stops at
+//! the first failing step, as the transaction carrying it would.
+
+use crate::plan::{ImportPlan, Step};
+use crate::validate::Operation;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use ocp_solana::engine::CapTable;
+use ocp_solana::instruction as ix;
+use ocp_solana::state::*;
+use ocp_solana_client::pda;
+use std::fmt::Display;
+
+/// Room the `create_stock_class` account leaves for the class type string
+const MAX_CLASS_TYPE_LEN: usize = 36;
+/// Room the `create_stock_plan` account leaves for stock class ids
+const MAX_STOCK_PLAN_CLASSES: usize = 32;
+
+#[derive(Debug, thiserror::Error)]
+#[error("step {step} ({label}) would fail: {message}")]
+pub struct DryRunFailure {
+    /// Index of the failing step in `ImportPlan::steps`
+    pub step: usize,
+    pub label: String,
+    pub message: String,
+}
+
+/// State the import leaves behind.
+pub struct DryRunReport {
+    pub steps: usize,
+    pub summary: CapTableSummary,
+    /// `TxLog` sequence after the last step
+    pub tx_log_sequence: u64,
+}
+
+fn ensure(condition: bool, error: impl Display) -> Result<(), String> {
+    if condition {
+        Ok(())
+    } else {
+        Err(error.to_string())
+    }
+}
+
+/// Decodes the arguments of an ocp-solana instruction of type `T`.
+fn decode<T: AnchorDeserialize + Discriminator>(instruction: &Instruction) -> Result<T, String> {
+    ensure(
+        instruction.program_id == ocp_solana::ID,
+        "instruction is not for ocp-solana",
+    )?;
+    let (discriminator, data) = instruction
+        .data
+        .split_at_checked(8)
+        .ok_or("instruction has no discriminator")?;
+    ensure(
+        discriminator == T::DISCRIMINATOR,
+        "instruction does not match the step",
+    )?;
+    T::try_from_slice(data).map_err(|e| format!("instruction data does not decode: {}", e))
+}
+
+/// Checks that `instruction` decodes back to `step`: its arguments equal the
+/// step's and it carries the accounts the step names, derived independently
+/// of the builder.
+fn check_instruction(
+    plan: &ImportPlan,
+    step: &Step,
+    instruction: &Instruction,
+    authority: &Pubkey,
+) -> Result<(), String> {
+    let has_account = |address: Pubkey| {
+        instruction
+            .accounts
+            .iter()
+            .any(|account| account.pubkey == address)
+    };
+    let accounts = |addresses: &[Pubkey]| {
+        ensure(
+            addresses.iter().all(|address| has_account(*address)),
+            "instruction is missing an account of the step",
+        )
+    };
+    let arguments = |matches: bool| ensure(matches, "instruction arguments do not match the step");
+    let security = |security_id: &[u8; 16]| {
+        plan.securities
+            .get(security_id)
+            .copied()
+            .ok_or("security is not in the plan")
+    };
+
+    ensure(
+        instruction
+            .accounts
+            .iter()
+            .any(|account| account.pubkey == *authority && account.is_signer),
+        "instruction is not signed by the authority",
+    )?;
+    accounts(&[pda::issuer(&plan.issuer_id)])?;
+
+    let operation = match step {
+        Step::InitializeIssuer(spec) => {
+            let args: ix::InitializeIssuer = decode(instruction)?;
+            return arguments(
+                args.id == spec.id && args.initial_shares_authorized == spec.shares_authorized,
+            );
+        }
+        Step::CreateStockClass(spec) => {
+            let args: ix::CreateStockClass = decode(instruction)?;
+            arguments(
+                args.id == spec.id
+                    && args.class_type == spec.class_type
+                    && args.price_per_share == spec.price_per_share
+                    && args.initial_shares_authorized == spec.shares_authorized
+                    && args.terms == spec.terms,
+            )?;
+            return accounts(&[pda::stock_class(&spec.id)]);
+        }
+        Step::CreateStockPlan(spec) => {
+            let args: ix::CreateStockPlan = decode(instruction)?;
+            arguments(
+                args.id == spec.id
+                    && args.stock_class_ids == spec.stock_class_ids
+                    && args.shares_reserved == spec.shares_reserved,
+            )?;
+            return accounts(&[pda::stock_plan(&spec.id)]);
+        }
+        Step::CreateStakeholder(id) => {
+            let args: ix::CreateStakeholder = decode(instruction)?;
+            arguments(args.id == *id)?;
+            return accounts(&[pda::stakeholder(id)]);
+        }
+        Step::Transaction(tx) => &tx.operation,
+    };
+
+    match *operation {
+        Operation::IssueStock {
+            security_id,
+            stakeholder_id,
+            stock_class_id,
+            quantity,
+            share_price,
+        } => {
+            let args: ix::IssueStock = decode(instruction)?;
+            arguments(
+                args.security_id == security_id
+                    && args.quantity == quantity
+                    && args.share_price == share_price,
+            )?;
+            accounts(&[
+                pda::stock_class(&stock_class_id),
+                pda::stakeholder(&stakeholder_id),
+                pda::stock_position(&stakeholder_id, &security_id),
+            ])
+        }
+        Operation::IssueConvertible {
+            security_id,
+            stakeholder_id,
+            investment_amount,
+        } => {
+            let args: ix::IssueConvertible = decode(instruction)?;
+            arguments(
+                args.security_id == security_id && args.investment_amount == investment_amount,
+            )?;
+            accounts(&[
+                pda::stakeholder(&stakeholder_id),
+                pda::convertible_position(&stakeholder_id, &security_id),
+            ])
+        }
+        Operation::IssueEquityCompensation {
+            security_id,
+            stakeholder_id,
+            stock_class_id,
+            stock_plan_id,
+            quantity,
+        } => {
+            let args: ix::IssueEquityCompensation = decode(instruction)?;
+            arguments(args.security_id == security_id && args.quantity == quantity)?;
+            accounts(&[
+                pda::stock_class(&stock_class_id),
+                pda::stakeholder(&stakeholder_id),
+                pda::equity_compensation_position(&security_id, &stock_class_id, &stakeholder_id),
+            ])?;
+            match stock_plan_id {
+                Some(stock_plan_id) => accounts(&[pda::stock_plan(&stock_plan_id)]),
+                None => Ok(()),
+            }
+        }
+        Operation::ExerciseEquityCompensation {
+            security_id,
+            resulting_security_id,
+            quantity,
+        } => {
+            let args: ix::ExerciseEquityCompensation = decode(instruction)?;
+            arguments(args.quantity == quantity)?;
+            let option = security(&security_id)?;
+            let (stakeholder_id, stock_class_id) = (
+                option.stakeholder_id,
+                option.stock_class_id.unwrap_or_default(),
+            );
+            accounts(&[
+                pda::equity_compensation_position(&security_id, &stock_class_id, &stakeholder_id),
+                pda::stock_position(&stakeholder_id, &resulting_security_id),
+            ])
+        }
+        Operation::IssueWarrant {
+            security_id,
+            stakeholder_id,
+            stock_class_id,
+            quantity,
+            exercise_price,
+            expiration_date,
+            exercise_trigger,
+        } => {
+            let args: ix::IssueWarrant = decode(instruction)?;
+            arguments(
+                args.security_id == security_id
+                    && args.quantity == quantity
+                    && args.exercise_price == exercise_price
+                    && args.expiration_date == expiration_date
+                    && args.exercise_trigger == exercise_trigger,
+            )?;
+            accounts(&[
+                pda::stock_class(&stock_class_id),
+                pda::stakeholder(&stakeholder_id),
+                pda::warrant_position(&stakeholder_id, &security_id),
+            ])
+        }
+        Operation::ExerciseWarrant {
+            security_id,
+            resulting_security_id,
+            quantity,
+        } => {
+            let args: ix::ExerciseWarrant = decode(instruction)?;
+            arguments(
+                args.resulting_security_id == resulting_security_id
+                    && args.quantity == quantity
+                    && args.fair_market_value.is_none(),
+            )?;
+            let stakeholder_id = security(&security_id)?.stakeholder_id;
+            accounts(&[
+                pda::warrant_position(&stakeholder_id, &security_id),
+                pda::stock_position(&stakeholder_id, &resulting_security_id),
+            ])
+        }
+        Operation::CancelWarrant {
+            security_id,
+            quantity,
+        } => {
+            let args: ix::CancelWarrant = decode(instruction)?;
+            arguments(args.quantity == quantity)?;
+            accounts(&[pda::warrant_position(
+                &security(&security_id)?.stakeholder_id,
+                &security_id,
+            )])
+        }
+        Operation::ConvertStock {
+            security_id,
+            resulting_security_id,
+            quantity,
+        } => {
+            let args: ix::ConvertStock = decode(instruction)?;
+            arguments(
+                args.resulting_security_id == resulting_security_id && args.quantity == quantity,
+            )?;
+            let stakeholder_id = security(&security_id)?.stakeholder_id;
+            accounts(&[
+                pda::stock_position(&stakeholder_id, &security_id),
+                pda::stock_position(&stakeholder_id, &resulting_security_id),
+            ])
+        }
+        Operation::SplitStockClass {
+            stock_class_id,
+            numerator,
+            denominator,
+        } => {
+            let args: ix::SplitStockClass = decode(instruction)?;
+            arguments(
+                args.numerator == numerator
+                    && args.denominator == denominator
+                    && args.fractional_share_policy == FractionalSharePolicy::Retain,
+            )?;
+            accounts(&[pda::stock_class(&stock_class_id)])
+        }
+        Operation::AdjustIssuerAuthorizedShares {
+            new_shares_authorized,
+        } => {
+            let args: ix::AdjustAuthorizedShares = decode(instruction)?;
+            arguments(args.new_shares_authorized == new_shares_authorized)
+        }
+        Operation::AdjustStockClassShares {
+            stock_class_id,
+            new_shares_authorized,
+        } => {
+            let args: ix::AdjustStockClassShares = decode(instruction)?;
+            arguments(args.new_shares_authorized == new_shares_authorized)?;
+            accounts(&[pda::stock_class(&stock_class_id)])
+        }
+        Operation::AdjustStockPlanShares {
+            stock_plan_id,
+            new_shares_reserved,
+        } => {
+            let args: ix::AdjustStockPlanShares = decode(instruction)?;
+            arguments(args.new_shares_reserved == new_shares_reserved)?;
+            accounts(&[pda::stock_plan(&stock_plan_id)])
+        }
+    }
+}
+
+#[derive(Default)]
+struct Bank {
+    now: i64,
+    /// `None` until the issuer step runs
+    cap_table: Option<CapTable>,
+    tx_log_sequence: u64,
+}
+
+/// Replays `plan` as if its instructions were sent at unix time `now`.
+pub fn dry_run(plan: &ImportPlan, now: i64) -> Result<DryRunReport, DryRunFailure> {
+    let mut bank = Bank {
+        now,
+        ..Default::default()
+    };
+    let authority = Pubkey::new_unique();
+    let instructions = plan.instructions(&authority);
+    for (index, (step, instruction)) in plan.steps.iter().zip(&instructions).enumerate() {
+        check_instruction(plan, step, instruction, &authority)
+            .and_then(|()| bank.execute(step))
+            .map_err(|message| DryRunFailure {
+                step: index,
+                label: step.label(),
+                message,
+            })?;
+    }
+    Ok(DryRunReport {
+        steps: plan.steps.len(),
+        summary: bank
+            .cap_table
+            .map(|cap_table| cap_table.cap_table_summary)
+            .unwrap_or_default(),
+        tx_log_sequence: bank.tx_log_sequence,
+    })
+}
+
+impl Bank {
+    fn execute(&mut self, step: &Step) -> Result<(), String> {
+        if let Step::InitializeIssuer(spec) = step {
+            ensure(self.cap_table.is_none(), "issuer already in use")?;
+            let cap_table =
+                CapTable::new(spec.id, spec.shares_authorized).map_err(|e| e.to_string())?;
+            self.cap_table = Some(cap_table);
+            return Ok(());
+        }
+        let cap_table = self.cap_table.as_mut().ok_or("issuer does not exist")?;
+
+        let operation = match step {
+            Step::InitializeIssuer(_) => unreachable!(),
+            Step::CreateStockClass(spec) => {
+                ensure(
+                    spec.class_type.len() <= MAX_CLASS_TYPE_LEN,
+                    format_args!("class type is longer than {} bytes", MAX_CLASS_TYPE_LEN),
+                )?;
+                return cap_table
+                    .create_stock_class(
+                        spec.id,
+                        spec.class_type.clone(),
+                        spec.price_per_share,
+                        spec.shares_authorized,
+                        spec.terms,
+                    )
+                    .map_err(|e| e.to_string());
+            }
+            Step::CreateStockPlan(spec) => {
+                ensure(
+                    spec.stock_class_ids.len() <= MAX_STOCK_PLAN_CLASSES,
+                    format_args!("more than {} stock classes", MAX_STOCK_PLAN_CLASSES),
+                )?;
+                // The instruction loads the plan's first class
+                if let Some(stock_class_id) = spec.stock_class_ids.first() {
+                    ensure(
+                        cap_table.stock_classes.contains_key(stock_class_id),
+                        "stock class does not exist",
+                    )?;
+                }
+                return cap_table
+                    .create_stock_plan(spec.id, spec.stock_class_ids.clone(), spec.shares_reserved)
+                    .map_err(|e| e.to_string());
+            }
+            Step::CreateStakeholder(id) => {
+                return cap_table.create_stakeholder(*id).map_err(|e| e.to_string());
+            }
+            Step::Transaction(tx) => &tx.operation,
+        };
+
+        let now = self.now;
+        let result = match *operation {
+            Operation::IssueStock {
+                security_id,
+                stakeholder_id,
+                stock_class_id,
+                quantity,
+                share_price,
+            } => cap_table.issue_stock(
+                stock_class_id,
+                stakeholder_id,
+                security_id,
+                quantity,
+                share_price,
+            ),
+            Operation::IssueConvertible {
+                security_id,
+                stakeholder_id,
+                investment_amount,
+            } => cap_table.issue_convertible(stakeholder_id, security_id, investment_amount),
+            Operation::IssueEquityCompensation {
+                security_id,
+                stakeholder_id,
+                stock_class_id,
+                stock_plan_id,
+                quantity,
+            } => cap_table.issue_equity_compensation(
+                stakeholder_id,
+                stock_class_id,
+                stock_plan_id,
+                security_id,
+                quantity,
+            ),
+            Operation::ExerciseEquityCompensation {
+                security_id,
+                resulting_security_id,
+                quantity,
+            } => {
+                cap_table.exercise_equity_compensation(security_id, resulting_security_id, quantity)
+            }
+            Operation::IssueWarrant {
+                security_id,
+                stakeholder_id,
+                stock_class_id,
+                quantity,
+                exercise_price,
+                expiration_date,
+                exercise_trigger,
+            } => cap_table.issue_warrant(
+                stakeholder_id,
+                stock_class_id,
+                security_id,
+                quantity,
+                exercise_price,
+                expiration_date,
+                exercise_trigger,
+                Pubkey::default(),
+                now,
+            ),
+            Operation::ExerciseWarrant {
+                security_id,
+                resulting_security_id,
+                quantity,
+            } => cap_table
+                .exercise_warrant(security_id, resulting_security_id, quantity, None, now)
+                .map(drop),
+            Operation::CancelWarrant {
+                security_id,
+                quantity,
+            } => cap_table.cancel_warrant(security_id, quantity),
+            Operation::ConvertStock {
+                security_id,
+                resulting_security_id,
+                quantity,
+            } => {
+                // The importer passes the class the source class converts to
+                let target_stock_class_id = cap_table
+                    .stock_positions
+                    .get(&security_id)
+                    .and_then(|position| cap_table.stock_classes.get(&position.stock_class_id))
+                    .map(|stock_class| stock_class.terms.converts_to_stock_class_id)
+                    .unwrap_or_default();
+                cap_table
+                    .convert_stock(
+                        security_id,
+                        target_stock_class_id,
+                        resulting_security_id,
+                        quantity,
+                    )
+                    .map(drop)
+            }
+            Operation::SplitStockClass {
+                stock_class_id,
+                numerator,
+                denominator,
+            } => cap_table.split_stock_class(
+                stock_class_id,
+                numerator,
+                denominator,
+                FractionalSharePolicy::Retain,
+            ),
+            Operation::AdjustIssuerAuthorizedShares {
+                new_shares_authorized,
+            } => {
+                cap_table.adjust_authorized_shares(new_shares_authorized);
+                return Ok(());
+            }
+            Operation::AdjustStockClassShares {
+                stock_class_id,
+                new_shares_authorized,
+            } => {
+                return cap_table
+                    .adjust_stock_class_shares(stock_class_id, new_shares_authorized)
+                    .map_err(|e| e.to_string());
+            }
+            Operation::AdjustStockPlanShares {
+                stock_plan_id,
+                new_shares_reserved,
+            } => {
+                return cap_table
+                    .adjust_stock_plan_shares(stock_plan_id, new_shares_reserved)
+                    .map_err(|e| e.to_string());
+            }
+        };
+        result.map_err(|e| e.to_string())?;
+
+        // Every remaining operation appends to the issuer's TxLog
+        self.tx_log_sequence += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate::{CapTableImport, IssuerSpec, StockClassSpec, TransactionSpec};
+
+    const ISSUER: [u8; 16] = [1; 16];
+    const COMMON: [u8; 16] = [2; 16];
+    const HOLDER: [u8; 16] = [3; 16];
+    const WARRANT: [u8; 16] = [4; 16];
+    const RESULTING: [u8; 16] = [5; 16];
+
+    fn transaction(ocf_id: &str, operation: Operation) -> TransactionSpec {
+        TransactionSpec {
+            ocf_id: ocf_id.to_string(),
+            date: 0,
+            operation,
+        }
+    }
+
+    fn plan() -> ImportPlan {
+        ImportPlan::new(CapTableImport {
+            issuer: IssuerSpec {
+                id: ISSUER,
+                shares_authorized: 10_000_000,
+            },
+            stock_classes: vec![StockClassSpec {
+                id: COMMON,
+                class_type: "COMMON".to_string(),
+                price_per_share: 1_000_000,
+                shares_authorized: 1_000_000,
+                terms: StockClassTerms::default(),
+            }],
+            stock_plans: Vec::new(),
+            stakeholders: vec![HOLDER],
+            transactions: vec![
+                transaction(
+                    "issue",
+                    Operation::IssueWarrant {
+                        security_id: WARRANT,
+                        stakeholder_id: HOLDER,
+                        stock_class_id: COMMON,
+                        quantity: 300,
+                        exercise_price: 1_000_000,
+                        expiration_date: 2_000_000_000,
+                        exercise_trigger: WarrantExerciseTrigger::ElectiveAtWill,
+                    },
+                ),
+                transaction(
+                    "exercise",
+                    Operation::ExerciseWarrant {
+                        security_id: WARRANT,
+                        resulting_security_id: RESULTING,
+                        quantity: 100,
+                    },
+                ),
+            ],
+        })
+        .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    #[test]
+    fn replays_the_built_instructions() {
+        let report = dry_run(&plan(), 1_700_000_000).unwrap_or_else(|error| panic!("{}", error));
+        assert_eq!(report.steps, 5);
+        assert_eq!(report.tx_log_sequence, 2);
+        assert_eq!(report.summary.shares_outstanding(), 100);
+        assert_eq!(report.summary.warrants_outstanding, 200);
+    }
+
+    #[test]
+    fn rejects_an_instruction_that_does_not_decode_to_its_step() {
+        let plan = plan();
+        let authority = Pubkey::new_unique();
+        let exercise = plan.steps.len() - 1;
+        let mut instruction = plan.instructions(&authority).remove(exercise);
+        check_instruction(&plan, &plan.steps[exercise], &instruction, &authority)
+            .unwrap_or_else(|error| panic!("{}", error));
+
+        // quantity follows the 8-byte discriminator and the resulting security id
+        instruction.data[24] += 1;
+        assert_eq!(
+            check_instruction(&plan, &plan.steps[exercise], &instruction, &authority),
+            Err("instruction arguments do not match the step".to_string())
+        );
+
+        instruction
+            .accounts
+            .retain(|account| account.pubkey != pda::stock_position(&HOLDER, &RESULTING));
+        instruction.data[24] -= 1;
+        assert_eq!(
+            check_instruction(&plan, &plan.steps[exercise], &instruction, &authority),
+            Err("instruction is missing an account of the step".to_string())
+        );
+
+        let issue = &plan.steps[exercise - 1];
+        assert_eq!(
+            check_instruction(&plan, issue, &instruction, &authority),
+            Err("instruction does not match the step".to_string())
+        );
+    }
+}
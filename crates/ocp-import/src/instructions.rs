@@ -0,0 +1,248 @@
+//! ocp-solana instructions for each step of an import plan.
+
+use crate::plan::{ImportPlan, Security, Step};
+use crate::validate::Operation;
+use ocp_solana::state::FractionalSharePolicy;
+use ocp_solana_client::instructions::*;
+use ocp_solana_client::{Instruction, InstructionBuilder, Pubkey};
+
+impl ImportPlan {
+    /// One instruction per step, in plan order, signed and paid for by `authority`.
+    pub fn instructions(&self, authority: &Pubkey) -> Vec<Instruction> {
+        self.steps
+            .iter()
+            .map(|step| self.instruction(step, *authority))
+            .collect()
+    }
+
+    fn security(&self, security_id: &[u8; 16]) -> Security {
+        // Planning resolves every security a transaction uses
+        self.securities[security_id]
+    }
+
+    fn instruction(&self, step: &Step, authority: Pubkey) -> Instruction {
+        let issuer_id = self.issuer_id;
+
+        let operation = match step {
+            Step::InitializeIssuer(spec) => {
+                return InitializeIssuer {
+                    id: spec.id,
+                    initial_shares_authorized: spec.shares_authorized,
+                    authority,
+                }
+                .instruction()
+            }
+            Step::CreateStockClass(spec) => {
+                return CreateStockClass {
+                    issuer_id,
+                    id: spec.id,
+                    class_type: spec.class_type.clone(),
+                    price_per_share: spec.price_per_share,
+                    initial_shares_authorized: spec.shares_authorized,
+                    terms: spec.terms,
+                    authority,
+                }
+                .instruction()
+            }
+            Step::CreateStockPlan(spec) => {
+                return CreateStockPlan {
+                    issuer_id,
+                    id: spec.id,
+                    stock_class_ids: spec.stock_class_ids.clone(),
+                    shares_reserved: spec.shares_reserved,
+                    authority,
+                }
+                .instruction()
+            }
+            Step::CreateStakeholder(id) => {
+                return CreateStakeholder {
+                    issuer_id,
+                    id: *id,
+                    authority,
+                }
+                .instruction()
+            }
+            Step::Transaction(tx) => &tx.operation,
+        };
+
+        match *operation {
+            Operation::IssueStock {
+                security_id,
+                stakeholder_id,
+                stock_class_id,
+                quantity,
+                share_price,
+            } => IssueStock {
+                issuer_id,
+                security_id,
+                stock_class_id,
+                stakeholder_id,
+                quantity,
+                share_price,
+                authority,
+            }
+            .instruction(),
+            Operation::IssueConvertible {
+                security_id,
+                stakeholder_id,
+                investment_amount,
+            } => IssueConvertible {
+                issuer_id,
+                security_id,
+                stakeholder_id,
+                investment_amount,
+                authority,
+            }
+            .instruction(),
+            Operation::IssueEquityCompensation {
+                security_id,
+                stakeholder_id,
+                stock_class_id,
+                stock_plan_id,
+                quantity,
+            } => IssueEquityCompensation {
+                issuer_id,
+                security_id,
+                stock_class_id,
+                stakeholder_id,
+                stock_plan_id,
+                quantity,
+                authority,
+            }
+            .instruction(),
+            Operation::ExerciseEquityCompensation {
+                security_id,
+                resulting_security_id,
+                quantity,
+            } => {
+                let option = self.security(&security_id);
+                ExerciseEquityCompensation {
+                    issuer_id,
+                    equity_comp_security_id: security_id,
+                    resulting_stock_security_id: resulting_security_id,
+                    stock_class_id: option.stock_class_id.unwrap_or_default(),
+                    stakeholder_id: option.stakeholder_id,
+                    quantity,
+                    // The import authority paid for every option position
+                    rent_payer: authority,
+                    authority,
+                }
+                .instruction()
+            }
+            Operation::IssueWarrant {
+                security_id,
+                stakeholder_id,
+                stock_class_id,
+                quantity,
+                exercise_price,
+                expiration_date,
+                exercise_trigger,
+            } => IssueWarrant {
+                issuer_id,
+                security_id,
+                stakeholder_id,
+                stock_class_id,
+                quantity,
+                exercise_price,
+                expiration_date,
+                exercise_trigger,
+                authority,
+            }
+            .instruction(),
+            Operation::ExerciseWarrant {
+                security_id,
+                resulting_security_id,
+                quantity,
+            } => {
+                let warrant = self.security(&security_id);
+                ExerciseWarrant {
+                    issuer_id,
+                    warrant_security_id: security_id,
+                    resulting_stock_security_id: resulting_security_id,
+                    stock_class_id: warrant.stock_class_id.unwrap_or_default(),
+                    stakeholder_id: warrant.stakeholder_id,
+                    quantity,
+                    // OCF records cash exercises; net exercises arrive as a
+                    // cancellation plus an exercise of the remainder
+                    fair_market_value: None,
+                    // The import authority paid for every warrant position
+                    rent_payer: authority,
+                    authority,
+                }
+                .instruction()
+            }
+            Operation::CancelWarrant {
+                security_id,
+                quantity,
+            } => CancelWarrant {
+                issuer_id,
+                security_id,
+                stakeholder_id: self.security(&security_id).stakeholder_id,
+                quantity,
+                rent_payer: authority,
+                authority,
+            }
+            .instruction(),
+            Operation::ConvertStock {
+                security_id,
+                resulting_security_id,
+                quantity,
+            } => {
+                let source = self.security(&security_id);
+                let target = self.security(&resulting_security_id);
+                ConvertStock {
+                    issuer_id,
+                    security_id,
+                    resulting_security_id,
+                    source_stock_class_id: source.stock_class_id.unwrap_or_default(),
+                    target_stock_class_id: target.stock_class_id.unwrap_or_default(),
+                    stakeholder_id: source.stakeholder_id,
+                    quantity,
+                    authority,
+                }
+                .instruction()
+            }
+            Operation::SplitStockClass {
+                stock_class_id,
+                numerator,
+                denominator,
+            } => SplitStockClass {
+                issuer_id,
+                id: stock_class_id,
+                numerator,
+                denominator,
+                fractional_share_policy: FractionalSharePolicy::Retain,
+                authority,
+            }
+            .instruction(),
+            Operation::AdjustIssuerAuthorizedShares {
+                new_shares_authorized,
+            } => AdjustAuthorizedShares {
+                issuer_id,
+                new_shares_authorized,
+                authority,
+            }
+            .instruction(),
+            Operation::AdjustStockClassShares {
+                stock_class_id,
+                new_shares_authorized,
+            } => AdjustStockClassShares {
+                issuer_id,
+                id: stock_class_id,
+                new_shares_authorized,
+                authority,
+            }
+            .instruction(),
+            Operation::AdjustStockPlanShares {
+                stock_plan_id,
+                new_shares_reserved,
+            } => AdjustStockPlanShares {
+                issuer_id,
+                id: stock_plan_id,
+                new_shares_reserved,
+                authority,
+            }
+            .instruction(),
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//! Replays an Open Cap Format (OCF) package onto ocp-solana.
+//!
+//! [`OcfPackage::read`] parses the package, [`validate`] checks it and
+//! converts it into program operations, [`ImportPlan::new`] orders them
+//! (issuer, stock classes, plans, stakeholders, then transactions in
+//! dependency order) and [`ImportPlan::instructions`] builds the program
+//! instructions, which [`batch`] packs into transactions. [`dry_run`] replays
+//! a plan against an in-memory model of the program first.
+
+pub mod batch;
+pub mod dry_run;
+pub mod instructions;
+pub mod ocf;
+pub mod parse;
+pub mod plan;
+pub mod validate;
+
+pub use batch::batch;
+pub use dry_run::dry_run;
+pub use ocf::OcfPackage;
+pub use plan::{ImportPlan, Step};
+pub use validate::{validate, ValidationError};
+
+use std::path::Path;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ImportError {
+    #[error("failed to read {path}: {source}")]
+    Read {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("failed to parse {path}: {source}")]
+    Parse {
+        path: String,
+        source: serde_json::Error,
+    },
+    #[error("transaction {id} has unsupported object type `{object_type}`")]
+    UnsupportedTransaction { id: String, object_type: String },
+    #[error("package failed validation:\n{}", format_errors(.0))]
+    Validation(Vec<ValidationError>),
+    #[error("transaction {id} uses security {security_id}, which no transaction issues")]
+    MissingSecurity { id: String, security_id: String },
+    #[error("transactions {0:?} depend on each other")]
+    DependencyCycle(Vec<String>),
+    #[error("instruction {index} alone exceeds the transaction size limit")]
+    InstructionTooLarge { index: usize },
+}
+
+fn format_errors(errors: &[ValidationError]) -> String {
+    errors
+        .iter()
+        .map(|error| format!("  {}", error))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Reads, validates and plans the package whose manifest is at `manifest_path`.
+pub fn load(manifest_path: &Path) -> Result<ImportPlan, ImportError> {
+    let package = OcfPackage::read(manifest_path)?;
+    let import = validate(&package).map_err(ImportError::Validation)?;
+    ImportPlan::new(import)
+}
@@ -0,0 +1,270 @@
+//! Reading an OCF package: the manifest and the object and transaction
+//! files it references. Only the fields the importer needs are deserialized.
+
+use crate::ImportError;
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::Path;
+
+#[derive(Deserialize)]
+struct Manifest {
+    issuer: OcfIssuer,
+    #[serde(default)]
+    stock_classes_files: Vec<FileReference>,
+    #[serde(default)]
+    stock_plans_files: Vec<FileReference>,
+    #[serde(default)]
+    stakeholders_files: Vec<FileReference>,
+    #[serde(default)]
+    transactions_files: Vec<FileReference>,
+}
+
+#[derive(Deserialize)]
+struct FileReference {
+    filepath: String,
+}
+
+#[derive(Deserialize)]
+struct OcfFile {
+    items: Vec<Value>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Monetary {
+    pub amount: String,
+    pub currency: String,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Ratio {
+    pub numerator: String,
+    pub denominator: String,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct OcfIssuer {
+    pub id: String,
+    pub initial_shares_authorized: Option<String>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct OcfStockClass {
+    pub id: String,
+    pub class_type: String,
+    pub default_id_prefix: Option<String>,
+    pub initial_shares_authorized: String,
+    pub votes_per_share: String,
+    pub price_per_share: Option<Monetary>,
+    /// Integer in the OCF schema, but commonly written as a string
+    pub seniority: Option<Value>,
+    pub liquidation_preference_multiple: Option<String>,
+    pub participation_cap_multiple: Option<String>,
+    #[serde(default)]
+    pub conversion_rights: Vec<OcfConversionRight>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct OcfConversionRight {
+    pub converts_to_stock_class_id: Option<String>,
+    pub conversion_mechanism: Option<OcfConversionMechanism>,
+    pub anti_dilution_provision: Option<String>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct OcfConversionMechanism {
+    pub ratio: Option<Ratio>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct OcfStockPlan {
+    pub id: String,
+    pub initial_shares_reserved: String,
+    #[serde(default)]
+    pub stock_class_ids: Vec<String>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct OcfStakeholder {
+    pub id: String,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct OcfTransaction {
+    pub id: String,
+    pub date: String,
+    #[serde(flatten)]
+    pub kind: OcfTransactionKind,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(tag = "object_type")]
+pub enum OcfTransactionKind {
+    #[serde(rename = "TX_STOCK_ISSUANCE")]
+    StockIssuance {
+        security_id: String,
+        stakeholder_id: String,
+        stock_class_id: String,
+        quantity: String,
+        share_price: Monetary,
+    },
+    #[serde(rename = "TX_CONVERTIBLE_ISSUANCE")]
+    ConvertibleIssuance {
+        security_id: String,
+        stakeholder_id: String,
+        investment_amount: Monetary,
+    },
+    #[serde(rename = "TX_EQUITY_COMPENSATION_ISSUANCE")]
+    EquityCompensationIssuance {
+        security_id: String,
+        stakeholder_id: String,
+        stock_class_id: Option<String>,
+        stock_plan_id: Option<String>,
+        quantity: String,
+    },
+    #[serde(rename = "TX_EQUITY_COMPENSATION_EXERCISE")]
+    EquityCompensationExercise {
+        security_id: String,
+        quantity: String,
+        resulting_security_ids: Vec<String>,
+    },
+    #[serde(rename = "TX_WARRANT_ISSUANCE")]
+    WarrantIssuance {
+        security_id: String,
+        stakeholder_id: String,
+        quantity: Option<String>,
+        exercise_price: Option<Monetary>,
+        warrant_expiration_date: Option<String>,
+        #[serde(default)]
+        exercise_triggers: Vec<Value>,
+    },
+    #[serde(rename = "TX_WARRANT_EXERCISE")]
+    WarrantExercise {
+        security_id: String,
+        quantity: Option<String>,
+        resulting_security_ids: Vec<String>,
+    },
+    #[serde(rename = "TX_WARRANT_CANCELLATION")]
+    WarrantCancellation {
+        security_id: String,
+        quantity: String,
+    },
+    #[serde(rename = "TX_STOCK_CONVERSION")]
+    StockConversion {
+        security_id: String,
+        quantity_converted: String,
+        resulting_security_ids: Vec<String>,
+    },
+    #[serde(rename = "TX_STOCK_CLASS_SPLIT")]
+    StockClassSplit {
+        stock_class_id: String,
+        split_ratio: Ratio,
+    },
+    #[serde(rename = "TX_ISSUER_AUTHORIZED_SHARES_ADJUSTMENT")]
+    IssuerAuthorizedSharesAdjustment { new_shares_authorized: String },
+    #[serde(rename = "TX_STOCK_CLASS_AUTHORIZED_SHARES_ADJUSTMENT")]
+    StockClassAuthorizedSharesAdjustment {
+        stock_class_id: String,
+        new_shares_authorized: String,
+    },
+    #[serde(rename = "TX_STOCK_PLAN_POOL_ADJUSTMENT")]
+    StockPlanPoolAdjustment {
+        stock_plan_id: String,
+        shares_reserved: String,
+    },
+}
+
+/// Transaction object types the importer can replay.
+pub const SUPPORTED_TRANSACTIONS: &[&str] = &[
+    "TX_STOCK_ISSUANCE",
+    "TX_CONVERTIBLE_ISSUANCE",
+    "TX_EQUITY_COMPENSATION_ISSUANCE",
+    "TX_EQUITY_COMPENSATION_EXERCISE",
+    "TX_WARRANT_ISSUANCE",
+    "TX_WARRANT_EXERCISE",
+    "TX_WARRANT_CANCELLATION",
+    "TX_STOCK_CONVERSION",
+    "TX_STOCK_CLASS_SPLIT",
+    "TX_ISSUER_AUTHORIZED_SHARES_ADJUSTMENT",
+    "TX_STOCK_CLASS_AUTHORIZED_SHARES_ADJUSTMENT",
+    "TX_STOCK_PLAN_POOL_ADJUSTMENT",
+];
+
+/// The parsed contents of an OCF package.
+pub struct OcfPackage {
+    pub issuer: OcfIssuer,
+    pub stock_classes: Vec<OcfStockClass>,
+    pub stock_plans: Vec<OcfStockPlan>,
+    pub stakeholders: Vec<OcfStakeholder>,
+    pub transactions: Vec<OcfTransaction>,
+}
+
+impl OcfPackage {
+    /// Reads the package whose manifest is at `manifest_path`. Referenced
+    /// files are resolved relative to the manifest's directory.
+    pub fn read(manifest_path: &Path) -> Result<Self, ImportError> {
+        let dir = manifest_path.parent().unwrap_or_else(|| Path::new("."));
+        let manifest: Manifest = read_json(manifest_path)?;
+
+        Ok(OcfPackage {
+            issuer: manifest.issuer,
+            stock_classes: read_items(dir, &manifest.stock_classes_files)?,
+            stock_plans: read_items(dir, &manifest.stock_plans_files)?,
+            stakeholders: read_items(dir, &manifest.stakeholders_files)?,
+            transactions: read_transactions(dir, &manifest.transactions_files)?,
+        })
+    }
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T, ImportError> {
+    let bytes = std::fs::read(path).map_err(|source| ImportError::Read {
+        path: path.display().to_string(),
+        source,
+    })?;
+    serde_json::from_slice(&bytes).map_err(|source| ImportError::Parse {
+        path: path.display().to_string(),
+        source,
+    })
+}
+
+fn read_items<T: serde::de::DeserializeOwned>(
+    dir: &Path,
+    files: &[FileReference],
+) -> Result<Vec<T>, ImportError> {
+    let mut items = Vec::new();
+    for file in files {
+        let path = dir.join(&file.filepath);
+        let contents: OcfFile = read_json(&path)?;
+        for item in contents.items {
+            items.push(
+                serde_json::from_value(item).map_err(|source| ImportError::Parse {
+                    path: path.display().to_string(),
+                    source,
+                })?,
+            );
+        }
+    }
+    Ok(items)
+}
+
+fn read_transactions(
+    dir: &Path,
+    files: &[FileReference],
+) -> Result<Vec<OcfTransaction>, ImportError> {
+    let mut transactions = Vec::new();
+    for item in read_items::<Value>(dir, files)? {
+        let object_type = item["object_type"].as_str().unwrap_or_default();
+        if !SUPPORTED_TRANSACTIONS.contains(&object_type) {
+            return Err(ImportError::UnsupportedTransaction {
+                id: item["id"].as_str().unwrap_or_default().to_string(),
+                object_type: object_type.to_string(),
+            });
+        }
+        transactions.push(
+            serde_json::from_value(item).map_err(|source| ImportError::Parse {
+                path: dir.display().to_string(),
+                source,
+            })?,
+        );
+    }
+    Ok(transactions)
+}
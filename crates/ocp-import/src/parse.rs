@@ -0,0 +1,67 @@
+//! Conversions from OCF primitive types to on-chain representations.
+
+/// Parses an OCF `Date` (`YYYY-MM-DD`) into the unix timestamp of its start (UTC).
+pub fn parse_date(value: &str) -> Result<i64, String> {
+    let invalid = || format!("`{}` is not a YYYY-MM-DD date", value);
+    let mut parts = value.splitn(3, '-');
+    let mut next = || -> Result<i64, String> {
+        parts
+            .next()
+            .ok_or_else(invalid)?
+            .parse()
+            .map_err(|_| invalid())
+    };
+    let (year, month, day) = (next()?, next()?, next()?);
+    let leap_year = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        2 if leap_year => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return Err(invalid()),
+    };
+    if !(1..=days_in_month).contains(&day) {
+        return Err(invalid());
+    }
+
+    // Civil-to-days conversion from Howard Hinnant's date algorithms
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+    Ok(days * 86_400)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_dates() {
+        assert_eq!(parse_date("1970-01-01"), Ok(0));
+        assert_eq!(parse_date("2024-02-29"), Ok(1_709_164_800));
+        assert_eq!(parse_date("2000-02-29"), Ok(951_782_400));
+        assert_eq!(parse_date("1969-12-31"), Ok(-86_400));
+    }
+
+    #[test]
+    fn rejects_dates_missing_from_the_calendar() {
+        for date in [
+            "2024-02-30",
+            "2024-02-31",
+            "2023-02-29",
+            "1900-02-29",
+            "2024-04-31",
+            "2024-13-01",
+            "2024-00-10",
+            "2024-01-00",
+            "2024-01",
+            "2024-01-01T00:00:00Z",
+        ] {
+            assert!(parse_date(date).is_err(), "{} was accepted", date);
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! Ordering a validated package into the steps to replay.
+
+use crate::validate::{
+    CapTableImport, IssuerSpec, Operation, StockClassSpec, StockPlanSpec, TransactionSpec,
+};
+use crate::ImportError;
+use ocp_solana::state::{PositionType, StockClassKind};
+use ocp_solana_client::bytes16_to_uuid;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+/// One program instruction of an import.
+#[derive(Clone)]
+pub enum Step {
+    InitializeIssuer(IssuerSpec),
+    CreateStockClass(StockClassSpec),
+    CreateStockPlan(StockPlanSpec),
+    CreateStakeholder([u8; 16]),
+    Transaction(TransactionSpec),
+}
+
+impl Step {
+    /// Human-readable name of the step for reports.
+    pub fn label(&self) -> String {
+        let uuid = bytes16_to_uuid;
+        match self {
+            Step::InitializeIssuer(issuer) => format!("issuer {}", uuid(&issuer.id)),
+            Step::CreateStockClass(class) => format!("stock class {}", uuid(&class.id)),
+            Step::CreateStockPlan(plan) => format!("stock plan {}", uuid(&plan.id)),
+            Step::CreateStakeholder(id) => format!("stakeholder {}", uuid(id)),
+            Step::Transaction(tx) => format!("transaction {}", tx.ocf_id),
+        }
+    }
+}
+
+/// Holder and class of a security, which its position accounts derive from.
+#[derive(Clone, Copy)]
+pub struct Security {
+    pub stakeholder_id: [u8; 16],
+    /// `None` for convertibles, which are not tied to a class
+    pub stock_class_id: Option<[u8; 16]>,
+    pub position_type: PositionType,
+}
+
+pub struct ImportPlan {
+    pub issuer_id: [u8; 16],
+    pub steps: Vec<Step>,
+    /// Every security the import creates
+    pub securities: HashMap<[u8; 16], Security>,
+}
+
+impl ImportPlan {
+    /// Orders `import` as issuer, stock classes, stock plans, stakeholders and
+    /// then transactions. A transaction runs after every transaction creating a
+    /// security it uses; otherwise transactions keep their date order.
+    pub fn new(import: CapTableImport) -> Result<Self, ImportError> {
+        let CapTableImport {
+            issuer,
+            mut stock_classes,
+            stock_plans,
+            stakeholders,
+            transactions,
+        } = import;
+
+        // Common classes first, so preferred classes convert into existing ones
+        stock_classes.sort_by_key(|class| class.terms.kind == StockClassKind::Preferred);
+        let converts_to: HashMap<_, _> = stock_classes
+            .iter()
+            .map(|class| (class.id, class.terms.converts_to_stock_class_id))
+            .collect();
+
+        let transactions = order(transactions)?;
+        let mut securities = HashMap::new();
+        for tx in &transactions {
+            if let Some((security_id, security)) =
+                created_security(&tx.operation, &securities, &converts_to)
+            {
+                securities.insert(security_id, security);
+            }
+        }
+
+        let mut steps = vec![Step::InitializeIssuer(issuer.clone())];
+        steps.extend(stock_classes.into_iter().map(Step::CreateStockClass));
+        steps.extend(stock_plans.into_iter().map(Step::CreateStockPlan));
+        steps.extend(stakeholders.into_iter().map(Step::CreateStakeholder));
+        steps.extend(transactions.into_iter().map(Step::Transaction));
+
+        Ok(ImportPlan {
+            issuer_id: issuer.id,
+            steps,
+            securities,
+        })
+    }
+}
+
+/// Topological sort of `transactions` over the securities they create and
+/// use, taking the earliest (date, position in package) among those ready.
+fn order(transactions: Vec<TransactionSpec>) -> Result<Vec<TransactionSpec>, ImportError> {
+    let creators: HashMap<[u8; 16], usize> = transactions
+        .iter()
+        .enumerate()
+        .filter_map(|(index, tx)| Some((tx.operation.creates()?, index)))
+        .collect();
+
+    let mut pending = vec![0usize; transactions.len()];
+    let mut dependents = vec![Vec::new(); transactions.len()];
+    for (index, tx) in transactions.iter().enumerate() {
+        for security_id in tx.operation.requires() {
+            let Some(&creator) = creators.get(&security_id) else {
+                return Err(ImportError::MissingSecurity {
+                    id: tx.ocf_id.clone(),
+                    security_id: bytes16_to_uuid(&security_id),
+                });
+            };
+            dependents[creator].push(index);
+            pending[index] += 1;
+        }
+    }
+
+    let mut ready: BinaryHeap<_> = transactions
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| pending[*index] == 0)
+        .map(|(index, tx)| Reverse((tx.date, index)))
+        .collect();
+    let mut ordered = Vec::with_capacity(transactions.len());
+    while let Some(Reverse((_, index))) = ready.pop() {
+        ordered.push(index);
+        for &dependent in &dependents[index] {
+            pending[dependent] -= 1;
+            if pending[dependent] == 0 {
+                ready.push(Reverse((transactions[dependent].date, dependent)));
+            }
+        }
+    }
+
+    if ordered.len() < transactions.len() {
+        return Err(ImportError::DependencyCycle(
+            transactions
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| pending[*index] > 0)
+                .map(|(_, tx)| tx.ocf_id.clone())
+                .collect(),
+        ));
+    }
+
+    let mut transactions: Vec<_> = transactions.into_iter().map(Some).collect();
+    Ok(ordered
+        .into_iter()
+        .filter_map(|index| transactions[index].take())
+        .collect())
+}
+
+fn created_security(
+    operation: &Operation,
+    securities: &HashMap<[u8; 16], Security>,
+    converts_to: &HashMap<[u8; 16], [u8; 16]>,
+) -> Option<([u8; 16], Security)> {
+    let security =
+        |stakeholder_id: &[u8; 16], stock_class_id: Option<&[u8; 16]>, position_type| Security {
+            stakeholder_id: *stakeholder_id,
+            stock_class_id: stock_class_id.copied(),
+            position_type,
+        };
+    match operation {
+        Operation::IssueStock {
+            security_id,
+            stakeholder_id,
+            stock_class_id,
+            ..
+        } => Some((
+            *security_id,
+            security(stakeholder_id, Some(stock_class_id), PositionType::Stock),
+        )),
+        Operation::IssueConvertible {
+            security_id,
+            stakeholder_id,
+            ..
+        } => Some((
+            *security_id,
+            security(stakeholder_id, None, PositionType::Convertible),
+        )),
+        Operation::IssueEquityCompensation {
+            security_id,
+            stakeholder_id,
+            stock_class_id,
+            ..
+        } => Some((
+            *security_id,
+            security(
+                stakeholder_id,
+                Some(stock_class_id),
+                PositionType::EquityCompensation,
+            ),
+        )),
+        Operation::IssueWarrant {
+            security_id,
+            stakeholder_id,
+            stock_class_id,
+            ..
+        } => Some((
+            *security_id,
+            security(stakeholder_id, Some(stock_class_id), PositionType::Warrant),
+        )),
+        Operation::ExerciseWarrant {
+            security_id,
+            resulting_security_id,
+            ..
+        } => {
+            let warrant = securities.get(security_id)?;
+            Some((
+                *resulting_security_id,
+                Security {
+                    position_type: PositionType::Stock,
+                    ..*warrant
+                },
+            ))
+        }
+        Operation::ConvertStock {
+            security_id,
+            resulting_security_id,
+            ..
+        } => {
+            let source = securities.get(security_id)?;
+            let target = converts_to.get(&source.stock_class_id?)?;
+            Some((
+                *resulting_security_id,
+                security(&source.stakeholder_id, Some(target), PositionType::Stock),
+            ))
+        }
+        _ => None,
+    }
+}
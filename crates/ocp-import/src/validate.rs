@@ -0,0 +1,957 @@
+//! Validation of an OCF package and conversion into on-chain operations.
+//!
+//! Every problem found is collected, so a package can be fixed in one pass
+//! instead of one error at a time.
+
+use crate::ocf::*;
+use crate::parse::parse_date;
+use ocp_solana::state::{
+    AntiDilutionType, StockClassKind, StockClassTerms, WarrantExerciseTrigger,
+    SHARE_DECIMALS_FACTOR,
+};
+use ocp_solana_client::{bytes16_to_uuid, decimal_to_u64, uuid_to_bytes16};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// OCF id of the offending object
+    pub object_id: String,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.object_id, self.message)
+    }
+}
+
+#[derive(Clone)]
+pub struct IssuerSpec {
+    pub id: [u8; 16],
+    pub shares_authorized: u64,
+}
+
+#[derive(Clone)]
+pub struct StockClassSpec {
+    pub id: [u8; 16],
+    pub class_type: String,
+    pub price_per_share: u64,
+    pub shares_authorized: u64,
+    pub terms: StockClassTerms,
+}
+
+#[derive(Clone)]
+pub struct StockPlanSpec {
+    pub id: [u8; 16],
+    pub stock_class_ids: Vec<[u8; 16]>,
+    pub shares_reserved: u64,
+}
+
+/// A single program instruction's worth of cap table change.
+#[derive(Clone, PartialEq)]
+pub enum Operation {
+    IssueStock {
+        security_id: [u8; 16],
+        stakeholder_id: [u8; 16],
+        stock_class_id: [u8; 16],
+        quantity: u64,
+        share_price: u64,
+    },
+    IssueConvertible {
+        security_id: [u8; 16],
+        stakeholder_id: [u8; 16],
+        investment_amount: u64,
+    },
+    IssueEquityCompensation {
+        security_id: [u8; 16],
+        stakeholder_id: [u8; 16],
+        stock_class_id: [u8; 16],
+        stock_plan_id: Option<[u8; 16]>,
+        quantity: u64,
+    },
+    /// The resulting stock is issued by its own `IssueStock` beforehand
+    ExerciseEquityCompensation {
+        security_id: [u8; 16],
+        resulting_security_id: [u8; 16],
+        quantity: u64,
+    },
+    IssueWarrant {
+        security_id: [u8; 16],
+        stakeholder_id: [u8; 16],
+        stock_class_id: [u8; 16],
+        quantity: u64,
+        exercise_price: u64,
+        expiration_date: i64,
+        exercise_trigger: WarrantExerciseTrigger,
+    },
+    /// Creates the resulting stock position itself
+    ExerciseWarrant {
+        security_id: [u8; 16],
+        resulting_security_id: [u8; 16],
+        quantity: u64,
+    },
+    CancelWarrant {
+        security_id: [u8; 16],
+        quantity: u64,
+    },
+    /// Creates the resulting stock position itself
+    ConvertStock {
+        security_id: [u8; 16],
+        resulting_security_id: [u8; 16],
+        quantity: u64,
+    },
+    SplitStockClass {
+        stock_class_id: [u8; 16],
+        numerator: u64,
+        denominator: u64,
+    },
+    AdjustIssuerAuthorizedShares {
+        new_shares_authorized: u64,
+    },
+    AdjustStockClassShares {
+        stock_class_id: [u8; 16],
+        new_shares_authorized: u64,
+    },
+    AdjustStockPlanShares {
+        stock_plan_id: [u8; 16],
+        new_shares_reserved: u64,
+    },
+}
+
+impl Operation {
+    /// Securities that must exist before this operation runs.
+    pub fn requires(&self) -> Vec<[u8; 16]> {
+        match self {
+            Operation::ExerciseEquityCompensation {
+                security_id,
+                resulting_security_id,
+                ..
+            } => vec![*security_id, *resulting_security_id],
+            Operation::ExerciseWarrant { security_id, .. }
+            | Operation::CancelWarrant { security_id, .. }
+            | Operation::ConvertStock { security_id, .. } => vec![*security_id],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Security this operation creates, if any.
+    pub fn creates(&self) -> Option<[u8; 16]> {
+        match self {
+            Operation::IssueStock { security_id, .. }
+            | Operation::IssueConvertible { security_id, .. }
+            | Operation::IssueEquityCompensation { security_id, .. }
+            | Operation::IssueWarrant { security_id, .. } => Some(*security_id),
+            Operation::ExerciseWarrant {
+                resulting_security_id,
+                ..
+            }
+            | Operation::ConvertStock {
+                resulting_security_id,
+                ..
+            } => Some(*resulting_security_id),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct TransactionSpec {
+    /// OCF id of the source transaction
+    pub ocf_id: String,
+    pub date: i64,
+    pub operation: Operation,
+}
+
+/// A validated OCF package, expressed in program terms.
+pub struct CapTableImport {
+    pub issuer: IssuerSpec,
+    pub stock_classes: Vec<StockClassSpec>,
+    pub stock_plans: Vec<StockPlanSpec>,
+    pub stakeholders: Vec<[u8; 16]>,
+    pub transactions: Vec<TransactionSpec>,
+}
+
+struct Validator {
+    errors: Vec<ValidationError>,
+}
+
+impl Validator {
+    fn check<T, E: ToString>(&mut self, object_id: &str, result: Result<T, E>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(message) => {
+                self.fail(object_id, message.to_string());
+                None
+            }
+        }
+    }
+
+    fn fail(&mut self, object_id: &str, message: impl Into<String>) {
+        self.errors.push(ValidationError {
+            object_id: object_id.to_string(),
+            message: message.into(),
+        });
+    }
+
+    fn reference(
+        &mut self,
+        object_id: &str,
+        known: &HashSet<[u8; 16]>,
+        kind: &str,
+        id: &str,
+    ) -> Option<[u8; 16]> {
+        let parsed = self.check(object_id, uuid_to_bytes16(id))?;
+        if !known.contains(&parsed) {
+            self.fail(object_id, format!("references unknown {} `{}`", kind, id));
+            return None;
+        }
+        Some(parsed)
+    }
+
+    fn unique(&mut self, object_id: &str, seen: &mut HashSet<[u8; 16]>) -> Option<[u8; 16]> {
+        let id = self.check(object_id, uuid_to_bytes16(object_id))?;
+        if !seen.insert(id) {
+            self.fail(object_id, "duplicate id");
+            return None;
+        }
+        Some(id)
+    }
+}
+
+/// Validates `package` and converts it into the operations to replay.
+pub fn validate(package: &OcfPackage) -> Result<CapTableImport, Vec<ValidationError>> {
+    let mut v = Validator { errors: Vec::new() };
+
+    let issuer_id = v.check(&package.issuer.id, uuid_to_bytes16(&package.issuer.id));
+    let issuer_shares = match package.issuer.initial_shares_authorized.as_deref() {
+        Some(shares) => v.check(&package.issuer.id, decimal_to_u64(shares)),
+        None => Some(0),
+    };
+
+    let mut stock_class_ids = HashSet::new();
+    let mut stock_classes = Vec::new();
+    for class in &package.stock_classes {
+        let Some(id) = v.unique(&class.id, &mut stock_class_ids) else {
+            continue;
+        };
+        if let Some(spec) = stock_class_spec(&mut v, id, class) {
+            stock_classes.push(spec);
+        }
+    }
+    for class in &stock_classes {
+        let target = class.terms.converts_to_stock_class_id;
+        if class.terms.kind == StockClassKind::Preferred && !stock_class_ids.contains(&target) {
+            v.fail(
+                &bytes16_to_uuid(&class.id),
+                "converts to a stock class that is not in the package",
+            );
+        }
+    }
+
+    let mut stock_plan_ids = HashSet::new();
+    let mut stock_plans = Vec::new();
+    for plan in &package.stock_plans {
+        let Some(id) = v.unique(&plan.id, &mut stock_plan_ids) else {
+            continue;
+        };
+        if plan.stock_class_ids.is_empty() {
+            v.fail(&plan.id, "a stock plan needs at least one stock class");
+        }
+        let class_ids: Vec<_> = plan
+            .stock_class_ids
+            .iter()
+            .filter_map(|class_id| v.reference(&plan.id, &stock_class_ids, "stock class", class_id))
+            .collect();
+        let shares_reserved = v.check(&plan.id, decimal_to_u64(&plan.initial_shares_reserved));
+        if let Some(shares_reserved) = shares_reserved {
+            stock_plans.push(StockPlanSpec {
+                id,
+                stock_class_ids: class_ids,
+                shares_reserved,
+            });
+        }
+    }
+
+    let mut stakeholder_ids = HashSet::new();
+    let stakeholders: Vec<_> = package
+        .stakeholders
+        .iter()
+        .filter_map(|stakeholder| v.unique(&stakeholder.id, &mut stakeholder_ids))
+        .collect();
+
+    let known = Known {
+        stock_classes: &stock_class_ids,
+        stock_plans: &stock_plan_ids,
+        stakeholders: &stakeholder_ids,
+    };
+    let transactions = transaction_specs(&mut v, package, &known);
+
+    match (issuer_id, issuer_shares) {
+        (Some(id), Some(shares_authorized)) if v.errors.is_empty() => Ok(CapTableImport {
+            issuer: IssuerSpec {
+                id,
+                shares_authorized,
+            },
+            stock_classes,
+            stock_plans,
+            stakeholders,
+            transactions,
+        }),
+        _ => Err(v.errors),
+    }
+}
+
+fn stock_class_spec(
+    v: &mut Validator,
+    id: [u8; 16],
+    class: &OcfStockClass,
+) -> Option<StockClassSpec> {
+    let kind = match class.class_type.as_str() {
+        "COMMON" => StockClassKind::Common,
+        "PREFERRED" => StockClassKind::Preferred,
+        other => {
+            v.fail(&class.id, format!("unknown class_type `{}`", other));
+            return None;
+        }
+    };
+    let shares_authorized = v.check(&class.id, decimal_to_u64(&class.initial_shares_authorized))?;
+    let votes_per_share = v.check(&class.id, decimal_to_u64(&class.votes_per_share))?;
+    let price_per_share = match &class.price_per_share {
+        Some(price) => v.check(&class.id, decimal_to_u64(&price.amount))?,
+        None => 0,
+    };
+    let seniority = match &class.seniority {
+        None => 0,
+        Some(value) => {
+            let parsed = value
+                .as_u64()
+                .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+                .and_then(|s| u32::try_from(s).ok());
+            v.check(
+                &class.id,
+                parsed.ok_or_else(|| "invalid seniority".to_string()),
+            )?
+        }
+    };
+    let optional_decimal = |v: &mut Validator, value: &Option<String>| match value {
+        Some(value) => v.check(&class.id, decimal_to_u64(value)),
+        None => Some(0),
+    };
+    let liquidation_preference_multiple =
+        optional_decimal(v, &class.liquidation_preference_multiple)?;
+    let participation_cap_multiple = optional_decimal(v, &class.participation_cap_multiple)?;
+
+    let mut terms = StockClassTerms {
+        kind,
+        seniority,
+        liquidation_preference_multiple,
+        is_participating: participation_cap_multiple > 0,
+        participation_cap_multiple,
+        votes_per_share,
+        ..Default::default()
+    };
+
+    if kind == StockClassKind::Preferred {
+        let Some(right) = class.conversion_rights.first() else {
+            v.fail(&class.id, "preferred stock class has no conversion right");
+            return None;
+        };
+        let target = right
+            .converts_to_stock_class_id
+            .as_deref()
+            .ok_or_else(|| "conversion right has no converts_to_stock_class_id".to_string());
+        terms.converts_to_stock_class_id = v.check(
+            &class.id,
+            target.and_then(|id| uuid_to_bytes16(id).map_err(|e| e.to_string())),
+        )?;
+        terms.conversion_ratio = match right
+            .conversion_mechanism
+            .as_ref()
+            .and_then(|mechanism| mechanism.ratio.as_ref())
+        {
+            Some(ratio) => v.check(&class.id, ratio_to_fixed_point(ratio))?,
+            // OCF defaults to converting one-for-one
+            None => SHARE_DECIMALS_FACTOR,
+        };
+        terms.anti_dilution_type = match right.anti_dilution_provision.as_deref() {
+            None | Some("NONE") => AntiDilutionType::None,
+            Some("FULL_RATCHET") => AntiDilutionType::FullRatchet,
+            Some("BROAD_BASED_WEIGHTED_AVERAGE") => AntiDilutionType::BroadBasedWeightedAverage,
+            Some("NARROW_BASED_WEIGHTED_AVERAGE") => AntiDilutionType::NarrowBasedWeightedAverage,
+            Some(other) => {
+                v.fail(
+                    &class.id,
+                    format!("unsupported anti-dilution provision `{}`", other),
+                );
+                return None;
+            }
+        };
+    }
+
+    Some(StockClassSpec {
+        id,
+        class_type: class
+            .default_id_prefix
+            .clone()
+            .unwrap_or_else(|| class.class_type.clone()),
+        price_per_share,
+        shares_authorized,
+        terms,
+    })
+}
+
+fn ratio_to_fixed_point(ratio: &Ratio) -> Result<u64, String> {
+    let numerator = decimal_to_u64(&ratio.numerator).map_err(|e| e.to_string())? as u128;
+    let denominator = decimal_to_u64(&ratio.denominator).map_err(|e| e.to_string())? as u128;
+    if denominator == 0 {
+        return Err("conversion ratio has a zero denominator".to_string());
+    }
+    u64::try_from(numerator * SHARE_DECIMALS_FACTOR as u128 / denominator)
+        .map_err(|_| "conversion ratio is too large".to_string())
+}
+
+struct Known<'a> {
+    stock_classes: &'a HashSet<[u8; 16]>,
+    stock_plans: &'a HashSet<[u8; 16]>,
+    stakeholders: &'a HashSet<[u8; 16]>,
+}
+
+fn transaction_specs(
+    v: &mut Validator,
+    package: &OcfPackage,
+    known: &Known,
+) -> Vec<TransactionSpec> {
+    // Stock issuances keyed by security id. Warrant exercises and stock
+    // conversions create their resulting stock on-chain, so the matching
+    // OCF issuance is folded into them rather than replayed separately.
+    let stock_issuances: HashMap<&str, &OcfTransaction> = package
+        .transactions
+        .iter()
+        .filter_map(|tx| match &tx.kind {
+            OcfTransactionKind::StockIssuance { security_id, .. } => {
+                Some((security_id.as_str(), tx))
+            }
+            _ => None,
+        })
+        .collect();
+    let mut folded = HashSet::new();
+    for tx in &package.transactions {
+        if let OcfTransactionKind::WarrantExercise {
+            resulting_security_ids,
+            ..
+        }
+        | OcfTransactionKind::StockConversion {
+            resulting_security_ids,
+            ..
+        } = &tx.kind
+        {
+            folded.extend(resulting_security_ids.iter().map(String::as_str));
+        }
+    }
+
+    let mut security_ids = HashSet::new();
+    let mut specs = Vec::new();
+    for tx in &package.transactions {
+        let Some(date) = v.check(&tx.id, parse_date(&tx.date)) else {
+            continue;
+        };
+        let operation = match &tx.kind {
+            OcfTransactionKind::StockIssuance { security_id, .. }
+                if folded.contains(security_id.as_str()) =>
+            {
+                continue;
+            }
+            kind => operation(v, tx, kind, known, &stock_issuances),
+        };
+        let Some(operation) = operation else {
+            continue;
+        };
+        if let Some(created) = operation.creates() {
+            if !security_ids.insert(created) {
+                v.fail(&tx.id, "security id is issued more than once");
+                continue;
+            }
+        }
+        specs.push(TransactionSpec {
+            ocf_id: tx.id.clone(),
+            date,
+            operation,
+        });
+    }
+    specs
+}
+
+fn single_resulting_security(
+    v: &mut Validator,
+    tx: &OcfTransaction,
+    ids: &[String],
+) -> Option<[u8; 16]> {
+    match ids {
+        [id] => v.check(&tx.id, uuid_to_bytes16(id)),
+        _ => {
+            v.fail(&tx.id, "exactly one resulting security is supported");
+            None
+        }
+    }
+}
+
+fn positive(v: &mut Validator, tx: &OcfTransaction, value: &str) -> Option<u64> {
+    let amount = v.check(&tx.id, decimal_to_u64(value))?;
+    if amount == 0 {
+        v.fail(&tx.id, "quantity must be positive");
+        return None;
+    }
+    Some(amount)
+}
+
+fn operation(
+    v: &mut Validator,
+    tx: &OcfTransaction,
+    kind: &OcfTransactionKind,
+    known: &Known,
+    stock_issuances: &HashMap<&str, &OcfTransaction>,
+) -> Option<Operation> {
+    let op = match kind {
+        OcfTransactionKind::StockIssuance {
+            security_id,
+            stakeholder_id,
+            stock_class_id,
+            quantity,
+            share_price,
+        } => Operation::IssueStock {
+            security_id: v.check(&tx.id, uuid_to_bytes16(security_id))?,
+            stakeholder_id: v.reference(
+                &tx.id,
+                known.stakeholders,
+                "stakeholder",
+                stakeholder_id,
+            )?,
+            stock_class_id: v.reference(
+                &tx.id,
+                known.stock_classes,
+                "stock class",
+                stock_class_id,
+            )?,
+            quantity: positive(v, tx, quantity)?,
+            share_price: positive(v, tx, &share_price.amount)?,
+        },
+        OcfTransactionKind::ConvertibleIssuance {
+            security_id,
+            stakeholder_id,
+            investment_amount,
+        } => Operation::IssueConvertible {
+            security_id: v.check(&tx.id, uuid_to_bytes16(security_id))?,
+            stakeholder_id: v.reference(
+                &tx.id,
+                known.stakeholders,
+                "stakeholder",
+                stakeholder_id,
+            )?,
+            investment_amount: positive(v, tx, &investment_amount.amount)?,
+        },
+        OcfTransactionKind::EquityCompensationIssuance {
+            security_id,
+            stakeholder_id,
+            stock_class_id,
+            stock_plan_id,
+            quantity,
+        } => {
+            let Some(stock_class_id) = stock_class_id else {
+                v.fail(&tx.id, "equity compensation needs a stock_class_id");
+                return None;
+            };
+            let stock_plan_id = match stock_plan_id {
+                Some(plan) => Some(v.reference(&tx.id, known.stock_plans, "stock plan", plan)?),
+                None => None,
+            };
+            Operation::IssueEquityCompensation {
+                security_id: v.check(&tx.id, uuid_to_bytes16(security_id))?,
+                stakeholder_id: v.reference(
+                    &tx.id,
+                    known.stakeholders,
+                    "stakeholder",
+                    stakeholder_id,
+                )?,
+                stock_class_id: v.reference(
+                    &tx.id,
+                    known.stock_classes,
+                    "stock class",
+                    stock_class_id,
+                )?,
+                stock_plan_id,
+                quantity: positive(v, tx, quantity)?,
+            }
+        }
+        OcfTransactionKind::EquityCompensationExercise {
+            security_id,
+            quantity,
+            resulting_security_ids,
+        } => Operation::ExerciseEquityCompensation {
+            security_id: v.check(&tx.id, uuid_to_bytes16(security_id))?,
+            resulting_security_id: single_resulting_security(v, tx, resulting_security_ids)?,
+            quantity: positive(v, tx, quantity)?,
+        },
+        OcfTransactionKind::WarrantIssuance {
+            security_id,
+            stakeholder_id,
+            quantity,
+            exercise_price,
+            warrant_expiration_date,
+            exercise_triggers,
+        } => {
+            let trigger = exercise_triggers.first();
+            let stock_class_id = trigger.and_then(|trigger| {
+                trigger["conversion_right"]["converts_to_stock_class_id"].as_str()
+            });
+            let Some(stock_class_id) = stock_class_id else {
+                v.fail(
+                    &tx.id,
+                    "warrant exercise trigger has no converts_to_stock_class_id",
+                );
+                return None;
+            };
+            let quantity = quantity.as_deref().or_else(|| {
+                trigger.and_then(|trigger| {
+                    trigger["conversion_right"]["conversion_mechanism"]["converts_to_quantity"]
+                        .as_str()
+                })
+            });
+            let Some(quantity) = quantity else {
+                v.fail(&tx.id, "warrant has no quantity");
+                return None;
+            };
+            let Some(exercise_price) = exercise_price else {
+                v.fail(&tx.id, "warrant has no exercise_price");
+                return None;
+            };
+            let Some(expiration_date) = warrant_expiration_date else {
+                v.fail(&tx.id, "warrant has no warrant_expiration_date");
+                return None;
+            };
+            let exercise_trigger = match trigger.and_then(|trigger| trigger["type"].as_str()) {
+                Some("ELECTIVE_IN_RANGE") => WarrantExerciseTrigger::ElectiveInRange,
+                Some("ELECTIVE_ON_CONDITION") => WarrantExerciseTrigger::ElectiveOnCondition,
+                // Nothing on chain exercises a warrant automatically
+                Some(trigger @ ("AUTOMATIC_ON_DATE" | "AUTOMATIC_ON_CONDITION")) => {
+                    v.fail(
+                        &tx.id,
+                        format!("unsupported warrant exercise trigger {trigger}"),
+                    );
+                    return None;
+                }
+                _ => WarrantExerciseTrigger::ElectiveAtWill,
+            };
+            Operation::IssueWarrant {
+                security_id: v.check(&tx.id, uuid_to_bytes16(security_id))?,
+                stakeholder_id: v.reference(
+                    &tx.id,
+                    known.stakeholders,
+                    "stakeholder",
+                    stakeholder_id,
+                )?,
+                stock_class_id: v.reference(
+                    &tx.id,
+                    known.stock_classes,
+                    "stock class",
+                    stock_class_id,
+                )?,
+                quantity: positive(v, tx, quantity)?,
+                exercise_price: positive(v, tx, &exercise_price.amount)?,
+                expiration_date: v.check(&tx.id, parse_date(expiration_date))?,
+                exercise_trigger,
+            }
+        }
+        OcfTransactionKind::WarrantExercise {
+            security_id,
+            quantity,
+            resulting_security_ids,
+        } => {
+            let resulting_security_id = single_resulting_security(v, tx, resulting_security_ids)?;
+            // Without an explicit quantity, one warrant is exercised per share received
+            let quantity = match quantity {
+                Some(quantity) => quantity.as_str(),
+                None => match resulting_security_ids
+                    .first()
+                    .and_then(|id| stock_issuances.get(id.as_str()))
+                    .map(|issuance| &issuance.kind)
+                {
+                    Some(OcfTransactionKind::StockIssuance { quantity, .. }) => quantity.as_str(),
+                    _ => {
+                        v.fail(
+                            &tx.id,
+                            "warrant exercise has no quantity and no resulting stock issuance",
+                        );
+                        return None;
+                    }
+                },
+            };
+            Operation::ExerciseWarrant {
+                security_id: v.check(&tx.id, uuid_to_bytes16(security_id))?,
+                resulting_security_id,
+                quantity: positive(v, tx, quantity)?,
+            }
+        }
+        OcfTransactionKind::WarrantCancellation {
+            security_id,
+            quantity,
+        } => Operation::CancelWarrant {
+            security_id: v.check(&tx.id, uuid_to_bytes16(security_id))?,
+            quantity: positive(v, tx, quantity)?,
+        },
+        OcfTransactionKind::StockConversion {
+            security_id,
+            quantity_converted,
+            resulting_security_ids,
+        } => Operation::ConvertStock {
+            security_id: v.check(&tx.id, uuid_to_bytes16(security_id))?,
+            resulting_security_id: single_resulting_security(v, tx, resulting_security_ids)?,
+            quantity: positive(v, tx, quantity_converted)?,
+        },
+        OcfTransactionKind::StockClassSplit {
+            stock_class_id,
+            split_ratio,
+        } => {
+            let ratio = |value: &str| {
+                value
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|value| *value > 0)
+                    .ok_or_else(|| format!("split ratio `{}` is not a positive integer", value))
+            };
+            Operation::SplitStockClass {
+                stock_class_id: v.reference(
+                    &tx.id,
+                    known.stock_classes,
+                    "stock class",
+                    stock_class_id,
+                )?,
+                numerator: v.check(&tx.id, ratio(&split_ratio.numerator))?,
+                denominator: v.check(&tx.id, ratio(&split_ratio.denominator))?,
+            }
+        }
+        OcfTransactionKind::IssuerAuthorizedSharesAdjustment {
+            new_shares_authorized,
+        } => Operation::AdjustIssuerAuthorizedShares {
+            new_shares_authorized: v.check(&tx.id, decimal_to_u64(new_shares_authorized))?,
+        },
+        OcfTransactionKind::StockClassAuthorizedSharesAdjustment {
+            stock_class_id,
+            new_shares_authorized,
+        } => Operation::AdjustStockClassShares {
+            stock_class_id: v.reference(
+                &tx.id,
+                known.stock_classes,
+                "stock class",
+                stock_class_id,
+            )?,
+            new_shares_authorized: v.check(&tx.id, decimal_to_u64(new_shares_authorized))?,
+        },
+        OcfTransactionKind::StockPlanPoolAdjustment {
+            stock_plan_id,
+            shares_reserved,
+        } => Operation::AdjustStockPlanShares {
+            stock_plan_id: v.reference(&tx.id, known.stock_plans, "stock plan", stock_plan_id)?,
+            new_shares_reserved: v.check(&tx.id, decimal_to_u64(shares_reserved))?,
+        },
+    };
+    Some(op)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    const ISSUER: &str = "00000000-0000-0000-0000-000000000001";
+    const COMMON: &str = "00000000-0000-0000-0000-000000000002";
+    const PREFERRED: &str = "00000000-0000-0000-0000-000000000003";
+    const HOLDER: &str = "00000000-0000-0000-0000-000000000004";
+    const SECURITY: &str = "00000000-0000-0000-0000-000000000005";
+    const RESULTING: &str = "00000000-0000-0000-0000-000000000006";
+
+    fn id(uuid: &str) -> [u8; 16] {
+        uuid_to_bytes16(uuid).unwrap()
+    }
+
+    fn from<T: serde::de::DeserializeOwned>(value: Value) -> T {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn package(transactions: Vec<Value>) -> OcfPackage {
+        OcfPackage {
+            issuer: from(json!({ "id": ISSUER, "initial_shares_authorized": "10000000" })),
+            stock_classes: vec![
+                from(json!({
+                    "id": COMMON,
+                    "class_type": "COMMON",
+                    "initial_shares_authorized": "5000000",
+                    "votes_per_share": "1",
+                    "price_per_share": { "amount": "0.0001", "currency": "USD" },
+                })),
+                from(json!({
+                    "id": PREFERRED,
+                    "class_type": "PREFERRED",
+                    "initial_shares_authorized": "1000000",
+                    "votes_per_share": "1",
+                    "liquidation_preference_multiple": "1",
+                    "conversion_rights": [{
+                        "converts_to_stock_class_id": COMMON,
+                        "conversion_mechanism": {
+                            "ratio": { "numerator": "3", "denominator": "2" }
+                        },
+                        "anti_dilution_provision": "BROAD_BASED_WEIGHTED_AVERAGE",
+                    }],
+                })),
+            ],
+            stock_plans: Vec::new(),
+            stakeholders: vec![from(json!({ "id": HOLDER }))],
+            transactions: transactions.into_iter().map(from).collect(),
+        }
+    }
+
+    fn stock_issuance(id: &str, security_id: &str, quantity: &str) -> Value {
+        json!({
+            "object_type": "TX_STOCK_ISSUANCE",
+            "id": id,
+            "date": "2024-03-01",
+            "security_id": security_id,
+            "stakeholder_id": HOLDER,
+            "stock_class_id": COMMON,
+            "quantity": quantity,
+            "share_price": { "amount": "1.25", "currency": "USD" },
+        })
+    }
+
+    fn warrant_issuance(trigger: &str) -> Value {
+        json!({
+            "object_type": "TX_WARRANT_ISSUANCE",
+            "id": "warrant",
+            "date": "2024-03-01",
+            "security_id": SECURITY,
+            "stakeholder_id": HOLDER,
+            "quantity": "500",
+            "exercise_price": { "amount": "2", "currency": "USD" },
+            "warrant_expiration_date": "2030-03-01",
+            "exercise_triggers": [{
+                "type": trigger,
+                "conversion_right": { "converts_to_stock_class_id": COMMON },
+            }],
+        })
+    }
+
+    fn errors(package: &OcfPackage) -> Vec<ValidationError> {
+        match validate(package) {
+            Ok(_) => panic!("package passed validation"),
+            Err(errors) => errors,
+        }
+    }
+
+    #[test]
+    fn converts_a_package_into_fixed_point_operations() {
+        let import = validate(&package(vec![stock_issuance("tx", SECURITY, "1000.5")]))
+            .unwrap_or_else(|errors| panic!("{:?}", errors));
+
+        assert_eq!(import.issuer.id, id(ISSUER));
+        assert_eq!(import.issuer.shares_authorized, 10_000_000_000_000);
+        assert_eq!(import.stakeholders, vec![id(HOLDER)]);
+
+        let preferred = &import.stock_classes[1];
+        assert!(preferred.terms.kind == StockClassKind::Preferred);
+        assert_eq!(preferred.terms.converts_to_stock_class_id, id(COMMON));
+        assert_eq!(preferred.terms.conversion_ratio, 1_500_000);
+        assert!(preferred.terms.anti_dilution_type == AntiDilutionType::BroadBasedWeightedAverage);
+
+        let tx = &import.transactions[0];
+        assert_eq!(tx.date, 1_709_251_200);
+        assert!(
+            tx.operation
+                == Operation::IssueStock {
+                    security_id: id(SECURITY),
+                    stakeholder_id: id(HOLDER),
+                    stock_class_id: id(COMMON),
+                    quantity: 1_000_500_000,
+                    share_price: 1_250_000,
+                }
+        );
+    }
+
+    #[test]
+    fn collects_every_error() {
+        let mut unknown_holder = stock_issuance("unknown-holder", SECURITY, "10");
+        unknown_holder["stakeholder_id"] = json!("00000000-0000-0000-0000-0000000000ff");
+        let mut bad_date = stock_issuance("bad-date", RESULTING, "10");
+        bad_date["date"] = json!("2024-02-30");
+        let mut package = package(vec![
+            unknown_holder,
+            bad_date,
+            stock_issuance("zero", SECURITY, "0"),
+        ]);
+        package.stakeholders.push(package.stakeholders[0].clone());
+
+        let errors = errors(&package);
+        let objects: Vec<_> = errors.iter().map(|e| e.object_id.as_str()).collect();
+        assert_eq!(objects, [HOLDER, "unknown-holder", "bad-date", "zero"]);
+        assert_eq!(errors[0].message, "duplicate id");
+        assert_eq!(errors[3].message, "quantity must be positive");
+    }
+
+    #[test]
+    fn rejects_a_conversion_to_a_class_outside_the_package() {
+        let mut package = package(Vec::new());
+        package.stock_classes.remove(0);
+
+        let errors = errors(&package);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].object_id, PREFERRED);
+    }
+
+    #[test]
+    fn folds_the_resulting_issuance_into_a_warrant_exercise() {
+        let exercise = json!({
+            "object_type": "TX_WARRANT_EXERCISE",
+            "id": "exercise",
+            "date": "2024-06-01",
+            "security_id": SECURITY,
+            "resulting_security_ids": [RESULTING],
+        });
+        let import = validate(&package(vec![
+            warrant_issuance("ELECTIVE_AT_WILL"),
+            exercise,
+            stock_issuance("resulting", RESULTING, "200"),
+        ]))
+        .unwrap_or_else(|errors| panic!("{:?}", errors));
+
+        // The resulting stock is created by the exercise, which takes its quantity
+        assert_eq!(import.transactions.len(), 2);
+        assert!(
+            import.transactions[1].operation
+                == Operation::ExerciseWarrant {
+                    security_id: id(SECURITY),
+                    resulting_security_id: id(RESULTING),
+                    quantity: 200_000_000,
+                }
+        );
+    }
+
+    #[test]
+    fn rejects_automatic_warrant_triggers() {
+        let errors = errors(&package(vec![warrant_issuance("AUTOMATIC_ON_DATE")]));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].message,
+            "unsupported warrant exercise trigger AUTOMATIC_ON_DATE"
+        );
+    }
+
+    #[test]
+    fn rejects_a_security_issued_twice() {
+        let errors = errors(&package(vec![
+            stock_issuance("first", SECURITY, "10"),
+            stock_issuance("second", SECURITY, "10"),
+        ]));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].object_id, "second");
+        assert_eq!(errors[0].message, "security id is issued more than once");
+    }
+}
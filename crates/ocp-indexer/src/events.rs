@@ -0,0 +1,98 @@
+//! Decodes ocp-solana events from transaction log messages.
+//!
+//! `emit!` writes each event as a `Program data: <base64>` line: an 8-byte
+//! discriminator followed by the Borsh-encoded event. Only lines written
+//! while ocp-solana is the executing program are decoded, so another
+//! program cannot forge events by logging the same bytes.
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ocp_solana::events::*;
+use ocp_solana::payload::TxCreatedV1;
+
+const PROGRAM_DATA: &str = "Program data: ";
+
+macro_rules! program_events {
+    ($($event:ident),* $(,)?) => {
+        /// Any event ocp-solana emits.
+        pub enum ProgramEvent {
+            $($event($event),)*
+        }
+
+        impl ProgramEvent {
+            /// Decodes one event from `Program data` bytes, or returns `None`
+            /// if the discriminator belongs to no known event.
+            pub fn decode(data: &[u8]) -> Result<Option<Self>, std::io::Error> {
+                if data.len() < 8 {
+                    return Ok(None);
+                }
+                let (discriminator, payload) = data.split_at(8);
+                if discriminator == TxCreatedV1::DISCRIMINATOR {
+                    let event = TxCreatedV1::try_from_slice(payload)?;
+                    return Ok(Some(ProgramEvent::TxCreatedV2(event.upgrade())));
+                }
+                $(
+                    if discriminator == $event::DISCRIMINATOR {
+                        return Ok(Some(ProgramEvent::$event($event::try_from_slice(payload)?)));
+                    }
+                )*
+                Ok(None)
+            }
+
+            pub fn envelope(&self) -> &EventEnvelope {
+                match self {
+                    $(ProgramEvent::$event(event) => &event.envelope,)*
+                }
+            }
+        }
+    };
+}
+
+program_events! {
+    IssuerCreated,
+    IssuerAuthorizedSharesAdjusted,
+    TxCreatedV2,
+    StockClassCreated,
+    StockClassSharesAdjusted,
+    StakeholderCreated,
+    StockPlanCreated,
+    StockPlanSharesAdjusted,
+    CapTableSnapshotTaken,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DecodeError {
+    #[error("log line is not valid base64: {0}")]
+    Base64(#[from] base64::DecodeError),
+    #[error("event does not match its layout: {0}")]
+    Borsh(#[from] std::io::Error),
+}
+
+/// Decodes, in emission order, the events `program_id` emitted in a
+/// successful transaction's logs.
+pub fn parse_logs(program_id: &Pubkey, logs: &[String]) -> Result<Vec<ProgramEvent>, DecodeError> {
+    let program_id = program_id.to_string();
+    let invoke = format!("Program {} invoke", program_id);
+    let mut stack: Vec<bool> = Vec::new();
+    let mut events = Vec::new();
+
+    for line in logs {
+        if let Some(data) = line.strip_prefix(PROGRAM_DATA) {
+            if stack.last() == Some(&true) {
+                if let Some(event) = ProgramEvent::decode(&STANDARD.decode(data)?)? {
+                    events.push(event);
+                }
+            }
+        } else if line.starts_with("Program ") && line.contains(" invoke [") {
+            stack.push(line.starts_with(&invoke));
+        } else if line.starts_with("Program ")
+            && (line.ends_with(" success") || line.contains(" failed: "))
+        {
+            stack.pop();
+        }
+    }
+
+    Ok(events)
+}
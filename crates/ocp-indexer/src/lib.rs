@@ -0,0 +1,89 @@
+//! Indexes ocp-solana events into SQLite.
+//!
+//! An [`EventSource`] yields the program's transactions in order, [`events`]
+//! decodes the events in their logs, and [`Store`] materializes issuers,
+//! stock classes, stakeholders, stock plans and positions from them. Every
+//! `TxCreated` is checked against the issuer's `TxLog` sequence and hash
+//! chain, so a missed transaction is detected rather than silently producing
+//! a wrong cap table, and replaying transactions already indexed is a no-op.
+//!
+//! [`replay`] independently rebuilds an issuer's cap table with the program's
+//! own rules and diffs it against the accounts on chain.
+
+pub mod events;
+pub mod replay;
+pub mod source;
+pub mod store;
+
+pub use events::{parse_logs, ProgramEvent};
+pub use replay::{diff, fetch_accounts, replay, Mismatch};
+pub use source::{EventSource, History, LoggedTransaction, Websocket};
+pub use store::{Checkpoint, Store};
+
+use ocp_solana::engine::{CoreError, ReplayError};
+use ocp_solana::payload::TxDecodeError;
+use solana_rpc_client_api::client_error::Error as ClientError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum IndexerError {
+    #[error("database error: {0}")]
+    Database(#[from] rusqlite::Error),
+    #[error("RPC request failed: {0}")]
+    Rpc(Box<ClientError>),
+    #[error("websocket subscription failed: {0}")]
+    Subscribe(String),
+    #[error("`{0}` is not a transaction signature")]
+    InvalidSignature(String),
+    #[error("transaction {0} has no log messages")]
+    MissingLogs(String),
+    #[error("failed to decode event in {signature}: {source}")]
+    Event {
+        signature: String,
+        source: events::DecodeError,
+    },
+    #[error("failed to decode transaction {sequence} of issuer {issuer_id}: {source}")]
+    Payload {
+        issuer_id: String,
+        sequence: u64,
+        source: TxDecodeError,
+    },
+    #[error("issuer {0} has not been indexed")]
+    UnknownIssuer(String),
+    #[error("issuer {issuer_id} is missing transactions: expected {expected}, got {found}")]
+    SequenceGap {
+        issuer_id: String,
+        expected: u64,
+        found: u64,
+    },
+    #[error("transaction {sequence} of issuer {issuer_id} does not continue its TxLog hash chain")]
+    HashMismatch { issuer_id: String, sequence: u64 },
+    #[error("arithmetic overflow applying {0}")]
+    Overflow(&'static str),
+    #[error("replaying issuer {issuer_id} failed: {source}")]
+    Replay {
+        issuer_id: String,
+        source: ReplayError,
+    },
+    #[error("replaying issuer {issuer_id} failed: event rejected by the rules: {error}")]
+    Rejected { issuer_id: String, error: CoreError },
+    #[error("{account} {address} is not a valid account")]
+    InvalidAccount { account: String, address: String },
+}
+
+impl From<ClientError> for IndexerError {
+    fn from(error: ClientError) -> Self {
+        IndexerError::Rpc(Box::new(error))
+    }
+}
+
+/// Indexes every transaction `source` yields and returns how many had not
+/// been indexed before.
+pub fn index(store: &mut Store, source: &mut impl EventSource) -> Result<usize, IndexerError> {
+    let mut indexed = 0;
+    while let Some(transaction) = source.next_transaction()? {
+        if store.index_transaction(&transaction)? {
+            indexed += 1;
+        }
+    }
+    Ok(indexed)
+}
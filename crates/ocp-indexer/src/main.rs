@@ -0,0 +1,118 @@
+//! `ocp-indexer`: keeps a SQLite copy of every ocp-solana cap table.
+//!
+//! On start it replays the program's history from the last checkpoint, then
+//! follows new transactions over a websocket. A sequence gap (for instance a
+//! dropped websocket message) triggers a replay from the affected issuer's
+//! last indexed transaction.
+//!
+//! With `--verify <ISSUER>` it instead rebuilds that issuer's cap table from
+//! the full history with the program's rules, compares it with the accounts
+//! on chain and exits non-zero if they disagree.
+
+use anyhow::{bail, Result};
+use clap::Parser;
+use ocp_indexer::{
+    diff, fetch_accounts, index, replay, EventSource, History, IndexerError, Store, Websocket,
+};
+use ocp_solana_client::{uuid_to_bytes16, PROGRAM_ID};
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(
+    name = "ocp-indexer",
+    version,
+    about = "Index ocp-solana events into SQLite"
+)]
+struct Cli {
+    /// SQLite database, created if missing
+    #[arg(long, env = "OCP_INDEXER_DB", default_value = "ocp-index.sqlite")]
+    db: PathBuf,
+    /// RPC endpoint history is replayed from; defaults to a local test validator
+    #[arg(long, env = "OCP_RPC_URL", default_value = "http://127.0.0.1:8899")]
+    url: String,
+    #[arg(long, env = "OCP_WS_URL", default_value = "ws://127.0.0.1:8900")]
+    ws_url: String,
+    /// Replay history up to now and exit instead of following new transactions
+    #[arg(long)]
+    replay_only: bool,
+    /// Replay this issuer's history through the program's rules, diff the
+    /// result against its accounts and exit
+    #[arg(long, value_name = "ISSUER", conflicts_with = "replay_only")]
+    verify: Option<String>,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let rpc = RpcClient::new_with_commitment(cli.url.clone(), CommitmentConfig::confirmed());
+    if let Some(issuer) = &cli.verify {
+        return verify(&rpc, issuer);
+    }
+
+    let mut store = Store::open(&cli.db)?;
+    let mut until = store.checkpoint()?.map(|checkpoint| checkpoint.signature);
+
+    loop {
+        // Subscribe before replaying so nothing confirmed in between is
+        // missed; transactions seen by both are only applied once
+        let mut websocket = match cli.replay_only {
+            true => None,
+            false => Some(Websocket::subscribe(&cli.ws_url, &PROGRAM_ID)?),
+        };
+
+        let mut history = History::new(&rpc, &PROGRAM_ID, until.as_deref())?;
+        let replayed = index(&mut store, &mut history)?;
+        eprintln!("replayed {} transactions", replayed);
+
+        let Some(websocket) = websocket.as_mut() else {
+            return Ok(());
+        };
+        match follow(&mut store, websocket) {
+            Err(IndexerError::SequenceGap { issuer_id, .. }) => {
+                eprintln!("missed transactions for issuer {}; replaying", issuer_id);
+                until = store.last_signature(&issuer_id)?;
+            }
+            Err(error) => return Err(error.into()),
+            Ok(()) => {
+                eprintln!("subscription closed; reconnecting");
+                until = store.checkpoint()?.map(|checkpoint| checkpoint.signature);
+            }
+        }
+    }
+}
+
+fn verify(rpc: &RpcClient, issuer: &str) -> Result<()> {
+    let issuer_id = uuid_to_bytes16(issuer)?;
+    let mut history = History::new(rpc, &PROGRAM_ID, None)?;
+    let replayed = replay(&issuer_id, &mut history)?;
+    let onchain = fetch_accounts(rpc, &replayed)?;
+
+    let mismatches = diff(&replayed, &onchain)?;
+    for mismatch in &mismatches {
+        println!("{}", mismatch);
+    }
+    if !mismatches.is_empty() {
+        bail!(
+            "{} mismatches between replayed and on-chain cap table",
+            mismatches.len()
+        );
+    }
+    println!(
+        "issuer {} matches its {} replayed transactions",
+        issuer, replayed.tx_log.sequence
+    );
+    Ok(())
+}
+
+fn follow(store: &mut Store, websocket: &mut Websocket) -> Result<(), IndexerError> {
+    while let Some(transaction) = websocket.next_transaction()? {
+        if store.index_transaction(&transaction)? {
+            eprintln!(
+                "indexed {} at slot {}",
+                transaction.signature, transaction.slot
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,486 @@
+//! Rebuilds an issuer's cap table from its events with the program's own
+//! rules and compares it with the accounts on chain.
+//!
+//! [`replay`] drives an [`ocp_solana::engine::CapTable`] with every event of
+//! one issuer, [`fetch_accounts`] loads the accounts the replayed table says
+//! should exist, and [`diff`] lists every field where the two disagree.
+
+use crate::events::{parse_logs, ProgramEvent};
+use crate::{EventSource, IndexerError};
+use anchor_lang::AccountDeserialize;
+use ocp_solana::engine::{CapTable, CoreError};
+use ocp_solana::state::*;
+use ocp_solana_client::{bytes16_to_uuid, pda, Pubkey, PROGRAM_ID};
+use solana_rpc_client::rpc_client::RpcClient;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// Accounts requested per `getMultipleAccounts` call; the RPC maximum.
+const ACCOUNTS_PAGE_SIZE: usize = 100;
+
+/// Replays every event `source` yields for `issuer_id`, oldest first.
+///
+/// `StockPlanCreated` does not carry the plan's stock classes, so replayed
+/// plans list none and [`diff`] does not compare them.
+pub fn replay(
+    issuer_id: &[u8; 16],
+    source: &mut impl EventSource,
+) -> Result<CapTable, IndexerError> {
+    let uuid = bytes16_to_uuid(issuer_id);
+    let rejected = |error| IndexerError::Rejected {
+        issuer_id: uuid.clone(),
+        error,
+    };
+    let mut cap_table: Option<CapTable> = None;
+
+    while let Some(transaction) = source.next_transaction()? {
+        let events =
+            parse_logs(&PROGRAM_ID, &transaction.logs).map_err(|source| IndexerError::Event {
+                signature: transaction.signature.clone(),
+                source,
+            })?;
+
+        for event in events {
+            if event.envelope().issuer_id != *issuer_id {
+                continue;
+            }
+            if let ProgramEvent::IssuerCreated(event) = &event {
+                cap_table = Some(
+                    CapTable::new(*issuer_id, event.initial_shares_authorized).map_err(rejected)?,
+                );
+                continue;
+            }
+            let cap_table = cap_table
+                .as_mut()
+                .ok_or_else(|| IndexerError::UnknownIssuer(uuid.clone()))?;
+
+            match event {
+                ProgramEvent::IssuerCreated(_) => unreachable!(),
+                ProgramEvent::IssuerAuthorizedSharesAdjusted(event) => {
+                    cap_table.adjust_authorized_shares(event.new_shares_authorized)
+                }
+                ProgramEvent::TxCreatedV2(event) => {
+                    cap_table
+                        .apply_tx(&event)
+                        .map_err(|source| IndexerError::Replay {
+                            issuer_id: uuid.clone(),
+                            source,
+                        })?
+                }
+                ProgramEvent::StockClassCreated(event) => cap_table
+                    .create_stock_class(
+                        event.id,
+                        event.class_type,
+                        event.price_per_share,
+                        event.initial_shares_authorized,
+                        event.terms,
+                    )
+                    .map_err(rejected)?,
+                ProgramEvent::StockClassSharesAdjusted(event) => cap_table
+                    .adjust_stock_class_shares(event.stock_class_id, event.new_shares_authorized)
+                    .map_err(rejected)?,
+                ProgramEvent::StakeholderCreated(event) => {
+                    cap_table.create_stakeholder(event.id).map_err(rejected)?
+                }
+                ProgramEvent::StockPlanCreated(event) => {
+                    // The rules require at least one class; the real list is unknown
+                    cap_table
+                        .create_stock_plan(event.id, vec![[0; 16]], event.shares_reserved)
+                        .map_err(rejected)?;
+                    if let Some(stock_plan) = cap_table.stock_plans.get_mut(&event.id) {
+                        stock_plan.stock_class_ids.clear();
+                    }
+                }
+                ProgramEvent::StockPlanSharesAdjusted(event) => cap_table
+                    .adjust_stock_plan_shares(event.id, event.new_shares_reserved)
+                    .map_err(rejected)?,
+                // Snapshots copy the summary without changing it
+                ProgramEvent::CapTableSnapshotTaken(_) => {}
+            }
+        }
+    }
+
+    cap_table.ok_or(IndexerError::UnknownIssuer(uuid))
+}
+
+/// Loads from chain every account `replayed` holds, plus any stock class the
+/// on-chain summary lists. Positions are found through their security
+/// registrations, so positions the program has closed are simply absent.
+pub fn fetch_accounts(rpc: &RpcClient, replayed: &CapTable) -> Result<CapTable, IndexerError> {
+    let issuer_id = &replayed.issuer.id;
+    let issuer: Issuer = fetch(rpc, &pda::issuer(issuer_id), "issuer")?;
+    let cap_table_summary: CapTableSummary =
+        fetch(rpc, &pda::cap_table_summary(issuer_id), "cap table summary")?;
+    let tx_log: TxLog = fetch(rpc, &pda::tx_log(issuer_id), "tx log")?;
+
+    let mut stock_class_ids: BTreeSet<[u8; 16]> = replayed.stock_classes.keys().copied().collect();
+    stock_class_ids.extend(
+        cap_table_summary
+            .stock_classes
+            .iter()
+            .map(|summary| summary.stock_class_id),
+    );
+    let stock_classes = fetch_all::<StockClass>(
+        rpc,
+        stock_class_ids.iter().map(|id| (*id, pda::stock_class(id))),
+        "stock class",
+    )?;
+
+    let stakeholders = fetch_all::<Stakeholder>(
+        rpc,
+        replayed
+            .stakeholders
+            .iter()
+            .map(|id| (*id, pda::stakeholder(id))),
+        "stakeholder",
+    )?
+    .into_keys()
+    .collect();
+
+    let stock_plans = fetch_all::<StockPlan>(
+        rpc,
+        replayed
+            .stock_plans
+            .keys()
+            .map(|id| (*id, pda::stock_plan(id))),
+        "stock plan",
+    )?;
+
+    let registrations = fetch_all::<SecurityRegistration>(
+        rpc,
+        replayed
+            .securities
+            .iter()
+            .map(|id| (*id, pda::security_registration(issuer_id, id))),
+        "security registration",
+    )?;
+
+    let mut cap_table = CapTable {
+        issuer,
+        cap_table_summary,
+        tx_log,
+        stock_classes,
+        stakeholders,
+        stock_plans,
+        securities: registrations.keys().copied().collect(),
+        ..CapTable::default()
+    };
+
+    let by_type = |position_type: PositionType| {
+        registrations
+            .iter()
+            .filter(move |(_, registration)| registration.position_type == position_type)
+            .map(|(security_id, registration)| (*security_id, registration.position))
+    };
+    cap_table.stock_positions = fetch_all(rpc, by_type(PositionType::Stock), "stock position")?;
+    cap_table.convertible_positions = fetch_all(
+        rpc,
+        by_type(PositionType::Convertible),
+        "convertible position",
+    )?;
+    cap_table.equity_compensation_positions = fetch_all(
+        rpc,
+        by_type(PositionType::EquityCompensation),
+        "equity compensation position",
+    )?;
+    cap_table.warrant_positions =
+        fetch_all(rpc, by_type(PositionType::Warrant), "warrant position")?;
+
+    Ok(cap_table)
+}
+
+fn fetch<T: AccountDeserialize>(
+    rpc: &RpcClient,
+    address: &Pubkey,
+    account: &str,
+) -> Result<T, IndexerError> {
+    let data = rpc.get_account_data(address)?;
+    T::try_deserialize(&mut data.as_slice()).map_err(|_| IndexerError::InvalidAccount {
+        account: account.to_string(),
+        address: address.to_string(),
+    })
+}
+
+/// Fetches the accounts at `addresses`, keyed by id; missing accounts are left out.
+fn fetch_all<T: AccountDeserialize>(
+    rpc: &RpcClient,
+    addresses: impl Iterator<Item = ([u8; 16], Pubkey)>,
+    account: &str,
+) -> Result<BTreeMap<[u8; 16], T>, IndexerError> {
+    let addresses: Vec<_> = addresses.collect();
+    let mut accounts = BTreeMap::new();
+
+    for page in addresses.chunks(ACCOUNTS_PAGE_SIZE) {
+        let keys: Vec<Pubkey> = page.iter().map(|(_, address)| *address).collect();
+        let fetched = rpc.get_multiple_accounts(&keys)?;
+        for ((id, address), fetched) in page.iter().zip(fetched) {
+            let Some(fetched) = fetched else {
+                continue;
+            };
+            let value = T::try_deserialize(&mut fetched.data.as_slice()).map_err(|_| {
+                IndexerError::InvalidAccount {
+                    account: account.to_string(),
+                    address: address.to_string(),
+                }
+            })?;
+            accounts.insert(*id, value);
+        }
+    }
+
+    Ok(accounts)
+}
+
+/// One field where the replayed cap table and the chain disagree.
+pub struct Mismatch {
+    /// Account the field belongs to, e.g. `stock position <uuid>`
+    pub account: String,
+    pub detail: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.account, self.detail)
+    }
+}
+
+/// Compares two cap tables after bringing both up to date with every split,
+/// as the split crank may have run on some positions and not others.
+pub fn diff(replayed: &CapTable, onchain: &CapTable) -> Result<Vec<Mismatch>, CoreError> {
+    let mut replayed = replayed.clone();
+    let mut onchain = onchain.clone();
+    replayed.apply_all_pending_splits()?;
+    onchain.apply_all_pending_splits()?;
+
+    let mut diff = Diff::default();
+
+    let (a, b) = (&replayed.issuer, &onchain.issuer);
+    diff.account("issuer".into())
+        .value("shares_issued", a.shares_issued, b.shares_issued)
+        .value(
+            "shares_authorized",
+            a.shares_authorized,
+            b.shares_authorized,
+        );
+
+    let (a, b) = (&replayed.cap_table_summary, &onchain.cap_table_summary);
+    diff.account("cap table summary".into())
+        .value(
+            "shares_authorized",
+            a.shares_authorized,
+            b.shares_authorized,
+        )
+        .opaque("stock_classes", &a.stock_classes, &b.stock_classes)
+        .value(
+            "options_outstanding",
+            a.options_outstanding,
+            b.options_outstanding,
+        )
+        .value(
+            "warrants_outstanding",
+            a.warrants_outstanding,
+            b.warrants_outstanding,
+        )
+        .value(
+            "plan_pool_reserved",
+            a.plan_pool_reserved,
+            b.plan_pool_reserved,
+        )
+        .value(
+            "plan_pool_granted",
+            a.plan_pool_granted,
+            b.plan_pool_granted,
+        )
+        .value(
+            "plan_pool_available",
+            a.plan_pool_available,
+            b.plan_pool_available,
+        )
+        .value(
+            "convertible_principal",
+            a.convertible_principal,
+            b.convertible_principal,
+        );
+
+    let (a, b) = (&replayed.tx_log, &onchain.tx_log);
+    diff.account("tx log".into())
+        .value("sequence", a.sequence, b.sequence)
+        .opaque("last_hash", &a.last_hash, &b.last_hash);
+
+    diff.each(
+        "stock class",
+        &replayed.stock_classes,
+        &onchain.stock_classes,
+        |d, a, b| {
+            d.value("class_type", &a.class_type, &b.class_type)
+                .value("price_per_share", a.price_per_share, b.price_per_share)
+                .value("shares_issued", a.shares_issued, b.shares_issued)
+                .value(
+                    "shares_authorized",
+                    a.shares_authorized,
+                    b.shares_authorized,
+                )
+                .opaque("splits", &a.splits, &b.splits)
+                .opaque("terms", &a.terms, &b.terms)
+                .value("conversion_price", a.conversion_price, b.conversion_price);
+        },
+    );
+
+    let stakeholders = |cap_table: &CapTable| {
+        cap_table
+            .stakeholders
+            .iter()
+            .map(|id| (*id, ()))
+            .collect::<BTreeMap<_, _>>()
+    };
+    diff.each(
+        "stakeholder",
+        &stakeholders(&replayed),
+        &stakeholders(&onchain),
+        |_, _, _| {},
+    );
+
+    diff.each(
+        "stock plan",
+        &replayed.stock_plans,
+        &onchain.stock_plans,
+        |d, a, b| {
+            d.value("shares_reserved", a.shares_reserved, b.shares_reserved);
+        },
+    );
+
+    diff.each(
+        "stock position",
+        &replayed.stock_positions,
+        &onchain.stock_positions,
+        |d, a, b| {
+            d.id("stakeholder_id", &a.stakeholder_id, &b.stakeholder_id)
+                .id("stock_class_id", &a.stock_class_id, &b.stock_class_id)
+                .value("quantity", a.quantity, b.quantity)
+                .value("share_price", a.share_price, b.share_price);
+        },
+    );
+
+    diff.each(
+        "convertible position",
+        &replayed.convertible_positions,
+        &onchain.convertible_positions,
+        |d, a, b| {
+            d.id("stakeholder_id", &a.stakeholder_id, &b.stakeholder_id)
+                .value(
+                    "investment_amount",
+                    a.investment_amount,
+                    b.investment_amount,
+                );
+        },
+    );
+
+    diff.each(
+        "equity compensation position",
+        &replayed.equity_compensation_positions,
+        &onchain.equity_compensation_positions,
+        |d, a, b| {
+            d.id("stakeholder_id", &a.stakeholder_id, &b.stakeholder_id)
+                .id("stock_class_id", &a.stock_class_id, &b.stock_class_id)
+                .id("stock_plan_id", &a.stock_plan_id, &b.stock_plan_id)
+                .value("quantity", a.quantity, b.quantity);
+        },
+    );
+
+    diff.each(
+        "warrant position",
+        &replayed.warrant_positions,
+        &onchain.warrant_positions,
+        |d, a, b| {
+            d.id("stakeholder_id", &a.stakeholder_id, &b.stakeholder_id)
+                .id("stock_class_id", &a.stock_class_id, &b.stock_class_id)
+                .value("quantity", a.quantity, b.quantity)
+                .value("exercise_price", a.exercise_price, b.exercise_price)
+                .value("expiration_date", a.expiration_date, b.expiration_date)
+                .opaque("exercise_trigger", &a.exercise_trigger, &b.exercise_trigger)
+                .value("rent_payer", a.rent_payer, b.rent_payer);
+        },
+    );
+
+    Ok(diff.mismatches)
+}
+
+#[derive(Default)]
+struct Diff {
+    mismatches: Vec<Mismatch>,
+}
+
+struct AccountDiff<'a> {
+    mismatches: &'a mut Vec<Mismatch>,
+    account: String,
+}
+
+impl Diff {
+    fn account(&mut self, account: String) -> AccountDiff<'_> {
+        AccountDiff {
+            mismatches: &mut self.mismatches,
+            account,
+        }
+    }
+
+    /// Compares the accounts present on both sides and reports the rest as
+    /// missing from one of them.
+    fn each<T>(
+        &mut self,
+        kind: &str,
+        replayed: &BTreeMap<[u8; 16], T>,
+        onchain: &BTreeMap<[u8; 16], T>,
+        compare: impl Fn(&mut AccountDiff, &T, &T),
+    ) {
+        let ids: BTreeSet<_> = replayed.keys().chain(onchain.keys()).collect();
+        for id in ids {
+            let mut account = self.account(format!("{} {}", kind, bytes16_to_uuid(id)));
+            match (replayed.get(id), onchain.get(id)) {
+                (Some(a), Some(b)) => compare(&mut account, a, b),
+                (Some(_), None) => account.push("missing on chain".into()),
+                (None, Some(_)) => account.push("on chain but not produced by replay".into()),
+                (None, None) => unreachable!(),
+            }
+        }
+    }
+}
+
+impl AccountDiff<'_> {
+    fn push(&mut self, detail: String) {
+        self.mismatches.push(Mismatch {
+            account: self.account.clone(),
+            detail,
+        });
+    }
+
+    fn value<T: PartialEq + fmt::Display>(
+        &mut self,
+        field: &str,
+        replayed: T,
+        onchain: T,
+    ) -> &mut Self {
+        if replayed != onchain {
+            self.push(format!(
+                "{} is {} on chain, replay gives {}",
+                field, onchain, replayed
+            ));
+        }
+        self
+    }
+
+    fn id(&mut self, field: &str, replayed: &[u8; 16], onchain: &[u8; 16]) -> &mut Self {
+        self.value(field, bytes16_to_uuid(replayed), bytes16_to_uuid(onchain))
+    }
+
+    /// For fields without a readable form; only reports that they differ.
+    fn opaque<T: PartialEq + ?Sized>(
+        &mut self,
+        field: &str,
+        replayed: &T,
+        onchain: &T,
+    ) -> &mut Self {
+        if replayed != onchain {
+            self.push(format!("{} differs", field));
+        }
+        self
+    }
+}
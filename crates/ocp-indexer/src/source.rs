@@ -0,0 +1,150 @@
+//! Where transaction logs come from.
+//!
+//! [`History`] replays the program's confirmed transactions from the ledger
+//! over RPC, oldest first, starting after a checkpoint; pointed at a local
+//! `solana-test-validator` it replays that validator's ledger. [`Websocket`]
+//! follows new transactions through a `logsSubscribe` subscription.
+
+use crate::IndexerError;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use solana_pubsub_client::pubsub_client::{LogsSubscription, PubsubClient};
+use solana_rpc_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_rpc_client_api::config::{
+    RpcTransactionConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter,
+};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::UiTransactionEncoding;
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+/// Signatures requested per `getSignaturesForAddress` page; the RPC maximum.
+const SIGNATURES_PAGE_SIZE: usize = 1000;
+
+/// A successful transaction that invoked the program, with its log messages.
+#[derive(Clone)]
+pub struct LoggedTransaction {
+    pub signature: String,
+    pub slot: u64,
+    pub logs: Vec<String>,
+}
+
+/// A stream of program transactions in execution order.
+pub trait EventSource {
+    /// The next transaction, or `None` once the source is exhausted.
+    fn next_transaction(&mut self) -> Result<Option<LoggedTransaction>, IndexerError>;
+}
+
+/// Confirmed program transactions after `until`, oldest first.
+pub struct History<'a> {
+    rpc: &'a RpcClient,
+    signatures: VecDeque<(Signature, u64)>,
+}
+
+impl<'a> History<'a> {
+    /// Lists every successful program transaction after `until` (or since
+    /// the program was deployed) up front, so transactions confirmed during
+    /// the replay are left for the next run or a [`Websocket`].
+    pub fn new(
+        rpc: &'a RpcClient,
+        program_id: &Pubkey,
+        until: Option<&str>,
+    ) -> Result<Self, IndexerError> {
+        let until = until.map(parse_signature).transpose()?;
+        let mut signatures = VecDeque::new();
+        let mut before = None;
+
+        loop {
+            let page = rpc.get_signatures_for_address_with_config(
+                program_id,
+                GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    until,
+                    limit: Some(SIGNATURES_PAGE_SIZE),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                },
+            )?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            before = Some(parse_signature(&last.signature)?);
+
+            // Pages run newest to oldest; prepend to end up oldest first
+            for status in page.iter().filter(|status| status.err.is_none()) {
+                signatures.push_front((parse_signature(&status.signature)?, status.slot));
+            }
+            if page.len() < SIGNATURES_PAGE_SIZE {
+                break;
+            }
+        }
+
+        Ok(Self { rpc, signatures })
+    }
+}
+
+impl EventSource for History<'_> {
+    fn next_transaction(&mut self) -> Result<Option<LoggedTransaction>, IndexerError> {
+        let Some((signature, slot)) = self.signatures.pop_front() else {
+            return Ok(None);
+        };
+        let transaction = self.rpc.get_transaction_with_config(
+            &signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Json),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )?;
+        let logs = match transaction.transaction.meta.map(|meta| meta.log_messages) {
+            Some(OptionSerializer::Some(logs)) => logs,
+            _ => return Err(IndexerError::MissingLogs(signature.to_string())),
+        };
+
+        Ok(Some(LoggedTransaction {
+            signature: signature.to_string(),
+            slot,
+            logs,
+        }))
+    }
+}
+
+/// New program transactions as the validator confirms them.
+pub struct Websocket {
+    // Dropping the subscription closes it
+    subscription: LogsSubscription,
+}
+
+impl Websocket {
+    pub fn subscribe(url: &str, program_id: &Pubkey) -> Result<Self, IndexerError> {
+        let subscription = PubsubClient::logs_subscribe(
+            url,
+            RpcTransactionLogsFilter::Mentions(vec![program_id.to_string()]),
+            RpcTransactionLogsConfig {
+                commitment: Some(CommitmentConfig::confirmed()),
+            },
+        )
+        .map_err(|error| IndexerError::Subscribe(error.to_string()))?;
+        Ok(Self { subscription })
+    }
+}
+
+impl EventSource for Websocket {
+    fn next_transaction(&mut self) -> Result<Option<LoggedTransaction>, IndexerError> {
+        // Failed transactions still log the events emitted before the failure
+        while let Ok(response) = self.subscription.1.recv() {
+            if response.value.err.is_none() {
+                return Ok(Some(LoggedTransaction {
+                    signature: response.value.signature,
+                    slot: response.context.slot,
+                    logs: response.value.logs,
+                }));
+            }
+        }
+        Ok(None)
+    }
+}
+
+fn parse_signature(signature: &str) -> Result<Signature, IndexerError> {
+    Signature::from_str(signature).map_err(|_| IndexerError::InvalidSignature(signature.into()))
+}
@@ -0,0 +1,961 @@
+//! SQLite tables materialized from ocp-solana events.
+//!
+//! Ids are stored as UUID strings and amounts as raw 6-decimal fixed-point
+//! integers, exactly as the program holds them. Each source transaction is
+//! applied inside one SQLite transaction together with its signature and the
+//! checkpoint, so a crash never leaves it half applied and replaying it is a
+//! no-op.
+
+use crate::events::{parse_logs, ProgramEvent};
+use crate::source::LoggedTransaction;
+use crate::IndexerError;
+use ocp_solana::events::*;
+use ocp_solana::payload::{decode_tx_created, DecodedTx};
+use ocp_solana::state::{
+    AntiDilutionType, FractionalSharePolicy, SplitRatio, StockClassKind, TxLog,
+    WarrantExerciseTrigger, SHARE_DECIMALS_FACTOR,
+};
+use ocp_solana_client::{bytes16_to_uuid, PROGRAM_ID};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use std::path::Path;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS checkpoint (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    slot INTEGER NOT NULL,
+    signature TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS processed_transactions (
+    signature TEXT PRIMARY KEY,
+    slot INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS issuers (
+    id TEXT PRIMARY KEY,
+    shares_authorized INTEGER NOT NULL,
+    tx_sequence INTEGER NOT NULL,
+    tx_hash BLOB NOT NULL,
+    signature TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS transactions (
+    issuer_id TEXT NOT NULL,
+    sequence INTEGER NOT NULL,
+    tx_type TEXT NOT NULL,
+    payload_version INTEGER NOT NULL,
+    tx_data BLOB NOT NULL,
+    hash BLOB NOT NULL,
+    object_id TEXT NOT NULL,
+    signature TEXT NOT NULL,
+    slot INTEGER NOT NULL,
+    unix_timestamp INTEGER NOT NULL,
+    PRIMARY KEY (issuer_id, sequence)
+);
+CREATE TABLE IF NOT EXISTS stock_classes (
+    id TEXT PRIMARY KEY,
+    issuer_id TEXT NOT NULL,
+    class_type TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    price_per_share INTEGER NOT NULL,
+    shares_authorized INTEGER NOT NULL,
+    shares_issued INTEGER NOT NULL,
+    split_epoch INTEGER NOT NULL,
+    seniority INTEGER NOT NULL,
+    liquidation_preference_multiple INTEGER NOT NULL,
+    is_participating INTEGER NOT NULL,
+    participation_cap_multiple INTEGER NOT NULL,
+    conversion_ratio INTEGER NOT NULL,
+    conversion_price INTEGER NOT NULL,
+    converts_to_stock_class_id TEXT,
+    votes_per_share INTEGER NOT NULL,
+    anti_dilution_type TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS stakeholders (
+    id TEXT PRIMARY KEY,
+    issuer_id TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS stock_plans (
+    id TEXT PRIMARY KEY,
+    issuer_id TEXT NOT NULL,
+    shares_reserved INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS stock_positions (
+    issuer_id TEXT NOT NULL,
+    security_id TEXT NOT NULL,
+    stakeholder_id TEXT NOT NULL,
+    stock_class_id TEXT NOT NULL,
+    quantity INTEGER NOT NULL,
+    share_price INTEGER NOT NULL,
+    PRIMARY KEY (issuer_id, security_id)
+);
+CREATE TABLE IF NOT EXISTS convertible_positions (
+    issuer_id TEXT NOT NULL,
+    security_id TEXT NOT NULL,
+    stakeholder_id TEXT NOT NULL,
+    investment_amount INTEGER NOT NULL,
+    PRIMARY KEY (issuer_id, security_id)
+);
+CREATE TABLE IF NOT EXISTS equity_compensation_positions (
+    issuer_id TEXT NOT NULL,
+    security_id TEXT NOT NULL,
+    stakeholder_id TEXT NOT NULL,
+    stock_class_id TEXT NOT NULL,
+    stock_plan_id TEXT,
+    quantity INTEGER NOT NULL,
+    PRIMARY KEY (issuer_id, security_id)
+);
+CREATE TABLE IF NOT EXISTS warrant_positions (
+    issuer_id TEXT NOT NULL,
+    security_id TEXT NOT NULL,
+    stakeholder_id TEXT NOT NULL,
+    stock_class_id TEXT NOT NULL,
+    quantity INTEGER NOT NULL,
+    exercise_price INTEGER NOT NULL,
+    expiration_date INTEGER NOT NULL,
+    exercise_trigger TEXT NOT NULL,
+    PRIMARY KEY (issuer_id, security_id)
+);
+CREATE TABLE IF NOT EXISTS cap_table_snapshots (
+    issuer_id TEXT NOT NULL,
+    id TEXT NOT NULL,
+    tx_sequence INTEGER NOT NULL,
+    tx_hash BLOB NOT NULL,
+    signature TEXT NOT NULL,
+    slot INTEGER NOT NULL,
+    unix_timestamp INTEGER NOT NULL,
+    PRIMARY KEY (issuer_id, id)
+);
+";
+
+/// The last transaction indexed.
+pub struct Checkpoint {
+    pub slot: u64,
+    pub signature: String,
+}
+
+pub struct Store {
+    connection: Connection,
+}
+
+impl Store {
+    /// Opens the database at `path`, creating it and its tables if needed.
+    pub fn open(path: &Path) -> Result<Self, IndexerError> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    pub fn in_memory() -> Result<Self, IndexerError> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self, IndexerError> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    pub fn checkpoint(&self) -> Result<Option<Checkpoint>, IndexerError> {
+        Ok(self
+            .connection
+            .query_row("SELECT slot, signature FROM checkpoint", [], |row| {
+                Ok(Checkpoint {
+                    slot: row.get(0)?,
+                    signature: row.get(1)?,
+                })
+            })
+            .optional()?)
+    }
+
+    /// Signature of the last transaction indexed for `issuer_id`, the point
+    /// to replay history from after a sequence gap.
+    pub fn last_signature(&self, issuer_id: &str) -> Result<Option<String>, IndexerError> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT COALESCE(
+                    (SELECT signature FROM transactions WHERE issuer_id = ?1
+                     ORDER BY sequence DESC LIMIT 1),
+                    signature)
+                 FROM issuers WHERE id = ?1",
+                [issuer_id],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Applies the program events in `transaction`. Returns `false` if the
+    /// transaction was already indexed.
+    pub fn index_transaction(
+        &mut self,
+        transaction: &LoggedTransaction,
+    ) -> Result<bool, IndexerError> {
+        let events =
+            parse_logs(&PROGRAM_ID, &transaction.logs).map_err(|source| IndexerError::Event {
+                signature: transaction.signature.clone(),
+                source,
+            })?;
+
+        let db = self.connection.transaction()?;
+        let processed = db
+            .query_row(
+                "SELECT 1 FROM processed_transactions WHERE signature = ?1",
+                [&transaction.signature],
+                |_| Ok(()),
+            )
+            .optional()?;
+        if processed.is_some() {
+            return Ok(false);
+        }
+
+        for event in &events {
+            apply_event(&db, transaction, event)?;
+        }
+
+        db.execute(
+            "INSERT INTO processed_transactions (signature, slot) VALUES (?1, ?2)",
+            params![transaction.signature, transaction.slot],
+        )?;
+        db.execute(
+            "INSERT INTO checkpoint (id, slot, signature) VALUES (1, ?1, ?2)
+             ON CONFLICT (id) DO UPDATE SET slot = excluded.slot, signature = excluded.signature
+             WHERE excluded.slot >= checkpoint.slot",
+            params![transaction.slot, transaction.signature],
+        )?;
+        db.commit()?;
+        Ok(true)
+    }
+}
+
+fn uuid(id: &[u8; 16]) -> String {
+    bytes16_to_uuid(id)
+}
+
+/// `None` for the all-zero id the program stores in place of an absent one.
+fn optional_uuid(id: &[u8; 16]) -> Option<String> {
+    (*id != [0; 16]).then(|| uuid(id))
+}
+
+fn apply_event(
+    db: &Transaction,
+    transaction: &LoggedTransaction,
+    event: &ProgramEvent,
+) -> Result<(), IndexerError> {
+    let issuer_id = uuid(&event.envelope().issuer_id);
+
+    match event {
+        ProgramEvent::IssuerCreated(event) => {
+            db.execute(
+                "INSERT OR IGNORE INTO issuers
+                 (id, shares_authorized, tx_sequence, tx_hash, signature, created_at)
+                 VALUES (?1, ?2, 0, ?3, ?4, ?5)",
+                params![
+                    issuer_id,
+                    event.initial_shares_authorized,
+                    [0u8; 32],
+                    transaction.signature,
+                    event.envelope.unix_timestamp
+                ],
+            )?;
+        }
+        ProgramEvent::IssuerAuthorizedSharesAdjusted(event) => {
+            db.execute(
+                "UPDATE issuers SET shares_authorized = ?2 WHERE id = ?1",
+                params![issuer_id, event.new_shares_authorized],
+            )?;
+        }
+        ProgramEvent::StockClassCreated(event) => {
+            let terms = &event.terms;
+            db.execute(
+                "INSERT OR IGNORE INTO stock_classes
+                 (id, issuer_id, class_type, kind, price_per_share, shares_authorized,
+                  shares_issued, split_epoch, seniority, liquidation_preference_multiple,
+                  is_participating, participation_cap_multiple, conversion_ratio,
+                  conversion_price, converts_to_stock_class_id, votes_per_share,
+                  anti_dilution_type)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, 0, 0, ?7, ?8, ?9, ?10, ?11, ?5, ?12, ?13, ?14)",
+                params![
+                    uuid(&event.id),
+                    issuer_id,
+                    event.class_type,
+                    kind_name(terms.kind),
+                    event.price_per_share,
+                    event.initial_shares_authorized,
+                    terms.seniority,
+                    terms.liquidation_preference_multiple,
+                    terms.is_participating,
+                    terms.participation_cap_multiple,
+                    terms.conversion_ratio,
+                    optional_uuid(&terms.converts_to_stock_class_id),
+                    terms.votes_per_share,
+                    anti_dilution_name(terms.anti_dilution_type),
+                ],
+            )?;
+        }
+        ProgramEvent::StockClassSharesAdjusted(event) => {
+            db.execute(
+                "UPDATE stock_classes SET shares_authorized = ?2 WHERE id = ?1",
+                params![uuid(&event.stock_class_id), event.new_shares_authorized],
+            )?;
+        }
+        ProgramEvent::StakeholderCreated(event) => {
+            db.execute(
+                "INSERT OR IGNORE INTO stakeholders (id, issuer_id) VALUES (?1, ?2)",
+                params![uuid(&event.id), issuer_id],
+            )?;
+        }
+        ProgramEvent::StockPlanCreated(event) => {
+            db.execute(
+                "INSERT OR IGNORE INTO stock_plans (id, issuer_id, shares_reserved)
+                 VALUES (?1, ?2, ?3)",
+                params![uuid(&event.id), issuer_id, event.shares_reserved],
+            )?;
+        }
+        ProgramEvent::StockPlanSharesAdjusted(event) => {
+            db.execute(
+                "UPDATE stock_plans SET shares_reserved = ?2 WHERE id = ?1",
+                params![uuid(&event.id), event.new_shares_reserved],
+            )?;
+        }
+        ProgramEvent::CapTableSnapshotTaken(event) => {
+            db.execute(
+                "INSERT OR IGNORE INTO cap_table_snapshots
+                 (issuer_id, id, tx_sequence, tx_hash, signature, slot, unix_timestamp)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    issuer_id,
+                    uuid(&event.snapshot_id),
+                    event.tx_log_sequence,
+                    event.tx_log_hash,
+                    transaction.signature,
+                    event.envelope.slot,
+                    event.envelope.unix_timestamp
+                ],
+            )?;
+        }
+        ProgramEvent::TxCreatedV2(event) => apply_tx_created(db, transaction, &issuer_id, event)?,
+    }
+
+    Ok(())
+}
+
+/// Appends a `TxCreated` to the issuer's transaction history after checking
+/// it continues the `TxLog` hash chain, then applies its payload.
+fn apply_tx_created(
+    db: &Transaction,
+    transaction: &LoggedTransaction,
+    issuer_id: &str,
+    event: &TxCreatedV2,
+) -> Result<(), IndexerError> {
+    let (last_sequence, last_hash): (u64, Vec<u8>) = db
+        .query_row(
+            "SELECT tx_sequence, tx_hash FROM issuers WHERE id = ?1",
+            [issuer_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?
+        .ok_or_else(|| IndexerError::UnknownIssuer(issuer_id.to_string()))?;
+
+    if event.sequence <= last_sequence {
+        // Already indexed from another source, or a `TxCreated` emitted before
+        // the issuer had a `TxLog` (sequence 0), which the chain does not cover
+        return Ok(());
+    }
+    if event.sequence != last_sequence + 1 {
+        return Err(IndexerError::SequenceGap {
+            issuer_id: issuer_id.to_string(),
+            expected: last_sequence + 1,
+            found: event.sequence,
+        });
+    }
+    if event.prev_hash[..] != last_hash[..] {
+        return Err(IndexerError::HashMismatch {
+            issuer_id: issuer_id.to_string(),
+            sequence: event.sequence,
+        });
+    }
+
+    let hash = TxLog::chain(
+        &event.prev_hash,
+        event.tx_type,
+        event.payload_version,
+        &event.tx_data,
+    );
+    db.execute(
+        "INSERT INTO transactions
+         (issuer_id, sequence, tx_type, payload_version, tx_data, hash, object_id, signature,
+          slot, unix_timestamp)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            issuer_id,
+            event.sequence,
+            format!("{:?}", event.tx_type),
+            event.payload_version,
+            event.tx_data,
+            hash,
+            uuid(&event.envelope.object_id),
+            transaction.signature,
+            transaction.slot,
+            event.envelope.unix_timestamp,
+        ],
+    )?;
+    db.execute(
+        "UPDATE issuers SET tx_sequence = ?2, tx_hash = ?3 WHERE id = ?1",
+        params![issuer_id, event.sequence, hash],
+    )?;
+
+    let payload = decode_tx_created(event).map_err(|source| IndexerError::Payload {
+        issuer_id: issuer_id.to_string(),
+        sequence: event.sequence,
+        source,
+    })?;
+    apply_payload(db, issuer_id, payload)
+}
+
+fn apply_payload(
+    db: &Transaction,
+    issuer_id: &str,
+    payload: DecodedTx,
+) -> Result<(), IndexerError> {
+    match payload {
+        DecodedTx::StockIssuance(tx) => {
+            insert_stock_position(
+                db,
+                issuer_id,
+                &tx.security_id,
+                &tx.stakeholder_id,
+                &tx.stock_class_id,
+                tx.quantity,
+                tx.share_price,
+            )?;
+            add_shares_issued(db, &tx.stock_class_id, tx.quantity)?;
+        }
+        DecodedTx::StockConversion(tx) => {
+            let share_price: u64 = db.query_row(
+                "SELECT share_price FROM stock_positions WHERE issuer_id = ?1 AND security_id = ?2",
+                params![issuer_id, uuid(&tx.security_id)],
+                |row| row.get(0),
+            )?;
+            // Cost basis carries over to the resulting shares
+            let resulting_share_price = u64::try_from(
+                share_price as u128 * tx.quantity_converted as u128 / tx.quantity_received as u128,
+            )
+            .map_err(|_| IndexerError::Overflow("stock conversion"))?;
+
+            reduce_position(
+                db,
+                "stock_positions",
+                issuer_id,
+                &tx.security_id,
+                tx.quantity_converted,
+            )?;
+            insert_stock_position(
+                db,
+                issuer_id,
+                &tx.resulting_security_id,
+                &tx.stakeholder_id,
+                &tx.target_stock_class_id,
+                tx.quantity_received,
+                resulting_share_price,
+            )?;
+            db.execute(
+                "UPDATE stock_classes SET shares_issued = MAX(shares_issued - ?2, 0) WHERE id = ?1",
+                params![uuid(&tx.source_stock_class_id), tx.quantity_converted],
+            )?;
+            add_shares_issued(db, &tx.target_stock_class_id, tx.quantity_received)?;
+        }
+        DecodedTx::StockClassSplit(tx) => apply_split(db, issuer_id, &tx)?,
+        DecodedTx::StockClassConversionRatioAdjustment(tx) => {
+            db.execute(
+                "UPDATE stock_classes SET conversion_ratio = ?2, conversion_price = ?3
+                 WHERE id = ?1",
+                params![
+                    uuid(&tx.stock_class_id),
+                    tx.new_conversion_ratio,
+                    tx.new_conversion_price
+                ],
+            )?;
+        }
+        DecodedTx::ConvertibleIssuance(tx) => {
+            db.execute(
+                "INSERT INTO convertible_positions
+                 (issuer_id, security_id, stakeholder_id, investment_amount)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    issuer_id,
+                    uuid(&tx.security_id),
+                    uuid(&tx.stakeholder_id),
+                    tx.investment_amount
+                ],
+            )?;
+        }
+        DecodedTx::ConvertibleConversion(tx) => {
+            // The whole principal converts; the position account follows once
+            // the round is settled
+            db.execute(
+                "DELETE FROM convertible_positions WHERE issuer_id = ?1 AND security_id = ?2",
+                params![issuer_id, uuid(&tx.security_id)],
+            )?;
+            insert_stock_position(
+                db,
+                issuer_id,
+                &tx.resulting_security_id,
+                &tx.stakeholder_id,
+                &tx.stock_class_id,
+                tx.quantity_received,
+                tx.conversion_price,
+            )?;
+            add_shares_issued(db, &tx.stock_class_id, tx.quantity_received)?;
+        }
+        DecodedTx::EquityCompensationIssuance(tx) => {
+            db.execute(
+                "INSERT INTO equity_compensation_positions
+                 (issuer_id, security_id, stakeholder_id, stock_class_id, stock_plan_id, quantity)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    issuer_id,
+                    uuid(&tx.security_id),
+                    uuid(&tx.stakeholder_id),
+                    uuid(&tx.stock_class_id),
+                    optional_uuid(&tx.stock_plan_id),
+                    tx.quantity
+                ],
+            )?;
+        }
+        DecodedTx::EquityCompensationExercise(tx) => {
+            // Exercised options stay on-chain at zero, so the row is kept too
+            db.execute(
+                "UPDATE equity_compensation_positions SET quantity = quantity - ?3
+                 WHERE issuer_id = ?1 AND security_id = ?2",
+                params![issuer_id, uuid(&tx.equity_comp_security_id), tx.quantity],
+            )?;
+        }
+        DecodedTx::WarrantIssuance(tx) => {
+            db.execute(
+                "INSERT INTO warrant_positions
+                 (issuer_id, security_id, stakeholder_id, stock_class_id, quantity,
+                  exercise_price, expiration_date, exercise_trigger)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    issuer_id,
+                    uuid(&tx.security_id),
+                    uuid(&tx.stakeholder_id),
+                    uuid(&tx.stock_class_id),
+                    tx.quantity,
+                    tx.exercise_price,
+                    tx.expiration_date,
+                    trigger_name(tx.exercise_trigger)
+                ],
+            )?;
+        }
+        DecodedTx::WarrantExercise(tx) => {
+            reduce_position(
+                db,
+                "warrant_positions",
+                issuer_id,
+                &tx.warrant_security_id,
+                tx.quantity,
+            )?;
+            insert_stock_position(
+                db,
+                issuer_id,
+                &tx.resulting_stock_security_id,
+                &tx.stakeholder_id,
+                &tx.stock_class_id,
+                tx.shares_received,
+                tx.exercise_price,
+            )?;
+            add_shares_issued(db, &tx.stock_class_id, tx.shares_received)?;
+        }
+        DecodedTx::WarrantCancellation(tx) => {
+            reduce_position(
+                db,
+                "warrant_positions",
+                issuer_id,
+                &tx.security_id,
+                tx.quantity,
+            )?;
+        }
+        DecodedTx::WarrantExpiration(tx) => {
+            db.execute(
+                "DELETE FROM warrant_positions WHERE issuer_id = ?1 AND security_id = ?2",
+                params![issuer_id, uuid(&tx.security_id)],
+            )?;
+        }
+        DecodedTx::WarrantTransfer(tx) => {
+            // The transferee's warrant carries the same terms as the original
+            db.execute(
+                "INSERT INTO warrant_positions
+                 (issuer_id, security_id, stakeholder_id, stock_class_id, quantity,
+                  exercise_price, expiration_date, exercise_trigger)
+                 SELECT issuer_id, ?3, ?4, stock_class_id, ?5, exercise_price, expiration_date,
+                        exercise_trigger
+                 FROM warrant_positions WHERE issuer_id = ?1 AND security_id = ?2",
+                params![
+                    issuer_id,
+                    uuid(&tx.security_id),
+                    uuid(&tx.resulting_security_id),
+                    uuid(&tx.to_stakeholder_id),
+                    tx.quantity
+                ],
+            )?;
+            reduce_position(
+                db,
+                "warrant_positions",
+                issuer_id,
+                &tx.security_id,
+                tx.quantity,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Restates the class and all of its positions at once. On-chain positions
+/// follow lazily through `apply_stock_class_split`; the tables show them as
+/// they will be once it has run, with the class total the sum of its
+/// positions.
+fn apply_split(
+    db: &Transaction,
+    issuer_id: &str,
+    tx: &StockClassSplit,
+) -> Result<(), IndexerError> {
+    let split = SplitRatio {
+        numerator: tx.numerator,
+        denominator: tx.denominator,
+        fractional_share_policy: tx.fractional_share_policy,
+        ..SplitRatio::default()
+    };
+    let stock_class_id = uuid(&tx.stock_class_id);
+    let overflow = || IndexerError::Overflow("stock class split");
+
+    let positions: Vec<(String, u64, u64)> = db
+        .prepare(
+            "SELECT security_id, quantity, share_price FROM stock_positions
+             WHERE issuer_id = ?1 AND stock_class_id = ?2",
+        )?
+        .query_map(params![issuer_id, stock_class_id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?
+        .collect::<Result<_, _>>()?;
+
+    let mut shares_issued = 0u64;
+    for (security_id, quantity, share_price) in positions {
+        let quantity = split.apply_to_quantity(quantity).ok_or_else(overflow)?;
+        let retained = match split.fractional_share_policy {
+            FractionalSharePolicy::Retain => quantity,
+            FractionalSharePolicy::RoundDown => quantity - quantity % SHARE_DECIMALS_FACTOR,
+        };
+        shares_issued = shares_issued.checked_add(retained).ok_or_else(overflow)?;
+        db.execute(
+            "UPDATE stock_positions SET quantity = ?3, share_price = ?4
+             WHERE issuer_id = ?1 AND security_id = ?2",
+            params![
+                issuer_id,
+                security_id,
+                retained,
+                split.apply_to_price(share_price).ok_or_else(overflow)?
+            ],
+        )?;
+    }
+
+    let conversion_price: u64 = db.query_row(
+        "SELECT conversion_price FROM stock_classes WHERE id = ?1",
+        [&stock_class_id],
+        |row| row.get(0),
+    )?;
+    db.execute(
+        "UPDATE stock_classes
+         SET shares_authorized = ?2, shares_issued = ?3, price_per_share = ?4,
+             conversion_price = ?5, split_epoch = ?6
+         WHERE id = ?1",
+        params![
+            stock_class_id,
+            tx.new_shares_authorized,
+            shares_issued,
+            tx.new_price_per_share,
+            split
+                .apply_to_price(conversion_price)
+                .ok_or_else(overflow)?,
+            tx.split_epoch
+        ],
+    )?;
+    Ok(())
+}
+
+fn insert_stock_position(
+    db: &Transaction,
+    issuer_id: &str,
+    security_id: &[u8; 16],
+    stakeholder_id: &[u8; 16],
+    stock_class_id: &[u8; 16],
+    quantity: u64,
+    share_price: u64,
+) -> Result<(), IndexerError> {
+    db.execute(
+        "INSERT INTO stock_positions
+         (issuer_id, security_id, stakeholder_id, stock_class_id, quantity, share_price)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            issuer_id,
+            uuid(security_id),
+            uuid(stakeholder_id),
+            uuid(stock_class_id),
+            quantity,
+            share_price
+        ],
+    )?;
+    Ok(())
+}
+
+/// Subtracts `quantity` from a position, deleting it once empty as the
+/// program closes the account.
+fn reduce_position(
+    db: &Transaction,
+    table: &str,
+    issuer_id: &str,
+    security_id: &[u8; 16],
+    quantity: u64,
+) -> Result<(), IndexerError> {
+    let security_id = uuid(security_id);
+    db.execute(
+        &format!(
+            "UPDATE {} SET quantity = quantity - ?3 WHERE issuer_id = ?1 AND security_id = ?2",
+            table
+        ),
+        params![issuer_id, security_id, quantity],
+    )?;
+    db.execute(
+        &format!(
+            "DELETE FROM {} WHERE issuer_id = ?1 AND security_id = ?2 AND quantity <= 0",
+            table
+        ),
+        params![issuer_id, security_id],
+    )?;
+    Ok(())
+}
+
+fn add_shares_issued(
+    db: &Transaction,
+    stock_class_id: &[u8; 16],
+    quantity: u64,
+) -> Result<(), IndexerError> {
+    db.execute(
+        "UPDATE stock_classes SET shares_issued = shares_issued + ?2 WHERE id = ?1",
+        params![uuid(stock_class_id), quantity],
+    )?;
+    Ok(())
+}
+
+fn kind_name(kind: StockClassKind) -> &'static str {
+    match kind {
+        StockClassKind::Common => "common",
+        StockClassKind::Preferred => "preferred",
+    }
+}
+
+fn anti_dilution_name(anti_dilution_type: AntiDilutionType) -> &'static str {
+    match anti_dilution_type {
+        AntiDilutionType::None => "none",
+        AntiDilutionType::FullRatchet => "full_ratchet",
+        AntiDilutionType::BroadBasedWeightedAverage => "broad_based_weighted_average",
+        AntiDilutionType::NarrowBasedWeightedAverage => "narrow_based_weighted_average",
+    }
+}
+
+fn trigger_name(trigger: WarrantExerciseTrigger) -> &'static str {
+    match trigger {
+        WarrantExerciseTrigger::ElectiveAtWill => "elective_at_will",
+        WarrantExerciseTrigger::ElectiveInRange => "elective_in_range",
+        WarrantExerciseTrigger::ElectiveOnCondition => "elective_on_condition",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{index, EventSource};
+    use anchor_lang::prelude::Pubkey;
+    use anchor_lang::{AnchorSerialize, Event};
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use ocp_solana::state::StockClassTerms;
+    use rusqlite::types::Value;
+    use std::collections::VecDeque;
+
+    const ISSUER: [u8; 16] = [1; 16];
+    const COMMON: [u8; 16] = [2; 16];
+    const FOUNDER: [u8; 16] = [3; 16];
+
+    struct Transactions(VecDeque<LoggedTransaction>);
+
+    impl EventSource for Transactions {
+        fn next_transaction(&mut self) -> Result<Option<LoggedTransaction>, IndexerError> {
+            Ok(self.0.pop_front())
+        }
+    }
+
+    fn envelope(object_id: [u8; 16]) -> EventEnvelope {
+        EventEnvelope {
+            issuer_id: ISSUER,
+            unix_timestamp: 1_700_000_000,
+            slot: 0,
+            actor: Pubkey::default(),
+            object_id,
+        }
+    }
+
+    /// A successful ocp-solana transaction that emitted `event`.
+    fn logged(slot: u64, event: impl Event) -> LoggedTransaction {
+        LoggedTransaction {
+            signature: format!("signature-{}", slot),
+            slot,
+            logs: vec![
+                format!("Program {} invoke [1]", PROGRAM_ID),
+                format!("Program data: {}", STANDARD.encode(event.data())),
+                format!("Program {} success", PROGRAM_ID),
+            ],
+        }
+    }
+
+    fn stock_issuance(sequence: u64, prev_hash: [u8; 32]) -> (TxCreatedV2, [u8; 32]) {
+        let security_id = [10 + sequence as u8; 16];
+        let tx_data = StockIssued {
+            envelope: envelope(security_id),
+            stock_class_id: COMMON,
+            security_id,
+            stakeholder_id: FOUNDER,
+            quantity: 1_000 * SHARE_DECIMALS_FACTOR,
+            share_price: SHARE_DECIMALS_FACTOR / 100,
+        }
+        .try_to_vec()
+        .unwrap();
+        let hash = TxLog::chain(&prev_hash, TxType::StockIssuance, 2, &tx_data);
+        let event = TxCreatedV2 {
+            envelope: envelope(security_id),
+            tx_type: TxType::StockIssuance,
+            payload_version: 2,
+            tx_data,
+            sequence,
+            prev_hash,
+        };
+        (event, hash)
+    }
+
+    /// One issuer's history, one transaction per slot from slot 10.
+    fn history() -> Vec<LoggedTransaction> {
+        let (first, hash) = stock_issuance(1, [0; 32]);
+        let (second, _) = stock_issuance(2, hash);
+        vec![
+            logged(
+                10,
+                IssuerCreated {
+                    envelope: envelope(ISSUER),
+                    initial_shares_authorized: 1_000_000 * SHARE_DECIMALS_FACTOR,
+                },
+            ),
+            logged(
+                11,
+                StockClassCreated {
+                    envelope: envelope(COMMON),
+                    id: COMMON,
+                    class_type: "Common".into(),
+                    price_per_share: SHARE_DECIMALS_FACTOR / 100,
+                    initial_shares_authorized: 1_000_000 * SHARE_DECIMALS_FACTOR,
+                    terms: StockClassTerms::default(),
+                },
+            ),
+            logged(
+                12,
+                StakeholderCreated {
+                    envelope: envelope(FOUNDER),
+                    id: FOUNDER,
+                },
+            ),
+            logged(13, first),
+            logged(14, second),
+        ]
+    }
+
+    fn ingest(store: &mut Store, transactions: &[LoggedTransaction]) -> usize {
+        index(store, &mut Transactions(transactions.to_vec().into())).unwrap()
+    }
+
+    /// Every row of every table, in a stable order.
+    fn rows(store: &Store) -> Vec<(String, Vec<Vec<Value>>)> {
+        let connection = store.connection();
+        let mut tables = connection
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name")
+            .unwrap();
+        let tables: Vec<String> = tables
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        tables
+            .into_iter()
+            .map(|table| {
+                let mut statement = connection
+                    .prepare(&format!("SELECT * FROM {} ORDER BY 1, 2", table))
+                    .unwrap();
+                let columns = statement.column_count();
+                let rows = statement
+                    .query_map([], |row| (0..columns).map(|i| row.get(i)).collect())
+                    .unwrap()
+                    .collect::<Result<_, _>>()
+                    .unwrap();
+                (table, rows)
+            })
+            .collect()
+    }
+
+    fn count(store: &Store, table: &str) -> i64 {
+        store
+            .connection()
+            .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn ingesting_a_slot_range_twice_leaves_the_rows_unchanged() {
+        let mut store = Store::in_memory().unwrap();
+        assert_eq!(ingest(&mut store, &history()), 5);
+        let indexed = rows(&store);
+        assert_eq!(count(&store, "transactions"), 2);
+        assert_eq!(count(&store, "stock_positions"), 2);
+
+        assert_eq!(ingest(&mut store, &history()), 0);
+        assert_eq!(rows(&store), indexed);
+    }
+
+    #[test]
+    fn resumes_from_the_checkpoint() {
+        let path = std::env::temp_dir().join(format!("ocp-indexer-{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let history = history();
+
+        let mut store = Store::open(&path).unwrap();
+        assert_eq!(ingest(&mut store, &history[..4]), 4);
+        drop(store);
+
+        // A restarted indexer picks up after the checkpoint, and the source
+        // may hand it transactions it already indexed
+        let mut store = Store::open(&path).unwrap();
+        let checkpoint = store.checkpoint().unwrap().unwrap();
+        assert_eq!(checkpoint.slot, 13);
+        assert_eq!(checkpoint.signature, "signature-13");
+        assert_eq!(ingest(&mut store, &history[2..]), 1);
+        assert_eq!(store.checkpoint().unwrap().unwrap().slot, 14);
+
+        let mut uninterrupted = Store::in_memory().unwrap();
+        ingest(&mut uninterrupted, &history);
+        assert_eq!(rows(&store), rows(&uninterrupted));
+
+        drop(store);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,147 @@
+//! Conversions between caller-facing strings and program units, equivalent to
+//! `uuidToBytes16`, `bytes16ToUuid` and `stringNumberToBN` in `sdk/helpers.ts`.
+
+use crate::ClientError;
+use ocp_solana::state::SHARE_DECIMALS_FACTOR;
+use uuid::Uuid;
+
+const DECIMALS: usize = 6;
+
+pub fn uuid_to_bytes16(uuid: &str) -> Result<[u8; 16], ClientError> {
+    Uuid::parse_str(uuid)
+        .map(|uuid| *uuid.as_bytes())
+        .map_err(|_| ClientError::InvalidUuid(uuid.to_string()))
+}
+
+/// Formats 16 id bytes as a hyphenated lowercase UUID.
+pub fn bytes16_to_uuid(bytes: &[u8; 16]) -> String {
+    Uuid::from_bytes(*bytes).to_string()
+}
+
+/// Parses a decimal string into a 6-decimal fixed-point amount. Like
+/// `stringNumberToBN`, digits past the sixth decimal place are truncated.
+pub fn string_number_to_u64(amount: &str) -> Result<u64, ClientError> {
+    parse_fixed_point(amount, false)
+}
+
+/// Parses a decimal string into a 6-decimal fixed-point amount, rejecting
+/// amounts with non-zero digits past the sixth decimal place instead of
+/// truncating them.
+pub fn decimal_to_u64(amount: &str) -> Result<u64, ClientError> {
+    parse_fixed_point(amount, true)
+}
+
+fn parse_fixed_point(amount: &str, exact: bool) -> Result<u64, ClientError> {
+    let invalid = || ClientError::InvalidNumber(amount.to_string());
+    let (whole, decimal) = amount.split_once('.').unwrap_or((amount, ""));
+    if whole.is_empty()
+        || !whole.bytes().all(|b| b.is_ascii_digit())
+        || !decimal.bytes().all(|b| b.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+    if exact && decimal.trim_end_matches('0').len() > DECIMALS {
+        return Err(ClientError::TooManyDecimals(amount.to_string()));
+    }
+
+    let decimal = &decimal[..decimal.len().min(DECIMALS)];
+    let whole: u64 = whole
+        .parse()
+        .map_err(|_| ClientError::NumberTooLarge(amount.to_string()))?;
+    let decimal: u64 = format!("{:0<width$}", decimal, width = DECIMALS)
+        .parse()
+        .map_err(|_| invalid())?;
+    whole
+        .checked_mul(SHARE_DECIMALS_FACTOR)
+        .and_then(|amount| amount.checked_add(decimal))
+        .ok_or_else(|| ClientError::NumberTooLarge(amount.to_string()))
+}
+
+/// Formats a 6-decimal fixed-point amount without trailing zeros.
+pub fn u64_to_string_number(amount: u64) -> String {
+    let whole = amount / SHARE_DECIMALS_FACTOR;
+    let decimal = amount % SHARE_DECIMALS_FACTOR;
+    if decimal == 0 {
+        return whole.to_string();
+    }
+    let decimal = format!("{:0width$}", decimal, width = DECIMALS);
+    format!("{}.{}", whole, decimal.trim_end_matches('0'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_uuids() {
+        let uuid = "0b1e8c4a-3f2d-4e5b-9a6c-7d8e9f0a1b2c";
+        let bytes = uuid_to_bytes16(uuid).unwrap();
+        assert_eq!(bytes[0], 0x0b);
+        assert_eq!(bytes[15], 0x2c);
+        assert_eq!(bytes16_to_uuid(&bytes), uuid);
+
+        // Parsing accepts other spellings; formatting is always hyphenated lowercase
+        let bytes = uuid_to_bytes16("0B1E8C4A3F2D4E5B9A6C7D8E9F0A1B2C").unwrap();
+        assert_eq!(bytes16_to_uuid(&bytes), uuid);
+    }
+
+    #[test]
+    fn rejects_non_uuids() {
+        for id in ["", "issuer-1", "0b1e8c4a-3f2d-4e5b-9a6c-7d8e9f0a1b2"] {
+            assert_eq!(
+                uuid_to_bytes16(id),
+                Err(ClientError::InvalidUuid(id.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn round_trips_decimals() {
+        for (text, amount) in [
+            ("0", 0),
+            ("1", 1_000_000),
+            ("1.5", 1_500_000),
+            ("0.000001", 1),
+            ("1234.56789", 1_234_567_890),
+            ("18446744073709.551615", u64::MAX),
+        ] {
+            assert_eq!(decimal_to_u64(text), Ok(amount));
+            assert_eq!(string_number_to_u64(text), Ok(amount));
+            assert_eq!(u64_to_string_number(amount), text);
+        }
+    }
+
+    #[test]
+    fn formats_decimals_without_trailing_zeros() {
+        assert_eq!(u64_to_string_number(10_000_000), "10");
+        assert_eq!(u64_to_string_number(10_100_000), "10.1");
+        assert_eq!(u64_to_string_number(10_000_010), "10.00001");
+        assert_eq!(decimal_to_u64("10.100000000"), Ok(10_100_000));
+        assert_eq!(decimal_to_u64("10."), Ok(10_000_000));
+    }
+
+    #[test]
+    fn truncates_or_rejects_excess_precision() {
+        assert_eq!(string_number_to_u64("1.0000019"), Ok(1_000_001));
+        assert_eq!(
+            decimal_to_u64("1.0000019"),
+            Err(ClientError::TooManyDecimals("1.0000019".to_string()))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_decimals() {
+        for text in ["", ".5", "-1", "1.2.3", "1e6", " 1"] {
+            assert_eq!(
+                decimal_to_u64(text),
+                Err(ClientError::InvalidNumber(text.to_string()))
+            );
+        }
+        assert_eq!(
+            decimal_to_u64("18446744073709.551616"),
+            Err(ClientError::NumberTooLarge(
+                "18446744073709.551616".to_string()
+            ))
+        );
+    }
+}
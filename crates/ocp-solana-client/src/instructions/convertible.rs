@@ -0,0 +1,38 @@
+use crate::{build, pda, Instruction, InstructionBuilder, Pubkey};
+use anchor_lang::solana_program::system_program;
+use ocp_solana::{accounts, instruction};
+
+#[derive(Clone)]
+pub struct IssueConvertible {
+    pub issuer_id: [u8; 16],
+    pub security_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    pub investment_amount: u64,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for IssueConvertible {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::IssueConvertible {
+                issuer: pda::issuer(&self.issuer_id),
+                tx_log: pda::tx_log(&self.issuer_id),
+                cap_table_summary: pda::cap_table_summary(&self.issuer_id),
+                stakeholder: pda::stakeholder(&self.stakeholder_id),
+                stakeholder_holdings: pda::stakeholder_holdings(&self.stakeholder_id),
+                position: pda::convertible_position(&self.stakeholder_id, &self.security_id),
+                security_registration: pda::security_registration(
+                    &self.issuer_id,
+                    &self.security_id,
+                ),
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::IssueConvertible {
+                security_id: self.security_id,
+                investment_amount: self.investment_amount,
+            },
+        )
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{build, pda, Instruction, InstructionBuilder, Pubkey};
+use anchor_lang::solana_program::system_program;
+use ocp_solana::{accounts, instruction};
+
+#[derive(Clone)]
+pub struct IssueEquityCompensation {
+    pub issuer_id: [u8; 16],
+    pub security_id: [u8; 16],
+    pub stock_class_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    /// `None` for grants made outside any stock plan
+    pub stock_plan_id: Option<[u8; 16]>,
+    pub quantity: u64,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for IssueEquityCompensation {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::IssueEquityCompensation {
+                issuer: pda::issuer(&self.issuer_id),
+                tx_log: pda::tx_log(&self.issuer_id),
+                cap_table_summary: pda::cap_table_summary(&self.issuer_id),
+                stakeholder: pda::stakeholder(&self.stakeholder_id),
+                stakeholder_holdings: pda::stakeholder_holdings(&self.stakeholder_id),
+                stock_class: pda::stock_class(&self.stock_class_id),
+                stock_plan: self.stock_plan_id.as_ref().map(pda::stock_plan),
+                position: pda::equity_compensation_position(
+                    &self.security_id,
+                    &self.stock_class_id,
+                    &self.stakeholder_id,
+                ),
+                security_registration: pda::security_registration(
+                    &self.issuer_id,
+                    &self.security_id,
+                ),
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::IssueEquityCompensation {
+                security_id: self.security_id,
+                quantity: self.quantity,
+            },
+        )
+    }
+}
+
+/// Exercises into a stock position issued beforehand with `IssueStock`.
+#[derive(Clone)]
+pub struct ExerciseEquityCompensation {
+    pub issuer_id: [u8; 16],
+    pub equity_comp_security_id: [u8; 16],
+    pub resulting_stock_security_id: [u8; 16],
+    pub stock_class_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    pub quantity: u64,
+    /// The position's `rent_payer`, refunded if the account closes
+    pub rent_payer: Pubkey,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for ExerciseEquityCompensation {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::ExerciseEquityCompensation {
+                issuer: pda::issuer(&self.issuer_id),
+                tx_log: pda::tx_log(&self.issuer_id),
+                cap_table_summary: pda::cap_table_summary(&self.issuer_id),
+                stakeholder_holdings: pda::stakeholder_holdings(&self.stakeholder_id),
+                equity_position: pda::equity_compensation_position(
+                    &self.equity_comp_security_id,
+                    &self.stock_class_id,
+                    &self.stakeholder_id,
+                ),
+                stock_position: pda::stock_position(
+                    &self.stakeholder_id,
+                    &self.resulting_stock_security_id,
+                ),
+                rent_payer: self.rent_payer,
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+            },
+            instruction::ExerciseEquityCompensation {
+                quantity: self.quantity,
+            },
+        )
+    }
+}
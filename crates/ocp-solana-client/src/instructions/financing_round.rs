@@ -0,0 +1,224 @@
+use crate::{build, pda, Instruction, InstructionBuilder, Pubkey};
+use anchor_lang::solana_program::instruction::AccountMeta;
+use anchor_lang::solana_program::system_program;
+use ocp_solana::state::StockClassTerms;
+use ocp_solana::{accounts, instruction};
+
+#[derive(Clone)]
+pub struct OpenFinancingRound {
+    pub issuer_id: [u8; 16],
+    pub id: [u8; 16],
+    pub stock_class_id: [u8; 16],
+    pub class_type: String,
+    pub terms: StockClassTerms,
+    pub pre_money_valuation: u64,
+    pub target_pool_fraction: u64,
+    /// Required when `target_pool_fraction` is not zero
+    pub stock_plan_id: Option<[u8; 16]>,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for OpenFinancingRound {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::OpenFinancingRound {
+                issuer: pda::issuer(&self.issuer_id),
+                round: pda::priced_round(&self.issuer_id, &self.id),
+                stock_plan: self.stock_plan_id.as_ref().map(pda::stock_plan),
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::OpenFinancingRound {
+                id: self.id,
+                stock_class_id: self.stock_class_id,
+                class_type: self.class_type.clone(),
+                terms: self.terms,
+                pre_money_valuation: self.pre_money_valuation,
+                target_pool_fraction: self.target_pool_fraction,
+            },
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct AddRoundSubscription {
+    pub issuer_id: [u8; 16],
+    pub round_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    pub security_id: [u8; 16],
+    pub amount: u64,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for AddRoundSubscription {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::AddRoundSubscription {
+                issuer: pda::issuer(&self.issuer_id),
+                round: pda::priced_round(&self.issuer_id, &self.round_id),
+                stakeholder: pda::stakeholder(&self.stakeholder_id),
+                security_registration: pda::security_registration(
+                    &self.issuer_id,
+                    &self.security_id,
+                ),
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::AddRoundSubscription {
+                security_id: self.security_id,
+                amount: self.amount,
+            },
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct AddRoundConversion {
+    pub issuer_id: [u8; 16],
+    pub round_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    /// The convertible being converted
+    pub security_id: [u8; 16],
+    pub resulting_security_id: [u8; 16],
+    pub discount: u64,
+    pub valuation_cap: u64,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for AddRoundConversion {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::AddRoundConversion {
+                issuer: pda::issuer(&self.issuer_id),
+                round: pda::priced_round(&self.issuer_id, &self.round_id),
+                convertible_position: pda::convertible_position(
+                    &self.stakeholder_id,
+                    &self.security_id,
+                ),
+                convertible_registration: pda::security_registration(
+                    &self.issuer_id,
+                    &self.security_id,
+                ),
+                security_registration: pda::security_registration(
+                    &self.issuer_id,
+                    &self.resulting_security_id,
+                ),
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::AddRoundConversion {
+                resulting_security_id: self.resulting_security_id,
+                discount: self.discount,
+                valuation_cap: self.valuation_cap,
+            },
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct CancelFinancingRound {
+    pub issuer_id: [u8; 16],
+    pub round_id: [u8; 16],
+    /// Security ids the round reserved: its subscriptions', then its
+    /// conversions' resulting ids, in entry order
+    pub security_ids: Vec<[u8; 16]>,
+    /// Stakeholder and security id of each convertible the round claimed
+    pub convertibles: Vec<([u8; 16], [u8; 16])>,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for CancelFinancingRound {
+    fn instruction(&self) -> Instruction {
+        let mut instruction = build(
+            accounts::CancelFinancingRound {
+                issuer: pda::issuer(&self.issuer_id),
+                round: pda::priced_round(&self.issuer_id, &self.round_id),
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+            },
+            instruction::CancelFinancingRound {},
+        );
+        instruction.accounts.extend(
+            self.security_ids
+                .iter()
+                .map(|security_id| pda::security_registration(&self.issuer_id, security_id))
+                .chain(
+                    self.convertibles
+                        .iter()
+                        .map(|(stakeholder_id, security_id)| {
+                            pda::convertible_position(stakeholder_id, security_id)
+                        }),
+                )
+                .map(|address| AccountMeta::new(address, false)),
+        );
+        instruction
+    }
+}
+
+#[derive(Clone)]
+pub struct CloseFinancingRound {
+    pub issuer_id: [u8; 16],
+    pub round_id: [u8; 16],
+    pub stock_class_id: [u8; 16],
+    /// The plan the round was opened with, if any
+    pub stock_plan_id: Option<[u8; 16]>,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for CloseFinancingRound {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::CloseFinancingRound {
+                issuer: pda::issuer(&self.issuer_id),
+                tx_log: pda::tx_log(&self.issuer_id),
+                cap_table_summary: pda::cap_table_summary(&self.issuer_id),
+                round: pda::priced_round(&self.issuer_id, &self.round_id),
+                stock_class: pda::stock_class(&self.stock_class_id),
+                stock_plan: self.stock_plan_id.as_ref().map(pda::stock_plan),
+                board: pda::board(&self.issuer_id),
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::CloseFinancingRound {},
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct SettleFinancingRound {
+    pub issuer_id: [u8; 16],
+    pub round_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    /// The subscription's security id, or the conversion's resulting one
+    pub security_id: [u8; 16],
+    /// The converted convertible when settling a conversion
+    pub convertible_security_id: Option<[u8; 16]>,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for SettleFinancingRound {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::SettleFinancingRound {
+                issuer: pda::issuer(&self.issuer_id),
+                round: pda::priced_round(&self.issuer_id, &self.round_id),
+                stakeholder: pda::stakeholder(&self.stakeholder_id),
+                stakeholder_holdings: pda::stakeholder_holdings(&self.stakeholder_id),
+                position: pda::stock_position(&self.stakeholder_id, &self.security_id),
+                convertible_position: self.convertible_security_id.map(|security_id| {
+                    pda::convertible_position(&self.stakeholder_id, &security_id)
+                }),
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::SettleFinancingRound {
+                security_id: self.security_id,
+            },
+        )
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{build, pda, Instruction, InstructionBuilder, Pubkey};
+use anchor_lang::solana_program::system_program;
+use ocp_solana::state::ProposalAction;
+use ocp_solana::{accounts, instruction};
+
+#[derive(Clone)]
+pub struct InitializeBoard {
+    pub issuer_id: [u8; 16],
+    pub members: Vec<Pubkey>,
+    pub threshold: u8,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for InitializeBoard {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::InitializeBoard {
+                issuer: pda::issuer(&self.issuer_id),
+                board: pda::board(&self.issuer_id),
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeBoard {
+                members: self.members.clone(),
+                threshold: self.threshold,
+            },
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct CreateProposal {
+    pub issuer_id: [u8; 16],
+    pub id: [u8; 16],
+    pub action: ProposalAction,
+    /// Unix timestamp; zero when the action needs no stockholder approval
+    pub stockholder_approval_date: i64,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for CreateProposal {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::CreateProposal {
+                issuer: pda::issuer(&self.issuer_id),
+                board: pda::board(&self.issuer_id),
+                proposal: pda::proposal(&self.issuer_id, &self.id),
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::CreateProposal {
+                id: self.id,
+                action: self.action.clone(),
+                stockholder_approval_date: self.stockholder_approval_date,
+            },
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct ApproveProposal {
+    pub issuer_id: [u8; 16],
+    pub proposal_id: [u8; 16],
+    /// Board member signing the approval
+    pub member: Pubkey,
+}
+
+impl InstructionBuilder for ApproveProposal {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::ApproveProposal {
+                issuer: pda::issuer(&self.issuer_id),
+                board: pda::board(&self.issuer_id),
+                proposal: pda::proposal(&self.issuer_id, &self.proposal_id),
+                member: self.member,
+            },
+            instruction::ApproveProposal {},
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct ExecuteProposal {
+    pub issuer_id: [u8; 16],
+    pub proposal_id: [u8; 16],
+    /// The proposal's action, which decides the accounts to pass
+    pub action: ProposalAction,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for ExecuteProposal {
+    fn instruction(&self) -> Instruction {
+        let created_id = self.action.created_id();
+        build(
+            accounts::ExecuteProposal {
+                issuer: pda::issuer(&self.issuer_id),
+                board: pda::board(&self.issuer_id),
+                proposal: pda::proposal(&self.issuer_id, &self.proposal_id),
+                cap_table_summary: pda::cap_table_summary(&self.issuer_id),
+                stock_class: self
+                    .action
+                    .creates_stock_class()
+                    .then(|| pda::stock_class(&created_id)),
+                stock_plan: self
+                    .action
+                    .creates_stock_plan()
+                    .then(|| pda::stock_plan(&created_id)),
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::ExecuteProposal {},
+        )
+    }
+}
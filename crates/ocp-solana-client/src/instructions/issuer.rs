@@ -0,0 +1,133 @@
+use crate::{build, pda, Instruction, InstructionBuilder, Pubkey};
+use anchor_lang::solana_program::instruction::AccountMeta;
+use anchor_lang::solana_program::system_program;
+use ocp_solana::engine::OptionExercisePrice;
+use ocp_solana::{accounts, instruction};
+
+#[derive(Clone)]
+pub struct InitializeIssuer {
+    pub id: [u8; 16],
+    pub initial_shares_authorized: u64,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for InitializeIssuer {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::InitializeIssuer {
+                issuer: pda::issuer(&self.id),
+                cap_table_summary: pda::cap_table_summary(&self.id),
+                tx_log: pda::tx_log(&self.id),
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeIssuer {
+                id: self.id,
+                initial_shares_authorized: self.initial_shares_authorized,
+            },
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct AdjustAuthorizedShares {
+    pub issuer_id: [u8; 16],
+    pub new_shares_authorized: u64,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for AdjustAuthorizedShares {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::AdjustAuthorizedShares {
+                issuer: pda::issuer(&self.issuer_id),
+                cap_table_summary: pda::cap_table_summary(&self.issuer_id),
+                board: pda::board(&self.issuer_id),
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+            },
+            instruction::AdjustAuthorizedShares {
+                new_shares_authorized: self.new_shares_authorized,
+            },
+        )
+    }
+}
+
+/// Read-only; the summary comes back as the transaction's return data.
+#[derive(Clone)]
+pub struct GetCapTableSummary {
+    pub issuer_id: [u8; 16],
+}
+
+impl InstructionBuilder for GetCapTableSummary {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::GetCapTableSummary {
+                issuer: pda::issuer(&self.issuer_id),
+                cap_table_summary: pda::cap_table_summary(&self.issuer_id),
+            },
+            instruction::GetCapTableSummary {},
+        )
+    }
+}
+
+/// Read-only; the distribution comes back as the transaction's return data.
+#[derive(Clone)]
+pub struct GetLiquidationWaterfall {
+    pub issuer_id: [u8; 16],
+    pub exit_value: u64,
+    /// One per option position; the program does not record exercise prices
+    pub option_exercise_prices: Vec<OptionExercisePrice>,
+    /// Every stock class of the issuer
+    pub stock_class_ids: Vec<[u8; 16]>,
+    /// Every option and warrant position, plus the stock positions to break
+    /// the result down by stakeholder
+    pub positions: Vec<Pubkey>,
+}
+
+impl InstructionBuilder for GetLiquidationWaterfall {
+    fn instruction(&self) -> Instruction {
+        let mut instruction = build(
+            accounts::GetLiquidationWaterfall {
+                issuer: pda::issuer(&self.issuer_id),
+                cap_table_summary: pda::cap_table_summary(&self.issuer_id),
+            },
+            instruction::GetLiquidationWaterfall {
+                exit_value: self.exit_value,
+                option_exercise_prices: self.option_exercise_prices.clone(),
+            },
+        );
+        instruction.accounts.extend(
+            self.stock_class_ids
+                .iter()
+                .map(pda::stock_class)
+                .chain(self.positions.iter().copied())
+                .map(|address| AccountMeta::new_readonly(address, false)),
+        );
+        instruction
+    }
+}
+
+#[derive(Clone)]
+pub struct SnapshotCapTable {
+    pub issuer_id: [u8; 16],
+    pub id: [u8; 16],
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for SnapshotCapTable {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::SnapshotCapTable {
+                issuer: pda::issuer(&self.issuer_id),
+                cap_table_summary: pda::cap_table_summary(&self.issuer_id),
+                tx_log: pda::tx_log(&self.issuer_id),
+                snapshot: pda::cap_table_snapshot(&self.issuer_id, &self.id),
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::SnapshotCapTable { id: self.id },
+        )
+    }
+}
@@ -0,0 +1,27 @@
+//! Instruction builders, one module per `sdk/controllers` file.
+
+pub mod convertible;
+pub mod equity_compensation;
+pub mod financing_round;
+pub mod governance;
+pub mod issuer;
+pub mod multisig;
+pub mod stakeholder;
+pub mod stock;
+pub mod stock_class;
+pub mod stock_plan;
+pub mod voting;
+pub mod warrant;
+
+pub use convertible::*;
+pub use equity_compensation::*;
+pub use financing_round::*;
+pub use governance::*;
+pub use issuer::*;
+pub use multisig::*;
+pub use stakeholder::*;
+pub use stock::*;
+pub use stock_class::*;
+pub use stock_plan::*;
+pub use voting::*;
+pub use warrant::*;
@@ -0,0 +1,153 @@
+use crate::{build, pda, Instruction, InstructionBuilder, Pubkey};
+use anchor_lang::solana_program::instruction::AccountMeta;
+use anchor_lang::solana_program::system_program;
+use ocp_solana::state::TransactionAccount;
+use ocp_solana::{accounts, instruction};
+
+#[derive(Clone)]
+pub struct CreateMultisig {
+    pub issuer_id: [u8; 16],
+    pub members: Vec<Pubkey>,
+    pub threshold: u8,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for CreateMultisig {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::CreateMultisig {
+                issuer: pda::issuer(&self.issuer_id),
+                multisig: pda::multisig(&self.issuer_id),
+                multisig_signer: pda::multisig_signer(&self.issuer_id),
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::CreateMultisig {
+                members: self.members.clone(),
+                threshold: self.threshold,
+            },
+        )
+    }
+}
+
+/// Needs the multisig's authorization, like every issuer instruction.
+#[derive(Clone)]
+pub struct UpdateMultisig {
+    pub issuer_id: [u8; 16],
+    pub members: Vec<Pubkey>,
+    pub threshold: u8,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for UpdateMultisig {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::UpdateMultisig {
+                issuer: pda::issuer(&self.issuer_id),
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+            },
+            instruction::UpdateMultisig {
+                members: self.members.clone(),
+                threshold: self.threshold,
+            },
+        )
+    }
+}
+
+/// Stores `instruction` for approval. Build it with
+/// [`pda::multisig_signer`] as its authority.
+#[derive(Clone)]
+pub struct CreateMultisigTransaction {
+    pub issuer_id: [u8; 16],
+    /// The multisig's current nonce
+    pub nonce: u64,
+    pub instruction: Instruction,
+    /// Multisig member proposing the transaction; counts as its first approval
+    pub proposer: Pubkey,
+}
+
+impl InstructionBuilder for CreateMultisigTransaction {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::CreateMultisigTransaction {
+                issuer: pda::issuer(&self.issuer_id),
+                multisig: pda::multisig(&self.issuer_id),
+                transaction: pda::multisig_transaction(&self.issuer_id, self.nonce),
+                proposer: self.proposer,
+                system_program: system_program::ID,
+            },
+            instruction::CreateMultisigTransaction {
+                accounts: self
+                    .instruction
+                    .accounts
+                    .iter()
+                    .map(|meta| TransactionAccount {
+                        pubkey: meta.pubkey,
+                        is_signer: meta.is_signer,
+                        is_writable: meta.is_writable,
+                    })
+                    .collect(),
+                data: self.instruction.data.clone(),
+            },
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct ApproveMultisigTransaction {
+    pub issuer_id: [u8; 16],
+    pub nonce: u64,
+    /// Multisig member signing the approval
+    pub member: Pubkey,
+}
+
+impl InstructionBuilder for ApproveMultisigTransaction {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::ApproveMultisigTransaction {
+                issuer: pda::issuer(&self.issuer_id),
+                multisig: pda::multisig(&self.issuer_id),
+                transaction: pda::multisig_transaction(&self.issuer_id, self.nonce),
+                member: self.member,
+            },
+            instruction::ApproveMultisigTransaction {},
+        )
+    }
+}
+
+/// Invokes an approved transaction. The multisig signer PDA pays for any
+/// accounts the stored instruction creates, so it must be funded first.
+#[derive(Clone)]
+pub struct ExecuteMultisigTransaction {
+    pub issuer_id: [u8; 16],
+    pub nonce: u64,
+    /// Accounts of the stored instruction, as in the `MultisigTransaction`
+    pub accounts: Vec<TransactionAccount>,
+    pub executor: Pubkey,
+}
+
+impl InstructionBuilder for ExecuteMultisigTransaction {
+    fn instruction(&self) -> Instruction {
+        let mut instruction = build(
+            accounts::ExecuteMultisigTransaction {
+                issuer: pda::issuer(&self.issuer_id),
+                multisig: pda::multisig(&self.issuer_id),
+                transaction: pda::multisig_transaction(&self.issuer_id, self.nonce),
+                multisig_signer: pda::multisig_signer(&self.issuer_id),
+                ocp_program: ocp_solana::ID,
+                executor: self.executor,
+            },
+            instruction::ExecuteMultisigTransaction {},
+        );
+        // The signer PDA signs only inside the program
+        instruction
+            .accounts
+            .extend(self.accounts.iter().map(|account| AccountMeta {
+                pubkey: account.pubkey,
+                is_signer: false,
+                is_writable: account.is_writable,
+            }));
+        instruction
+    }
+}
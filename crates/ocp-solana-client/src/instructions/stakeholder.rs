@@ -0,0 +1,26 @@
+use crate::{build, pda, Instruction, InstructionBuilder, Pubkey};
+use anchor_lang::solana_program::system_program;
+use ocp_solana::{accounts, instruction};
+
+#[derive(Clone)]
+pub struct CreateStakeholder {
+    pub issuer_id: [u8; 16],
+    pub id: [u8; 16],
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for CreateStakeholder {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::CreateStakeholder {
+                issuer: pda::issuer(&self.issuer_id),
+                stakeholder: pda::stakeholder(&self.id),
+                stakeholder_holdings: pda::stakeholder_holdings(&self.id),
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::CreateStakeholder { id: self.id },
+        )
+    }
+}
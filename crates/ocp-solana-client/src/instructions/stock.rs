@@ -0,0 +1,86 @@
+use crate::{build, pda, Instruction, InstructionBuilder, Pubkey};
+use anchor_lang::solana_program::system_program;
+use ocp_solana::{accounts, instruction};
+
+#[derive(Clone)]
+pub struct IssueStock {
+    pub issuer_id: [u8; 16],
+    pub security_id: [u8; 16],
+    pub stock_class_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    pub quantity: u64,
+    pub share_price: u64,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for IssueStock {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::IssueStock {
+                stock_class: pda::stock_class(&self.stock_class_id),
+                issuer: pda::issuer(&self.issuer_id),
+                tx_log: pda::tx_log(&self.issuer_id),
+                cap_table_summary: pda::cap_table_summary(&self.issuer_id),
+                stakeholder: pda::stakeholder(&self.stakeholder_id),
+                stakeholder_holdings: pda::stakeholder_holdings(&self.stakeholder_id),
+                position: pda::stock_position(&self.stakeholder_id, &self.security_id),
+                security_registration: pda::security_registration(
+                    &self.issuer_id,
+                    &self.security_id,
+                ),
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::IssueStock {
+                security_id: self.security_id,
+                quantity: self.quantity,
+                share_price: self.share_price,
+            },
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct ConvertStock {
+    pub issuer_id: [u8; 16],
+    pub security_id: [u8; 16],
+    pub resulting_security_id: [u8; 16],
+    pub source_stock_class_id: [u8; 16],
+    pub target_stock_class_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    pub quantity: u64,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for ConvertStock {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::ConvertStock {
+                issuer: pda::issuer(&self.issuer_id),
+                tx_log: pda::tx_log(&self.issuer_id),
+                cap_table_summary: pda::cap_table_summary(&self.issuer_id),
+                stakeholder: pda::stakeholder(&self.stakeholder_id),
+                stakeholder_holdings: pda::stakeholder_holdings(&self.stakeholder_id),
+                source_stock_class: pda::stock_class(&self.source_stock_class_id),
+                target_stock_class: pda::stock_class(&self.target_stock_class_id),
+                source_position: pda::stock_position(&self.stakeholder_id, &self.security_id),
+                resulting_position: pda::stock_position(
+                    &self.stakeholder_id,
+                    &self.resulting_security_id,
+                ),
+                security_registration: pda::security_registration(
+                    &self.issuer_id,
+                    &self.resulting_security_id,
+                ),
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::ConvertStock {
+                resulting_security_id: self.resulting_security_id,
+                quantity: self.quantity,
+            },
+        )
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{build, pda, Instruction, InstructionBuilder, Pubkey};
+use anchor_lang::solana_program::instruction::AccountMeta;
+use anchor_lang::solana_program::system_program;
+use ocp_solana::state::{FractionalSharePolicy, StockClassTerms};
+use ocp_solana::{accounts, instruction};
+
+#[derive(Clone)]
+pub struct CreateStockClass {
+    pub issuer_id: [u8; 16],
+    pub id: [u8; 16],
+    pub class_type: String,
+    pub price_per_share: u64,
+    pub initial_shares_authorized: u64,
+    pub terms: StockClassTerms,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for CreateStockClass {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::CreateStockClass {
+                issuer: pda::issuer(&self.issuer_id),
+                cap_table_summary: pda::cap_table_summary(&self.issuer_id),
+                stock_class: pda::stock_class(&self.id),
+                board: pda::board(&self.issuer_id),
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::CreateStockClass {
+                id: self.id,
+                class_type: self.class_type.clone(),
+                price_per_share: self.price_per_share,
+                initial_shares_authorized: self.initial_shares_authorized,
+                terms: self.terms,
+            },
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct AdjustStockClassShares {
+    pub issuer_id: [u8; 16],
+    pub id: [u8; 16],
+    pub new_shares_authorized: u64,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for AdjustStockClassShares {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::AdjustStockClassShares {
+                issuer: pda::issuer(&self.issuer_id),
+                cap_table_summary: pda::cap_table_summary(&self.issuer_id),
+                stock_class: pda::stock_class(&self.id),
+                board: pda::board(&self.issuer_id),
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+            },
+            instruction::AdjustStockClassShares {
+                new_shares_authorized: self.new_shares_authorized,
+            },
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct SplitStockClass {
+    pub issuer_id: [u8; 16],
+    pub id: [u8; 16],
+    pub numerator: u64,
+    pub denominator: u64,
+    pub fractional_share_policy: FractionalSharePolicy,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for SplitStockClass {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::SplitStockClass {
+                issuer: pda::issuer(&self.issuer_id),
+                tx_log: pda::tx_log(&self.issuer_id),
+                cap_table_summary: pda::cap_table_summary(&self.issuer_id),
+                stock_class: pda::stock_class(&self.id),
+                board: pda::board(&self.issuer_id),
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+            },
+            instruction::SplitStockClass {
+                numerator: self.numerator,
+                denominator: self.denominator,
+                fractional_share_policy: self.fractional_share_policy,
+            },
+        )
+    }
+}
+
+/// Permissionless crank bringing `positions` up to date with the class's splits.
+#[derive(Clone)]
+pub struct ApplyStockClassSplit {
+    pub issuer_id: [u8; 16],
+    pub id: [u8; 16],
+    /// Stock position accounts of this class
+    pub positions: Vec<Pubkey>,
+}
+
+impl InstructionBuilder for ApplyStockClassSplit {
+    fn instruction(&self) -> Instruction {
+        let mut instruction = build(
+            accounts::ApplyStockClassSplit {
+                issuer: pda::issuer(&self.issuer_id),
+                cap_table_summary: pda::cap_table_summary(&self.issuer_id),
+                stock_class: pda::stock_class(&self.id),
+            },
+            instruction::ApplyStockClassSplit {},
+        );
+        instruction.accounts.extend(
+            self.positions
+                .iter()
+                .map(|position| AccountMeta::new(*position, false)),
+        );
+        instruction
+    }
+}
+
+#[derive(Clone)]
+pub struct RecordDilutiveIssuance {
+    pub issuer_id: [u8; 16],
+    pub id: [u8; 16],
+    pub new_issue_price: u64,
+    pub new_shares_issued: u64,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for RecordDilutiveIssuance {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::RecordDilutiveIssuance {
+                issuer: pda::issuer(&self.issuer_id),
+                cap_table_summary: pda::cap_table_summary(&self.issuer_id),
+                tx_log: pda::tx_log(&self.issuer_id),
+                stock_class: pda::stock_class(&self.id),
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+            },
+            instruction::RecordDilutiveIssuance {
+                new_issue_price: self.new_issue_price,
+                new_shares_issued: self.new_shares_issued,
+            },
+        )
+    }
+}
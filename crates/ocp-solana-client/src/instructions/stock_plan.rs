@@ -0,0 +1,61 @@
+use crate::{build, pda, Instruction, InstructionBuilder, Pubkey};
+use anchor_lang::solana_program::system_program;
+use ocp_solana::{accounts, instruction};
+
+#[derive(Clone)]
+pub struct CreateStockPlan {
+    pub issuer_id: [u8; 16],
+    pub id: [u8; 16],
+    /// Must not be empty; the first class is passed as the `stock_class` account
+    pub stock_class_ids: Vec<[u8; 16]>,
+    pub shares_reserved: u64,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for CreateStockPlan {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::CreateStockPlan {
+                issuer: pda::issuer(&self.issuer_id),
+                cap_table_summary: pda::cap_table_summary(&self.issuer_id),
+                stock_plan: pda::stock_plan(&self.id),
+                stock_class: pda::stock_class(&self.stock_class_ids[0]),
+                board: pda::board(&self.issuer_id),
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::CreateStockPlan {
+                id: self.id,
+                stock_class_ids: self.stock_class_ids.clone(),
+                shares_reserved: self.shares_reserved,
+            },
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct AdjustStockPlanShares {
+    pub issuer_id: [u8; 16],
+    pub id: [u8; 16],
+    pub new_shares_reserved: u64,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for AdjustStockPlanShares {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::AdjustStockPlanShares {
+                issuer: pda::issuer(&self.issuer_id),
+                cap_table_summary: pda::cap_table_summary(&self.issuer_id),
+                stock_plan: pda::stock_plan(&self.id),
+                board: pda::board(&self.issuer_id),
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+            },
+            instruction::AdjustStockPlanShares {
+                new_shares_reserved: self.new_shares_reserved,
+            },
+        )
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{build, pda, Instruction, InstructionBuilder, Pubkey};
+use anchor_lang::solana_program::instruction::AccountMeta;
+use anchor_lang::solana_program::system_program;
+use ocp_solana::state::VoteChoice;
+use ocp_solana::{accounts, instruction};
+
+/// Registers the wallet a stakeholder votes with; replaces any earlier one.
+#[derive(Clone)]
+pub struct SetStakeholderWallet {
+    pub issuer_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    pub wallet: Pubkey,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for SetStakeholderWallet {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::SetStakeholderWallet {
+                issuer: pda::issuer(&self.issuer_id),
+                stakeholder: pda::stakeholder(&self.stakeholder_id),
+                stakeholder_wallet: pda::stakeholder_wallet(&self.issuer_id, &self.stakeholder_id),
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::SetStakeholderWallet {
+                wallet: self.wallet,
+            },
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct CreateResolution {
+    pub issuer_id: [u8; 16],
+    pub id: [u8; 16],
+    /// Unix timestamps; holdings are recorded up to the record date and votes
+    /// cast after it until the deadline
+    pub record_date: i64,
+    pub voting_deadline: i64,
+    /// Classes whose approval is needed voting as a separate class
+    pub separate_class_ids: Vec<[u8; 16]>,
+    /// Every stock class of the issuer
+    pub stock_class_ids: Vec<[u8; 16]>,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for CreateResolution {
+    fn instruction(&self) -> Instruction {
+        let mut instruction = build(
+            accounts::CreateResolution {
+                issuer: pda::issuer(&self.issuer_id),
+                cap_table_summary: pda::cap_table_summary(&self.issuer_id),
+                resolution: pda::resolution(&self.issuer_id, &self.id),
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::CreateResolution {
+                id: self.id,
+                record_date: self.record_date,
+                voting_deadline: self.voting_deadline,
+                separate_class_ids: self.separate_class_ids.clone(),
+            },
+        );
+        instruction.accounts.extend(
+            self.stock_class_ids
+                .iter()
+                .map(|id| AccountMeta::new_readonly(pda::stock_class(id), false)),
+        );
+        instruction
+    }
+}
+
+/// Permissionless crank recording a stakeholder's shares before the record
+/// date.
+#[derive(Clone)]
+pub struct RecordVotingPower {
+    pub issuer_id: [u8; 16],
+    pub resolution_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    /// Every stock position the stakeholder holds
+    pub security_ids: Vec<[u8; 16]>,
+    /// The stock classes of those positions
+    pub stock_class_ids: Vec<[u8; 16]>,
+    pub cranker: Pubkey,
+}
+
+impl InstructionBuilder for RecordVotingPower {
+    fn instruction(&self) -> Instruction {
+        let mut instruction = build(
+            accounts::RecordVotingPower {
+                issuer: pda::issuer(&self.issuer_id),
+                resolution: pda::resolution(&self.issuer_id, &self.resolution_id),
+                stakeholder_holdings: pda::stakeholder_holdings(&self.stakeholder_id),
+                voter_record: pda::voter_record(
+                    &self.issuer_id,
+                    &self.resolution_id,
+                    &self.stakeholder_id,
+                ),
+                cranker: self.cranker,
+                system_program: system_program::ID,
+            },
+            instruction::RecordVotingPower {},
+        );
+        instruction.accounts.extend(
+            self.stock_class_ids
+                .iter()
+                .map(|id| AccountMeta::new_readonly(pda::stock_class(id), false))
+                .chain(self.security_ids.iter().map(|security_id| {
+                    AccountMeta::new_readonly(
+                        pda::stock_position(&self.stakeholder_id, security_id),
+                        false,
+                    )
+                })),
+        );
+        instruction
+    }
+}
+
+#[derive(Clone)]
+pub struct CastVote {
+    pub issuer_id: [u8; 16],
+    pub resolution_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    pub choice: VoteChoice,
+    /// The stakeholder's registered wallet, signing the vote
+    pub wallet: Pubkey,
+}
+
+impl InstructionBuilder for CastVote {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::CastVote {
+                issuer: pda::issuer(&self.issuer_id),
+                resolution: pda::resolution(&self.issuer_id, &self.resolution_id),
+                voter_record: pda::voter_record(
+                    &self.issuer_id,
+                    &self.resolution_id,
+                    &self.stakeholder_id,
+                ),
+                stakeholder_holdings: pda::stakeholder_holdings(&self.stakeholder_id),
+                stakeholder_wallet: pda::stakeholder_wallet(&self.issuer_id, &self.stakeholder_id),
+                wallet: self.wallet,
+            },
+            instruction::CastVote {
+                choice: self.choice,
+            },
+        )
+    }
+}
@@ -0,0 +1,219 @@
+use crate::{build, pda, Instruction, InstructionBuilder, Pubkey};
+use anchor_lang::solana_program::system_program;
+use ocp_solana::state::WarrantExerciseTrigger;
+use ocp_solana::{accounts, instruction};
+
+#[derive(Clone)]
+pub struct IssueWarrant {
+    pub issuer_id: [u8; 16],
+    pub security_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    pub stock_class_id: [u8; 16],
+    pub quantity: u64,
+    pub exercise_price: u64,
+    /// Unix timestamp
+    pub expiration_date: i64,
+    pub exercise_trigger: WarrantExerciseTrigger,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for IssueWarrant {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::IssueWarrant {
+                issuer: pda::issuer(&self.issuer_id),
+                tx_log: pda::tx_log(&self.issuer_id),
+                cap_table_summary: pda::cap_table_summary(&self.issuer_id),
+                stakeholder: pda::stakeholder(&self.stakeholder_id),
+                stakeholder_holdings: pda::stakeholder_holdings(&self.stakeholder_id),
+                stock_class: pda::stock_class(&self.stock_class_id),
+                position: pda::warrant_position(&self.stakeholder_id, &self.security_id),
+                security_registration: pda::security_registration(
+                    &self.issuer_id,
+                    &self.security_id,
+                ),
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::IssueWarrant {
+                security_id: self.security_id,
+                quantity: self.quantity,
+                exercise_price: self.exercise_price,
+                expiration_date: self.expiration_date,
+                exercise_trigger: self.exercise_trigger,
+            },
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct ExerciseWarrant {
+    pub issuer_id: [u8; 16],
+    pub warrant_security_id: [u8; 16],
+    pub resulting_stock_security_id: [u8; 16],
+    pub stock_class_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    pub quantity: u64,
+    /// Set for a net (cashless) exercise at this fair market value per share
+    pub fair_market_value: Option<u64>,
+    /// The position's `rent_payer`, refunded if the account closes
+    pub rent_payer: Pubkey,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for ExerciseWarrant {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::ExerciseWarrant {
+                issuer: pda::issuer(&self.issuer_id),
+                tx_log: pda::tx_log(&self.issuer_id),
+                cap_table_summary: pda::cap_table_summary(&self.issuer_id),
+                stakeholder: pda::stakeholder(&self.stakeholder_id),
+                stakeholder_holdings: pda::stakeholder_holdings(&self.stakeholder_id),
+                stock_class: pda::stock_class(&self.stock_class_id),
+                warrant_position: pda::warrant_position(
+                    &self.stakeholder_id,
+                    &self.warrant_security_id,
+                ),
+                warrant_registration: pda::security_registration(
+                    &self.issuer_id,
+                    &self.warrant_security_id,
+                ),
+                stock_position: pda::stock_position(
+                    &self.stakeholder_id,
+                    &self.resulting_stock_security_id,
+                ),
+                security_registration: pda::security_registration(
+                    &self.issuer_id,
+                    &self.resulting_stock_security_id,
+                ),
+                rent_payer: self.rent_payer,
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::ExerciseWarrant {
+                resulting_security_id: self.resulting_stock_security_id,
+                quantity: self.quantity,
+                fair_market_value: self.fair_market_value,
+            },
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct CancelWarrant {
+    pub issuer_id: [u8; 16],
+    pub security_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    pub quantity: u64,
+    /// The position's `rent_payer`, refunded if the account closes
+    pub rent_payer: Pubkey,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for CancelWarrant {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::CancelWarrant {
+                issuer: pda::issuer(&self.issuer_id),
+                tx_log: pda::tx_log(&self.issuer_id),
+                cap_table_summary: pda::cap_table_summary(&self.issuer_id),
+                stakeholder: pda::stakeholder(&self.stakeholder_id),
+                stakeholder_holdings: pda::stakeholder_holdings(&self.stakeholder_id),
+                warrant_position: pda::warrant_position(&self.stakeholder_id, &self.security_id),
+                security_registration: pda::security_registration(
+                    &self.issuer_id,
+                    &self.security_id,
+                ),
+                rent_payer: self.rent_payer,
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+            },
+            instruction::CancelWarrant {
+                quantity: self.quantity,
+            },
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct ExpireWarrant {
+    pub issuer_id: [u8; 16],
+    pub security_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    /// The position's `rent_payer`, refunded when the account closes
+    pub rent_payer: Pubkey,
+    pub cranker: Pubkey,
+}
+
+impl InstructionBuilder for ExpireWarrant {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::ExpireWarrant {
+                issuer: pda::issuer(&self.issuer_id),
+                tx_log: pda::tx_log(&self.issuer_id),
+                cap_table_summary: pda::cap_table_summary(&self.issuer_id),
+                warrant_position: pda::warrant_position(&self.stakeholder_id, &self.security_id),
+                security_registration: pda::security_registration(
+                    &self.issuer_id,
+                    &self.security_id,
+                ),
+                stakeholder_holdings: pda::stakeholder_holdings(&self.stakeholder_id),
+                rent_payer: self.rent_payer,
+                cranker: self.cranker,
+            },
+            instruction::ExpireWarrant {},
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct TransferWarrant {
+    pub issuer_id: [u8; 16],
+    pub security_id: [u8; 16],
+    pub resulting_security_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    pub new_stakeholder_id: [u8; 16],
+    pub quantity: u64,
+    /// The position's `rent_payer`, refunded if the account closes
+    pub rent_payer: Pubkey,
+    pub authority: Pubkey,
+}
+
+impl InstructionBuilder for TransferWarrant {
+    fn instruction(&self) -> Instruction {
+        build(
+            accounts::TransferWarrant {
+                issuer: pda::issuer(&self.issuer_id),
+                tx_log: pda::tx_log(&self.issuer_id),
+                stakeholder: pda::stakeholder(&self.stakeholder_id),
+                stakeholder_holdings: pda::stakeholder_holdings(&self.stakeholder_id),
+                new_stakeholder: pda::stakeholder(&self.new_stakeholder_id),
+                new_stakeholder_holdings: pda::stakeholder_holdings(&self.new_stakeholder_id),
+                warrant_position: pda::warrant_position(&self.stakeholder_id, &self.security_id),
+                warrant_registration: pda::security_registration(
+                    &self.issuer_id,
+                    &self.security_id,
+                ),
+                new_warrant_position: pda::warrant_position(
+                    &self.new_stakeholder_id,
+                    &self.resulting_security_id,
+                ),
+                security_registration: pda::security_registration(
+                    &self.issuer_id,
+                    &self.resulting_security_id,
+                ),
+                rent_payer: self.rent_payer,
+                multisig: pda::multisig(&self.issuer_id),
+                authority: self.authority,
+                system_program: system_program::ID,
+            },
+            instruction::TransferWarrant {
+                resulting_security_id: self.resulting_security_id,
+                quantity: self.quantity,
+            },
+        )
+    }
+}
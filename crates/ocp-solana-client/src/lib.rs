@@ -0,0 +1,57 @@
+//! Rust client for the ocp-solana program, mirroring `sdk/controllers`.
+//!
+//! Each instruction has a builder in [`instructions`] that takes ids and
+//! amounts in program units and derives every account from the [`pda`] seed
+//! schemes. [`convert`] turns the UUID and decimal strings used by callers
+//! into those units.
+
+pub mod convert;
+pub mod instructions;
+pub mod pda;
+
+pub use anchor_lang::solana_program::instruction::Instruction;
+pub use anchor_lang::solana_program::pubkey::Pubkey;
+pub use convert::{
+    bytes16_to_uuid, decimal_to_u64, string_number_to_u64, u64_to_string_number, uuid_to_bytes16,
+};
+pub use ocp_solana::ID as PROGRAM_ID;
+
+use anchor_lang::solana_program::instruction::AccountMeta;
+use anchor_lang::{InstructionData, ToAccountMetas};
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum ClientError {
+    #[error("`{0}` is not a UUID")]
+    InvalidUuid(String),
+    #[error("`{0}` is not a non-negative decimal")]
+    InvalidNumber(String),
+    #[error("`{0}` does not fit in 6-decimal fixed point")]
+    NumberTooLarge(String),
+    #[error("`{0}` has more than 6 decimal places")]
+    TooManyDecimals(String),
+}
+
+/// A typed description of one ocp-solana instruction.
+pub trait InstructionBuilder {
+    fn instruction(&self) -> Instruction;
+
+    /// The instruction with `cosigners` appended as signers, so that together
+    /// with the authority they meet the issuer multisig's threshold.
+    fn cosigned_instruction(&self, cosigners: &[Pubkey]) -> Instruction {
+        let mut instruction = self.instruction();
+        instruction.accounts.extend(
+            cosigners
+                .iter()
+                .map(|cosigner| AccountMeta::new_readonly(*cosigner, true)),
+        );
+        instruction
+    }
+}
+
+pub(crate) fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ocp_solana::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
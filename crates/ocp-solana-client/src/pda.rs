@@ -0,0 +1,109 @@
+//! Program-derived addresses of ocp-solana accounts, one function per seed scheme.
+
+use anchor_lang::prelude::Pubkey;
+
+fn find(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &ocp_solana::ID).0
+}
+
+pub fn issuer(issuer_id: &[u8; 16]) -> Pubkey {
+    find(&[b"issuer", issuer_id])
+}
+
+pub fn cap_table_summary(issuer_id: &[u8; 16]) -> Pubkey {
+    find(&[b"cap_table_summary", issuer_id])
+}
+
+pub fn tx_log(issuer_id: &[u8; 16]) -> Pubkey {
+    find(&[b"tx_log", issuer_id])
+}
+
+pub fn cap_table_snapshot(issuer_id: &[u8; 16], snapshot_id: &[u8; 16]) -> Pubkey {
+    find(&[b"cap_table_snapshot", issuer_id, snapshot_id])
+}
+
+pub fn board(issuer_id: &[u8; 16]) -> Pubkey {
+    find(&[b"board", issuer_id])
+}
+
+pub fn proposal(issuer_id: &[u8; 16], proposal_id: &[u8; 16]) -> Pubkey {
+    find(&[b"proposal", issuer_id, proposal_id])
+}
+
+pub fn multisig(issuer_id: &[u8; 16]) -> Pubkey {
+    find(&[b"multisig", issuer_id])
+}
+
+/// Signs instructions executed from an approved multisig transaction.
+pub fn multisig_signer(issuer_id: &[u8; 16]) -> Pubkey {
+    find(&[b"multisig_signer", issuer_id])
+}
+
+pub fn multisig_transaction(issuer_id: &[u8; 16], nonce: u64) -> Pubkey {
+    find(&[b"multisig_transaction", issuer_id, &nonce.to_le_bytes()])
+}
+
+pub fn priced_round(issuer_id: &[u8; 16], round_id: &[u8; 16]) -> Pubkey {
+    find(&[b"priced_round", issuer_id, round_id])
+}
+
+pub fn resolution(issuer_id: &[u8; 16], resolution_id: &[u8; 16]) -> Pubkey {
+    find(&[b"resolution", issuer_id, resolution_id])
+}
+
+pub fn voter_record(
+    issuer_id: &[u8; 16],
+    resolution_id: &[u8; 16],
+    stakeholder_id: &[u8; 16],
+) -> Pubkey {
+    find(&[b"voter_record", issuer_id, resolution_id, stakeholder_id])
+}
+
+pub fn stock_class(stock_class_id: &[u8; 16]) -> Pubkey {
+    find(&[b"stock_class", stock_class_id])
+}
+
+pub fn stock_plan(stock_plan_id: &[u8; 16]) -> Pubkey {
+    find(&[b"stock_plan", stock_plan_id])
+}
+
+pub fn stakeholder(stakeholder_id: &[u8; 16]) -> Pubkey {
+    find(&[b"stakeholder", stakeholder_id])
+}
+
+pub fn stakeholder_holdings(stakeholder_id: &[u8; 16]) -> Pubkey {
+    find(&[b"stakeholder_holdings", stakeholder_id])
+}
+
+pub fn stakeholder_wallet(issuer_id: &[u8; 16], stakeholder_id: &[u8; 16]) -> Pubkey {
+    find(&[b"stakeholder_wallet", issuer_id, stakeholder_id])
+}
+
+pub fn stock_position(stakeholder_id: &[u8; 16], security_id: &[u8; 16]) -> Pubkey {
+    find(&[b"stock_position", stakeholder_id, security_id])
+}
+
+pub fn convertible_position(stakeholder_id: &[u8; 16], security_id: &[u8; 16]) -> Pubkey {
+    find(&[b"convertible_position", stakeholder_id, security_id])
+}
+
+pub fn equity_compensation_position(
+    security_id: &[u8; 16],
+    stock_class_id: &[u8; 16],
+    stakeholder_id: &[u8; 16],
+) -> Pubkey {
+    find(&[
+        b"equity_compensation_position",
+        security_id,
+        stock_class_id,
+        stakeholder_id,
+    ])
+}
+
+pub fn warrant_position(stakeholder_id: &[u8; 16], security_id: &[u8; 16]) -> Pubkey {
+    find(&[b"warrant_position", stakeholder_id, security_id])
+}
+
+pub fn security_registration(issuer_id: &[u8; 16], security_id: &[u8; 16]) -> Pubkey {
+    find(&[b"security", issuer_id, security_id])
+}
@@ -0,0 +1,1281 @@
+//! In-memory cap table of a single issuer.
+//!
+//! Each method mirrors one instruction: it checks the accounts the instruction
+//! would load, then applies the same [`rules`] the handler does. Methods are
+//! atomic, so a rejected operation leaves the table unchanged.
+
+use super::financing_round::{model_financing_round, FinancingRound, RoundInput, RoundModel};
+use super::rules::{self, ConversionAdjustment};
+use super::waterfall::{liquidation_waterfall, LiquidationDistribution, WaterfallInput};
+use super::{ensure, CoreError};
+use crate::state::*;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
+use alloc::vec::Vec;
+use anchor_lang::prelude::Pubkey;
+
+#[cfg(feature = "decode")]
+use crate::events::TxCreatedV2;
+#[cfg(feature = "decode")]
+use crate::payload::{decode_tx_created, DecodedTx, TxDecodeError};
+
+type Id = [u8; 16];
+
+/// Every account of one issuer, keyed by OCF id. Positions are keyed by
+/// security id, which the program keeps unique within an issuer.
+#[derive(Clone, Default)]
+pub struct CapTable {
+    pub issuer: Issuer,
+    pub cap_table_summary: CapTableSummary,
+    pub tx_log: TxLog,
+    pub stock_classes: BTreeMap<Id, StockClass>,
+    pub stakeholders: BTreeSet<Id>,
+    pub stock_plans: BTreeMap<Id, StockPlan>,
+    pub stock_positions: BTreeMap<Id, StockActivePosition>,
+    pub convertible_positions: BTreeMap<Id, ConvertibleActivePosition>,
+    pub equity_compensation_positions: BTreeMap<Id, EquityCompensationActivePosition>,
+    pub warrant_positions: BTreeMap<Id, WarrantActivePosition>,
+    /// Every security id registered so far. Registrations outlive the
+    /// positions they point to, so a closed position's id stays taken.
+    pub securities: BTreeSet<Id>,
+}
+
+fn get<'a, T>(
+    map: &'a BTreeMap<Id, T>,
+    id: &Id,
+    account: &'static str,
+) -> Result<&'a T, CoreError> {
+    map.get(id).ok_or(CoreError::AccountNotFound(account))
+}
+
+impl CapTable {
+    pub fn new(id: Id, initial_shares_authorized: u64) -> Result<Self, CoreError> {
+        let mut cap_table = Self::default();
+        rules::initialize_issuer(
+            &mut cap_table.issuer,
+            &mut cap_table.cap_table_summary,
+            id,
+            initial_shares_authorized,
+        )?;
+        cap_table.tx_log.issuer_id = id;
+        Ok(cap_table)
+    }
+
+    pub fn adjust_authorized_shares(&mut self, new_shares_authorized: u64) {
+        rules::adjust_authorized_shares(
+            &mut self.issuer,
+            &mut self.cap_table_summary,
+            new_shares_authorized,
+        );
+    }
+
+    pub fn create_stock_class(
+        &mut self,
+        id: Id,
+        class_type: String,
+        price_per_share: u64,
+        initial_shares_authorized: u64,
+        terms: StockClassTerms,
+    ) -> Result<(), CoreError> {
+        ensure(
+            !self.stock_classes.contains_key(&id),
+            CoreError::AccountInUse("stock class"),
+        )?;
+
+        let mut stock_class = StockClass::default();
+        rules::create_stock_class(
+            &mut stock_class,
+            &mut self.cap_table_summary,
+            id,
+            class_type,
+            price_per_share,
+            initial_shares_authorized,
+            terms,
+        )?;
+        self.stock_classes.insert(id, stock_class);
+        Ok(())
+    }
+
+    pub fn adjust_stock_class_shares(
+        &mut self,
+        stock_class_id: Id,
+        new_shares_authorized: u64,
+    ) -> Result<(), CoreError> {
+        let mut stock_class = get(&self.stock_classes, &stock_class_id, "stock class")?.clone();
+        let mut cap_table_summary = self.cap_table_summary.clone();
+        rules::adjust_stock_class_shares(
+            &mut stock_class,
+            &mut cap_table_summary,
+            new_shares_authorized,
+        )?;
+
+        self.stock_classes.insert(stock_class_id, stock_class);
+        self.cap_table_summary = cap_table_summary;
+        Ok(())
+    }
+
+    pub fn split_stock_class(
+        &mut self,
+        stock_class_id: Id,
+        numerator: u64,
+        denominator: u64,
+        fractional_share_policy: FractionalSharePolicy,
+    ) -> Result<(), CoreError> {
+        let mut stock_class = get(&self.stock_classes, &stock_class_id, "stock class")?.clone();
+        let mut cap_table_summary = self.cap_table_summary.clone();
+        rules::split_stock_class(
+            &mut stock_class,
+            &mut cap_table_summary,
+            numerator,
+            denominator,
+            fractional_share_policy,
+        )?;
+
+        self.stock_classes.insert(stock_class_id, stock_class);
+        self.cap_table_summary = cap_table_summary;
+        Ok(())
+    }
+
+    /// The `apply_stock_class_split` crank for the given stock positions.
+    pub fn apply_stock_class_split(
+        &mut self,
+        stock_class_id: Id,
+        security_ids: &[Id],
+    ) -> Result<(), CoreError> {
+        let mut stock_class = get(&self.stock_classes, &stock_class_id, "stock class")?.clone();
+        let mut positions = Vec::with_capacity(security_ids.len());
+        for security_id in security_ids {
+            let mut position = get(&self.stock_positions, security_id, "stock position")?.clone();
+            rules::apply_pending_splits(&mut stock_class, &mut position)?;
+            positions.push(position);
+        }
+        let mut cap_table_summary = self.cap_table_summary.clone();
+        cap_table_summary.sync_stock_class(&stock_class)?;
+
+        for position in positions {
+            self.stock_positions.insert(position.security_id, position);
+        }
+        self.stock_classes.insert(stock_class_id, stock_class);
+        self.cap_table_summary = cap_table_summary;
+        Ok(())
+    }
+
+    /// Runs the split crank over every stock position. The crank emits no
+    /// event, so two cap tables are only comparable once both are brought up
+    /// to date this way.
+    pub fn apply_all_pending_splits(&mut self) -> Result<(), CoreError> {
+        let mut positions_by_class = BTreeMap::<Id, Vec<Id>>::new();
+        for position in self.stock_positions.values() {
+            positions_by_class
+                .entry(position.stock_class_id)
+                .or_default()
+                .push(position.security_id);
+        }
+        for (stock_class_id, security_ids) in positions_by_class {
+            self.apply_stock_class_split(stock_class_id, &security_ids)?;
+        }
+        Ok(())
+    }
+
+    /// Issued shares of every stock class other than `stock_class_id`, the
+    /// broad-based part of an anti-dilution basis.
+    pub fn other_shares_issued(&self, stock_class_id: Id) -> Result<u64, CoreError> {
+        self.stock_classes
+            .values()
+            .filter(|stock_class| stock_class.id != stock_class_id)
+            .try_fold(0u64, |total, stock_class| {
+                total.checked_add(stock_class.shares_issued)
+            })
+            .ok_or(CoreError::StockClass(
+                crate::errors::StockClassError::ArithmeticOverflow,
+            ))
+    }
+
+    /// Splits `exit_value` over the table as [`liquidation_waterfall`] does,
+    /// with the classes in summary order.
+    pub fn liquidation_waterfall(
+        &self,
+        exit_value: u64,
+        option_exercise_prices: BTreeMap<Id, u64>,
+        now: i64,
+    ) -> Result<LiquidationDistribution, CoreError> {
+        let stock_classes = self
+            .cap_table_summary
+            .stock_classes
+            .iter()
+            .map(|summary| get(&self.stock_classes, &summary.stock_class_id, "stock class"))
+            .collect::<Result<_, _>>()?;
+        let input = WaterfallInput {
+            stock_classes,
+            stock_positions: self.stock_positions.values().collect(),
+            equity_compensation_positions: self.equity_compensation_positions.values().collect(),
+            warrant_positions: self.warrant_positions.values().collect(),
+            option_exercise_prices,
+        };
+        liquidation_waterfall(&input, exit_value, now)
+    }
+
+    /// Models `round` over the table as [`model_financing_round`] does.
+    pub fn model_financing_round(&self, round: &FinancingRound) -> Result<RoundModel, CoreError> {
+        let input = RoundInput {
+            cap_table_summary: &self.cap_table_summary,
+            stock_classes: self.stock_classes.values().collect(),
+            stock_positions: self.stock_positions.values().collect(),
+            convertible_positions: self.convertible_positions.values().collect(),
+            equity_compensation_positions: self.equity_compensation_positions.values().collect(),
+            warrant_positions: self.warrant_positions.values().collect(),
+        };
+        model_financing_round(&input, round)
+    }
+
+    pub fn record_dilutive_issuance(
+        &mut self,
+        stock_class_id: Id,
+        other_shares_issued: u64,
+        new_issue_price: u64,
+        new_shares_issued: u64,
+    ) -> Result<ConversionAdjustment, CoreError> {
+        let mut stock_class = get(&self.stock_classes, &stock_class_id, "stock class")?.clone();
+        let adjustment = rules::record_dilutive_issuance(
+            &mut stock_class,
+            other_shares_issued,
+            new_issue_price,
+            new_shares_issued,
+        )?;
+
+        self.stock_classes.insert(stock_class_id, stock_class);
+        Ok(adjustment)
+    }
+
+    pub fn create_stakeholder(&mut self, id: Id) -> Result<(), CoreError> {
+        ensure(
+            self.stakeholders.insert(id),
+            CoreError::AccountInUse("stakeholder"),
+        )
+    }
+
+    pub fn create_stock_plan(
+        &mut self,
+        id: Id,
+        stock_class_ids: Vec<Id>,
+        shares_reserved: u64,
+    ) -> Result<(), CoreError> {
+        ensure(
+            !self.stock_plans.contains_key(&id),
+            CoreError::AccountInUse("stock plan"),
+        )?;
+
+        let mut stock_plan = StockPlan::default();
+        let mut cap_table_summary = self.cap_table_summary.clone();
+        rules::create_stock_plan(
+            &mut stock_plan,
+            &mut cap_table_summary,
+            id,
+            stock_class_ids,
+            shares_reserved,
+        )?;
+
+        self.stock_plans.insert(id, stock_plan);
+        self.cap_table_summary = cap_table_summary;
+        Ok(())
+    }
+
+    pub fn adjust_stock_plan_shares(
+        &mut self,
+        stock_plan_id: Id,
+        new_shares_reserved: u64,
+    ) -> Result<(), CoreError> {
+        let mut stock_plan = get(&self.stock_plans, &stock_plan_id, "stock plan")?.clone();
+        let mut cap_table_summary = self.cap_table_summary.clone();
+        rules::adjust_stock_plan_shares(
+            &mut stock_plan,
+            &mut cap_table_summary,
+            new_shares_reserved,
+        )?;
+
+        self.stock_plans.insert(stock_plan_id, stock_plan);
+        self.cap_table_summary = cap_table_summary;
+        Ok(())
+    }
+
+    fn ensure_stakeholder(&self, stakeholder_id: &Id) -> Result<(), CoreError> {
+        ensure(
+            self.stakeholders.contains(stakeholder_id),
+            CoreError::AccountNotFound("stakeholder"),
+        )
+    }
+
+    fn ensure_unregistered(&self, security_id: &Id) -> Result<(), CoreError> {
+        ensure(
+            !self.securities.contains(security_id),
+            CoreError::AccountInUse("security registration"),
+        )
+    }
+
+    pub fn issue_stock(
+        &mut self,
+        stock_class_id: Id,
+        stakeholder_id: Id,
+        security_id: Id,
+        quantity: u64,
+        share_price: u64,
+    ) -> Result<(), CoreError> {
+        let mut stock_class = get(&self.stock_classes, &stock_class_id, "stock class")?.clone();
+        self.ensure_stakeholder(&stakeholder_id)?;
+        self.ensure_unregistered(&security_id)?;
+
+        let mut position = StockActivePosition::default();
+        let mut cap_table_summary = self.cap_table_summary.clone();
+        rules::issue_stock(
+            &mut stock_class,
+            &mut cap_table_summary,
+            &mut position,
+            stakeholder_id,
+            security_id,
+            quantity,
+            share_price,
+        )?;
+
+        self.stock_classes.insert(stock_class_id, stock_class);
+        self.stock_positions.insert(security_id, position);
+        self.securities.insert(security_id);
+        self.cap_table_summary = cap_table_summary;
+        Ok(())
+    }
+
+    /// Returns the quantity received.
+    pub fn convert_stock(
+        &mut self,
+        security_id: Id,
+        target_stock_class_id: Id,
+        resulting_security_id: Id,
+        quantity: u64,
+    ) -> Result<u64, CoreError> {
+        let mut source_position =
+            get(&self.stock_positions, &security_id, "stock position")?.clone();
+        let mut source_stock_class = get(
+            &self.stock_classes,
+            &source_position.stock_class_id,
+            "stock class",
+        )?
+        .clone();
+        let mut target_stock_class =
+            get(&self.stock_classes, &target_stock_class_id, "stock class")?.clone();
+        self.ensure_unregistered(&resulting_security_id)?;
+
+        let mut resulting_position = StockActivePosition::default();
+        let mut cap_table_summary = self.cap_table_summary.clone();
+        let quantity_received = rules::convert_stock(
+            &mut source_stock_class,
+            &mut target_stock_class,
+            &mut cap_table_summary,
+            &mut source_position,
+            &mut resulting_position,
+            resulting_security_id,
+            quantity,
+        )?;
+
+        self.stock_classes
+            .insert(source_stock_class.id, source_stock_class);
+        self.stock_classes
+            .insert(target_stock_class.id, target_stock_class);
+        if source_position.quantity == 0 {
+            self.stock_positions.remove(&security_id);
+        } else {
+            self.stock_positions.insert(security_id, source_position);
+        }
+        self.stock_positions
+            .insert(resulting_security_id, resulting_position);
+        self.securities.insert(resulting_security_id);
+        self.cap_table_summary = cap_table_summary;
+        Ok(quantity_received)
+    }
+
+    pub fn issue_convertible(
+        &mut self,
+        stakeholder_id: Id,
+        security_id: Id,
+        investment_amount: u64,
+    ) -> Result<(), CoreError> {
+        self.ensure_stakeholder(&stakeholder_id)?;
+        self.ensure_unregistered(&security_id)?;
+
+        let mut position = ConvertibleActivePosition::default();
+        let mut cap_table_summary = self.cap_table_summary.clone();
+        rules::issue_convertible(
+            &mut position,
+            &mut cap_table_summary,
+            stakeholder_id,
+            security_id,
+            investment_amount,
+        )?;
+
+        self.convertible_positions.insert(security_id, position);
+        self.securities.insert(security_id);
+        self.cap_table_summary = cap_table_summary;
+        Ok(())
+    }
+
+    /// Returns the shares received.
+    pub fn convert_convertible(
+        &mut self,
+        security_id: Id,
+        stock_class_id: Id,
+        resulting_security_id: Id,
+        conversion_price: u64,
+    ) -> Result<u64, CoreError> {
+        let mut convertible_position = get(
+            &self.convertible_positions,
+            &security_id,
+            "convertible position",
+        )?
+        .clone();
+        let mut stock_class = get(&self.stock_classes, &stock_class_id, "stock class")?.clone();
+        self.ensure_unregistered(&resulting_security_id)?;
+
+        let mut stock_position = StockActivePosition::default();
+        let mut cap_table_summary = self.cap_table_summary.clone();
+        let quantity_received = rules::convert_convertible(
+            &mut convertible_position,
+            &mut stock_class,
+            &mut cap_table_summary,
+            &mut stock_position,
+            resulting_security_id,
+            conversion_price,
+        )?;
+
+        self.stock_classes.insert(stock_class_id, stock_class);
+        self.convertible_positions.remove(&security_id);
+        self.stock_positions
+            .insert(resulting_security_id, stock_position);
+        self.securities.insert(resulting_security_id);
+        self.cap_table_summary = cap_table_summary;
+        Ok(quantity_received)
+    }
+
+    pub fn issue_equity_compensation(
+        &mut self,
+        stakeholder_id: Id,
+        stock_class_id: Id,
+        stock_plan_id: Option<Id>,
+        security_id: Id,
+        quantity: u64,
+    ) -> Result<(), CoreError> {
+        self.ensure_stakeholder(&stakeholder_id)?;
+        get(&self.stock_classes, &stock_class_id, "stock class")?;
+        if let Some(stock_plan_id) = &stock_plan_id {
+            get(&self.stock_plans, stock_plan_id, "stock plan")?;
+        }
+        self.ensure_unregistered(&security_id)?;
+
+        let mut position = EquityCompensationActivePosition::default();
+        let mut cap_table_summary = self.cap_table_summary.clone();
+        rules::issue_equity_compensation(
+            &mut position,
+            &mut cap_table_summary,
+            stakeholder_id,
+            stock_class_id,
+            stock_plan_id,
+            security_id,
+            quantity,
+        )?;
+
+        self.equity_compensation_positions
+            .insert(security_id, position);
+        self.securities.insert(security_id);
+        self.cap_table_summary = cap_table_summary;
+        Ok(())
+    }
+
+    /// Exercises into `stock_security_id`, which is issued separately
+    /// beforehand, exactly as the program expects.
+    pub fn exercise_equity_compensation(
+        &mut self,
+        security_id: Id,
+        stock_security_id: Id,
+        quantity: u64,
+    ) -> Result<(), CoreError> {
+        let mut equity_position = get(
+            &self.equity_compensation_positions,
+            &security_id,
+            "equity compensation position",
+        )?
+        .clone();
+        let stock_position = get(&self.stock_positions, &stock_security_id, "stock position")?;
+
+        let mut cap_table_summary = self.cap_table_summary.clone();
+        rules::exercise_equity_compensation(
+            &mut equity_position,
+            stock_position,
+            &mut cap_table_summary,
+            quantity,
+        )?;
+
+        // The program closes fully exercised options
+        if equity_position.quantity == 0 {
+            self.equity_compensation_positions.remove(&security_id);
+        } else {
+            self.equity_compensation_positions
+                .insert(security_id, equity_position);
+        }
+        self.cap_table_summary = cap_table_summary;
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn issue_warrant(
+        &mut self,
+        stakeholder_id: Id,
+        stock_class_id: Id,
+        security_id: Id,
+        quantity: u64,
+        exercise_price: u64,
+        expiration_date: i64,
+        exercise_trigger: WarrantExerciseTrigger,
+        rent_payer: Pubkey,
+        now: i64,
+    ) -> Result<(), CoreError> {
+        get(&self.stock_classes, &stock_class_id, "stock class")?;
+        self.ensure_stakeholder(&stakeholder_id)?;
+        self.ensure_unregistered(&security_id)?;
+
+        let position = WarrantActivePosition {
+            stakeholder_id,
+            stock_class_id,
+            security_id,
+            quantity,
+            exercise_price,
+            expiration_date,
+            exercise_trigger,
+            rent_payer,
+        };
+        let mut cap_table_summary = self.cap_table_summary.clone();
+        rules::issue_warrant(&position, &mut cap_table_summary, now)?;
+
+        self.warrant_positions.insert(security_id, position);
+        self.securities.insert(security_id);
+        self.cap_table_summary = cap_table_summary;
+        Ok(())
+    }
+
+    /// Returns the shares received.
+    pub fn exercise_warrant(
+        &mut self,
+        security_id: Id,
+        resulting_security_id: Id,
+        quantity: u64,
+        fair_market_value: Option<u64>,
+        now: i64,
+    ) -> Result<u64, CoreError> {
+        let mut warrant_position =
+            get(&self.warrant_positions, &security_id, "warrant position")?.clone();
+        let mut stock_class = get(
+            &self.stock_classes,
+            &warrant_position.stock_class_id,
+            "stock class",
+        )?
+        .clone();
+        self.ensure_unregistered(&resulting_security_id)?;
+
+        let mut stock_position = StockActivePosition::default();
+        let mut cap_table_summary = self.cap_table_summary.clone();
+        let shares_received = rules::exercise_warrant(
+            &mut warrant_position,
+            &mut stock_class,
+            &mut stock_position,
+            &mut cap_table_summary,
+            resulting_security_id,
+            quantity,
+            fair_market_value,
+            now,
+        )?;
+
+        self.stock_classes.insert(stock_class.id, stock_class);
+        self.stock_positions
+            .insert(resulting_security_id, stock_position);
+        self.securities.insert(resulting_security_id);
+        self.put_warrant(warrant_position);
+        self.cap_table_summary = cap_table_summary;
+        Ok(shares_received)
+    }
+
+    pub fn cancel_warrant(&mut self, security_id: Id, quantity: u64) -> Result<(), CoreError> {
+        let mut warrant_position =
+            get(&self.warrant_positions, &security_id, "warrant position")?.clone();
+        let mut cap_table_summary = self.cap_table_summary.clone();
+        rules::cancel_warrant(&mut warrant_position, &mut cap_table_summary, quantity)?;
+
+        self.put_warrant(warrant_position);
+        self.cap_table_summary = cap_table_summary;
+        Ok(())
+    }
+
+    /// Returns the quantity that expired.
+    pub fn expire_warrant(&mut self, security_id: Id, now: i64) -> Result<u64, CoreError> {
+        let warrant_position = get(&self.warrant_positions, &security_id, "warrant position")?;
+        let quantity = warrant_position.quantity;
+        let mut cap_table_summary = self.cap_table_summary.clone();
+        rules::expire_warrant(warrant_position, &mut cap_table_summary, now)?;
+
+        self.warrant_positions.remove(&security_id);
+        self.cap_table_summary = cap_table_summary;
+        Ok(quantity)
+    }
+
+    pub fn transfer_warrant(
+        &mut self,
+        security_id: Id,
+        new_stakeholder_id: Id,
+        resulting_security_id: Id,
+        quantity: u64,
+        rent_payer: Pubkey,
+        now: i64,
+    ) -> Result<(), CoreError> {
+        self.ensure_stakeholder(&new_stakeholder_id)?;
+        let mut warrant_position =
+            get(&self.warrant_positions, &security_id, "warrant position")?.clone();
+        self.ensure_unregistered(&resulting_security_id)?;
+
+        let mut new_warrant_position = WarrantActivePosition::default();
+        rules::transfer_warrant(
+            &mut warrant_position,
+            &mut new_warrant_position,
+            new_stakeholder_id,
+            resulting_security_id,
+            quantity,
+            now,
+        )?;
+        new_warrant_position.rent_payer = rent_payer;
+
+        self.warrant_positions
+            .insert(resulting_security_id, new_warrant_position);
+        self.securities.insert(resulting_security_id);
+        self.put_warrant(warrant_position);
+        Ok(())
+    }
+
+    /// Stores a warrant position, dropping it once nothing is left, as the
+    /// program closes it.
+    fn put_warrant(&mut self, warrant_position: WarrantActivePosition) {
+        if warrant_position.quantity == 0 {
+            self.warrant_positions.remove(&warrant_position.security_id);
+        } else {
+            self.warrant_positions
+                .insert(warrant_position.security_id, warrant_position);
+        }
+    }
+
+    /// Applies a `TxCreated` emitted for this issuer and appends it to the
+    /// `TxLog`. Values the program computed and recorded in the payload are
+    /// checked against the replayed ones, so a payload that disagrees with
+    /// the rules is reported rather than trusted. Replay should stop at the
+    /// first error.
+    ///
+    /// Split cranks emit no event: after a split the replayed class counts
+    /// its positions at the pending estimate, which the program may already
+    /// have replaced with their adjusted quantities, until
+    /// [`Self::apply_all_pending_splits`] runs.
+    #[cfg(feature = "decode")]
+    pub fn apply_tx(&mut self, event: &TxCreatedV2) -> Result<(), ReplayError> {
+        let sequence = event.sequence;
+        if event.envelope.issuer_id != self.issuer.id {
+            return Err(ReplayError::WrongIssuer { sequence });
+        }
+        let expected = self.tx_log.sequence + 1;
+        if sequence != expected {
+            return Err(ReplayError::SequenceGap {
+                expected,
+                found: sequence,
+            });
+        }
+        if event.prev_hash != self.tx_log.last_hash {
+            return Err(ReplayError::HashMismatch { sequence });
+        }
+
+        let decoded =
+            decode_tx_created(event).map_err(|source| ReplayError::Payload { sequence, source })?;
+        let rejected = |error| ReplayError::Rejected { sequence, error };
+        let check = |field: &'static str, payload: u64, replayed: u64| {
+            if payload == replayed {
+                Ok(())
+            } else {
+                Err(ReplayError::Diverged {
+                    sequence,
+                    field,
+                    payload,
+                    replayed,
+                })
+            }
+        };
+        let now = event.envelope.unix_timestamp;
+
+        match decoded {
+            DecodedTx::StockIssuance(tx) => self
+                .issue_stock(
+                    tx.stock_class_id,
+                    tx.stakeholder_id,
+                    tx.security_id,
+                    tx.quantity,
+                    tx.share_price,
+                )
+                .map_err(rejected)?,
+            DecodedTx::StockConversion(tx) => {
+                let quantity_received = self
+                    .convert_stock(
+                        tx.security_id,
+                        tx.target_stock_class_id,
+                        tx.resulting_security_id,
+                        tx.quantity_converted,
+                    )
+                    .map_err(rejected)?;
+                check("quantity_received", tx.quantity_received, quantity_received)?;
+                let conversion_ratio = self.stock_classes[&tx.source_stock_class_id]
+                    .terms
+                    .conversion_ratio;
+                check("conversion_ratio", tx.conversion_ratio, conversion_ratio)?;
+            }
+            DecodedTx::StockClassSplit(tx) => {
+                self.split_stock_class(
+                    tx.stock_class_id,
+                    tx.numerator,
+                    tx.denominator,
+                    tx.fractional_share_policy,
+                )
+                .map_err(rejected)?;
+                let stock_class = &self.stock_classes[&tx.stock_class_id];
+                check(
+                    "split_epoch",
+                    tx.split_epoch.into(),
+                    stock_class.split_epoch().into(),
+                )?;
+                check(
+                    "new_shares_authorized",
+                    tx.new_shares_authorized,
+                    stock_class.shares_authorized,
+                )?;
+                check(
+                    "new_shares_issued",
+                    tx.new_shares_issued,
+                    stock_class.shares_issued,
+                )?;
+                check(
+                    "new_price_per_share",
+                    tx.new_price_per_share,
+                    stock_class.price_per_share,
+                )?;
+            }
+            DecodedTx::StockClassConversionRatioAdjustment(tx) => {
+                // The basis depends on which classes the caller passed, so it is
+                // taken from the payload rather than recomputed
+                let class_shares_issued = self
+                    .stock_classes
+                    .get(&tx.stock_class_id)
+                    .map_or(0, |stock_class| stock_class.shares_issued);
+                let adjustment = self
+                    .record_dilutive_issuance(
+                        tx.stock_class_id,
+                        tx.shares_outstanding_basis
+                            .saturating_sub(class_shares_issued),
+                        tx.new_issue_price,
+                        tx.new_shares_issued,
+                    )
+                    .map_err(rejected)?;
+                check(
+                    "previous_conversion_price",
+                    tx.previous_conversion_price,
+                    adjustment.previous_conversion_price,
+                )?;
+                check(
+                    "new_conversion_price",
+                    tx.new_conversion_price,
+                    adjustment.new_conversion_price,
+                )?;
+                check(
+                    "previous_conversion_ratio",
+                    tx.previous_conversion_ratio,
+                    adjustment.previous_conversion_ratio,
+                )?;
+                check(
+                    "new_conversion_ratio",
+                    tx.new_conversion_ratio,
+                    adjustment.new_conversion_ratio,
+                )?;
+            }
+            DecodedTx::ConvertibleIssuance(tx) => self
+                .issue_convertible(tx.stakeholder_id, tx.security_id, tx.investment_amount)
+                .map_err(rejected)?,
+            DecodedTx::ConvertibleConversion(tx) => {
+                // The price depends on the round that converted the note, so it
+                // is taken from the payload rather than recomputed
+                let investment_amount = self
+                    .convertible_positions
+                    .get(&tx.security_id)
+                    .map_or(0, |position| position.investment_amount);
+                let quantity_received = self
+                    .convert_convertible(
+                        tx.security_id,
+                        tx.stock_class_id,
+                        tx.resulting_security_id,
+                        tx.conversion_price,
+                    )
+                    .map_err(rejected)?;
+                check("investment_amount", tx.investment_amount, investment_amount)?;
+                check("quantity_received", tx.quantity_received, quantity_received)?;
+            }
+            DecodedTx::EquityCompensationIssuance(tx) => self
+                .issue_equity_compensation(
+                    tx.stakeholder_id,
+                    tx.stock_class_id,
+                    Some(tx.stock_plan_id).filter(|id| *id != [0; 16]),
+                    tx.security_id,
+                    tx.quantity,
+                )
+                .map_err(rejected)?,
+            DecodedTx::EquityCompensationExercise(tx) => self
+                .exercise_equity_compensation(
+                    tx.equity_comp_security_id,
+                    tx.resulting_stock_security_id,
+                    tx.quantity,
+                )
+                .map_err(rejected)?,
+            DecodedTx::WarrantIssuance(tx) => self
+                .issue_warrant(
+                    tx.stakeholder_id,
+                    tx.stock_class_id,
+                    tx.security_id,
+                    tx.quantity,
+                    tx.exercise_price,
+                    tx.expiration_date,
+                    tx.exercise_trigger,
+                    event.envelope.actor,
+                    now,
+                )
+                .map_err(rejected)?,
+            DecodedTx::WarrantExercise(tx) => {
+                let shares_received = self
+                    .exercise_warrant(
+                        tx.warrant_security_id,
+                        tx.resulting_stock_security_id,
+                        tx.quantity,
+                        tx.fair_market_value,
+                        now,
+                    )
+                    .map_err(rejected)?;
+                check("shares_received", tx.shares_received, shares_received)?;
+            }
+            DecodedTx::WarrantCancellation(tx) => self
+                .cancel_warrant(tx.security_id, tx.quantity)
+                .map_err(rejected)?,
+            DecodedTx::WarrantExpiration(tx) => {
+                let quantity = self.expire_warrant(tx.security_id, now).map_err(rejected)?;
+                check("quantity", tx.quantity, quantity)?;
+            }
+            DecodedTx::WarrantTransfer(tx) => self
+                .transfer_warrant(
+                    tx.security_id,
+                    tx.to_stakeholder_id,
+                    tx.resulting_security_id,
+                    tx.quantity,
+                    event.envelope.actor,
+                    now,
+                )
+                .map_err(rejected)?,
+        }
+
+        self.tx_log
+            .append(event.tx_type, event.payload_version, &event.tx_data)
+            .map_err(|_| rejected(crate::errors::TxLogError::SequenceOverflow.into()))?;
+        Ok(())
+    }
+}
+
+/// Why a `TxCreated` could not be replayed onto a [`CapTable`].
+#[cfg(feature = "decode")]
+#[derive(Debug)]
+pub enum ReplayError {
+    /// The transaction belongs to another issuer.
+    WrongIssuer { sequence: u64 },
+    /// A transaction is missing before this one.
+    SequenceGap { expected: u64, found: u64 },
+    /// The transaction does not continue the `TxLog` hash chain.
+    HashMismatch { sequence: u64 },
+    Payload {
+        sequence: u64,
+        source: TxDecodeError,
+    },
+    /// The rules reject the transaction the program accepted.
+    Rejected { sequence: u64, error: CoreError },
+    /// A value the program recorded differs from the replayed one.
+    Diverged {
+        sequence: u64,
+        field: &'static str,
+        payload: u64,
+        replayed: u64,
+    },
+}
+
+#[cfg(feature = "decode")]
+impl core::fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ReplayError::WrongIssuer { sequence } => {
+                write!(f, "transaction {} belongs to another issuer", sequence)
+            }
+            ReplayError::SequenceGap { expected, found } => write!(
+                f,
+                "missing transactions: expected sequence {}, got {}",
+                expected, found
+            ),
+            ReplayError::HashMismatch { sequence } => write!(
+                f,
+                "transaction {} does not continue the TxLog hash chain",
+                sequence
+            ),
+            ReplayError::Payload { sequence, source } => {
+                write!(f, "transaction {}: {}", sequence, source)
+            }
+            ReplayError::Rejected { sequence, error } => {
+                write!(
+                    f,
+                    "transaction {} is rejected by the rules: {}",
+                    sequence, error
+                )
+            }
+            ReplayError::Diverged {
+                sequence,
+                field,
+                payload,
+                replayed,
+            } => write!(
+                f,
+                "transaction {} records {} = {}, replay computes {}",
+                sequence, field, payload, replayed
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "decode")]
+impl core::error::Error for ReplayError {}
+
+#[cfg(all(test, feature = "decode"))]
+mod tests {
+    use super::*;
+    use crate::events::{EventEnvelope, StockClassSplit, StockIssued, WarrantIssued};
+    use crate::payload::TxPayload;
+    use anchor_lang::prelude::AnchorSerialize;
+
+    const SHARE: u64 = SHARE_DECIMALS_FACTOR;
+    const ISSUER: Id = [1; 16];
+    const COMMON: Id = [2; 16];
+    const FOUNDER: Id = [3; 16];
+    const FOUNDER_STOCK: Id = [4; 16];
+    const FOUNDER_WARRANT: Id = [5; 16];
+    const NOW: i64 = 1_700_000_000;
+
+    fn envelope(object_id: Id) -> EventEnvelope {
+        EventEnvelope {
+            issuer_id: ISSUER,
+            unix_timestamp: NOW,
+            slot: 1,
+            actor: Pubkey::default(),
+            object_id,
+        }
+    }
+
+    /// A founder with common stock authorized, before any transaction.
+    fn cap_table() -> CapTable {
+        let mut cap_table = CapTable::new(ISSUER, 10_000_000 * SHARE).unwrap();
+        cap_table
+            .create_stock_class(
+                COMMON,
+                "Common".into(),
+                SHARE / 100,
+                5_000_000 * SHARE,
+                StockClassTerms::default(),
+            )
+            .unwrap();
+        cap_table.create_stakeholder(FOUNDER).unwrap();
+        cap_table
+    }
+
+    /// Chains `payload` onto `tx_log` the way the program emits it.
+    fn tx_created<T: TxPayload + AnchorSerialize>(
+        tx_log: &mut TxLog,
+        object_id: Id,
+        payload: &T,
+    ) -> TxCreatedV2 {
+        let tx_data = payload.try_to_vec().unwrap();
+        let (sequence, prev_hash) = tx_log.append(T::TX_TYPE, T::VERSION, &tx_data).unwrap();
+        TxCreatedV2 {
+            envelope: envelope(object_id),
+            tx_type: T::TX_TYPE,
+            payload_version: T::VERSION,
+            tx_data,
+            sequence,
+            prev_hash,
+        }
+    }
+
+    fn stock_issued() -> StockIssued {
+        StockIssued {
+            envelope: envelope(FOUNDER_STOCK),
+            stock_class_id: COMMON,
+            security_id: FOUNDER_STOCK,
+            stakeholder_id: FOUNDER,
+            quantity: 1_000_000 * SHARE,
+            share_price: SHARE / 100,
+        }
+    }
+
+    fn warrant_issued() -> WarrantIssued {
+        WarrantIssued {
+            envelope: envelope(FOUNDER_WARRANT),
+            stakeholder_id: FOUNDER,
+            stock_class_id: COMMON,
+            security_id: FOUNDER_WARRANT,
+            quantity: 10_000 * SHARE,
+            exercise_price: SHARE,
+            expiration_date: NOW + 86_400,
+            exercise_trigger: WarrantExerciseTrigger::ElectiveAtWill,
+        }
+    }
+
+    fn stock_class_split() -> StockClassSplit {
+        StockClassSplit {
+            envelope: envelope(COMMON),
+            stock_class_id: COMMON,
+            split_epoch: 1,
+            numerator: 2,
+            denominator: 1,
+            fractional_share_policy: FractionalSharePolicy::Retain,
+            new_shares_authorized: 10_000_000 * SHARE,
+            new_shares_issued: 2_000_000 * SHARE,
+            new_price_per_share: SHARE / 200,
+        }
+    }
+
+    #[test]
+    fn replays_a_transaction_sequence() {
+        let mut cap_table = cap_table();
+        let mut tx_log = cap_table.tx_log.clone();
+        let events = [
+            tx_created(&mut tx_log, FOUNDER_STOCK, &stock_issued()),
+            tx_created(&mut tx_log, FOUNDER_WARRANT, &warrant_issued()),
+            tx_created(&mut tx_log, COMMON, &stock_class_split()),
+        ];
+        for event in &events {
+            cap_table.apply_tx(event).unwrap();
+        }
+
+        assert_eq!(cap_table.tx_log.sequence, 3);
+        assert_eq!(cap_table.tx_log.last_hash, tx_log.last_hash);
+
+        let common = &cap_table.stock_classes[&COMMON];
+        assert_eq!(common.shares_issued, 2_000_000 * SHARE);
+        assert_eq!(common.shares_authorized, 10_000_000 * SHARE);
+        assert_eq!(common.price_per_share, SHARE / 200);
+        assert_eq!(
+            cap_table.cap_table_summary.shares_outstanding(),
+            2_000_000 * SHARE
+        );
+        assert_eq!(
+            cap_table.cap_table_summary.warrants_outstanding,
+            10_000 * SHARE
+        );
+
+        // Positions follow the split once the crank runs
+        assert_eq!(
+            cap_table.stock_positions[&FOUNDER_STOCK].quantity,
+            1_000_000 * SHARE
+        );
+        cap_table.apply_all_pending_splits().unwrap();
+        assert_eq!(
+            cap_table.stock_positions[&FOUNDER_STOCK].quantity,
+            2_000_000 * SHARE
+        );
+    }
+
+    /// Issues each quantity of common to its own position, with security ids
+    /// counting up from 100.
+    fn positions(quantities: &[u64]) -> CapTable {
+        let mut cap_table = cap_table();
+        for (i, quantity) in quantities.iter().enumerate() {
+            cap_table
+                .issue_stock(COMMON, FOUNDER, [100 + i as u8; 16], *quantity, 1)
+                .unwrap();
+        }
+        cap_table
+    }
+
+    fn positions_total(cap_table: &CapTable) -> u64 {
+        cap_table
+            .stock_positions
+            .values()
+            .map(|position| position.quantity)
+            .sum()
+    }
+
+    #[test]
+    fn settles_the_class_total_on_the_sum_of_split_positions() {
+        // Each position rounds 1.5 units down to 1, the class total 4.5 to 4
+        let mut cap_table = positions(&[1, 1, 1]);
+        cap_table
+            .split_stock_class(COMMON, 3, 2, FractionalSharePolicy::Retain)
+            .unwrap();
+        assert_eq!(cap_table.stock_classes[&COMMON].shares_issued, 4);
+
+        cap_table
+            .apply_stock_class_split(COMMON, &[[100; 16]])
+            .unwrap();
+        assert_eq!(cap_table.stock_classes[&COMMON].shares_issued, 4);
+
+        cap_table.apply_all_pending_splits().unwrap();
+        assert_eq!(positions_total(&cap_table), 3);
+        assert_eq!(cap_table.stock_classes[&COMMON].shares_issued, 3);
+        assert_eq!(cap_table.cap_table_summary.shares_outstanding(), 3);
+    }
+
+    #[test]
+    fn settles_positions_that_missed_several_splits() {
+        let mut cap_table = positions(&[7, 5 * SHARE + 3, 11]);
+        cap_table
+            .split_stock_class(COMMON, 3, 2, FractionalSharePolicy::Retain)
+            .unwrap();
+        cap_table
+            .apply_stock_class_split(COMMON, &[[100; 16]])
+            .unwrap();
+        cap_table
+            .split_stock_class(COMMON, 5, 7, FractionalSharePolicy::RoundDown)
+            .unwrap();
+        cap_table
+            .issue_stock(COMMON, FOUNDER, [103; 16], 9, 1)
+            .unwrap();
+        cap_table
+            .split_stock_class(COMMON, 2, 1, FractionalSharePolicy::Retain)
+            .unwrap();
+
+        cap_table.apply_all_pending_splits().unwrap();
+        assert_eq!(
+            cap_table.stock_classes[&COMMON].shares_issued,
+            positions_total(&cap_table)
+        );
+        // 5.000003 shares become 7.5000045, then 5 whole shares, then 10
+        assert_eq!(cap_table.stock_positions[&[101; 16]].quantity, 10 * SHARE);
+        assert!(cap_table.stock_classes[&COMMON]
+            .splits
+            .iter()
+            .all(|split| split.pending_quantity == 0));
+    }
+
+    #[test]
+    fn rejects_a_transaction_out_of_sequence() {
+        let mut cap_table = cap_table();
+        let mut tx_log = cap_table.tx_log.clone();
+        tx_created(&mut tx_log, FOUNDER_STOCK, &stock_issued());
+        let skipped = tx_created(&mut tx_log, FOUNDER_WARRANT, &warrant_issued());
+
+        match cap_table.apply_tx(&skipped) {
+            Err(ReplayError::SequenceGap { expected, found }) => {
+                assert_eq!((expected, found), (1, 2))
+            }
+            _ => panic!("replayed a transaction past a gap"),
+        }
+        assert_eq!(cap_table.tx_log.sequence, 0);
+    }
+
+    #[test]
+    fn rejects_a_transaction_off_the_hash_chain() {
+        let mut cap_table = cap_table();
+        let mut event = tx_created(
+            &mut cap_table.tx_log.clone(),
+            FOUNDER_STOCK,
+            &stock_issued(),
+        );
+        event.prev_hash = [7; 32];
+
+        assert!(matches!(
+            cap_table.apply_tx(&event),
+            Err(ReplayError::HashMismatch { sequence: 1 })
+        ));
+    }
+
+    #[test]
+    fn reports_a_payload_that_disagrees_with_the_rules() {
+        let mut cap_table = cap_table();
+        let mut tx_log = cap_table.tx_log.clone();
+        let split = StockClassSplit {
+            new_shares_issued: 2_000_001 * SHARE,
+            ..stock_class_split()
+        };
+        let events = [
+            tx_created(&mut tx_log, FOUNDER_STOCK, &stock_issued()),
+            tx_created(&mut tx_log, COMMON, &split),
+        ];
+        cap_table.apply_tx(&events[0]).unwrap();
+
+        match cap_table.apply_tx(&events[1]) {
+            Err(ReplayError::Diverged {
+                sequence,
+                field,
+                payload,
+                replayed,
+            }) => {
+                assert_eq!(sequence, 2);
+                assert_eq!(field, "new_shares_issued");
+                assert_eq!(payload, 2_000_001 * SHARE);
+                assert_eq!(replayed, 2_000_000 * SHARE);
+            }
+            _ => panic!("accepted a split that disagrees with the replay"),
+        }
+    }
+
+    #[test]
+    fn leaves_the_table_unchanged_when_the_rules_reject_a_transaction() {
+        let mut cap_table = cap_table();
+        let mut tx_log = cap_table.tx_log.clone();
+        let over_authorized = StockIssued {
+            quantity: 5_000_001 * SHARE,
+            ..stock_issued()
+        };
+        let event = tx_created(&mut tx_log, FOUNDER_STOCK, &over_authorized);
+
+        assert!(matches!(
+            cap_table.apply_tx(&event),
+            Err(ReplayError::Rejected { sequence: 1, .. })
+        ));
+        assert_eq!(cap_table.tx_log.sequence, 0);
+        assert_eq!(cap_table.stock_classes[&COMMON].shares_issued, 0);
+        assert!(cap_table.stock_positions.is_empty());
+        assert!(cap_table.securities.is_empty());
+    }
+
+    #[test]
+    fn drops_options_once_fully_exercised() {
+        let mut cap_table = cap_table();
+        let option = [6; 16];
+        cap_table
+            .issue_equity_compensation(FOUNDER, COMMON, None, option, 100)
+            .unwrap();
+
+        cap_table
+            .issue_stock(COMMON, FOUNDER, [7; 16], 40, 1)
+            .unwrap();
+        cap_table
+            .exercise_equity_compensation(option, [7; 16], 40)
+            .unwrap();
+        assert_eq!(
+            cap_table.equity_compensation_positions[&option].quantity,
+            60
+        );
+
+        cap_table
+            .issue_stock(COMMON, FOUNDER, [8; 16], 60, 1)
+            .unwrap();
+        cap_table
+            .exercise_equity_compensation(option, [8; 16], 60)
+            .unwrap();
+        assert!(cap_table.equity_compensation_positions.is_empty());
+        assert_eq!(cap_table.cap_table_summary.options_outstanding, 0);
+    }
+}
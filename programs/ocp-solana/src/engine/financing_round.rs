@@ -0,0 +1,658 @@
+//! Pro-forma priced financing round.
+//!
+//! Amounts are 6-decimal fixed-point money and prices are per whole share, as
+//! in `issue_stock`. The round follows the usual pre-money conventions:
+//!
+//! - The price per share is the pre-money valuation over the pre-money fully
+//!   diluted shares, which include the pool top-up and the conversion shares.
+//! - A convertible converts its principal at the lower of the round price less
+//!   its discount and its valuation cap over the fully diluted shares before
+//!   the round. Convertibles without terms convert at the round price.
+//! - The pool is topped up so the available plan pool is the target fraction
+//!   of the post-money fully diluted shares. A pool already above target is
+//!   left as is.
+//!
+//! Pool top-up and conversions depend on the price and the price on them, so
+//! the pre-money share count is iterated to its fixed point. Share counts
+//! round down.
+
+use super::{ensure, CoreError};
+use crate::errors::FinancingRoundError;
+use crate::state::*;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use anchor_lang::prelude::{borsh, AnchorDeserialize, AnchorSerialize};
+
+type Id = [u8; 16];
+
+const FACTOR: u128 = SHARE_DECIMALS_FACTOR as u128;
+
+/// Bound on the fixed-point iteration. The gap shrinks each step by the share
+/// of the pre-money taken by the pool top-up and the discounted conversions,
+/// so rounds where those stay under about 60% settle well within it. A step
+/// costs `3 + 2 * conversions` multiply-divides, which at
+/// `MAX_FINANCING_ROUND_ENTRIES` bounds the search to about 2,000 of them.
+const MAX_ITERATIONS: usize = 64;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Investment {
+    pub stakeholder_id: [u8; 16],
+    pub amount: u64,
+}
+
+/// Conversion terms of one convertible, which the program does not record
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct ConvertibleTerms {
+    pub security_id: [u8; 16],
+    /// Discount to the round price, e.g. `200_000` for 20%
+    pub discount: u64,
+    /// Zero for no cap
+    pub valuation_cap: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Default, Debug)]
+pub struct FinancingRound {
+    pub pre_money_valuation: u64,
+    pub investments: Vec<Investment>,
+    /// Available plan pool after the round as a fraction of the post-money
+    /// fully diluted shares, e.g. `100_000` for 10%; zero for no top-up
+    pub target_pool_fraction: u64,
+    pub convertible_terms: Vec<ConvertibleTerms>,
+}
+
+/// The accounts to model the round over. Only the convertibles supplied
+/// convert; any others stay outstanding. The other positions only need to be
+/// complete for the holder table to be.
+pub struct RoundInput<'a> {
+    pub cap_table_summary: &'a CapTableSummary,
+    pub stock_classes: Vec<&'a StockClass>,
+    pub stock_positions: Vec<&'a StockActivePosition>,
+    pub convertible_positions: Vec<&'a ConvertibleActivePosition>,
+    pub equity_compensation_positions: Vec<&'a EquityCompensationActivePosition>,
+    pub warrant_positions: Vec<&'a WarrantActivePosition>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct RoundIssuance {
+    pub stakeholder_id: [u8; 16],
+    pub amount: u64,
+    pub shares: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct RoundConversion {
+    pub security_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    pub principal: u64,
+    pub conversion_price: u64,
+    pub shares: u64,
+}
+
+/// A stakeholder's fully diluted shares (stock, options and warrants) around
+/// the round
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct PostMoneyHolding {
+    pub stakeholder_id: [u8; 16],
+    pub pre_money_shares: u64,
+    pub new_shares: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Default, Debug)]
+pub struct RoundModel {
+    pub price_per_share: u64,
+    /// Existing fully diluted shares plus the pool top-up and conversions
+    pub pre_money_fully_diluted_shares: u64,
+    pub post_money_valuation: u64,
+    pub post_money_fully_diluted_shares: u64,
+    pub pool_increase: u64,
+    /// Plan pool available after the top-up
+    pub plan_pool_available: u64,
+    /// In the order of `FinancingRound::investments`
+    pub issuances: Vec<RoundIssuance>,
+    /// In the order of `RoundInput::convertible_positions`
+    pub conversions: Vec<RoundConversion>,
+    /// Sorted by stakeholder id
+    pub holders: Vec<PostMoneyHolding>,
+}
+
+fn overflow() -> CoreError {
+    FinancingRoundError::ArithmeticOverflow.into()
+}
+
+fn to_u64(value: u128) -> Result<u64, CoreError> {
+    u64::try_from(value).map_err(|_| overflow())
+}
+
+fn mul_div(value: u128, numerator: u128, denominator: u128) -> Result<u128, CoreError> {
+    ensure(denominator > 0, FinancingRoundError::InvalidValuation)?;
+    value
+        .checked_mul(numerator)
+        .map(|product| product / denominator)
+        .ok_or_else(overflow)
+}
+
+/// Share counts for one candidate pre-money fully diluted share count. One is
+/// reused for every candidate: on-chain the heap is a bump allocator that
+/// never frees.
+struct Trial {
+    price_per_share: u128,
+    conversion_prices: Vec<u128>,
+    conversion_shares: Vec<u128>,
+    pool_increase: u128,
+}
+
+/// Iterates `step` from `start` until it returns its argument. `step` must
+/// never fall as its argument rises, so the count rises to the least fixed
+/// point and stops on it exactly.
+fn fixed_point(
+    start: u128,
+    mut step: impl FnMut(u128) -> Result<u128, CoreError>,
+) -> Result<u128, CoreError> {
+    let mut current = start;
+    for _ in 0..MAX_ITERATIONS {
+        let next = match step(current) {
+            Ok(next) => next,
+            // Past the first step a trial only fails once the count has run
+            // away, pricing shares at zero or overflowing
+            Err(_) if current != start => return Err(FinancingRoundError::DoesNotConverge.into()),
+            Err(error) => return Err(error),
+        };
+        if next == current {
+            return Ok(current);
+        }
+        current = next;
+    }
+    Err(FinancingRoundError::DoesNotConverge.into())
+}
+
+pub fn model_financing_round(
+    input: &RoundInput,
+    round: &FinancingRound,
+) -> Result<RoundModel, CoreError> {
+    let summary = input.cap_table_summary;
+    ensure(
+        round.pre_money_valuation > 0,
+        FinancingRoundError::InvalidValuation,
+    )?;
+    ensure(
+        round.target_pool_fraction < SHARE_DECIMALS_FACTOR,
+        FinancingRoundError::InvalidPoolTarget,
+    )?;
+    for investment in &round.investments {
+        ensure(
+            investment.amount > 0,
+            FinancingRoundError::InvalidInvestment,
+        )?;
+    }
+
+    let convertible_principal = input
+        .convertible_positions
+        .iter()
+        .try_fold(0u64, |total, position| {
+            total.checked_add(position.investment_amount)
+        })
+        .ok_or_else(overflow)?;
+    ensure(
+        convertible_principal <= summary.convertible_principal,
+        FinancingRoundError::ExcessConvertibles,
+    )?;
+
+    let terms: BTreeMap<Id, &ConvertibleTerms> = round
+        .convertible_terms
+        .iter()
+        .map(|terms| (terms.security_id, terms))
+        .collect();
+    for terms in &round.convertible_terms {
+        ensure(
+            terms.discount < SHARE_DECIMALS_FACTOR,
+            FinancingRoundError::InvalidConvertibleTerms,
+        )?;
+        ensure(
+            input
+                .convertible_positions
+                .iter()
+                .any(|position| position.security_id == terms.security_id),
+            FinancingRoundError::ConvertibleNotFound,
+        )?;
+    }
+
+    let existing = summary.fully_diluted_shares() as u128;
+    ensure(existing > 0, FinancingRoundError::NoSharesOutstanding)?;
+    let pre_money = round.pre_money_valuation as u128;
+    let new_money = round
+        .investments
+        .iter()
+        .map(|investment| investment.amount as u128)
+        .sum::<u128>();
+    let post_money = pre_money + new_money;
+
+    // A convertible's discount and cap do not depend on the share count
+    let conversion_terms = input
+        .convertible_positions
+        .iter()
+        .map(|position| match terms.get(&position.security_id) {
+            Some(terms) if terms.valuation_cap > 0 => Ok((
+                FACTOR - terms.discount as u128,
+                Some(mul_div(terms.valuation_cap as u128, FACTOR, existing)?),
+            )),
+            Some(terms) => Ok((FACTOR - terms.discount as u128, None)),
+            None => Ok((FACTOR, None)),
+        })
+        .collect::<Result<Vec<_>, CoreError>>()?;
+
+    let mut trial = Trial {
+        price_per_share: 0,
+        conversion_prices: Vec::with_capacity(conversion_terms.len()),
+        conversion_shares: Vec::with_capacity(conversion_terms.len()),
+        pool_increase: 0,
+    };
+    let pre_money_shares = fixed_point(existing, |pre_money_shares| {
+        trial.price_per_share = mul_div(pre_money, FACTOR, pre_money_shares)?;
+        ensure(
+            trial.price_per_share > 0,
+            FinancingRoundError::InvalidValuation,
+        )?;
+
+        trial.conversion_prices.clear();
+        trial.conversion_shares.clear();
+        for (position, (discounted, cap_price)) in
+            input.convertible_positions.iter().zip(&conversion_terms)
+        {
+            let mut price = mul_div(trial.price_per_share, *discounted, FACTOR)?;
+            if let Some(cap_price) = cap_price {
+                price = price.min(*cap_price);
+            }
+            ensure(price > 0, FinancingRoundError::InvalidConvertibleTerms)?;
+            trial.conversion_shares.push(mul_div(
+                position.investment_amount as u128,
+                FACTOR,
+                price,
+            )?);
+            trial.conversion_prices.push(price);
+        }
+
+        // The post-money is the pre-money grossed up by the new money
+        let post_money_shares = mul_div(pre_money_shares, post_money, pre_money)?;
+        trial.pool_increase = mul_div(
+            post_money_shares,
+            round.target_pool_fraction as u128,
+            FACTOR,
+        )?
+        .saturating_sub(summary.plan_pool_available as u128);
+
+        Ok(existing + trial.pool_increase + trial.conversion_shares.iter().sum::<u128>())
+    })?;
+
+    let mut model = RoundModel {
+        price_per_share: to_u64(trial.price_per_share)?,
+        pre_money_fully_diluted_shares: to_u64(pre_money_shares)?,
+        post_money_valuation: to_u64(post_money)?,
+        pool_increase: to_u64(trial.pool_increase)?,
+        plan_pool_available: to_u64(summary.plan_pool_available as u128 + trial.pool_increase)?,
+        ..Default::default()
+    };
+
+    let mut holders: BTreeMap<Id, (u128, u128)> = BTreeMap::new();
+    let mut new_money_shares = 0u128;
+    for investment in &round.investments {
+        let shares = mul_div(investment.amount as u128, FACTOR, trial.price_per_share)?;
+        model.issuances.push(RoundIssuance {
+            stakeholder_id: investment.stakeholder_id,
+            amount: investment.amount,
+            shares: to_u64(shares)?,
+        });
+        holders.entry(investment.stakeholder_id).or_default().1 += shares;
+        new_money_shares += shares;
+    }
+    model.post_money_fully_diluted_shares = to_u64(pre_money_shares + new_money_shares)?;
+    for ((position, price), shares) in input
+        .convertible_positions
+        .iter()
+        .zip(&trial.conversion_prices)
+        .zip(&trial.conversion_shares)
+    {
+        model.conversions.push(RoundConversion {
+            security_id: position.security_id,
+            stakeholder_id: position.stakeholder_id,
+            principal: position.investment_amount,
+            conversion_price: to_u64(*price)?,
+            shares: to_u64(*shares)?,
+        });
+        holders.entry(position.stakeholder_id).or_default().1 += shares;
+    }
+
+    for position in &input.stock_positions {
+        let stock_class = input
+            .stock_classes
+            .iter()
+            .find(|stock_class| stock_class.id == position.stock_class_id)
+            .ok_or(CoreError::AccountNotFound("stock class"))?;
+        let mut position = (*position).clone();
+        position
+            .apply_pending_splits(stock_class)
+            .ok_or_else(overflow)?;
+        holders.entry(position.stakeholder_id).or_default().0 += position.quantity as u128;
+    }
+    for position in &input.equity_compensation_positions {
+        holders.entry(position.stakeholder_id).or_default().0 += position.quantity as u128;
+    }
+    for position in &input.warrant_positions {
+        holders.entry(position.stakeholder_id).or_default().0 += position.quantity as u128;
+    }
+    for (stakeholder_id, (pre_money_shares, new_shares)) in holders {
+        model.holders.push(PostMoneyHolding {
+            stakeholder_id,
+            pre_money_shares: to_u64(pre_money_shares)?,
+            new_shares: to_u64(new_shares)?,
+        });
+    }
+    Ok(model)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHARE: u64 = SHARE_DECIMALS_FACTOR;
+    const FOUNDER: Id = [1; 16];
+    const INVESTOR: Id = [2; 16];
+    const NOTE_HOLDER: Id = [3; 16];
+
+    fn cap_table_summary(shares_outstanding: u64, plan_pool_available: u64) -> CapTableSummary {
+        CapTableSummary {
+            stock_classes: vec![StockClassSummary {
+                stock_class_id: [9; 16],
+                shares_authorized: shares_outstanding,
+                shares_outstanding,
+            }],
+            plan_pool_reserved: plan_pool_available,
+            plan_pool_available,
+            ..CapTableSummary::default()
+        }
+    }
+
+    fn input<'a>(
+        cap_table_summary: &'a CapTableSummary,
+        convertible_positions: Vec<&'a ConvertibleActivePosition>,
+    ) -> RoundInput<'a> {
+        RoundInput {
+            cap_table_summary,
+            stock_classes: Vec::new(),
+            stock_positions: Vec::new(),
+            convertible_positions,
+            equity_compensation_positions: Vec::new(),
+            warrant_positions: Vec::new(),
+        }
+    }
+
+    fn round(pre_money_valuation: u64, new_money: u64) -> FinancingRound {
+        FinancingRound {
+            pre_money_valuation,
+            investments: vec![Investment {
+                stakeholder_id: INVESTOR,
+                amount: new_money,
+            }],
+            ..FinancingRound::default()
+        }
+    }
+
+    fn note(security_id: Id, investment_amount: u64) -> ConvertibleActivePosition {
+        ConvertibleActivePosition {
+            stakeholder_id: NOTE_HOLDER,
+            security_id,
+            investment_amount,
+            ..ConvertibleActivePosition::default()
+        }
+    }
+
+    #[test]
+    fn prices_the_round_over_the_fully_diluted_shares() {
+        // $10M pre-money over 8M shares, with $2.5M of new money
+        let summary = cap_table_summary(8_000_000 * SHARE, 0);
+        let model = model_financing_round(
+            &input(&summary, Vec::new()),
+            &round(10_000_000 * SHARE, 2_500_000 * SHARE),
+        )
+        .unwrap();
+
+        assert_eq!(model.price_per_share, 1_250_000);
+        assert_eq!(model.post_money_valuation, 12_500_000 * SHARE);
+        assert_eq!(model.issuances[0].shares, 2_000_000 * SHARE);
+        assert_eq!(model.pre_money_fully_diluted_shares, 8_000_000 * SHARE);
+        assert_eq!(model.post_money_fully_diluted_shares, 10_000_000 * SHARE);
+        assert_eq!(model.pool_increase, 0);
+        assert_eq!(
+            model.holders,
+            vec![PostMoneyHolding {
+                stakeholder_id: INVESTOR,
+                pre_money_shares: 0,
+                new_shares: 2_000_000 * SHARE,
+            }]
+        );
+    }
+
+    #[test]
+    fn tops_up_the_pool_to_the_post_money_target() {
+        // A 10% post-money pool is carved out of the $9M pre-money:
+        // n = 9M + n * 10/9 * 10%, so n = 10.125M and the pool is 1.125M
+        let summary = cap_table_summary(9_000_000 * SHARE, 0);
+        let mut round = round(9_000_000 * SHARE, 1_000_000 * SHARE);
+        round.target_pool_fraction = 100_000;
+        let model = model_financing_round(&input(&summary, Vec::new()), &round).unwrap();
+
+        // Rounding down stops the count one unit short
+        assert_eq!(model.pre_money_fully_diluted_shares, 10_125_000 * SHARE - 1);
+        assert_eq!(model.pool_increase, 1_125_000 * SHARE - 1);
+        assert_eq!(model.plan_pool_available, 1_125_000 * SHARE - 1);
+        assert_eq!(model.price_per_share, 888_888);
+
+        // An existing pool counts towards the target
+        let summary = cap_table_summary(9_000_000 * SHARE, 2_000_000 * SHARE);
+        let model = model_financing_round(&input(&summary, Vec::new()), &round).unwrap();
+        assert_eq!(model.pool_increase, 0);
+        assert_eq!(model.plan_pool_available, 2_000_000 * SHARE);
+    }
+
+    #[test]
+    fn converts_at_the_discount_when_below_the_cap() {
+        // The note's shares dilute the pre-money: n = 8M + n / 8
+        let summary = CapTableSummary {
+            convertible_principal: 1_000_000 * SHARE,
+            ..cap_table_summary(8_000_000 * SHARE, 0)
+        };
+        let safe = note([4; 16], 1_000_000 * SHARE);
+        let mut round = round(10_000_000 * SHARE, 2_500_000 * SHARE);
+        round.convertible_terms = vec![ConvertibleTerms {
+            security_id: safe.security_id,
+            discount: 200_000,
+            valuation_cap: 8_000_000 * SHARE,
+        }];
+        let model = model_financing_round(&input(&summary, vec![&safe]), &round).unwrap();
+
+        assert_eq!(model.price_per_share, 1_093_750);
+        assert_eq!(model.conversions[0].conversion_price, 875_000);
+        assert_eq!(model.conversions[0].shares, 1_142_857_142_857);
+        assert_eq!(model.issuances[0].shares, 2_285_714_285_714);
+    }
+
+    #[test]
+    fn converts_at_the_cap_when_below_the_discount() {
+        // A $4M cap over 8M shares prices the note at 0.50 whatever the round
+        let summary = CapTableSummary {
+            convertible_principal: 1_000_000 * SHARE,
+            ..cap_table_summary(8_000_000 * SHARE, 0)
+        };
+        let safe = note([4; 16], 1_000_000 * SHARE);
+        let mut round = round(10_000_000 * SHARE, 2_500_000 * SHARE);
+        round.convertible_terms = vec![ConvertibleTerms {
+            security_id: safe.security_id,
+            discount: 200_000,
+            valuation_cap: 4_000_000 * SHARE,
+        }];
+        let model = model_financing_round(&input(&summary, vec![&safe]), &round).unwrap();
+
+        assert_eq!(model.conversions[0].conversion_price, 500_000);
+        assert_eq!(model.conversions[0].shares, 2_000_000 * SHARE);
+        assert_eq!(model.pre_money_fully_diluted_shares, 10_000_000 * SHARE);
+        assert_eq!(model.price_per_share, 1_000_000);
+    }
+
+    #[test]
+    fn converts_only_the_notes_supplied() {
+        // The issuer has a second note that stays outstanding
+        let summary = CapTableSummary {
+            convertible_principal: 1_500_000 * SHARE,
+            ..cap_table_summary(8_000_000 * SHARE, 0)
+        };
+        let safe = note([4; 16], 1_000_000 * SHARE);
+        let model = model_financing_round(
+            &input(&summary, vec![&safe]),
+            &round(10_000_000 * SHARE, 2_500_000 * SHARE),
+        )
+        .unwrap();
+        assert_eq!(model.conversions.len(), 1);
+
+        let excess = note([5; 16], 1_000_000 * SHARE);
+        assert!(matches!(
+            model_financing_round(
+                &input(&summary, vec![&safe, &excess]),
+                &round(10_000_000 * SHARE, 2_500_000 * SHARE),
+            ),
+            Err(CoreError::FinancingRound(
+                FinancingRoundError::ExcessConvertibles
+            ))
+        ));
+    }
+
+    #[test]
+    fn stops_on_the_fixed_point() {
+        // Each step the pool takes 30% and the note 40% of the new shares, so
+        // the count takes dozens of steps to settle on its fixed point
+        let summary = CapTableSummary {
+            convertible_principal: 1_000_000 * SHARE,
+            ..cap_table_summary(1_000_000 * SHARE, 0)
+        };
+        let safe = note([4; 16], 1_000_000 * SHARE);
+        let mut round = round(5_000_000 * SHARE, 1_000_000 * SHARE);
+        round.target_pool_fraction = 250_000;
+        round.convertible_terms = vec![ConvertibleTerms {
+            security_id: safe.security_id,
+            discount: 500_000,
+            valuation_cap: 0,
+        }];
+        let model = model_financing_round(&input(&summary, vec![&safe]), &round).unwrap();
+
+        assert_eq!(
+            model.pre_money_fully_diluted_shares,
+            1_000_000 * SHARE + model.pool_increase + model.conversions[0].shares
+        );
+        let post_money_shares = model.pre_money_fully_diluted_shares as u128 * 6 / 5;
+        assert_eq!(model.pool_increase as u128, post_money_shares / 4);
+        // n = 1M + 0.3n + 0.4n, so n is 3.33M shares
+        assert_eq!(model.pre_money_fully_diluted_shares / SHARE, 3_333_333);
+    }
+
+    #[test]
+    fn settles_within_the_iteration_bound() {
+        // Rises by one per step and stops after exactly MAX_ITERATIONS steps
+        let last = MAX_ITERATIONS as u128 - 1;
+        let mut steps = 0;
+        let fixed = fixed_point(0, |count| {
+            steps += 1;
+            Ok((count + 1).min(last))
+        });
+        assert_eq!(fixed.unwrap(), last);
+        assert_eq!(steps, MAX_ITERATIONS);
+
+        // One more step than the bound allows
+        assert!(matches!(
+            fixed_point(0, |count| Ok((count + 1).min(last + 1))),
+            Err(CoreError::FinancingRound(
+                FinancingRoundError::DoesNotConverge
+            ))
+        ));
+    }
+
+    #[test]
+    fn settles_a_full_round_with_a_heavy_top_up() {
+        // Every entry but the subscription is a discounted note, with a 20% pool
+        let notes: Vec<_> = (0..MAX_FINANCING_ROUND_ENTRIES as u8 - 1)
+            .map(|index| note([100 + index; 16], 100_000 * SHARE))
+            .collect();
+        let summary = CapTableSummary {
+            convertible_principal: 1_500_000 * SHARE,
+            ..cap_table_summary(1_000_000 * SHARE, 0)
+        };
+        let mut round = round(5_000_000 * SHARE, 1_000_000 * SHARE);
+        round.target_pool_fraction = 200_000;
+        round.convertible_terms = notes
+            .iter()
+            .map(|note| ConvertibleTerms {
+                security_id: note.security_id,
+                discount: 300_000,
+                valuation_cap: 0,
+            })
+            .collect();
+        let model =
+            model_financing_round(&input(&summary, notes.iter().collect()), &round).unwrap();
+
+        let conversion_shares: u64 = model.conversions.iter().map(|c| c.shares).sum();
+        assert_eq!(
+            model.pre_money_fully_diluted_shares,
+            1_000_000 * SHARE + model.pool_increase + conversion_shares
+        );
+    }
+
+    #[test]
+    fn fails_when_the_pool_outgrows_the_pre_money() {
+        // A pool of 95% of the post-money can never fit in the pre-money
+        let summary = cap_table_summary(1_000_000 * SHARE, 0);
+        let mut round = round(1_000_000 * SHARE, 1_000_000 * SHARE);
+        round.target_pool_fraction = 950_000;
+
+        assert!(matches!(
+            model_financing_round(&input(&summary, Vec::new()), &round),
+            Err(CoreError::FinancingRound(
+                FinancingRoundError::DoesNotConverge
+            ))
+        ));
+    }
+
+    #[test]
+    fn records_existing_holders() {
+        let summary = cap_table_summary(8_000_000 * SHARE, 0);
+        let stock_class = StockClass {
+            id: [9; 16],
+            ..StockClass::default()
+        };
+        let founder = StockActivePosition {
+            stakeholder_id: FOUNDER,
+            stock_class_id: stock_class.id,
+            quantity: 8_000_000 * SHARE,
+            ..StockActivePosition::default()
+        };
+        let input = RoundInput {
+            stock_classes: vec![&stock_class],
+            stock_positions: vec![&founder],
+            ..input(&summary, Vec::new())
+        };
+        let model =
+            model_financing_round(&input, &round(10_000_000 * SHARE, 2_500_000 * SHARE)).unwrap();
+
+        assert_eq!(
+            model.holders,
+            vec![
+                PostMoneyHolding {
+                    stakeholder_id: FOUNDER,
+                    pre_money_shares: 8_000_000 * SHARE,
+                    new_shares: 0,
+                },
+                PostMoneyHolding {
+                    stakeholder_id: INVESTOR,
+                    pre_money_shares: 0,
+                    new_shares: 2_000_000 * SHARE,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,119 @@
+//! Cap table business rules, independent of the Solana runtime.
+//!
+//! [`rules`] holds the checks and state transitions of every instruction,
+//! written against the plain account structs in `state` with the clock passed
+//! in, so no rule calls a sysvar, loads an account or invokes another program.
+//! The engine itself only uses `core` and `alloc`; `std` is confined to the
+//! `std` feature, which `decode` enables for event replay. It still links
+//! anchor-lang through the account structs and `Pubkey`.
+//! The instruction handlers call these rules on their loaded accounts;
+//! [`CapTable`] calls the same rules on an in-memory copy of a whole issuer,
+//! which lets off-chain tools replay the event history and check the result
+//! against the accounts. [`waterfall`] models exit proceeds over the same
+//! accounts and [`financing_round`] a priced round.
+#![deny(clippy::std_instead_of_core, clippy::std_instead_of_alloc)]
+
+pub mod cap_table;
+pub mod financing_round;
+pub mod rules;
+pub mod waterfall;
+
+pub use cap_table::CapTable;
+#[cfg(feature = "decode")]
+pub use cap_table::ReplayError;
+pub use financing_round::{model_financing_round, FinancingRound, RoundInput, RoundModel};
+pub use waterfall::{
+    liquidation_waterfall, LiquidationDistribution, OptionExercisePrice, WaterfallInput,
+};
+
+use crate::errors::*;
+
+/// A rule violation, carrying the program error the handler reports for it.
+#[derive(Debug, Clone, Copy)]
+pub enum CoreError {
+    Issuer(IssuerError),
+    CapTableSummary(CapTableSummaryError),
+    StockClass(StockClassError),
+    Stock(StockError),
+    StockPlan(StockPlanError),
+    Convertible(ConvertibleError),
+    EquityCompensation(EquityCompensationError),
+    Warrant(WarrantError),
+    TxLog(TxLogError),
+    Waterfall(WaterfallError),
+    FinancingRound(FinancingRoundError),
+    Governance(GovernanceError),
+    Voting(VotingError),
+    /// An account the operation needs does not exist. On-chain Anchor
+    /// rejects the instruction before the handler runs.
+    AccountNotFound(&'static str),
+    /// The operation would create an account that already exists. On-chain
+    /// the system program rejects the `init`.
+    AccountInUse(&'static str),
+}
+
+macro_rules! core_errors {
+    ($($error:ident => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$error> for CoreError {
+                fn from(error: $error) -> Self {
+                    CoreError::$variant(error)
+                }
+            }
+        )*
+
+        impl From<CoreError> for anchor_lang::error::Error {
+            fn from(error: CoreError) -> Self {
+                match error {
+                    $(CoreError::$variant(error) => error.into(),)*
+                    CoreError::AccountNotFound(_) => {
+                        anchor_lang::error::ErrorCode::AccountNotInitialized.into()
+                    }
+                    CoreError::AccountInUse(_) => {
+                        anchor_lang::error::ErrorCode::AccountDiscriminatorAlreadySet.into()
+                    }
+                }
+            }
+        }
+
+        impl core::fmt::Display for CoreError {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                match self {
+                    $(CoreError::$variant(error) => write!(f, "{:?}: {}", error, error),)*
+                    CoreError::AccountNotFound(account) => write!(f, "{} does not exist", account),
+                    CoreError::AccountInUse(account) => write!(f, "{} already exists", account),
+                }
+            }
+        }
+    };
+}
+
+core_errors! {
+    IssuerError => Issuer,
+    CapTableSummaryError => CapTableSummary,
+    StockClassError => StockClass,
+    StockError => Stock,
+    StockPlanError => StockPlan,
+    ConvertibleError => Convertible,
+    EquityCompensationError => EquityCompensation,
+    WarrantError => Warrant,
+    TxLogError => TxLog,
+    WaterfallError => Waterfall,
+    FinancingRoundError => FinancingRound,
+    GovernanceError => Governance,
+    VotingError => Voting,
+}
+
+// `core::error::Error` is newer than the Rust of the Solana toolchain, so it
+// is only implemented for off-chain builds, which enable `std`
+#[cfg(feature = "std")]
+impl core::error::Error for CoreError {}
+
+/// `require!` for rules: fails with `error` unless `condition` holds.
+pub(crate) fn ensure(condition: bool, error: impl Into<CoreError>) -> Result<(), CoreError> {
+    if condition {
+        Ok(())
+    } else {
+        Err(error.into())
+    }
+}
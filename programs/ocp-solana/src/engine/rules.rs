@@ -0,0 +1,1525 @@
+//! Checks and state transitions of each instruction, applied to plain
+//! account structs. Callers own account creation and closing, holdings,
+//! security registrations, the `TxLog` and events.
+
+use super::financing_round::{
+    model_financing_round, ConvertibleTerms, FinancingRound, Investment, RoundInput, RoundModel,
+};
+use super::{ensure, CoreError};
+use crate::errors::*;
+use crate::state::*;
+use alloc::string::String;
+use alloc::vec::Vec;
+use anchor_lang::prelude::Pubkey;
+
+/// Result of an anti-dilution adjustment, as recorded in
+/// `StockClassConversionRatioAdjusted`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ConversionAdjustment {
+    pub shares_outstanding_basis: u64,
+    pub previous_conversion_price: u64,
+    pub new_conversion_price: u64,
+    pub previous_conversion_ratio: u64,
+    pub new_conversion_ratio: u64,
+}
+
+pub fn initialize_issuer(
+    issuer: &mut Issuer,
+    cap_table_summary: &mut CapTableSummary,
+    id: [u8; 16],
+    initial_shares_authorized: u64,
+) -> Result<(), CoreError> {
+    ensure(
+        issuer.shares_authorized == 0,
+        IssuerError::AlreadyInitialized,
+    )?;
+
+    issuer.id = id;
+    issuer.shares_issued = 0;
+    issuer.shares_authorized = initial_shares_authorized;
+
+    cap_table_summary.issuer_id = id;
+    cap_table_summary.shares_authorized = initial_shares_authorized;
+    Ok(())
+}
+
+pub fn adjust_authorized_shares(
+    issuer: &mut Issuer,
+    cap_table_summary: &mut CapTableSummary,
+    new_shares_authorized: u64,
+) {
+    issuer.shares_authorized = new_shares_authorized;
+    cap_table_summary.shares_authorized = new_shares_authorized;
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_stock_class(
+    stock_class: &mut StockClass,
+    cap_table_summary: &mut CapTableSummary,
+    id: [u8; 16],
+    class_type: String,
+    price_per_share: u64,
+    initial_shares_authorized: u64,
+    terms: StockClassTerms,
+) -> Result<(), CoreError> {
+    check_stock_class_terms(&terms)?;
+
+    stock_class.id = id;
+    stock_class.class_type = class_type;
+    stock_class.price_per_share = price_per_share;
+    stock_class.shares_issued = 0;
+    stock_class.shares_authorized = initial_shares_authorized;
+    stock_class.splits = Vec::new();
+    stock_class.terms = terms;
+    stock_class.conversion_price = price_per_share;
+
+    cap_table_summary.stock_classes.push(StockClassSummary {
+        stock_class_id: id,
+        shares_authorized: initial_shares_authorized,
+        shares_outstanding: 0,
+    });
+    Ok(())
+}
+
+fn check_stock_class_terms(terms: &StockClassTerms) -> Result<(), CoreError> {
+    if terms.kind == StockClassKind::Preferred {
+        ensure(
+            terms.conversion_ratio > 0 && terms.converts_to_stock_class_id != [0; 16],
+            StockClassError::InvalidConversionTerms,
+        )?;
+    }
+    Ok(())
+}
+
+pub fn adjust_stock_class_shares(
+    stock_class: &mut StockClass,
+    cap_table_summary: &mut CapTableSummary,
+    new_shares_authorized: u64,
+) -> Result<(), CoreError> {
+    stock_class.shares_authorized = new_shares_authorized;
+    cap_table_summary.sync_stock_class(stock_class)?;
+    Ok(())
+}
+
+/// Restates the class totals; positions follow lazily through
+/// [`apply_pending_splits`]. Until every position has, `shares_issued`
+/// counts the positions still pending at their class-level estimate.
+///
+/// Options and warrants on the class keep their quantity and exercise price:
+/// the summary counts them per issuer rather than per class, and how a split
+/// adjusts them is set by each grant or warrant agreement, which the program
+/// does not hold. Record such an adjustment by cancelling and reissuing.
+pub fn split_stock_class(
+    stock_class: &mut StockClass,
+    cap_table_summary: &mut CapTableSummary,
+    numerator: u64,
+    denominator: u64,
+    fractional_share_policy: FractionalSharePolicy,
+) -> Result<(), CoreError> {
+    ensure(
+        numerator > 0 && denominator > 0 && numerator != denominator,
+        StockClassError::InvalidSplitRatio,
+    )?;
+    ensure(
+        stock_class.splits.len() < MAX_STOCK_CLASS_SPLITS,
+        StockClassError::SplitHistoryFull,
+    )?;
+    let overflow = || CoreError::from(StockClassError::ArithmeticOverflow);
+
+    // Every position is pending after the split, including the ones already
+    // up to date with the previous one
+    let pending = stock_class.pending_shares().ok_or_else(overflow)?;
+    let split = SplitRatio {
+        numerator,
+        denominator,
+        fractional_share_policy,
+        pending_quantity: stock_class.shares_issued.saturating_sub(pending),
+    };
+
+    stock_class.shares_authorized = split
+        .apply_to_quantity(stock_class.shares_authorized)
+        .ok_or_else(overflow)?;
+    stock_class.price_per_share = split
+        .apply_to_price(stock_class.price_per_share)
+        .ok_or_else(overflow)?;
+    stock_class.conversion_price = split
+        .apply_to_price(stock_class.conversion_price)
+        .ok_or_else(overflow)?;
+    stock_class.splits.push(split);
+    stock_class.shares_issued = stock_class.pending_shares().ok_or_else(overflow)?;
+    cap_table_summary.sync_stock_class(stock_class)?;
+    Ok(())
+}
+
+/// Brings a position up to date with its class's splits and swaps its
+/// estimate in `shares_issued` for its adjusted quantity, so once every
+/// position is up to date the class total is the sum of its positions. The
+/// caller syncs the summary.
+pub fn apply_pending_splits(
+    stock_class: &mut StockClass,
+    position: &mut StockActivePosition,
+) -> Result<(), CoreError> {
+    ensure(
+        position.stock_class_id == stock_class.id,
+        StockClassError::StockClassMismatch,
+    )?;
+    let epoch = position.split_epoch as usize;
+    if epoch >= stock_class.splits.len() {
+        return Ok(());
+    }
+    let overflow = || CoreError::from(StockClassError::ArithmeticOverflow);
+
+    let pending = stock_class.pending_shares().ok_or_else(overflow)?;
+    let up_to_date = stock_class.shares_issued.saturating_sub(pending);
+    let split = &mut stock_class.splits[epoch];
+    split.pending_quantity = split.pending_quantity.saturating_sub(position.quantity);
+
+    position
+        .apply_pending_splits(stock_class)
+        .ok_or_else(overflow)?;
+    stock_class.shares_issued = stock_class
+        .pending_shares()
+        .and_then(|pending| pending.checked_add(up_to_date))
+        .and_then(|total| total.checked_add(position.quantity))
+        .ok_or_else(overflow)?;
+    Ok(())
+}
+
+/// Adjusts the class's conversion price and ratio for an issuance below its
+/// conversion price. `other_shares_issued` is the issued total of every other
+/// class of the issuer and only counts toward a broad-based basis.
+pub fn record_dilutive_issuance(
+    stock_class: &mut StockClass,
+    other_shares_issued: u64,
+    new_issue_price: u64,
+    new_shares_issued: u64,
+) -> Result<ConversionAdjustment, CoreError> {
+    ensure(
+        new_issue_price > 0 && new_shares_issued > 0,
+        StockClassError::InvalidDilutiveIssuance,
+    )?;
+
+    let anti_dilution_type = stock_class.terms.anti_dilution_type;
+    let previous_conversion_price = stock_class.conversion_price;
+    let previous_conversion_ratio = stock_class.terms.conversion_ratio;
+
+    ensure(
+        anti_dilution_type != AntiDilutionType::None,
+        StockClassError::NoAntiDilutionProtection,
+    )?;
+    ensure(
+        new_issue_price < previous_conversion_price,
+        StockClassError::NotDilutive,
+    )?;
+
+    let shares_outstanding_basis = match anti_dilution_type {
+        AntiDilutionType::BroadBasedWeightedAverage => stock_class
+            .shares_issued
+            .checked_add(other_shares_issued)
+            .ok_or(StockClassError::ArithmeticOverflow)?,
+        _ => stock_class.shares_issued,
+    };
+
+    // Weighted average: CP2 = CP1 * (A + B) / (A + C), where B = C * P / CP1 is the
+    // number of shares the new money would have bought at CP1. This simplifies to
+    // (CP1 * A + P * C) / (A + C). Full ratchet drops straight to the new price.
+    let new_conversion_price = match anti_dilution_type {
+        AntiDilutionType::FullRatchet => new_issue_price,
+        _ => {
+            let numerator = (previous_conversion_price as u128)
+                * (shares_outstanding_basis as u128)
+                + (new_issue_price as u128) * (new_shares_issued as u128);
+            let denominator = (shares_outstanding_basis as u128) + (new_shares_issued as u128);
+            u64::try_from(numerator / denominator)
+                .map_err(|_| StockClassError::ArithmeticOverflow)?
+        }
+    };
+
+    let new_conversion_ratio = u64::try_from(
+        (previous_conversion_ratio as u128) * (previous_conversion_price as u128)
+            / (new_conversion_price as u128),
+    )
+    .map_err(|_| StockClassError::ArithmeticOverflow)?;
+
+    stock_class.conversion_price = new_conversion_price;
+    stock_class.terms.conversion_ratio = new_conversion_ratio;
+
+    Ok(ConversionAdjustment {
+        shares_outstanding_basis,
+        previous_conversion_price,
+        new_conversion_price,
+        previous_conversion_ratio,
+        new_conversion_ratio,
+    })
+}
+
+pub fn create_stock_plan(
+    stock_plan: &mut StockPlan,
+    cap_table_summary: &mut CapTableSummary,
+    id: [u8; 16],
+    stock_class_ids: Vec<[u8; 16]>,
+    shares_reserved: u64,
+) -> Result<(), CoreError> {
+    ensure(
+        !stock_class_ids.is_empty(),
+        StockPlanError::InvalidStockClassCount,
+    )?;
+
+    stock_plan.id = id;
+    stock_plan.stock_class_ids = stock_class_ids;
+    stock_plan.shares_reserved = shares_reserved;
+
+    cap_table_summary.plan_pool_reserved = cap_table_summary
+        .plan_pool_reserved
+        .checked_add(shares_reserved)
+        .ok_or(CapTableSummaryError::ArithmeticOverflow)?;
+    cap_table_summary.sync_plan_pool_available();
+    Ok(())
+}
+
+pub fn adjust_stock_plan_shares(
+    stock_plan: &mut StockPlan,
+    cap_table_summary: &mut CapTableSummary,
+    new_shares_reserved: u64,
+) -> Result<(), CoreError> {
+    let previous_shares_reserved = stock_plan.shares_reserved;
+    stock_plan.shares_reserved = new_shares_reserved;
+
+    cap_table_summary.plan_pool_reserved = cap_table_summary
+        .plan_pool_reserved
+        .checked_sub(previous_shares_reserved)
+        .and_then(|reserved| reserved.checked_add(new_shares_reserved))
+        .ok_or(CapTableSummaryError::ArithmeticOverflow)?;
+    cap_table_summary.sync_plan_pool_available();
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn issue_stock(
+    stock_class: &mut StockClass,
+    cap_table_summary: &mut CapTableSummary,
+    position: &mut StockActivePosition,
+    stakeholder_id: [u8; 16],
+    security_id: [u8; 16],
+    quantity: u64,
+    share_price: u64,
+) -> Result<(), CoreError> {
+    ensure(quantity > 0, StockError::InvalidQuantity)?;
+    ensure(share_price > 0, StockError::InvalidSharePrice)?;
+    ensure(
+        stock_class.shares_issued + quantity <= stock_class.shares_authorized,
+        StockError::InsufficientShares,
+    )?;
+
+    position.stakeholder_id = stakeholder_id;
+    position.stock_class_id = stock_class.id;
+    position.security_id = security_id;
+    position.quantity = quantity;
+    position.share_price = share_price;
+    position.split_epoch = stock_class.split_epoch();
+
+    stock_class.shares_issued += quantity;
+    cap_table_summary.sync_stock_class(stock_class)?;
+    Ok(())
+}
+
+/// Converts part of a preferred position into the class it converts to and
+/// returns the quantity received. The caller closes the source position once
+/// its quantity reaches zero.
+#[allow(clippy::too_many_arguments)]
+pub fn convert_stock(
+    source_stock_class: &mut StockClass,
+    target_stock_class: &mut StockClass,
+    cap_table_summary: &mut CapTableSummary,
+    source_position: &mut StockActivePosition,
+    resulting_position: &mut StockActivePosition,
+    resulting_security_id: [u8; 16],
+    quantity: u64,
+) -> Result<u64, CoreError> {
+    ensure(
+        source_stock_class.id == source_position.stock_class_id
+            && target_stock_class.id == source_stock_class.terms.converts_to_stock_class_id,
+        StockError::ConversionClassMismatch,
+    )?;
+    ensure(
+        source_stock_class.terms.kind == StockClassKind::Preferred,
+        StockError::NotConvertible,
+    )?;
+    ensure(quantity > 0, StockError::InvalidQuantity)?;
+
+    // Convert on post-split quantities only
+    apply_pending_splits(source_stock_class, source_position)?;
+
+    ensure(
+        source_position.quantity >= quantity,
+        StockError::InsufficientPositionQuantity,
+    )?;
+
+    let conversion_ratio = source_stock_class.terms.conversion_ratio;
+    let quantity_received = u64::try_from(
+        (quantity as u128) * (conversion_ratio as u128) / (SHARE_DECIMALS_FACTOR as u128),
+    )
+    .map_err(|_| StockError::ArithmeticOverflow)?;
+    ensure(quantity_received > 0, StockError::NoSharesReceived)?;
+
+    let new_target_shares_issued = target_stock_class
+        .shares_issued
+        .checked_add(quantity_received)
+        .ok_or(StockError::InsufficientShares)?;
+    ensure(
+        new_target_shares_issued <= target_stock_class.shares_authorized,
+        StockError::InsufficientShares,
+    )?;
+
+    // Carry the cost basis over to the resulting shares
+    let resulting_share_price = u64::try_from(
+        (source_position.share_price as u128) * (quantity as u128) / (quantity_received as u128),
+    )
+    .map_err(|_| StockError::ArithmeticOverflow)?;
+
+    resulting_position.stakeholder_id = source_position.stakeholder_id;
+    resulting_position.stock_class_id = target_stock_class.id;
+    resulting_position.security_id = resulting_security_id;
+    resulting_position.quantity = quantity_received;
+    resulting_position.share_price = resulting_share_price;
+    resulting_position.split_epoch = target_stock_class.split_epoch();
+
+    source_position.quantity -= quantity;
+    source_stock_class.shares_issued = source_stock_class.shares_issued.saturating_sub(quantity);
+    target_stock_class.shares_issued = new_target_shares_issued;
+
+    cap_table_summary.sync_stock_class(source_stock_class)?;
+    cap_table_summary.sync_stock_class(target_stock_class)?;
+    Ok(quantity_received)
+}
+
+pub fn issue_convertible(
+    position: &mut ConvertibleActivePosition,
+    cap_table_summary: &mut CapTableSummary,
+    stakeholder_id: [u8; 16],
+    security_id: [u8; 16],
+    investment_amount: u64,
+) -> Result<(), CoreError> {
+    ensure(investment_amount > 0, ConvertibleError::InvalidAmount)?;
+
+    position.stakeholder_id = stakeholder_id;
+    position.security_id = security_id;
+    position.investment_amount = investment_amount;
+
+    cap_table_summary.convertible_principal = cap_table_summary
+        .convertible_principal
+        .checked_add(investment_amount)
+        .ok_or(CapTableSummaryError::ArithmeticOverflow)?;
+    Ok(())
+}
+
+/// Converts the whole principal of a convertible into `stock_class` at
+/// `conversion_price` and returns the shares received. The caller closes the
+/// convertible.
+pub fn convert_convertible(
+    convertible_position: &mut ConvertibleActivePosition,
+    stock_class: &mut StockClass,
+    cap_table_summary: &mut CapTableSummary,
+    resulting_position: &mut StockActivePosition,
+    resulting_security_id: [u8; 16],
+    conversion_price: u64,
+) -> Result<u64, CoreError> {
+    ensure(
+        conversion_price > 0,
+        ConvertibleError::InvalidConversionPrice,
+    )?;
+
+    let investment_amount = convertible_position.investment_amount;
+    let quantity_received = u64::try_from(
+        (investment_amount as u128) * (SHARE_DECIMALS_FACTOR as u128) / (conversion_price as u128),
+    )
+    .map_err(|_| ConvertibleError::ArithmeticOverflow)?;
+    ensure(quantity_received > 0, ConvertibleError::NoSharesReceived)?;
+
+    let new_shares_issued = stock_class
+        .shares_issued
+        .checked_add(quantity_received)
+        .ok_or(ConvertibleError::InsufficientShares)?;
+    ensure(
+        new_shares_issued <= stock_class.shares_authorized,
+        ConvertibleError::InsufficientShares,
+    )?;
+
+    resulting_position.stakeholder_id = convertible_position.stakeholder_id;
+    resulting_position.stock_class_id = stock_class.id;
+    resulting_position.security_id = resulting_security_id;
+    resulting_position.quantity = quantity_received;
+    resulting_position.share_price = conversion_price;
+    resulting_position.split_epoch = stock_class.split_epoch();
+
+    stock_class.shares_issued = new_shares_issued;
+    convertible_position.investment_amount = 0;
+
+    cap_table_summary.convertible_principal = cap_table_summary
+        .convertible_principal
+        .checked_sub(investment_amount)
+        .ok_or(CapTableSummaryError::ArithmeticOverflow)?;
+    cap_table_summary.sync_stock_class(stock_class)?;
+    Ok(quantity_received)
+}
+
+/// Checks the terms of a newly written round.
+pub fn open_financing_round(round: &PricedRound) -> Result<(), CoreError> {
+    ensure(
+        round.pre_money_valuation > 0,
+        FinancingRoundError::InvalidValuation,
+    )?;
+    ensure(
+        round.target_pool_fraction < SHARE_DECIMALS_FACTOR,
+        FinancingRoundError::InvalidPoolTarget,
+    )?;
+    ensure(
+        round.target_pool_fraction == 0 || round.stock_plan_id != [0; 16],
+        FinancingRoundError::StockPlanMismatch,
+    )?;
+    check_stock_class_terms(&round.terms)
+}
+
+/// An entry can be added while the round is open and has room, and each
+/// security id appears in it once.
+fn ensure_round_entry(round: &PricedRound, security_id: &[u8; 16]) -> Result<(), CoreError> {
+    ensure(
+        round.status == PricedRoundStatus::Open,
+        FinancingRoundError::RoundNotOpen,
+    )?;
+    ensure(
+        round.entry_count() < MAX_FINANCING_ROUND_ENTRIES,
+        FinancingRoundError::TooManyEntries,
+    )?;
+    ensure(
+        !round
+            .subscriptions
+            .iter()
+            .any(|subscription| subscription.security_id == *security_id)
+            && !round.conversions.iter().any(|conversion| {
+                conversion.security_id == *security_id
+                    || conversion.resulting_security_id == *security_id
+            }),
+        FinancingRoundError::DuplicateSecurity,
+    )
+}
+
+pub fn add_round_subscription(
+    round: &mut PricedRound,
+    stakeholder_id: [u8; 16],
+    security_id: [u8; 16],
+    amount: u64,
+) -> Result<(), CoreError> {
+    ensure_round_entry(round, &security_id)?;
+    ensure(amount > 0, FinancingRoundError::InvalidInvestment)?;
+
+    round.subscriptions.push(RoundSubscription {
+        stakeholder_id,
+        security_id,
+        amount,
+        shares: 0,
+    });
+    Ok(())
+}
+
+/// Claims `convertible_position` for the round, so that no other round can
+/// convert it.
+pub fn add_round_conversion(
+    round: &mut PricedRound,
+    convertible_position: &mut ConvertibleActivePosition,
+    resulting_security_id: [u8; 16],
+    discount: u64,
+    valuation_cap: u64,
+) -> Result<(), CoreError> {
+    ensure_round_entry(round, &convertible_position.security_id)?;
+    ensure_round_entry(round, &resulting_security_id)?;
+    ensure(
+        convertible_position.security_id != resulting_security_id,
+        FinancingRoundError::DuplicateSecurity,
+    )?;
+    ensure(
+        discount < SHARE_DECIMALS_FACTOR,
+        FinancingRoundError::InvalidConvertibleTerms,
+    )?;
+    ensure(
+        convertible_position.round_id == [0; 16],
+        FinancingRoundError::ConvertibleInRound,
+    )?;
+
+    convertible_position.round_id = round.id;
+    round.conversions.push(RoundConvertible {
+        stakeholder_id: convertible_position.stakeholder_id,
+        security_id: convertible_position.security_id,
+        resulting_security_id,
+        principal: convertible_position.investment_amount,
+        discount,
+        valuation_cap,
+        conversion_price: 0,
+        shares: 0,
+    });
+    Ok(())
+}
+
+/// Releases a convertible claimed by `round`, which is being cancelled.
+pub fn release_round_conversion(
+    round: &PricedRound,
+    convertible_position: &mut ConvertibleActivePosition,
+) -> Result<(), CoreError> {
+    ensure(
+        round.status == PricedRoundStatus::Open,
+        FinancingRoundError::RoundNotOpen,
+    )?;
+    ensure(
+        convertible_position.round_id == round.id
+            && round
+                .conversions
+                .iter()
+                .any(|conversion| conversion.security_id == convertible_position.security_id),
+        FinancingRoundError::EntryNotFound,
+    )?;
+
+    convertible_position.round_id = [0; 16];
+    Ok(())
+}
+
+/// Prices the round with [`model_financing_round`] and applies all of it:
+/// creates the round's stock class with exactly the shares the round issues,
+/// issues every subscription, converts every convertible, tops up the stock
+/// plan and raises the issuer's authorized shares to cover every class.
+/// The shares and prices are recorded on the round's entries, from which
+/// [`settle_financing_round`] later writes the positions.
+pub fn close_financing_round(
+    round: &mut PricedRound,
+    issuer: &mut Issuer,
+    stock_class: &mut StockClass,
+    stock_plan: Option<&mut StockPlan>,
+    cap_table_summary: &mut CapTableSummary,
+) -> Result<RoundModel, CoreError> {
+    ensure(
+        round.status == PricedRoundStatus::Open,
+        FinancingRoundError::RoundNotOpen,
+    )?;
+    ensure(
+        !round.subscriptions.is_empty(),
+        FinancingRoundError::NoSubscriptions,
+    )?;
+    ensure(
+        stock_plan.as_ref().map(|stock_plan| stock_plan.id)
+            == Some(round.stock_plan_id).filter(|id| *id != [0; 16]),
+        FinancingRoundError::StockPlanMismatch,
+    )?;
+
+    let mut convertible_positions: Vec<ConvertibleActivePosition> = round
+        .conversions
+        .iter()
+        .map(|conversion| ConvertibleActivePosition {
+            stakeholder_id: conversion.stakeholder_id,
+            security_id: conversion.security_id,
+            investment_amount: conversion.principal,
+            round_id: round.id,
+        })
+        .collect();
+    let model = model_financing_round(
+        &RoundInput {
+            cap_table_summary,
+            stock_classes: Vec::new(),
+            stock_positions: Vec::new(),
+            convertible_positions: convertible_positions.iter().collect(),
+            equity_compensation_positions: Vec::new(),
+            warrant_positions: Vec::new(),
+        },
+        &FinancingRound {
+            pre_money_valuation: round.pre_money_valuation,
+            investments: round
+                .subscriptions
+                .iter()
+                .map(|subscription| Investment {
+                    stakeholder_id: subscription.stakeholder_id,
+                    amount: subscription.amount,
+                })
+                .collect(),
+            target_pool_fraction: round.target_pool_fraction,
+            convertible_terms: round
+                .conversions
+                .iter()
+                .map(|conversion| ConvertibleTerms {
+                    security_id: conversion.security_id,
+                    discount: conversion.discount,
+                    valuation_cap: conversion.valuation_cap,
+                })
+                .collect(),
+        },
+    )?;
+
+    let shares_issued = model
+        .issuances
+        .iter()
+        .map(|issuance| issuance.shares)
+        .chain(model.conversions.iter().map(|conversion| conversion.shares))
+        .try_fold(0u64, |total, shares| total.checked_add(shares))
+        .ok_or(FinancingRoundError::ArithmeticOverflow)?;
+    create_stock_class(
+        stock_class,
+        cap_table_summary,
+        round.stock_class_id,
+        round.class_type.clone(),
+        model.price_per_share,
+        shares_issued,
+        round.terms,
+    )?;
+
+    for (subscription, issuance) in round.subscriptions.iter_mut().zip(&model.issuances) {
+        issue_stock(
+            stock_class,
+            cap_table_summary,
+            &mut StockActivePosition::default(),
+            subscription.stakeholder_id,
+            subscription.security_id,
+            issuance.shares,
+            model.price_per_share,
+        )?;
+        subscription.shares = issuance.shares;
+    }
+    for ((conversion, convertible_position), modelled) in round
+        .conversions
+        .iter_mut()
+        .zip(convertible_positions.iter_mut())
+        .zip(&model.conversions)
+    {
+        conversion.shares = convert_convertible(
+            convertible_position,
+            stock_class,
+            cap_table_summary,
+            &mut StockActivePosition::default(),
+            conversion.resulting_security_id,
+            modelled.conversion_price,
+        )?;
+        conversion.conversion_price = modelled.conversion_price;
+    }
+
+    if let Some(stock_plan) = stock_plan {
+        if model.pool_increase > 0 {
+            let new_shares_reserved = stock_plan
+                .shares_reserved
+                .checked_add(model.pool_increase)
+                .ok_or(FinancingRoundError::ArithmeticOverflow)?;
+            adjust_stock_plan_shares(stock_plan, cap_table_summary, new_shares_reserved)?;
+        }
+    }
+
+    let classes_authorized = cap_table_summary
+        .stock_classes
+        .iter()
+        .try_fold(0u64, |total, summary| {
+            total.checked_add(summary.shares_authorized)
+        })
+        .ok_or(FinancingRoundError::ArithmeticOverflow)?;
+    if issuer.shares_authorized < classes_authorized {
+        adjust_authorized_shares(issuer, cap_table_summary, classes_authorized);
+    }
+
+    round.status = PricedRoundStatus::Closed;
+    round.price_per_share = model.price_per_share;
+    round.pool_increase = model.pool_increase;
+    Ok(model)
+}
+
+/// Writes the stock position of the closed round's entry that issued
+/// `security_id` and returns the convertible it replaces, if any, for the
+/// caller to close.
+pub fn settle_financing_round(
+    round: &mut PricedRound,
+    position: &mut StockActivePosition,
+    stakeholder_id: [u8; 16],
+    security_id: [u8; 16],
+) -> Result<Option<[u8; 16]>, CoreError> {
+    ensure(
+        round.status == PricedRoundStatus::Closed,
+        FinancingRoundError::RoundNotClosed,
+    )?;
+
+    let (entry_stakeholder_id, quantity, share_price, convertible_security_id) =
+        if let Some(subscription) = round
+            .subscriptions
+            .iter()
+            .find(|subscription| subscription.security_id == security_id)
+        {
+            (
+                subscription.stakeholder_id,
+                subscription.shares,
+                round.price_per_share,
+                None,
+            )
+        } else if let Some(conversion) = round
+            .conversions
+            .iter()
+            .find(|conversion| conversion.resulting_security_id == security_id)
+        {
+            (
+                conversion.stakeholder_id,
+                conversion.shares,
+                conversion.conversion_price,
+                Some(conversion.security_id),
+            )
+        } else {
+            return Err(FinancingRoundError::EntryNotFound.into());
+        };
+    ensure(
+        entry_stakeholder_id == stakeholder_id,
+        FinancingRoundError::StakeholderMismatch,
+    )?;
+
+    position.stakeholder_id = stakeholder_id;
+    position.stock_class_id = round.stock_class_id;
+    position.security_id = security_id;
+    position.quantity = quantity;
+    position.share_price = share_price;
+    // The round created the class, so its positions start before any split
+    position.split_epoch = 0;
+
+    round.settled += 1;
+    Ok(convertible_security_id)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn issue_equity_compensation(
+    position: &mut EquityCompensationActivePosition,
+    cap_table_summary: &mut CapTableSummary,
+    stakeholder_id: [u8; 16],
+    stock_class_id: [u8; 16],
+    stock_plan_id: Option<[u8; 16]>,
+    security_id: [u8; 16],
+    quantity: u64,
+) -> Result<(), CoreError> {
+    ensure(quantity > 0, EquityCompensationError::InvalidQuantity)?;
+
+    position.stakeholder_id = stakeholder_id;
+    position.stock_class_id = stock_class_id;
+    if let Some(stock_plan_id) = stock_plan_id {
+        position.stock_plan_id = stock_plan_id;
+    }
+    position.security_id = security_id;
+    position.quantity = quantity;
+
+    cap_table_summary.options_outstanding = cap_table_summary
+        .options_outstanding
+        .checked_add(quantity)
+        .ok_or(CapTableSummaryError::ArithmeticOverflow)?;
+    if stock_plan_id.is_some() {
+        cap_table_summary.plan_pool_granted = cap_table_summary
+            .plan_pool_granted
+            .checked_add(quantity)
+            .ok_or(CapTableSummaryError::ArithmeticOverflow)?;
+        cap_table_summary.sync_plan_pool_available();
+    }
+    Ok(())
+}
+
+/// Exercises options into `stock_position`, which must already hold exactly
+/// the exercised quantity.
+pub fn exercise_equity_compensation(
+    equity_position: &mut EquityCompensationActivePosition,
+    stock_position: &StockActivePosition,
+    cap_table_summary: &mut CapTableSummary,
+    quantity: u64,
+) -> Result<(), CoreError> {
+    ensure(quantity > 0, EquityCompensationError::InvalidQuantity)?;
+    ensure(
+        equity_position.quantity >= quantity,
+        EquityCompensationError::InsufficientShares,
+    )?;
+    ensure(
+        stock_position.quantity == quantity,
+        EquityCompensationError::QuantityMismatch,
+    )?;
+    ensure(
+        stock_position.stakeholder_id == equity_position.stakeholder_id,
+        EquityCompensationError::InvalidStakeholder,
+    )?;
+
+    equity_position.quantity = equity_position
+        .quantity
+        .checked_sub(quantity)
+        .ok_or(EquityCompensationError::InsufficientShares)?;
+
+    cap_table_summary.options_outstanding = cap_table_summary
+        .options_outstanding
+        .checked_sub(quantity)
+        .ok_or(CapTableSummaryError::ArithmeticOverflow)?;
+    Ok(())
+}
+
+/// Validates a newly written warrant position against the clock and counts
+/// it toward the outstanding total.
+pub fn issue_warrant(
+    position: &WarrantActivePosition,
+    cap_table_summary: &mut CapTableSummary,
+    now: i64,
+) -> Result<(), CoreError> {
+    ensure(position.quantity > 0, WarrantError::InvalidQuantity)?;
+    ensure(
+        position.exercise_price > 0,
+        WarrantError::InvalidExercisePrice,
+    )?;
+    ensure(
+        position.expiration_date > now,
+        WarrantError::InvalidExpirationDate,
+    )?;
+
+    cap_table_summary.warrants_outstanding = cap_table_summary
+        .warrants_outstanding
+        .checked_add(position.quantity)
+        .ok_or(CapTableSummaryError::ArithmeticOverflow)?;
+    Ok(())
+}
+
+/// Exercises warrants into a new stock position and returns the shares
+/// received. The caller closes the warrant once its quantity reaches zero.
+#[allow(clippy::too_many_arguments)]
+pub fn exercise_warrant(
+    warrant_position: &mut WarrantActivePosition,
+    stock_class: &mut StockClass,
+    stock_position: &mut StockActivePosition,
+    cap_table_summary: &mut CapTableSummary,
+    resulting_security_id: [u8; 16],
+    quantity: u64,
+    fair_market_value: Option<u64>,
+    now: i64,
+) -> Result<u64, CoreError> {
+    ensure(
+        stock_class.id == warrant_position.stock_class_id,
+        WarrantError::StockClassMismatch,
+    )?;
+    ensure(quantity > 0, WarrantError::InvalidQuantity)?;
+    ensure(
+        warrant_position.quantity >= quantity,
+        WarrantError::InsufficientQuantity,
+    )?;
+    ensure(
+        now <= warrant_position.expiration_date,
+        WarrantError::WarrantExpired,
+    )?;
+
+    // Cash exercise receives one share per warrant. Net exercise surrenders the
+    // in-the-money value only: quantity * (fmv - exercise_price) / fmv, rounded down.
+    let shares_received = match fair_market_value {
+        None => quantity,
+        Some(fmv) => {
+            ensure(
+                fmv > warrant_position.exercise_price,
+                WarrantError::NotInTheMoney,
+            )?;
+            let shares = (quantity as u128) * ((fmv - warrant_position.exercise_price) as u128)
+                / (fmv as u128);
+            ensure(shares > 0, WarrantError::NoSharesReceived)?;
+            shares as u64
+        }
+    };
+
+    let new_shares_issued = stock_class
+        .shares_issued
+        .checked_add(shares_received)
+        .ok_or(WarrantError::InsufficientShares)?;
+    ensure(
+        new_shares_issued <= stock_class.shares_authorized,
+        WarrantError::InsufficientShares,
+    )?;
+
+    stock_position.stakeholder_id = warrant_position.stakeholder_id;
+    stock_position.stock_class_id = stock_class.id;
+    stock_position.security_id = resulting_security_id;
+    stock_position.quantity = shares_received;
+    stock_position.share_price = warrant_position.exercise_price;
+    stock_position.split_epoch = stock_class.split_epoch();
+
+    stock_class.shares_issued = new_shares_issued;
+
+    warrant_position.quantity = warrant_position
+        .quantity
+        .checked_sub(quantity)
+        .ok_or(WarrantError::InsufficientQuantity)?;
+
+    cap_table_summary.warrants_outstanding = cap_table_summary
+        .warrants_outstanding
+        .checked_sub(quantity)
+        .ok_or(CapTableSummaryError::ArithmeticOverflow)?;
+    cap_table_summary.sync_stock_class(stock_class)?;
+    Ok(shares_received)
+}
+
+pub fn cancel_warrant(
+    warrant_position: &mut WarrantActivePosition,
+    cap_table_summary: &mut CapTableSummary,
+    quantity: u64,
+) -> Result<(), CoreError> {
+    ensure(quantity > 0, WarrantError::InvalidQuantity)?;
+
+    warrant_position.quantity = warrant_position
+        .quantity
+        .checked_sub(quantity)
+        .ok_or(WarrantError::InsufficientQuantity)?;
+
+    cap_table_summary.warrants_outstanding = cap_table_summary
+        .warrants_outstanding
+        .checked_sub(quantity)
+        .ok_or(CapTableSummaryError::ArithmeticOverflow)?;
+    Ok(())
+}
+
+/// Removes an expired warrant from the outstanding total; the caller closes it.
+pub fn expire_warrant(
+    warrant_position: &WarrantActivePosition,
+    cap_table_summary: &mut CapTableSummary,
+    now: i64,
+) -> Result<(), CoreError> {
+    ensure(
+        now > warrant_position.expiration_date,
+        WarrantError::WarrantNotExpired,
+    )?;
+
+    cap_table_summary.warrants_outstanding = cap_table_summary
+        .warrants_outstanding
+        .checked_sub(warrant_position.quantity)
+        .ok_or(CapTableSummaryError::ArithmeticOverflow)?;
+    Ok(())
+}
+
+/// Moves `quantity` warrants into a new position for `new_stakeholder_id`
+/// with the same terms. The caller records the new position's rent payer
+/// and closes the original once its quantity reaches zero.
+pub fn transfer_warrant(
+    warrant_position: &mut WarrantActivePosition,
+    new_warrant_position: &mut WarrantActivePosition,
+    new_stakeholder_id: [u8; 16],
+    resulting_security_id: [u8; 16],
+    quantity: u64,
+    now: i64,
+) -> Result<(), CoreError> {
+    ensure(
+        new_stakeholder_id != warrant_position.stakeholder_id,
+        WarrantError::SelfTransfer,
+    )?;
+    ensure(quantity > 0, WarrantError::InvalidQuantity)?;
+    ensure(
+        now <= warrant_position.expiration_date,
+        WarrantError::WarrantExpired,
+    )?;
+
+    warrant_position.quantity = warrant_position
+        .quantity
+        .checked_sub(quantity)
+        .ok_or(WarrantError::InsufficientQuantity)?;
+
+    new_warrant_position.stakeholder_id = new_stakeholder_id;
+    new_warrant_position.stock_class_id = warrant_position.stock_class_id;
+    new_warrant_position.security_id = resulting_security_id;
+    new_warrant_position.quantity = quantity;
+    new_warrant_position.exercise_price = warrant_position.exercise_price;
+    new_warrant_position.expiration_date = warrant_position.expiration_date;
+    new_warrant_position.exercise_trigger = warrant_position.exercise_trigger;
+    Ok(())
+}
+
+/// Checks a board's or multisig's member list and approval threshold.
+fn check_members(members: &[Pubkey], threshold: u8, max_members: usize) -> Result<(), CoreError> {
+    ensure(
+        !members.is_empty() && members.len() <= max_members,
+        GovernanceError::InvalidMemberCount,
+    )?;
+    ensure(
+        members
+            .iter()
+            .enumerate()
+            .all(|(index, member)| !members[..index].contains(member)),
+        GovernanceError::DuplicateMember,
+    )?;
+    ensure(
+        threshold > 0 && threshold as usize <= members.len(),
+        GovernanceError::InvalidThreshold,
+    )
+}
+
+pub fn configure_board(
+    board: &mut Board,
+    issuer_id: [u8; 16],
+    members: Vec<Pubkey>,
+    threshold: u8,
+) -> Result<(), CoreError> {
+    check_members(&members, threshold, MAX_BOARD_MEMBERS)?;
+
+    board.issuer_id = issuer_id;
+    board.members = members;
+    board.threshold = threshold;
+    Ok(())
+}
+
+/// Checks what can be checked of the action before anyone approves it; the
+/// operation's own rules run again when it executes.
+pub fn create_proposal(proposal: &Proposal, now: i64) -> Result<(), CoreError> {
+    ensure(
+        (0..=now).contains(&proposal.stockholder_approval_date),
+        GovernanceError::InvalidApprovalDate,
+    )?;
+    match &proposal.action {
+        ProposalAction::CreateStockClass { terms, .. } => check_stock_class_terms(terms),
+        ProposalAction::UpdateBoard { members, threshold } => {
+            check_members(members, *threshold, MAX_BOARD_MEMBERS)
+        }
+        ProposalAction::AdjustAuthorizedShares { .. } | ProposalAction::CreateStockPlan { .. } => {
+            Ok(())
+        }
+    }
+}
+
+/// Approvals count while their signer is on the board, so members removed by
+/// a later board update no longer carry a proposal.
+fn current_approvals(proposal: &Proposal, board: &Board) -> usize {
+    proposal
+        .approvals
+        .iter()
+        .filter(|member| board.is_member(member))
+        .count()
+}
+
+pub fn approve_proposal(
+    proposal: &mut Proposal,
+    board: &Board,
+    member: Pubkey,
+    now: i64,
+) -> Result<(), CoreError> {
+    ensure(proposal.executed_at == 0, GovernanceError::AlreadyExecuted)?;
+    ensure(board.is_member(&member), GovernanceError::NotBoardMember)?;
+    ensure(
+        !proposal.approvals.contains(&member),
+        GovernanceError::AlreadyApproved,
+    )?;
+
+    proposal.approvals.push(member);
+    if proposal.board_approval_date == 0
+        && current_approvals(proposal, board) >= board.threshold as usize
+    {
+        proposal.board_approval_date = now;
+    }
+    Ok(())
+}
+
+/// Marks the proposal executed; the caller then performs its action.
+pub fn execute_proposal(proposal: &mut Proposal, board: &Board, now: i64) -> Result<(), CoreError> {
+    ensure(proposal.executed_at == 0, GovernanceError::AlreadyExecuted)?;
+    ensure(
+        proposal.board_approval_date != 0
+            && current_approvals(proposal, board) >= board.threshold as usize,
+        GovernanceError::NotApproved,
+    )?;
+
+    proposal.executed_at = now;
+    Ok(())
+}
+
+pub fn configure_multisig(
+    multisig: &mut Multisig,
+    issuer_id: [u8; 16],
+    members: Vec<Pubkey>,
+    threshold: u8,
+) -> Result<(), CoreError> {
+    check_members(&members, threshold, MAX_MULTISIG_MEMBERS)?;
+
+    multisig.issuer_id = issuer_id;
+    multisig.members = members;
+    multisig.threshold = threshold;
+    Ok(())
+}
+
+/// Signers authorize an instruction when `threshold` distinct members are
+/// among them.
+pub fn authorize_multisig(multisig: &Multisig, signers: &[Pubkey]) -> Result<(), CoreError> {
+    let approvals = multisig
+        .members
+        .iter()
+        .filter(|member| signers.contains(member))
+        .count();
+    ensure(
+        approvals >= multisig.threshold as usize,
+        GovernanceError::MultisigUnauthorized,
+    )
+}
+
+/// Takes the multisig's next nonce for the transaction; the proposer's
+/// approval is recorded with it.
+pub fn create_multisig_transaction(
+    multisig: &mut Multisig,
+    transaction: &mut MultisigTransaction,
+    proposer: Pubkey,
+) -> Result<(), CoreError> {
+    ensure(
+        multisig.is_member(&proposer),
+        GovernanceError::NotMultisigMember,
+    )?;
+    ensure(
+        !transaction.data.is_empty(),
+        GovernanceError::EmptyTransaction,
+    )?;
+
+    transaction.issuer_id = multisig.issuer_id;
+    transaction.nonce = multisig.nonce;
+    transaction.proposer = proposer;
+    transaction.approvals = Vec::from([proposer]);
+    multisig.nonce += 1;
+    Ok(())
+}
+
+pub fn approve_multisig_transaction(
+    transaction: &mut MultisigTransaction,
+    multisig: &Multisig,
+    member: Pubkey,
+) -> Result<(), CoreError> {
+    ensure(!transaction.executed, GovernanceError::AlreadyExecuted)?;
+    ensure(
+        multisig.is_member(&member),
+        GovernanceError::NotMultisigMember,
+    )?;
+    ensure(
+        !transaction.approvals.contains(&member),
+        GovernanceError::AlreadyApproved,
+    )?;
+
+    transaction.approvals.push(member);
+    Ok(())
+}
+
+/// Approvals count while their signer is a member, so a membership change
+/// applies to transactions already pending.
+pub fn execute_multisig_transaction(
+    transaction: &mut MultisigTransaction,
+    multisig: &Multisig,
+) -> Result<(), CoreError> {
+    ensure(!transaction.executed, GovernanceError::AlreadyExecuted)?;
+    let approvals = transaction
+        .approvals
+        .iter()
+        .filter(|member| multisig.is_member(member))
+        .count();
+    ensure(
+        approvals >= multisig.threshold as usize,
+        GovernanceError::NotApproved,
+    )?;
+
+    transaction.executed = true;
+    Ok(())
+}
+
+/// `stock_classes` must be every class in the cap table summary; each gets a
+/// tally, with `separate_class_ids` marking those voting as a separate class.
+pub fn create_resolution(
+    resolution: &mut Resolution,
+    cap_table_summary: &CapTableSummary,
+    stock_classes: &[&StockClass],
+    separate_class_ids: &[[u8; 16]],
+    now: i64,
+) -> Result<(), CoreError> {
+    ensure(
+        resolution.record_date >= now && resolution.voting_deadline > resolution.record_date,
+        VotingError::InvalidVotingPeriod,
+    )?;
+    ensure(
+        stock_classes.len() == cap_table_summary.stock_classes.len()
+            && cap_table_summary.stock_classes.iter().all(|summary| {
+                stock_classes
+                    .iter()
+                    .any(|stock_class| stock_class.id == summary.stock_class_id)
+            }),
+        VotingError::MissingStockClass,
+    )?;
+    ensure(
+        separate_class_ids.iter().all(|id| {
+            stock_classes
+                .iter()
+                .any(|stock_class| stock_class.id == *id)
+        }),
+        VotingError::UnknownStockClass,
+    )?;
+
+    resolution.tallies = stock_classes
+        .iter()
+        .map(|stock_class| ClassTally {
+            stock_class_id: stock_class.id,
+            votes_per_share: stock_class.terms.votes_per_share,
+            separate_vote: separate_class_ids.contains(&stock_class.id),
+            ..ClassTally::default()
+        })
+        .collect();
+    Ok(())
+}
+
+/// Replaces the voter's shares with the split-adjusted quantities of
+/// `positions`, which must be every stock position in `holdings`. Classes
+/// created after the resolution do not vote on it. After the record date,
+/// holdings are only recorded while their stock is unchanged since then.
+pub fn record_voting_power(
+    resolution: &Resolution,
+    voter: &mut VoterRecord,
+    holdings: &StakeholderHoldings,
+    stock_classes: &[&StockClass],
+    positions: &[&StockActivePosition],
+    now: i64,
+) -> Result<(), CoreError> {
+    ensure(
+        now <= resolution.record_date || holdings.stock_changed_at <= resolution.record_date,
+        VotingError::RecordDatePassed,
+    )?;
+    let stock_holdings = holdings
+        .holdings
+        .iter()
+        .filter(|holding| holding.position_type == PositionType::Stock);
+    ensure(
+        stock_holdings.clone().count() == positions.len()
+            && stock_holdings.into_iter().all(|holding| {
+                positions
+                    .iter()
+                    .any(|position| position.security_id == holding.security_id)
+            }),
+        VotingError::IncompletePositions,
+    )?;
+
+    let mut shares = alloc::vec![0u64; resolution.tallies.len()];
+    for position in positions {
+        ensure(
+            position.stakeholder_id == holdings.stakeholder_id,
+            VotingError::PositionMismatch,
+        )?;
+        let Some(index) = resolution
+            .tallies
+            .iter()
+            .position(|tally| tally.stock_class_id == position.stock_class_id)
+        else {
+            continue;
+        };
+        let stock_class = stock_classes
+            .iter()
+            .find(|stock_class| stock_class.id == position.stock_class_id)
+            .ok_or(VotingError::MissingStockClass)?;
+
+        let mut current = (*position).clone();
+        current
+            .apply_pending_splits(stock_class)
+            .ok_or(VotingError::ArithmeticOverflow)?;
+        shares[index] = shares[index]
+            .checked_add(current.quantity)
+            .ok_or(VotingError::ArithmeticOverflow)?;
+    }
+
+    voter.resolution_id = resolution.id;
+    voter.stakeholder_id = holdings.stakeholder_id;
+    voter.shares = shares;
+    voter.recorded_at = now;
+    Ok(())
+}
+
+/// Adds the voter's shares times each class's votes per share to the
+/// tallies for `choice`. Returns the votes added to each tally.
+///
+/// A record taken before the record date counts only if the voter's stock
+/// has not changed since, so shares that moved to another stakeholder after
+/// being recorded are not counted for both. A record taken after the record
+/// date already reflects the holdings as of that date.
+pub fn cast_vote(
+    resolution: &mut Resolution,
+    voter: &mut VoterRecord,
+    holdings: &StakeholderHoldings,
+    choice: VoteChoice,
+    now: i64,
+) -> Result<Vec<u64>, CoreError> {
+    ensure(
+        now > resolution.record_date && now <= resolution.voting_deadline,
+        VotingError::VotingClosed,
+    )?;
+    ensure(voter.choice.is_none(), VotingError::AlreadyVoted)?;
+    ensure(
+        voter.recorded_at > resolution.record_date || holdings.stock_changed_at < voter.recorded_at,
+        VotingError::StaleVoterRecord,
+    )?;
+
+    let votes = resolution
+        .tallies
+        .iter()
+        .zip(&voter.shares)
+        .map(|(tally, shares)| {
+            u64::try_from(
+                *shares as u128 * tally.votes_per_share as u128 / SHARE_DECIMALS_FACTOR as u128,
+            )
+            .map_err(|_| VotingError::ArithmeticOverflow.into())
+        })
+        .collect::<Result<Vec<u64>, CoreError>>()?;
+    ensure(
+        votes.iter().any(|votes| *votes > 0),
+        VotingError::NoVotingPower,
+    )?;
+
+    for (tally, votes) in resolution.tallies.iter_mut().zip(&votes) {
+        let total = match choice {
+            VoteChoice::For => &mut tally.votes_for,
+            VoteChoice::Against => &mut tally.votes_against,
+            VoteChoice::Abstain => &mut tally.votes_abstain,
+        };
+        *total = total
+            .checked_add(*votes)
+            .ok_or(VotingError::ArithmeticOverflow)?;
+    }
+    voter.choice = Some(choice);
+    Ok(votes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    const COMMON: [u8; 16] = [1; 16];
+    const SELLER: [u8; 16] = [2; 16];
+    const BUYER: [u8; 16] = [3; 16];
+    const RECORD_DATE: i64 = 1_700_000_000;
+
+    fn resolution() -> Resolution {
+        Resolution {
+            record_date: RECORD_DATE,
+            voting_deadline: RECORD_DATE + 86_400,
+            tallies: vec![ClassTally {
+                stock_class_id: COMMON,
+                votes_per_share: SHARE_DECIMALS_FACTOR,
+                ..ClassTally::default()
+            }],
+            ..Resolution::default()
+        }
+    }
+
+    fn position(stakeholder_id: [u8; 16], security_id: [u8; 16]) -> StockActivePosition {
+        StockActivePosition {
+            stakeholder_id,
+            stock_class_id: COMMON,
+            security_id,
+            quantity: 100,
+            ..StockActivePosition::default()
+        }
+    }
+
+    fn holdings(
+        stakeholder_id: [u8; 16],
+        positions: &[&StockActivePosition],
+    ) -> StakeholderHoldings {
+        StakeholderHoldings {
+            stakeholder_id,
+            holdings: positions
+                .iter()
+                .map(|position| Holding {
+                    security_id: position.security_id,
+                    position_type: PositionType::Stock,
+                })
+                .collect(),
+            ..StakeholderHoldings::default()
+        }
+    }
+
+    #[test]
+    fn counts_shares_moved_after_recording_once() {
+        let mut resolution = resolution();
+        let common = StockClass {
+            id: COMMON,
+            ..StockClass::default()
+        };
+        let sold = position(SELLER, [4; 16]);
+        let mut seller = holdings(SELLER, &[&sold]);
+        let mut seller_record = VoterRecord::default();
+        record_voting_power(
+            &resolution,
+            &mut seller_record,
+            &seller,
+            &[&common],
+            &[&sold],
+            RECORD_DATE - 20,
+        )
+        .unwrap();
+
+        // The shares move to the buyer before the record date
+        seller.holdings.clear();
+        seller.stock_changed_at = RECORD_DATE - 10;
+        let bought = position(BUYER, [5; 16]);
+        let mut buyer = holdings(BUYER, &[&bought]);
+        buyer.stock_changed_at = RECORD_DATE - 10;
+        let mut buyer_record = VoterRecord::default();
+        record_voting_power(
+            &resolution,
+            &mut buyer_record,
+            &buyer,
+            &[&common],
+            &[&bought],
+            RECORD_DATE - 5,
+        )
+        .unwrap();
+
+        let now = RECORD_DATE + 1;
+        assert!(matches!(
+            cast_vote(
+                &mut resolution,
+                &mut seller_record,
+                &seller,
+                VoteChoice::For,
+                now
+            ),
+            Err(CoreError::Voting(VotingError::StaleVoterRecord))
+        ));
+        cast_vote(
+            &mut resolution,
+            &mut buyer_record,
+            &buyer,
+            VoteChoice::For,
+            now,
+        )
+        .unwrap();
+        assert_eq!(resolution.tallies[0].votes_for, 100);
+
+        // The seller's record can be retaken from its holdings at the record date
+        record_voting_power(
+            &resolution,
+            &mut seller_record,
+            &seller,
+            &[&common],
+            &[],
+            now,
+        )
+        .unwrap();
+        assert!(matches!(
+            cast_vote(
+                &mut resolution,
+                &mut seller_record,
+                &seller,
+                VoteChoice::For,
+                now
+            ),
+            Err(CoreError::Voting(VotingError::NoVotingPower))
+        ));
+    }
+
+    #[test]
+    fn rejects_recording_holdings_changed_after_the_record_date() {
+        let resolution = resolution();
+        let common = StockClass {
+            id: COMMON,
+            ..StockClass::default()
+        };
+        let held = position(BUYER, [5; 16]);
+        let mut buyer = holdings(BUYER, &[&held]);
+        buyer.stock_changed_at = RECORD_DATE + 1;
+
+        assert!(matches!(
+            record_voting_power(
+                &resolution,
+                &mut VoterRecord::default(),
+                &buyer,
+                &[&common],
+                &[&held],
+                RECORD_DATE + 2,
+            ),
+            Err(CoreError::Voting(VotingError::RecordDatePassed))
+        ));
+    }
+}
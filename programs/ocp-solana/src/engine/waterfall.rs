@@ -0,0 +1,668 @@
+//! Liquidation waterfall: how an exit value is split across stock classes and
+//! stakeholders under the classes' preference, participation and seniority
+//! terms.
+//!
+//! Amounts are 6-decimal fixed-point like share prices. The model works per
+//! class and then splits each class's proceeds pro rata over its positions:
+//!
+//! - Options and unexpired warrants are exercised when the value per share of
+//!   their class, with them exercised, exceeds the exercise price. Candidates
+//!   are tried cheapest first and the exercise price paid in is added to the
+//!   proceeds. The program records no option exercise price, so the caller
+//!   supplies one for every option and the model fails without it; pass zero
+//!   to model an option exercised for free.
+//! - Each preferred class converts to common when that pays it more. Classes
+//!   convert one at a time, the largest gain first, until none gains.
+//! - Unconverted preferred is paid its preference (class price per share times
+//!   the preference multiple) by seniority, highest first, pro rata by
+//!   preference within a seniority.
+//! - What is left goes to common, converted preferred and participating
+//!   preferred pro rata by as-converted shares. Participating preferred stops
+//!   at its participation cap and the excess is shared among the rest.
+//!
+//! Every division rounds down; the dust is reported as `unallocated`, together
+//! with anything no class can take.
+
+use super::CoreError;
+use crate::errors::WaterfallError;
+use crate::state::*;
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use anchor_lang::prelude::{borsh, AnchorDeserialize, AnchorSerialize};
+
+type Id = [u8; 16];
+
+const FACTOR: u128 = SHARE_DECIMALS_FACTOR as u128;
+
+/// The accounts to model. Stock classes must be complete; positions only need
+/// to be complete for the stakeholder table to be.
+#[derive(Default)]
+pub struct WaterfallInput<'a> {
+    pub stock_classes: Vec<&'a StockClass>,
+    pub stock_positions: Vec<&'a StockActivePosition>,
+    pub equity_compensation_positions: Vec<&'a EquityCompensationActivePosition>,
+    pub warrant_positions: Vec<&'a WarrantActivePosition>,
+    /// Exercise price per option security id, required for every option
+    pub option_exercise_prices: BTreeMap<Id, u64>,
+}
+
+/// Exercise price of one option, which the program does not record
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct OptionExercisePrice {
+    pub security_id: [u8; 16],
+    pub exercise_price: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct ClassDistribution {
+    pub stock_class_id: [u8; 16],
+    pub converted: bool,
+    /// Issued shares plus the options and warrants exercised into the class
+    pub shares: u64,
+    pub preference: u64,
+    pub participation: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct StakeholderProceeds {
+    pub stakeholder_id: [u8; 16],
+    /// Net of the exercise price of any options and warrants exercised
+    pub proceeds: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Default, Debug)]
+pub struct LiquidationDistribution {
+    pub exit_value: u64,
+    /// Exercise price paid in by the options and warrants exercised
+    pub exercise_proceeds: u64,
+    /// In the order of `WaterfallInput::stock_classes`
+    pub classes: Vec<ClassDistribution>,
+    /// Sorted by stakeholder id
+    pub stakeholders: Vec<StakeholderProceeds>,
+    pub unallocated: u64,
+}
+
+struct Class {
+    preferred: bool,
+    seniority: u32,
+    preference_per_share: u128,
+    participating: bool,
+    /// Total return per share for capped participating preferred
+    cap_per_share: Option<u128>,
+    conversion_ratio: u128,
+    shares: u128,
+}
+
+/// An option or warrant that may be exercised
+struct Instrument {
+    stakeholder_id: Id,
+    class: usize,
+    quantity: u128,
+    exercise_price: u128,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Payout {
+    preference: u128,
+    participation: u128,
+}
+
+impl Payout {
+    fn total(&self) -> u128 {
+        self.preference + self.participation
+    }
+}
+
+struct Settlement {
+    converted: Vec<bool>,
+    payouts: Vec<Payout>,
+}
+
+fn overflow() -> CoreError {
+    WaterfallError::ArithmeticOverflow.into()
+}
+
+fn to_u64(value: u128) -> Result<u64, CoreError> {
+    u64::try_from(value).map_err(|_| overflow())
+}
+
+/// `value * numerator / denominator`, or zero when the denominator is zero
+fn pro_rata(value: u128, numerator: u128, denominator: u128) -> Result<u128, CoreError> {
+    if denominator == 0 {
+        return Ok(0);
+    }
+    value
+        .checked_mul(numerator)
+        .map(|product| product / denominator)
+        .ok_or_else(overflow)
+}
+
+pub fn liquidation_waterfall(
+    input: &WaterfallInput,
+    exit_value: u64,
+    now: i64,
+) -> Result<LiquidationDistribution, CoreError> {
+    let index: BTreeMap<Id, usize> = input
+        .stock_classes
+        .iter()
+        .enumerate()
+        .map(|(i, stock_class)| (stock_class.id, i))
+        .collect();
+    let class_of = |id: &Id| {
+        index
+            .get(id)
+            .copied()
+            .ok_or(CoreError::from(WaterfallError::StockClassNotFound))
+    };
+
+    let mut classes = Vec::with_capacity(input.stock_classes.len());
+    for stock_class in &input.stock_classes {
+        let terms = &stock_class.terms;
+        let price = stock_class.price_per_share as u128;
+        let preferred = terms.kind == StockClassKind::Preferred;
+        classes.push(Class {
+            preferred,
+            seniority: terms.seniority,
+            preference_per_share: pro_rata(
+                price,
+                terms.liquidation_preference_multiple as u128,
+                FACTOR,
+            )?,
+            participating: preferred && terms.is_participating,
+            cap_per_share: match terms.participation_cap_multiple {
+                0 => None,
+                multiple => Some(pro_rata(price, multiple as u128, FACTOR)?),
+            },
+            conversion_ratio: terms.conversion_ratio as u128,
+            shares: stock_class.shares_issued as u128,
+        });
+    }
+
+    let mut instruments = Vec::new();
+    for position in &input.equity_compensation_positions {
+        instruments.push(Instrument {
+            stakeholder_id: position.stakeholder_id,
+            class: class_of(&position.stock_class_id)?,
+            quantity: position.quantity as u128,
+            exercise_price: *input
+                .option_exercise_prices
+                .get(&position.security_id)
+                .ok_or(WaterfallError::MissingExercisePrice)? as u128,
+        });
+    }
+    for position in &input.warrant_positions {
+        if now > position.expiration_date {
+            continue;
+        }
+        instruments.push(Instrument {
+            stakeholder_id: position.stakeholder_id,
+            class: class_of(&position.stock_class_id)?,
+            quantity: position.quantity as u128,
+            exercise_price: position.exercise_price as u128,
+        });
+    }
+    instruments.sort_by_key(|instrument| (instrument.exercise_price, instrument.class));
+
+    // Exercise cheapest first, one class and price at a time
+    let mut exercised = vec![false; instruments.len()];
+    let mut exercise_proceeds = 0u128;
+    let mut settlement = settle(&classes, exit_value as u128)?;
+    let mut start = 0;
+    while start < instruments.len() {
+        let (class, exercise_price) = (instruments[start].class, instruments[start].exercise_price);
+        let end = start
+            + instruments[start..]
+                .iter()
+                .take_while(|i| i.class == class && i.exercise_price == exercise_price)
+                .count();
+        let quantity: u128 = instruments[start..end].iter().map(|i| i.quantity).sum();
+        let cost = pro_rata(quantity, exercise_price, FACTOR)?;
+
+        classes[class].shares += quantity;
+        let trial = settle(&classes, exit_value as u128 + exercise_proceeds + cost)?;
+        let value_per_share =
+            pro_rata(trial.payouts[class].total(), FACTOR, classes[class].shares)?;
+        if value_per_share > exercise_price {
+            exercised[start..end].iter_mut().for_each(|e| *e = true);
+            exercise_proceeds += cost;
+            settlement = trial;
+        } else {
+            classes[class].shares -= quantity;
+        }
+        start = end;
+    }
+
+    // Split each class's proceeds over its positions by quantity
+    let mut stakeholders: BTreeMap<Id, u128> = BTreeMap::new();
+    for position in &input.stock_positions {
+        let class = class_of(&position.stock_class_id)?;
+        let mut position = (*position).clone();
+        position
+            .apply_pending_splits(input.stock_classes[class])
+            .ok_or_else(overflow)?;
+        let proceeds = pro_rata(
+            settlement.payouts[class].total(),
+            position.quantity as u128,
+            classes[class].shares,
+        )?;
+        *stakeholders.entry(position.stakeholder_id).or_default() += proceeds;
+    }
+    for (instrument, _) in instruments.iter().zip(&exercised).filter(|(_, e)| **e) {
+        let gross = pro_rata(
+            settlement.payouts[instrument.class].total(),
+            instrument.quantity,
+            classes[instrument.class].shares,
+        )?;
+        let cost = pro_rata(instrument.quantity, instrument.exercise_price, FACTOR)?;
+        *stakeholders.entry(instrument.stakeholder_id).or_default() += gross.saturating_sub(cost);
+    }
+
+    let distributed: u128 = settlement.payouts.iter().map(Payout::total).sum();
+    let mut distribution = LiquidationDistribution {
+        exit_value,
+        exercise_proceeds: to_u64(exercise_proceeds)?,
+        unallocated: to_u64((exit_value as u128 + exercise_proceeds).saturating_sub(distributed))?,
+        ..Default::default()
+    };
+    for ((stock_class, class), (payout, converted)) in input
+        .stock_classes
+        .iter()
+        .zip(&classes)
+        .zip(settlement.payouts.iter().zip(&settlement.converted))
+    {
+        distribution.classes.push(ClassDistribution {
+            stock_class_id: stock_class.id,
+            converted: *converted,
+            shares: to_u64(class.shares)?,
+            preference: to_u64(payout.preference)?,
+            participation: to_u64(payout.participation)?,
+        });
+    }
+    for (stakeholder_id, proceeds) in stakeholders {
+        distribution.stakeholders.push(StakeholderProceeds {
+            stakeholder_id,
+            proceeds: to_u64(proceeds)?,
+        });
+    }
+    Ok(distribution)
+}
+
+/// Converts preferred classes, largest gain first, while any gains.
+fn settle(classes: &[Class], proceeds: u128) -> Result<Settlement, CoreError> {
+    let mut converted = vec![false; classes.len()];
+    let mut payouts = distribute(classes, &converted, proceeds)?;
+
+    loop {
+        let mut best: Option<(usize, u128, Vec<Payout>)> = None;
+        for (i, class) in classes.iter().enumerate() {
+            if !class.preferred || converted[i] || class.shares == 0 {
+                continue;
+            }
+            converted[i] = true;
+            let trial = distribute(classes, &converted, proceeds)?;
+            converted[i] = false;
+
+            let gain = trial[i].total().saturating_sub(payouts[i].total());
+            if gain > 0 && !matches!(&best, Some((_, best_gain, _)) if *best_gain >= gain) {
+                best = Some((i, gain, trial));
+            }
+        }
+        match best {
+            Some((i, _, trial)) => {
+                converted[i] = true;
+                payouts = trial;
+            }
+            None => return Ok(Settlement { converted, payouts }),
+        }
+    }
+}
+
+/// Pays preferences by seniority, then shares the rest by as-converted shares.
+fn distribute(
+    classes: &[Class],
+    converted: &[bool],
+    proceeds: u128,
+) -> Result<Vec<Payout>, CoreError> {
+    let mut payouts = vec![Payout::default(); classes.len()];
+    let mut remaining = proceeds;
+
+    let preferred = |i: usize| classes[i].preferred && !converted[i];
+    let mut seniorities: Vec<u32> = (0..classes.len())
+        .filter(|&i| preferred(i))
+        .map(|i| classes[i].seniority)
+        .collect();
+    seniorities.sort_unstable_by(|a, b| b.cmp(a));
+    seniorities.dedup();
+
+    for seniority in seniorities {
+        let tier: Vec<(usize, u128)> = (0..classes.len())
+            .filter(|&i| preferred(i) && classes[i].seniority == seniority)
+            .map(|i| {
+                pro_rata(classes[i].shares, classes[i].preference_per_share, FACTOR)
+                    .map(|preference| (i, preference))
+            })
+            .collect::<Result<_, _>>()?;
+        let owed: u128 = tier.iter().map(|(_, preference)| preference).sum();
+        let available = remaining.min(owed);
+        for (i, preference) in tier {
+            let paid = pro_rata(available, preference, owed)?;
+            payouts[i].preference = paid;
+            remaining -= paid;
+        }
+    }
+
+    // (class, as-converted shares, room left under its cap)
+    let mut participants: Vec<(usize, u128, Option<u128>)> = Vec::new();
+    for (i, class) in classes.iter().enumerate() {
+        if !class.preferred {
+            participants.push((i, class.shares, None));
+        } else if converted[i] || class.participating {
+            let shares = pro_rata(class.shares, class.conversion_ratio, FACTOR)?;
+            let room = match class.cap_per_share {
+                Some(cap) if !converted[i] => {
+                    Some(pro_rata(class.shares, cap, FACTOR)?.saturating_sub(payouts[i].preference))
+                }
+                _ => None,
+            };
+            participants.push((i, shares, room));
+        }
+    }
+
+    // Pay out capped classes that would exceed their cap, then share the rest
+    loop {
+        let total_shares: u128 = participants.iter().map(|(_, shares, _)| shares).sum();
+        let mut capped = None;
+        for (position, (_, shares, room)) in participants.iter().enumerate() {
+            if let Some(room) = room {
+                if pro_rata(remaining, *shares, total_shares)? >= *room {
+                    capped = Some(position);
+                    break;
+                }
+            }
+        }
+        match capped {
+            Some(position) => {
+                let (i, _, room) = participants.remove(position);
+                let room = room.unwrap_or_default();
+                payouts[i].participation = room;
+                remaining -= room;
+            }
+            None => {
+                for (i, shares, _) in &participants {
+                    payouts[*i].participation = pro_rata(remaining, *shares, total_shares)?;
+                }
+                return Ok(payouts);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHARE: u64 = SHARE_DECIMALS_FACTOR;
+    const DOLLAR: u64 = SHARE_DECIMALS_FACTOR;
+    const COMMON: Id = [1; 16];
+    const SERIES_A: Id = [2; 16];
+    const SERIES_B: Id = [3; 16];
+    const FOUNDER: Id = [11; 16];
+    const INVESTOR_A: Id = [12; 16];
+    const INVESTOR_B: Id = [13; 16];
+    const EMPLOYEE: Id = [14; 16];
+    const NOW: i64 = 1_700_000_000;
+
+    fn common(shares: u64) -> StockClass {
+        StockClass {
+            id: COMMON,
+            price_per_share: DOLLAR / 100,
+            shares_issued: shares * SHARE,
+            ..StockClass::default()
+        }
+    }
+
+    /// Non-participating 1x preferred bought at $1, converting 1:1 into common
+    fn preferred(id: Id, shares: u64, seniority: u32) -> StockClass {
+        StockClass {
+            id,
+            price_per_share: DOLLAR,
+            shares_issued: shares * SHARE,
+            terms: StockClassTerms {
+                kind: StockClassKind::Preferred,
+                seniority,
+                liquidation_preference_multiple: SHARE_DECIMALS_FACTOR,
+                conversion_ratio: SHARE_DECIMALS_FACTOR,
+                converts_to_stock_class_id: COMMON,
+                ..StockClassTerms::default()
+            },
+            ..StockClass::default()
+        }
+    }
+
+    fn participating(id: Id, shares: u64, cap_multiple: u64) -> StockClass {
+        let mut stock_class = preferred(id, shares, 0);
+        stock_class.terms.is_participating = true;
+        stock_class.terms.participation_cap_multiple = cap_multiple;
+        stock_class
+    }
+
+    fn holding(stakeholder_id: Id, stock_class: &StockClass) -> StockActivePosition {
+        StockActivePosition {
+            stakeholder_id,
+            stock_class_id: stock_class.id,
+            security_id: stakeholder_id,
+            quantity: stock_class.shares_issued,
+            share_price: stock_class.price_per_share,
+            split_epoch: 0,
+        }
+    }
+
+    fn option(security_id: Id, shares: u64) -> EquityCompensationActivePosition {
+        EquityCompensationActivePosition {
+            stakeholder_id: EMPLOYEE,
+            stock_class_id: COMMON,
+            security_id,
+            quantity: shares * SHARE,
+            ..EquityCompensationActivePosition::default()
+        }
+    }
+
+    fn distribution(
+        stock_classes: &[&StockClass],
+        stock_positions: &[StockActivePosition],
+        exit_value: u64,
+    ) -> LiquidationDistribution {
+        let input = WaterfallInput {
+            stock_classes: stock_classes.to_vec(),
+            stock_positions: stock_positions.iter().collect(),
+            ..WaterfallInput::default()
+        };
+        liquidation_waterfall(&input, exit_value, NOW).unwrap()
+    }
+
+    fn class<'a>(distribution: &'a LiquidationDistribution, id: &Id) -> &'a ClassDistribution {
+        distribution
+            .classes
+            .iter()
+            .find(|class| class.stock_class_id == *id)
+            .unwrap()
+    }
+
+    fn proceeds(distribution: &LiquidationDistribution, stakeholder_id: &Id) -> u64 {
+        distribution
+            .stakeholders
+            .iter()
+            .find(|stakeholder| stakeholder.stakeholder_id == *stakeholder_id)
+            .map_or(0, |stakeholder| stakeholder.proceeds)
+    }
+
+    #[test]
+    fn stacks_preferences_by_seniority() {
+        let (common, series_a, series_b) = (
+            common(8_000_000),
+            preferred(SERIES_A, 1_000_000, 1),
+            preferred(SERIES_B, 1_000_000, 2),
+        );
+        let positions = [
+            holding(FOUNDER, &common),
+            holding(INVESTOR_A, &series_a),
+            holding(INVESTOR_B, &series_b),
+        ];
+        let result = distribution(
+            &[&common, &series_a, &series_b],
+            &positions,
+            1_500_000 * DOLLAR,
+        );
+
+        // Series B is senior and paid in full; Series A takes the rest
+        assert_eq!(class(&result, &SERIES_B).preference, 1_000_000 * DOLLAR);
+        assert_eq!(class(&result, &SERIES_A).preference, 500_000 * DOLLAR);
+        assert_eq!(class(&result, &COMMON).participation, 0);
+        assert_eq!(proceeds(&result, &INVESTOR_B), 1_000_000 * DOLLAR);
+        assert_eq!(proceeds(&result, &INVESTOR_A), 500_000 * DOLLAR);
+        assert_eq!(proceeds(&result, &FOUNDER), 0);
+        assert_eq!(result.unallocated, 0);
+    }
+
+    #[test]
+    fn shares_a_seniority_pro_rata_by_preference() {
+        let (common, series_a, mut series_b) = (
+            common(8_000_000),
+            preferred(SERIES_A, 1_000_000, 1),
+            preferred(SERIES_B, 1_000_000, 1),
+        );
+        // Series B owes 3x what Series A owes
+        series_b.price_per_share = 3 * DOLLAR;
+        let result = distribution(&[&common, &series_a, &series_b], &[], 2_000_000 * DOLLAR);
+
+        assert_eq!(class(&result, &SERIES_A).preference, 500_000 * DOLLAR);
+        assert_eq!(class(&result, &SERIES_B).preference, 1_500_000 * DOLLAR);
+        assert_eq!(class(&result, &COMMON).participation, 0);
+    }
+
+    #[test]
+    fn participates_after_the_preference() {
+        let (common, series_a) = (common(1_000_000), participating(SERIES_A, 1_000_000, 0));
+        let result = distribution(&[&common, &series_a], &[], 3_500_000 * DOLLAR);
+
+        let series_a = class(&result, &SERIES_A);
+        assert!(!series_a.converted);
+        assert_eq!(series_a.preference, 1_000_000 * DOLLAR);
+        assert_eq!(series_a.participation, 1_250_000 * DOLLAR);
+        assert_eq!(class(&result, &COMMON).participation, 1_250_000 * DOLLAR);
+    }
+
+    #[test]
+    fn stops_participation_at_the_cap() {
+        // A 2x cap binds at $2M, while converting would only pay $1.75M
+        let (common, series_a) = (
+            common(1_000_000),
+            participating(SERIES_A, 1_000_000, 2 * SHARE_DECIMALS_FACTOR),
+        );
+        let result = distribution(&[&common, &series_a], &[], 3_500_000 * DOLLAR);
+
+        let series_a = class(&result, &SERIES_A);
+        assert!(!series_a.converted);
+        assert_eq!(series_a.preference, 1_000_000 * DOLLAR);
+        assert_eq!(series_a.participation, 1_000_000 * DOLLAR);
+        assert_eq!(class(&result, &COMMON).participation, 1_500_000 * DOLLAR);
+    }
+
+    #[test]
+    fn converts_a_capped_class_when_converting_pays_more() {
+        let (common, series_a) = (
+            common(1_000_000),
+            participating(SERIES_A, 1_000_000, 2 * SHARE_DECIMALS_FACTOR),
+        );
+        let result = distribution(&[&common, &series_a], &[], 10_000_000 * DOLLAR);
+
+        let series_a = class(&result, &SERIES_A);
+        assert!(series_a.converted);
+        assert_eq!(series_a.preference, 0);
+        assert_eq!(series_a.participation, 5_000_000 * DOLLAR);
+        assert_eq!(class(&result, &COMMON).participation, 5_000_000 * DOLLAR);
+    }
+
+    #[test]
+    fn converts_preferred_only_when_it_pays_more() {
+        let (common, series_a) = (common(4_000_000), preferred(SERIES_A, 1_000_000, 0));
+
+        let low = distribution(&[&common, &series_a], &[], 2_000_000 * DOLLAR);
+        let series_a_low = class(&low, &SERIES_A);
+        assert!(!series_a_low.converted);
+        assert_eq!(series_a_low.preference, 1_000_000 * DOLLAR);
+        assert_eq!(class(&low, &COMMON).participation, 1_000_000 * DOLLAR);
+
+        // One fifth of $10M beats the $1M preference
+        let high = distribution(&[&common, &series_a], &[], 10_000_000 * DOLLAR);
+        let series_a_high = class(&high, &SERIES_A);
+        assert!(series_a_high.converted);
+        assert_eq!(series_a_high.preference, 0);
+        assert_eq!(series_a_high.participation, 2_000_000 * DOLLAR);
+        assert_eq!(class(&high, &COMMON).participation, 8_000_000 * DOLLAR);
+    }
+
+    #[test]
+    fn exercises_options_in_the_money_at_their_exercise_price() {
+        let common = common(1_000_000);
+        let positions = [holding(FOUNDER, &common)];
+        let (cheap, dear) = (option([21; 16], 1_000_000), option([22; 16], 1_000_000));
+        let input = WaterfallInput {
+            stock_classes: vec![&common],
+            stock_positions: positions.iter().collect(),
+            equity_compensation_positions: vec![&cheap, &dear],
+            option_exercise_prices: BTreeMap::from([
+                (cheap.security_id, DOLLAR),
+                (dear.security_id, 10 * DOLLAR),
+            ]),
+            ..WaterfallInput::default()
+        };
+        let result = liquidation_waterfall(&input, 4_000_000 * DOLLAR, NOW).unwrap();
+
+        // $5M over 2M shares is $2.50 a share: above $1, below $10
+        assert_eq!(result.exercise_proceeds, 1_000_000 * DOLLAR);
+        assert_eq!(class(&result, &COMMON).shares, 2_000_000 * SHARE);
+        assert_eq!(proceeds(&result, &FOUNDER), 2_500_000 * DOLLAR);
+        assert_eq!(proceeds(&result, &EMPLOYEE), 1_500_000 * DOLLAR);
+    }
+
+    #[test]
+    fn requires_an_exercise_price_for_every_option() {
+        let common = common(1_000_000);
+        let option = option([21; 16], 1_000_000);
+        let input = WaterfallInput {
+            stock_classes: vec![&common],
+            equity_compensation_positions: vec![&option],
+            ..WaterfallInput::default()
+        };
+
+        assert!(matches!(
+            liquidation_waterfall(&input, 4_000_000 * DOLLAR, NOW),
+            Err(CoreError::Waterfall(WaterfallError::MissingExercisePrice))
+        ));
+    }
+
+    #[test]
+    fn leaves_expired_warrants_unexercised() {
+        let common = common(1_000_000);
+        let warrant = WarrantActivePosition {
+            stakeholder_id: EMPLOYEE,
+            stock_class_id: COMMON,
+            quantity: 1_000_000 * SHARE,
+            exercise_price: DOLLAR,
+            expiration_date: NOW - 1,
+            ..WarrantActivePosition::default()
+        };
+        let input = WaterfallInput {
+            stock_classes: vec![&common],
+            warrant_positions: vec![&warrant],
+            ..WaterfallInput::default()
+        };
+        let result = liquidation_waterfall(&input, 4_000_000 * DOLLAR, NOW).unwrap();
+
+        assert_eq!(result.exercise_proceeds, 0);
+        assert_eq!(class(&result, &COMMON).shares, 1_000_000 * SHARE);
+        assert_eq!(class(&result, &COMMON).participation, 4_000_000 * DOLLAR);
+    }
+}
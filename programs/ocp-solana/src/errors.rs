@@ -6,10 +6,44 @@ pub enum IssuerError {
     AlreadyInitialized,
 }
 
+#[error_code]
+pub enum CapTableSummaryError {
+    #[msg("Stock class is not tracked in the cap table summary")]
+    StockClassNotFound,
+    #[msg("Cap table summary arithmetic overflow")]
+    ArithmeticOverflow,
+}
+
 #[error_code]
 pub enum StockClassError {
     #[msg("Shares authorized cannot be zero")]
     SharesAuthorizedCannotBeZero,
+    #[msg("Split ratio terms must be non-zero and not equal")]
+    InvalidSplitRatio,
+    #[msg("Stock class has reached its maximum number of splits")]
+    SplitHistoryFull,
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+    #[msg("Stock position does not belong to this stock class")]
+    StockClassMismatch,
+    #[msg("Stock position account must be writable")]
+    PositionNotWritable,
+    #[msg("Preferred stock classes require a conversion ratio and target class")]
+    InvalidConversionTerms,
+    #[msg("Stock class has no anti-dilution protection")]
+    NoAntiDilutionProtection,
+    #[msg("Issuance price is not below the class conversion price")]
+    NotDilutive,
+    #[msg("Dilutive issuance price and quantity must be greater than zero")]
+    InvalidDilutiveIssuance,
+}
+
+#[error_code]
+pub enum StakeholderError {
+    #[msg("Security is already listed in the stakeholder's holdings")]
+    DuplicateHolding,
+    #[msg("Security is not listed in the stakeholder's holdings")]
+    HoldingNotFound,
 }
 
 #[error_code]
@@ -20,6 +54,16 @@ pub enum StockError {
     InvalidQuantity,
     #[msg("Share price must be greater than zero")]
     InvalidSharePrice,
+    #[msg("Only preferred stock can be converted")]
+    NotConvertible,
+    #[msg("Target stock class does not match the class's conversion terms")]
+    ConversionClassMismatch,
+    #[msg("Conversion would not yield any shares")]
+    NoSharesReceived,
+    #[msg("Stock position quantity is insufficient")]
+    InsufficientPositionQuantity,
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
 }
 
 #[error_code]
@@ -36,6 +80,14 @@ pub enum StockPlanError {
 pub enum ConvertibleError {
     #[msg("Investment amount must be greater than zero")]
     InvalidAmount,
+    #[msg("Conversion price must be greater than zero")]
+    InvalidConversionPrice,
+    #[msg("Conversion would not yield any shares")]
+    NoSharesReceived,
+    #[msg("Insufficient shares available in stock class")]
+    InsufficientShares,
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
 }
 
 #[error_code]
@@ -48,10 +100,168 @@ pub enum EquityCompensationError {
     QuantityMismatch,
     #[msg("Stock position must belong to same stakeholder")]
     InvalidStakeholder,
+    #[msg("Rent recipient must be the account that paid for the position")]
+    InvalidRentPayer,
 }
 
 #[error_code]
 pub enum WarrantError {
     #[msg("Quantity must be greater than zero")]
     InvalidQuantity,
+    #[msg("Exercise price must be greater than zero")]
+    InvalidExercisePrice,
+    #[msg("Expiration date must be in the future")]
+    InvalidExpirationDate,
+    #[msg("Insufficient warrants available for exercise")]
+    InsufficientQuantity,
+    #[msg("Warrant has expired")]
+    WarrantExpired,
+    #[msg("Stock class does not match the warrant")]
+    StockClassMismatch,
+    #[msg("Fair market value must exceed the exercise price for net exercise")]
+    NotInTheMoney,
+    #[msg("Net exercise would not yield any shares")]
+    NoSharesReceived,
+    #[msg("Insufficient shares available in stock class")]
+    InsufficientShares,
+    #[msg("Warrant has not yet expired")]
+    WarrantNotExpired,
+    #[msg("Rent recipient must be the account that paid for the warrant")]
+    InvalidRentPayer,
+    #[msg("Warrant cannot be transferred to its current holder")]
+    SelfTransfer,
+    #[msg("Warrant position is not registered to the issuer")]
+    PositionNotRegistered,
+    #[msg("Stock class does not belong to the issuer")]
+    UnknownStockClass,
+}
+
+#[error_code]
+pub enum TxLogError {
+    #[msg("Transaction log sequence overflow")]
+    SequenceOverflow,
+}
+
+#[error_code]
+pub enum WaterfallError {
+    #[msg("Every stock class in the cap table summary must be supplied")]
+    MissingStockClass,
+    #[msg("Position references a stock class that was not supplied")]
+    StockClassNotFound,
+    #[msg("Account is not a stock class or position of this issuer")]
+    UnsupportedAccount,
+    #[msg("Option and warrant positions do not add up to the outstanding totals")]
+    IncompletePositions,
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+    #[msg("Distribution does not fit in return data; compute it off-chain")]
+    ReturnDataTooLarge,
+    #[msg("Every option position needs an exercise price")]
+    MissingExercisePrice,
+}
+
+#[error_code]
+pub enum FinancingRoundError {
+    #[msg("Pre-money valuation and price per share must be greater than zero")]
+    InvalidValuation,
+    #[msg("Target pool fraction must be below 100%")]
+    InvalidPoolTarget,
+    #[msg("Investment amount must be greater than zero")]
+    InvalidInvestment,
+    #[msg("Convertible positions exceed the outstanding principal")]
+    ExcessConvertibles,
+    #[msg("Convertible discount must be below 100% and leave a positive price")]
+    InvalidConvertibleTerms,
+    #[msg("Conversion terms reference a convertible that was not supplied")]
+    ConvertibleNotFound,
+    #[msg("Issuer has no fully diluted shares to price the round against")]
+    NoSharesOutstanding,
+    #[msg("Pool top-up and conversions leave no room for a price per share")]
+    DoesNotConverge,
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+    #[msg("Financing round is not open")]
+    RoundNotOpen,
+    #[msg("Financing round has not closed")]
+    RoundNotClosed,
+    #[msg("Financing round has no room for another subscription or conversion")]
+    TooManyEntries,
+    #[msg("Security id is already used by the financing round")]
+    DuplicateSecurity,
+    #[msg("Financing round has no subscriptions")]
+    NoSubscriptions,
+    #[msg("A pool top-up requires the round's stock plan")]
+    StockPlanMismatch,
+    #[msg("Security is not issued by the financing round")]
+    EntryNotFound,
+    #[msg("Stakeholder does not match the financing round entry")]
+    StakeholderMismatch,
+    #[msg("Convertible is already part of a financing round")]
+    ConvertibleInRound,
+    #[msg("Every security registration and convertible of the round must be supplied")]
+    IncompleteRoundAccounts,
+    #[msg("Convertible position is not registered to the issuer")]
+    ConvertibleNotRegistered,
+}
+
+#[error_code]
+pub enum GovernanceError {
+    #[msg("Board or multisig must have between one and the maximum number of members")]
+    InvalidMemberCount,
+    #[msg("Member is listed more than once")]
+    DuplicateMember,
+    #[msg("Approval threshold must be between one and the number of members")]
+    InvalidThreshold,
+    #[msg("Signer is not a member of the issuer's board")]
+    NotBoardMember,
+    #[msg("Member has already approved")]
+    AlreadyApproved,
+    #[msg("Not enough approvals from current members")]
+    NotApproved,
+    #[msg("Already executed")]
+    AlreadyExecuted,
+    #[msg("Stockholder approval date cannot be negative or in the future")]
+    InvalidApprovalDate,
+    #[msg("Accounts passed do not match the proposal's action")]
+    AccountMismatch,
+    #[msg("Signer is not a member of the issuer's multisig")]
+    NotMultisigMember,
+    #[msg("Instruction lacks the signatures the issuer's multisig requires")]
+    MultisigUnauthorized,
+    #[msg("Multisig transaction has no instruction data")]
+    EmptyTransaction,
+    #[msg("Signer is not the issuer authority")]
+    NotIssuerAuthority,
+    #[msg("The issuer's board must approve this action through a proposal")]
+    BoardApprovalRequired,
+}
+
+#[error_code]
+pub enum VotingError {
+    #[msg("Record date cannot be in the past and must precede the voting deadline")]
+    InvalidVotingPeriod,
+    #[msg("Every stock class in the cap table summary must be supplied")]
+    MissingStockClass,
+    #[msg("Stock class is not part of the resolution")]
+    UnknownStockClass,
+    #[msg("Voting power can only be recorded from holdings as of the record date")]
+    RecordDatePassed,
+    #[msg("Voting opens after the record date and closes at the deadline")]
+    VotingClosed,
+    #[msg("Every stock position in the stakeholder's holdings must be supplied")]
+    IncompletePositions,
+    #[msg("Remaining accounts must be stock classes or stock positions")]
+    UnsupportedAccount,
+    #[msg("Stock position does not belong to the stakeholder")]
+    PositionMismatch,
+    #[msg("Signer is not the stakeholder's registered wallet")]
+    WalletMismatch,
+    #[msg("Stakeholder has already voted")]
+    AlreadyVoted,
+    #[msg("Stakeholder held no voting shares at the record date")]
+    NoVotingPower,
+    #[msg("Vote tally arithmetic overflow")]
+    ArithmeticOverflow,
+    #[msg("Stock holdings changed after voting power was recorded")]
+    StaleVoterRecord,
 }
@@ -1,6 +1,10 @@
+use crate::state::{
+    AntiDilutionType, FractionalSharePolicy, ProposalAction, StockClassTerms, VoteChoice,
+    WarrantExerciseTrigger,
+};
 use anchor_lang::prelude::*;
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Debug)]
 pub enum TxType {
     Invalid,
     IssuerAuthorizedSharesAdjustment,
@@ -17,69 +21,243 @@ pub enum TxType {
     StockPlanPoolAdjustment,
     WarrantIssuance,
     EquityCompensationExercise,
+    WarrantExercise,
+    WarrantCancellation,
+    WarrantExpiration,
+    WarrantTransfer,
+    StockClassSplit,
+    StockConversion,
+    StockClassConversionRatioAdjustment,
+    ConvertibleConversion,
 }
 
+/// Common header carried as the first field of every event, so indexers can
+/// attribute any event without correlating it with the instruction accounts.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Debug)]
+pub struct EventEnvelope {
+    pub issuer_id: [u8; 16],
+    pub unix_timestamp: i64,
+    pub slot: u64,
+    /// Signer that triggered the event
+    pub actor: Pubkey,
+    /// OCF id of the object the event is about (security, stock class,
+    /// stakeholder, stock plan or issuer)
+    pub object_id: [u8; 16],
+}
+
+impl EventEnvelope {
+    pub fn new(issuer_id: [u8; 16], actor: Pubkey, object_id: [u8; 16]) -> Result<Self> {
+        let clock = Clock::get()?;
+        Ok(Self {
+            issuer_id,
+            unix_timestamp: clock.unix_timestamp,
+            slot: clock.slot,
+            actor,
+            object_id,
+        })
+    }
+}
+
+#[event]
+pub struct IssuerCreated {
+    pub envelope: EventEnvelope,
+    pub initial_shares_authorized: u64,
+}
+
+#[event]
+pub struct IssuerAuthorizedSharesAdjusted {
+    pub envelope: EventEnvelope,
+    pub new_shares_authorized: u64,
+}
+
+#[event]
+pub struct CapTableSnapshotTaken {
+    pub envelope: EventEnvelope,
+    pub snapshot_id: [u8; 16],
+    /// Last logged transaction before the snapshot. Mutations the `TxLog`
+    /// does not record, such as split cranks and authorized share
+    /// adjustments, are in the snapshot but not in this sequence.
+    pub tx_log_sequence: u64,
+    pub tx_log_hash: [u8; 32],
+}
+
+/// Version 2 of `TxCreated`, with the envelope in place of `issuer_id`, the
+/// payload version and the transaction's place in the `TxLog`. Anchor derives
+/// an event's discriminator from its name, so the new layout is emitted under
+/// a new name and `payload::TxCreatedV1` still decodes the old one.
 #[event]
-pub struct TxCreated {
+pub struct TxCreatedV2 {
+    pub envelope: EventEnvelope,
     pub tx_type: TxType,
+    /// Layout version of `tx_data` for this `tx_type`, see `payload.rs`
+    pub payload_version: u8,
     pub tx_data: Vec<u8>, // Anchor automatically handles serialization
-    pub issuer_id: [u8; 16],
+    /// Position of this transaction in the issuer's `TxLog`, starting at 1
+    pub sequence: u64,
+    /// `TxLog` hash before this transaction was appended
+    pub prev_hash: [u8; 32],
 }
 
 #[event]
 pub struct StockClassCreated {
+    pub envelope: EventEnvelope,
     pub id: [u8; 16],
     pub class_type: String,
     pub price_per_share: u64,
     pub initial_shares_authorized: u64,
-    pub issuer_id: [u8; 16],
+    pub terms: StockClassTerms,
 }
 
 #[event]
 pub struct StockClassSharesAdjusted {
+    pub envelope: EventEnvelope,
     pub stock_class_id: [u8; 16],
     pub new_shares_authorized: u64,
-    pub issuer_id: [u8; 16],
+}
+
+#[event]
+pub struct StockClassSplit {
+    pub envelope: EventEnvelope,
+    pub stock_class_id: [u8; 16],
+    pub split_epoch: u32,
+    pub numerator: u64,
+    pub denominator: u64,
+    pub fractional_share_policy: FractionalSharePolicy,
+    pub new_shares_authorized: u64,
+    pub new_shares_issued: u64,
+    pub new_price_per_share: u64,
+}
+
+#[event]
+pub struct StockClassConversionRatioAdjusted {
+    pub envelope: EventEnvelope,
+    pub stock_class_id: [u8; 16],
+    pub anti_dilution_type: AntiDilutionType,
+    pub new_issue_price: u64,
+    pub new_shares_issued: u64,
+    pub shares_outstanding_basis: u64,
+    pub previous_conversion_price: u64,
+    pub new_conversion_price: u64,
+    pub previous_conversion_ratio: u64,
+    pub new_conversion_ratio: u64,
 }
 
 #[event]
 pub struct StakeholderCreated {
+    pub envelope: EventEnvelope,
     pub id: [u8; 16],
-    pub issuer_id: [u8; 16],
 }
 
 #[event]
 pub struct StockIssued {
+    pub envelope: EventEnvelope,
     pub stock_class_id: [u8; 16],
     pub security_id: [u8; 16],
     pub stakeholder_id: [u8; 16],
     pub quantity: u64,
     pub share_price: u64,
-    pub issuer_id: [u8; 16],
+}
+
+#[event]
+pub struct StockConverted {
+    pub envelope: EventEnvelope,
+    pub security_id: [u8; 16],
+    pub resulting_security_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    pub source_stock_class_id: [u8; 16],
+    pub target_stock_class_id: [u8; 16],
+    pub quantity_converted: u64,
+    pub quantity_received: u64,
+    pub conversion_ratio: u64,
 }
 
 #[event]
 pub struct StockPlanCreated {
+    pub envelope: EventEnvelope,
     pub id: [u8; 16],
     pub shares_reserved: u64,
-    pub issuer_id: [u8; 16],
 }
 
 #[event]
 pub struct StockPlanSharesAdjusted {
+    pub envelope: EventEnvelope,
     pub id: [u8; 16],
     pub new_shares_reserved: u64,
 }
 
 #[event]
 pub struct ConvertibleIssued {
+    pub envelope: EventEnvelope,
     pub stakeholder_id: [u8; 16],
     pub security_id: [u8; 16],
     pub investment_amount: u64,
 }
 
+#[event]
+pub struct ConvertibleConverted {
+    pub envelope: EventEnvelope,
+    pub security_id: [u8; 16],
+    pub resulting_security_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    pub stock_class_id: [u8; 16],
+    pub investment_amount: u64,
+    pub conversion_price: u64,
+    pub quantity_received: u64,
+}
+
+#[event]
+pub struct FinancingRoundOpened {
+    pub envelope: EventEnvelope,
+    pub round_id: [u8; 16],
+    pub stock_class_id: [u8; 16],
+    pub pre_money_valuation: u64,
+    pub target_pool_fraction: u64,
+    pub stock_plan_id: [u8; 16],
+}
+
+#[event]
+pub struct RoundSubscriptionAdded {
+    pub envelope: EventEnvelope,
+    pub round_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    pub security_id: [u8; 16],
+    pub amount: u64,
+}
+
+#[event]
+pub struct RoundConversionAdded {
+    pub envelope: EventEnvelope,
+    pub round_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    pub security_id: [u8; 16],
+    pub resulting_security_id: [u8; 16],
+    pub discount: u64,
+    pub valuation_cap: u64,
+}
+
+#[event]
+pub struct FinancingRoundCancelled {
+    pub envelope: EventEnvelope,
+    pub round_id: [u8; 16],
+}
+
+/// Emitted after the class creation, issuances, conversions and adjustments
+/// the round applied, which carry their own events
+#[event]
+pub struct FinancingRoundClosed {
+    pub envelope: EventEnvelope,
+    pub round_id: [u8; 16],
+    pub stock_class_id: [u8; 16],
+    pub price_per_share: u64,
+    pub pre_money_valuation: u64,
+    pub post_money_valuation: u64,
+    pub shares_issued: u64,
+    pub pool_increase: u64,
+}
+
 #[event]
 pub struct EquityCompensationIssued {
+    pub envelope: EventEnvelope,
     pub security_id: [u8; 16],
     pub stakeholder_id: [u8; 16],
     pub stock_class_id: [u8; 16],
@@ -89,6 +267,7 @@ pub struct EquityCompensationIssued {
 
 #[event]
 pub struct EquityCompensationExercised {
+    pub envelope: EventEnvelope,
     pub equity_comp_security_id: [u8; 16],
     pub resulting_stock_security_id: [u8; 16],
     pub quantity: u64,
@@ -96,7 +275,144 @@ pub struct EquityCompensationExercised {
 
 #[event]
 pub struct WarrantIssued {
+    pub envelope: EventEnvelope,
+    pub stakeholder_id: [u8; 16],
+    pub stock_class_id: [u8; 16],
+    pub security_id: [u8; 16],
+    pub quantity: u64,
+    pub exercise_price: u64,
+    pub expiration_date: i64,
+    pub exercise_trigger: WarrantExerciseTrigger,
+}
+
+#[event]
+pub struct WarrantExercised {
+    pub envelope: EventEnvelope,
+    pub warrant_security_id: [u8; 16],
+    pub resulting_stock_security_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    pub stock_class_id: [u8; 16],
+    pub quantity: u64,
+    pub shares_received: u64,
+    pub exercise_price: u64,
+    pub fair_market_value: Option<u64>,
+}
+
+#[event]
+pub struct WarrantCancelled {
+    pub envelope: EventEnvelope,
+    pub security_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    pub quantity: u64,
+}
+
+#[event]
+pub struct WarrantExpired {
+    pub envelope: EventEnvelope,
+    pub security_id: [u8; 16],
     pub stakeholder_id: [u8; 16],
+    pub quantity: u64,
+    pub expiration_date: i64,
+}
+
+#[event]
+pub struct WarrantTransferred {
+    pub envelope: EventEnvelope,
     pub security_id: [u8; 16],
+    pub resulting_security_id: [u8; 16],
+    pub from_stakeholder_id: [u8; 16],
+    pub to_stakeholder_id: [u8; 16],
     pub quantity: u64,
 }
+
+#[event]
+pub struct BoardConfigured {
+    pub envelope: EventEnvelope,
+    pub members: Vec<Pubkey>,
+    pub threshold: u8,
+}
+
+#[event]
+pub struct ProposalCreated {
+    pub envelope: EventEnvelope,
+    pub proposal_id: [u8; 16],
+    pub action: ProposalAction,
+    pub stockholder_approval_date: i64,
+}
+
+#[event]
+pub struct ProposalApproved {
+    pub envelope: EventEnvelope,
+    pub proposal_id: [u8; 16],
+    pub member: Pubkey,
+    pub approvals: u8,
+    /// Set by the approval that reaches the threshold
+    pub board_approval_date: i64,
+}
+
+/// Emitted after the events of the operation the proposal performed, with
+/// the approval dates OCF records on it
+#[event]
+pub struct ProposalExecuted {
+    pub envelope: EventEnvelope,
+    pub proposal_id: [u8; 16],
+    pub board_approval_date: i64,
+    pub stockholder_approval_date: i64,
+}
+
+#[event]
+pub struct MultisigConfigured {
+    pub envelope: EventEnvelope,
+    pub members: Vec<Pubkey>,
+    pub threshold: u8,
+}
+
+#[event]
+pub struct MultisigTransactionCreated {
+    pub envelope: EventEnvelope,
+    pub nonce: u64,
+    pub proposer: Pubkey,
+}
+
+#[event]
+pub struct MultisigTransactionApproved {
+    pub envelope: EventEnvelope,
+    pub nonce: u64,
+    pub member: Pubkey,
+    pub approvals: u8,
+}
+
+/// Emitted before the events of the instruction the transaction invoked
+#[event]
+pub struct MultisigTransactionExecuted {
+    pub envelope: EventEnvelope,
+    pub nonce: u64,
+}
+
+#[event]
+pub struct StakeholderWalletSet {
+    pub envelope: EventEnvelope,
+    pub stakeholder_id: [u8; 16],
+    pub wallet: Pubkey,
+}
+
+#[event]
+pub struct ResolutionCreated {
+    pub envelope: EventEnvelope,
+    pub resolution_id: [u8; 16],
+    pub record_date: i64,
+    pub voting_deadline: i64,
+    /// Classes that must approve the resolution voting as a separate class
+    pub separate_class_ids: Vec<[u8; 16]>,
+}
+
+#[event]
+pub struct VoteCast {
+    pub envelope: EventEnvelope,
+    pub resolution_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    pub wallet: Pubkey,
+    pub choice: VoteChoice,
+    /// Votes added to each class tally, in the order of the resolution's tallies
+    pub votes: Vec<u64>,
+}
@@ -1,5 +1,7 @@
-use crate::errors::*;
+use crate::engine::rules;
 use crate::events::*;
+use crate::instructions::multisig::authorize;
+use crate::payload::TxPayload;
 use crate::state::*;
 use anchor_lang::prelude::*;
 
@@ -7,11 +9,32 @@ use anchor_lang::prelude::*;
 #[instruction(security_id: [u8; 16], investment_amount: u64)]
 pub struct IssueConvertible<'info> {
     pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"tx_log", issuer.id.as_ref()],
+        bump
+    )]
+    pub tx_log: Account<'info, TxLog>,
+    #[account(
+        mut,
+        seeds = [b"cap_table_summary", issuer.id.as_ref()],
+        bump
+    )]
+    pub cap_table_summary: Account<'info, CapTableSummary>,
     pub stakeholder: Account<'info, Stakeholder>,
+    #[account(
+        mut,
+        seeds = [b"stakeholder_holdings", stakeholder.id.as_ref()],
+        bump,
+        realloc = StakeholderHoldings::space(stakeholder_holdings.holdings.len() + 1),
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub stakeholder_holdings: Account<'info, StakeholderHoldings>,
     #[account(
         init,
         payer = authority,
-        space = 8 + 16 + 16 + 8, // discriminator + stakeholder_id + security_id + investment_amount
+        space = 8 + 16 + 16 + 8 + 16, // discriminator + stakeholder_id + security_id + investment_amount + round_id
         // Convertible active position seeding
         seeds = [
             b"convertible_position",
@@ -21,6 +44,17 @@ pub struct IssueConvertible<'info> {
         bump
     )]
     pub position: Account<'info, ConvertibleActivePosition>,
+    #[account(
+        init,
+        payer = authority,
+        space = SecurityRegistration::SPACE,
+        seeds = [b"security", issuer.id.as_ref(), security_id.as_ref()],
+        bump
+    )]
+    pub security_registration: Account<'info, SecurityRegistration>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
     #[account(mut)]
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
@@ -31,28 +65,61 @@ pub fn issue_convertible(
     security_id: [u8; 16],
     investment_amount: u64,
 ) -> Result<()> {
-    require!(investment_amount > 0, ConvertibleError::InvalidAmount);
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
 
     let position = &mut ctx.accounts.position;
     let stakeholder = &ctx.accounts.stakeholder;
 
-    position.stakeholder_id = stakeholder.id;
-    position.security_id = security_id;
-    position.investment_amount = investment_amount;
+    rules::issue_convertible(
+        position,
+        &mut ctx.accounts.cap_table_summary,
+        stakeholder.id,
+        security_id,
+        investment_amount,
+    )?;
+
+    ctx.accounts
+        .stakeholder_holdings
+        .add(security_id, PositionType::Convertible)?;
+    ctx.accounts.security_registration.register(
+        ctx.accounts.issuer.id,
+        security_id,
+        PositionType::Convertible,
+        position.key(),
+    );
 
     // Serialize using the ConvertibleIssued event struct
+    let envelope = EventEnvelope::new(
+        ctx.accounts.issuer.id,
+        ctx.accounts.authority.key(),
+        security_id,
+    )?;
     let tx_data = AnchorSerialize::try_to_vec(
         &(ConvertibleIssued {
+            envelope,
             stakeholder_id: stakeholder.id,
             security_id,
             investment_amount,
         }),
     )?;
 
-    emit!(TxCreated {
+    let (sequence, prev_hash) = ctx.accounts.tx_log.append(
+        TxType::ConvertibleIssuance,
+        ConvertibleIssued::VERSION,
+        &tx_data,
+    )?;
+    emit!(TxCreatedV2 {
+        envelope,
         tx_type: TxType::ConvertibleIssuance,
+        payload_version: ConvertibleIssued::VERSION,
         tx_data,
-        issuer_id: ctx.accounts.issuer.id,
+        sequence,
+        prev_hash,
     });
 
     Ok(())
@@ -1,5 +1,8 @@
-use crate::errors::*;
+use crate::engine::rules;
+use crate::errors::EquityCompensationError;
 use crate::events::*;
+use crate::instructions::multisig::authorize;
+use crate::payload::TxPayload;
 use crate::state::*;
 use anchor_lang::prelude::*;
 
@@ -8,15 +11,36 @@ use anchor_lang::prelude::*;
 pub struct IssueEquityCompensation<'info> {
     #[account(mut)]
     pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"tx_log", issuer.id.as_ref()],
+        bump
+    )]
+    pub tx_log: Account<'info, TxLog>,
+    #[account(
+        mut,
+        seeds = [b"cap_table_summary", issuer.id.as_ref()],
+        bump
+    )]
+    pub cap_table_summary: Account<'info, CapTableSummary>,
     pub stakeholder: Account<'info, Stakeholder>,
+    #[account(
+        mut,
+        seeds = [b"stakeholder_holdings", stakeholder.id.as_ref()],
+        bump,
+        realloc = StakeholderHoldings::space(stakeholder_holdings.holdings.len() + 1),
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub stakeholder_holdings: Account<'info, StakeholderHoldings>,
     pub stock_class: Account<'info, StockClass>,
     #[account()]
     pub stock_plan: Option<Account<'info, StockPlan>>,
     #[account(
         init,
         payer = authority,
-        // Important Space allocation: discriminator(8) + security_id(16) + stock_class_id(16) + stakeholder_id(16) + stock_plan_id(16) + quantity(8)
-        space = 8 + 16 + 16 + 16 + 16 + 8,
+        // Important Space allocation: discriminator(8) + security_id(16) + stock_class_id(16) + stakeholder_id(16) + stock_plan_id(16) + quantity(8) + rent_payer(32)
+        space = 8 + 16 + 16 + 16 + 16 + 8 + 32,
         seeds = [
             b"equity_compensation_position",
             security_id.as_ref(),
@@ -26,6 +50,17 @@ pub struct IssueEquityCompensation<'info> {
         bump
     )]
     pub position: Account<'info, EquityCompensationActivePosition>,
+    #[account(
+        init,
+        payer = authority,
+        space = SecurityRegistration::SPACE,
+        seeds = [b"security", issuer.id.as_ref(), security_id.as_ref()],
+        bump
+    )]
+    pub security_registration: Account<'info, SecurityRegistration>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
     #[account(mut)]
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
@@ -35,10 +70,37 @@ pub struct IssueEquityCompensation<'info> {
 #[instruction(quantity: u64)]
 pub struct ExerciseEquityCompensation<'info> {
     pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"tx_log", issuer.id.as_ref()],
+        bump
+    )]
+    pub tx_log: Account<'info, TxLog>,
+    #[account(
+        mut,
+        seeds = [b"cap_table_summary", issuer.id.as_ref()],
+        bump
+    )]
+    pub cap_table_summary: Account<'info, CapTableSummary>,
+    #[account(
+        mut,
+        seeds = [b"stakeholder_holdings", equity_position.stakeholder_id.as_ref()],
+        bump
+    )]
+    pub stakeholder_holdings: Account<'info, StakeholderHoldings>,
     #[account(mut)]
     pub equity_position: Account<'info, EquityCompensationActivePosition>,
     #[account(mut)]
     pub stock_position: Account<'info, StockActivePosition>,
+    /// CHECK: only receives the closed account's lamports; must match the recorded payer
+    #[account(
+        mut,
+        address = equity_position.rent_payer @ EquityCompensationError::InvalidRentPayer
+    )]
+    pub rent_payer: UncheckedAccount<'info>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
     #[account(mut)]
     pub authority: Signer<'info>,
 }
@@ -48,26 +110,50 @@ pub fn issue_equity_compensation(
     security_id: [u8; 16],
     quantity: u64,
 ) -> Result<()> {
-    require!(quantity > 0, EquityCompensationError::InvalidQuantity);
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
 
     let position = &mut ctx.accounts.position;
     let stakeholder = &ctx.accounts.stakeholder;
     let stock_class = &ctx.accounts.stock_class;
 
-    position.stakeholder_id = stakeholder.id;
-    position.stock_class_id = stock_class.id;
-
-    // Optional stock plan
-    if let Some(stock_plan) = &ctx.accounts.stock_plan {
-        position.stock_plan_id = stock_plan.id;
-    }
-
-    position.security_id = security_id;
-    position.quantity = quantity;
+    rules::issue_equity_compensation(
+        position,
+        &mut ctx.accounts.cap_table_summary,
+        stakeholder.id,
+        stock_class.id,
+        ctx.accounts
+            .stock_plan
+            .as_ref()
+            .map(|stock_plan| stock_plan.id),
+        security_id,
+        quantity,
+    )?;
+    position.rent_payer = ctx.accounts.authority.key();
+
+    ctx.accounts
+        .stakeholder_holdings
+        .add(security_id, PositionType::EquityCompensation)?;
+    ctx.accounts.security_registration.register(
+        ctx.accounts.issuer.id,
+        security_id,
+        PositionType::EquityCompensation,
+        position.key(),
+    );
 
     // Serialize using the EquityCompensationIssued event struct
+    let envelope = EventEnvelope::new(
+        ctx.accounts.issuer.id,
+        ctx.accounts.authority.key(),
+        security_id,
+    )?;
     let tx_data = AnchorSerialize::try_to_vec(
         &(EquityCompensationIssued {
+            envelope,
             security_id,
             stakeholder_id: stakeholder.id,
             stock_class_id: stock_class.id,
@@ -80,10 +166,18 @@ pub fn issue_equity_compensation(
         }),
     )?;
 
-    emit!(TxCreated {
+    let (sequence, prev_hash) = ctx.accounts.tx_log.append(
+        TxType::EquityCompensationIssuance,
+        EquityCompensationIssued::VERSION,
+        &tx_data,
+    )?;
+    emit!(TxCreatedV2 {
+        envelope,
         tx_type: TxType::EquityCompensationIssuance,
+        payload_version: EquityCompensationIssued::VERSION,
         tx_data,
-        issuer_id: ctx.accounts.issuer.id,
+        sequence,
+        prev_hash,
     });
 
     Ok(())
@@ -93,44 +187,62 @@ pub fn exercise_equity_compensation(
     ctx: Context<ExerciseEquityCompensation>,
     quantity: u64,
 ) -> Result<()> {
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
+
     let equity_position = &mut ctx.accounts.equity_position;
     let stock_position = &ctx.accounts.stock_position;
 
-    require!(quantity > 0, EquityCompensationError::InvalidQuantity);
-    require!(
-        equity_position.quantity >= quantity,
-        EquityCompensationError::InsufficientShares
-    );
-    require!(
-        stock_position.quantity == quantity,
-        EquityCompensationError::QuantityMismatch
-    );
-    require!(
-        stock_position.stakeholder_id == equity_position.stakeholder_id,
-        EquityCompensationError::InvalidStakeholder
-    );
-
-    // Update equity position
-    // use checked_sub to prevent overflow
-    equity_position.quantity = equity_position
-        .quantity
-        .checked_sub(quantity)
-        .ok_or(EquityCompensationError::InsufficientShares)?;
+    rules::exercise_equity_compensation(
+        equity_position,
+        stock_position,
+        &mut ctx.accounts.cap_table_summary,
+        quantity,
+    )?;
 
     // Serialize using the EquityCompensationExercised event struct
+    let envelope = EventEnvelope::new(
+        ctx.accounts.issuer.id,
+        ctx.accounts.authority.key(),
+        equity_position.security_id,
+    )?;
     let tx_data = AnchorSerialize::try_to_vec(
         &(EquityCompensationExercised {
+            envelope,
             equity_comp_security_id: equity_position.security_id,
             resulting_stock_security_id: stock_position.security_id,
             quantity,
         }),
     )?;
 
-    emit!(TxCreated {
+    let (sequence, prev_hash) = ctx.accounts.tx_log.append(
+        TxType::EquityCompensationExercise,
+        EquityCompensationExercised::VERSION,
+        &tx_data,
+    )?;
+    emit!(TxCreatedV2 {
+        envelope,
         tx_type: TxType::EquityCompensationExercise,
+        payload_version: EquityCompensationExercised::VERSION,
         tx_data,
-        issuer_id: ctx.accounts.issuer.id
+        sequence,
+        prev_hash,
     });
 
+    // Fully exercised options are closed and their rent returned
+    if equity_position.quantity == 0 {
+        ctx.accounts.stakeholder_holdings.remove(
+            equity_position.security_id,
+            PositionType::EquityCompensation,
+        )?;
+        ctx.accounts
+            .equity_position
+            .close(ctx.accounts.rent_payer.to_account_info())?;
+    }
+
     Ok(())
 }
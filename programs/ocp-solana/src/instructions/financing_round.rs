@@ -0,0 +1,596 @@
+use crate::engine::rules;
+use crate::errors::{FinancingRoundError, GovernanceError};
+use crate::events::*;
+use crate::instructions::multisig::authorize;
+use crate::payload::TxPayload;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+#[instruction(id: [u8; 16])]
+pub struct OpenFinancingRound<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        init,
+        payer = authority,
+        space = PricedRound::space(0, 0),
+        seeds = [
+            b"priced_round",
+            issuer.id.as_ref(),
+            id.as_ref(),
+        ],
+        bump
+    )]
+    pub round: Account<'info, PricedRound>,
+    /// Plan topped up to the target pool; required when the target is not zero
+    pub stock_plan: Option<Account<'info, StockPlan>>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(security_id: [u8; 16])]
+pub struct AddRoundSubscription<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"priced_round", issuer.id.as_ref(), round.id.as_ref()],
+        bump,
+        realloc = PricedRound::space(round.subscriptions.len() + 1, round.conversions.len()),
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub round: Account<'info, PricedRound>,
+    pub stakeholder: Account<'info, Stakeholder>,
+    /// Reserves the security id now, so settling the closed round cannot fail
+    #[account(
+        init,
+        payer = authority,
+        space = SecurityRegistration::SPACE,
+        seeds = [b"security", issuer.id.as_ref(), security_id.as_ref()],
+        bump
+    )]
+    pub security_registration: Account<'info, SecurityRegistration>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(resulting_security_id: [u8; 16])]
+pub struct AddRoundConversion<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"priced_round", issuer.id.as_ref(), round.id.as_ref()],
+        bump,
+        realloc = PricedRound::space(round.subscriptions.len(), round.conversions.len() + 1),
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub round: Account<'info, PricedRound>,
+    #[account(
+        mut,
+        seeds = [
+            b"convertible_position",
+            convertible_position.stakeholder_id.as_ref(),
+            convertible_position.security_id.as_ref()
+        ],
+        bump
+    )]
+    pub convertible_position: Account<'info, ConvertibleActivePosition>,
+    /// Ties the convertible to the issuer, since its seeds do not include it
+    #[account(
+        seeds = [b"security", issuer.id.as_ref(), convertible_position.security_id.as_ref()],
+        bump,
+        constraint = convertible_registration.position == convertible_position.key() @ FinancingRoundError::ConvertibleNotRegistered
+    )]
+    pub convertible_registration: Account<'info, SecurityRegistration>,
+    /// Reserves the resulting security id now, so settling the closed round
+    /// cannot fail
+    #[account(
+        init,
+        payer = authority,
+        space = SecurityRegistration::SPACE,
+        seeds = [b"security", issuer.id.as_ref(), resulting_security_id.as_ref()],
+        bump
+    )]
+    pub security_registration: Account<'info, SecurityRegistration>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Discards an open round; nothing it recorded has been applied yet. The
+/// remaining accounts are the security registrations the round reserved, in
+/// entry order (subscriptions, then conversions), followed by the
+/// convertibles it claimed, which are released.
+#[derive(Accounts)]
+pub struct CancelFinancingRound<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"priced_round", issuer.id.as_ref(), round.id.as_ref()],
+        bump,
+        constraint = round.status == PricedRoundStatus::Open @ FinancingRoundError::RoundNotOpen,
+        close = authority
+    )]
+    pub round: Account<'info, PricedRound>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+/// The round's stock plan is required when the round names one.
+#[derive(Accounts)]
+pub struct CloseFinancingRound<'info> {
+    #[account(mut)]
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"tx_log", issuer.id.as_ref()],
+        bump
+    )]
+    pub tx_log: Account<'info, TxLog>,
+    #[account(
+        mut,
+        seeds = [b"cap_table_summary", issuer.id.as_ref()],
+        bump,
+        realloc = CapTableSummary::space(cap_table_summary.stock_classes.len() + 1),
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub cap_table_summary: Account<'info, CapTableSummary>,
+    #[account(
+        mut,
+        seeds = [b"priced_round", issuer.id.as_ref(), round.id.as_ref()],
+        bump
+    )]
+    pub round: Account<'info, PricedRound>,
+    #[account(
+        init,
+        payer = authority,
+        space = StockClass::SPACE,
+        seeds = [
+            b"stock_class",
+            round.stock_class_id.as_ref(),
+        ],
+        bump
+    )]
+    pub stock_class: Account<'info, StockClass>,
+    #[account(mut)]
+    pub stock_plan: Option<Account<'info, StockPlan>>,
+    /// CHECK: the issuer's board; once it exists the action needs a proposal
+    #[account(
+        seeds = [b"board", issuer.id.as_ref()],
+        bump,
+        constraint = board.data_is_empty() @ GovernanceError::BoardApprovalRequired
+    )]
+    pub board: UncheckedAccount<'info>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Creates the stock position of one entry of a closed round. For a
+/// conversion, the convertible it replaces is passed and closed.
+#[derive(Accounts)]
+#[instruction(security_id: [u8; 16])]
+pub struct SettleFinancingRound<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"priced_round", issuer.id.as_ref(), round.id.as_ref()],
+        bump
+    )]
+    pub round: Account<'info, PricedRound>,
+    pub stakeholder: Account<'info, Stakeholder>,
+    #[account(
+        mut,
+        seeds = [b"stakeholder_holdings", stakeholder.id.as_ref()],
+        bump,
+        realloc = StakeholderHoldings::space(stakeholder_holdings.holdings.len() + 1),
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub stakeholder_holdings: Account<'info, StakeholderHoldings>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 16 + 16 + 16 + 8 + 8 + 4,
+        seeds = [
+            b"stock_position",
+            stakeholder.id.as_ref(),
+            security_id.as_ref()
+        ],
+        bump
+    )]
+    pub position: Account<'info, StockActivePosition>,
+    #[account(
+        mut,
+        seeds = [
+            b"convertible_position",
+            stakeholder.id.as_ref(),
+            convertible_position.security_id.as_ref()
+        ],
+        bump
+    )]
+    pub convertible_position: Option<Account<'info, ConvertibleActivePosition>>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn open_financing_round(
+    ctx: Context<OpenFinancingRound>,
+    id: [u8; 16],
+    stock_class_id: [u8; 16],
+    class_type: String,
+    terms: StockClassTerms,
+    pre_money_valuation: u64,
+    target_pool_fraction: u64,
+) -> Result<()> {
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
+
+    let issuer_id = ctx.accounts.issuer.id;
+    let stock_plan_id = ctx
+        .accounts
+        .stock_plan
+        .as_ref()
+        .map_or([0; 16], |stock_plan| stock_plan.id);
+    let round = &mut ctx.accounts.round;
+    round.set_inner(PricedRound {
+        issuer_id,
+        id,
+        stock_class_id,
+        class_type,
+        terms,
+        pre_money_valuation,
+        target_pool_fraction,
+        stock_plan_id,
+        ..PricedRound::default()
+    });
+    rules::open_financing_round(round)?;
+
+    emit!(FinancingRoundOpened {
+        envelope: EventEnvelope::new(issuer_id, ctx.accounts.authority.key(), id)?,
+        round_id: id,
+        stock_class_id,
+        pre_money_valuation,
+        target_pool_fraction,
+        stock_plan_id,
+    });
+
+    Ok(())
+}
+
+pub fn add_round_subscription(
+    ctx: Context<AddRoundSubscription>,
+    security_id: [u8; 16],
+    amount: u64,
+) -> Result<()> {
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
+
+    let round = &mut ctx.accounts.round;
+    let stakeholder_id = ctx.accounts.stakeholder.id;
+    rules::add_round_subscription(round, stakeholder_id, security_id, amount)?;
+    ctx.accounts.security_registration.register(
+        ctx.accounts.issuer.id,
+        security_id,
+        PositionType::Stock,
+        stock_position_address(&stakeholder_id, &security_id),
+    );
+
+    emit!(RoundSubscriptionAdded {
+        envelope: EventEnvelope::new(
+            ctx.accounts.issuer.id,
+            ctx.accounts.authority.key(),
+            security_id
+        )?,
+        round_id: round.id,
+        stakeholder_id,
+        security_id,
+        amount,
+    });
+
+    Ok(())
+}
+
+pub fn add_round_conversion(
+    ctx: Context<AddRoundConversion>,
+    resulting_security_id: [u8; 16],
+    discount: u64,
+    valuation_cap: u64,
+) -> Result<()> {
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
+
+    let round = &mut ctx.accounts.round;
+    let convertible_position = &mut ctx.accounts.convertible_position;
+    rules::add_round_conversion(
+        round,
+        convertible_position,
+        resulting_security_id,
+        discount,
+        valuation_cap,
+    )?;
+    ctx.accounts.security_registration.register(
+        ctx.accounts.issuer.id,
+        resulting_security_id,
+        PositionType::Stock,
+        stock_position_address(&convertible_position.stakeholder_id, &resulting_security_id),
+    );
+
+    emit!(RoundConversionAdded {
+        envelope: EventEnvelope::new(
+            ctx.accounts.issuer.id,
+            ctx.accounts.authority.key(),
+            convertible_position.security_id
+        )?,
+        round_id: round.id,
+        stakeholder_id: convertible_position.stakeholder_id,
+        security_id: convertible_position.security_id,
+        resulting_security_id,
+        discount,
+        valuation_cap,
+    });
+
+    Ok(())
+}
+
+pub fn cancel_financing_round<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CancelFinancingRound<'info>>,
+) -> Result<()> {
+    let accounts = authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
+
+    let round = &ctx.accounts.round;
+    let round_id = round.id;
+    require_eq!(
+        accounts.len(),
+        round.entry_count() + round.conversions.len(),
+        FinancingRoundError::IncompleteRoundAccounts
+    );
+    let (registrations, convertibles) = accounts.split_at(round.entry_count());
+
+    let security_ids = round
+        .subscriptions
+        .iter()
+        .map(|subscription| subscription.security_id)
+        .chain(
+            round
+                .conversions
+                .iter()
+                .map(|conversion| conversion.resulting_security_id),
+        );
+    for (account, security_id) in registrations.iter().zip(security_ids) {
+        let registration = Account::<SecurityRegistration>::try_from(account)?;
+        require!(
+            registration.issuer_id == round.issuer_id && registration.security_id == security_id,
+            FinancingRoundError::IncompleteRoundAccounts
+        );
+        registration.close(ctx.accounts.authority.to_account_info())?;
+    }
+    for account in convertibles {
+        let mut convertible_position = Account::<ConvertibleActivePosition>::try_from(account)?;
+        rules::release_round_conversion(round, &mut convertible_position)?;
+        convertible_position.exit(&crate::ID)?;
+    }
+
+    emit!(FinancingRoundCancelled {
+        envelope: EventEnvelope::new(
+            ctx.accounts.issuer.id,
+            ctx.accounts.authority.key(),
+            round_id
+        )?,
+        round_id,
+    });
+
+    Ok(())
+}
+
+/// The stock position a round entry settles into, registered when the entry
+/// is added.
+fn stock_position_address(stakeholder_id: &[u8; 16], security_id: &[u8; 16]) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"stock_position", stakeholder_id, security_id],
+        &crate::ID,
+    )
+    .0
+}
+
+/// Appends a payload to the `TxLog` and emits its `TxCreated`.
+fn record_tx<T: AnchorSerialize + TxPayload>(
+    tx_log: &mut TxLog,
+    envelope: EventEnvelope,
+    payload: &T,
+) -> Result<()> {
+    let tx_data = AnchorSerialize::try_to_vec(payload)?;
+    let (sequence, prev_hash) = tx_log.append(T::TX_TYPE, T::VERSION, &tx_data)?;
+    emit!(TxCreatedV2 {
+        envelope,
+        tx_type: T::TX_TYPE,
+        payload_version: T::VERSION,
+        tx_data,
+        sequence,
+        prev_hash,
+    });
+    Ok(())
+}
+
+/// Applies the whole round in this one instruction, so either every
+/// issuance, conversion and adjustment is recorded or none is. Each effect
+/// emits the event its standalone instruction would, in the order an
+/// indexer must replay them.
+pub fn close_financing_round(ctx: Context<CloseFinancingRound>) -> Result<()> {
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
+
+    let accounts = &mut *ctx.accounts;
+    let issuer_id = accounts.issuer.id;
+    let actor = accounts.authority.key();
+    let previous_shares_authorized = accounts.issuer.shares_authorized;
+    let round = &mut accounts.round;
+    let stock_class = &mut accounts.stock_class;
+
+    let model = rules::close_financing_round(
+        round,
+        &mut accounts.issuer,
+        stock_class,
+        accounts.stock_plan.as_deref_mut(),
+        &mut accounts.cap_table_summary,
+    )?;
+
+    emit!(StockClassCreated {
+        envelope: EventEnvelope::new(issuer_id, actor, stock_class.id)?,
+        id: stock_class.id,
+        class_type: stock_class.class_type.clone(),
+        price_per_share: stock_class.price_per_share,
+        initial_shares_authorized: stock_class.shares_authorized,
+        terms: stock_class.terms,
+    });
+
+    for subscription in &round.subscriptions {
+        let envelope = EventEnvelope::new(issuer_id, actor, subscription.security_id)?;
+        record_tx(
+            &mut accounts.tx_log,
+            envelope,
+            &StockIssued {
+                envelope,
+                stock_class_id: stock_class.id,
+                security_id: subscription.security_id,
+                stakeholder_id: subscription.stakeholder_id,
+                quantity: subscription.shares,
+                share_price: round.price_per_share,
+            },
+        )?;
+    }
+
+    for conversion in &round.conversions {
+        let envelope = EventEnvelope::new(issuer_id, actor, conversion.security_id)?;
+        record_tx(
+            &mut accounts.tx_log,
+            envelope,
+            &ConvertibleConverted {
+                envelope,
+                security_id: conversion.security_id,
+                resulting_security_id: conversion.resulting_security_id,
+                stakeholder_id: conversion.stakeholder_id,
+                stock_class_id: stock_class.id,
+                investment_amount: conversion.principal,
+                conversion_price: conversion.conversion_price,
+                quantity_received: conversion.shares,
+            },
+        )?;
+    }
+
+    if let Some(stock_plan) = &accounts.stock_plan {
+        if model.pool_increase > 0 {
+            emit!(StockPlanSharesAdjusted {
+                envelope: EventEnvelope::new(issuer_id, actor, stock_plan.id)?,
+                id: stock_plan.id,
+                new_shares_reserved: stock_plan.shares_reserved,
+            });
+        }
+    }
+
+    let shares_authorized = accounts.issuer.shares_authorized;
+    if shares_authorized != previous_shares_authorized {
+        emit!(IssuerAuthorizedSharesAdjusted {
+            envelope: EventEnvelope::new(issuer_id, actor, issuer_id)?,
+            new_shares_authorized: shares_authorized,
+        });
+    }
+
+    emit!(FinancingRoundClosed {
+        envelope: EventEnvelope::new(issuer_id, actor, round.id)?,
+        round_id: round.id,
+        stock_class_id: stock_class.id,
+        price_per_share: model.price_per_share,
+        pre_money_valuation: round.pre_money_valuation,
+        post_money_valuation: model.post_money_valuation,
+        shares_issued: stock_class.shares_issued,
+        pool_increase: model.pool_increase,
+    });
+
+    Ok(())
+}
+
+pub fn settle_financing_round(
+    ctx: Context<SettleFinancingRound>,
+    security_id: [u8; 16],
+) -> Result<()> {
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
+
+    let position = &mut ctx.accounts.position;
+    let stakeholder_id = ctx.accounts.stakeholder.id;
+
+    let convertible_security_id = rules::settle_financing_round(
+        &mut ctx.accounts.round,
+        position,
+        stakeholder_id,
+        security_id,
+    )?;
+
+    let stakeholder_holdings = &mut ctx.accounts.stakeholder_holdings;
+    stakeholder_holdings.add(security_id, PositionType::Stock)?;
+
+    // A converted note is closed once the shares it became exist
+    if let Some(convertible_security_id) = convertible_security_id {
+        let convertible_position = ctx
+            .accounts
+            .convertible_position
+            .as_ref()
+            .filter(|position| position.security_id == convertible_security_id)
+            .ok_or(FinancingRoundError::EntryNotFound)?;
+        stakeholder_holdings.remove(convertible_security_id, PositionType::Convertible)?;
+        convertible_position.close(ctx.accounts.authority.to_account_info())?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,310 @@
+use crate::engine::rules;
+use crate::errors::GovernanceError;
+use crate::events::*;
+use crate::instructions::multisig::authorize;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct InitializeBoard<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        init,
+        payer = authority,
+        space = Board::SPACE,
+        seeds = [b"board", issuer.id.as_ref()],
+        bump
+    )]
+    pub board: Account<'info, Board>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(id: [u8; 16], action: ProposalAction)]
+pub struct CreateProposal<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        seeds = [b"board", issuer.id.as_ref()],
+        bump
+    )]
+    pub board: Account<'info, Board>,
+    #[account(
+        init,
+        payer = authority,
+        space = Proposal::space(&action),
+        seeds = [b"proposal", issuer.id.as_ref(), id.as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveProposal<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        seeds = [b"board", issuer.id.as_ref()],
+        bump
+    )]
+    pub board: Account<'info, Board>,
+    #[account(
+        mut,
+        seeds = [b"proposal", issuer.id.as_ref(), proposal.id.as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    pub member: Signer<'info>,
+}
+
+/// The stock class or plan account is passed only when the proposal creates
+/// one.
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    #[account(mut)]
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"board", issuer.id.as_ref()],
+        bump
+    )]
+    pub board: Account<'info, Board>,
+    #[account(
+        mut,
+        seeds = [b"proposal", issuer.id.as_ref(), proposal.id.as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    #[account(
+        mut,
+        seeds = [b"cap_table_summary", issuer.id.as_ref()],
+        bump,
+        realloc = CapTableSummary::space(
+            cap_table_summary.stock_classes.len() + proposal.action.creates_stock_class() as usize
+        ),
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub cap_table_summary: Account<'info, CapTableSummary>,
+    #[account(
+        init,
+        payer = authority,
+        space = StockClass::SPACE,
+        seeds = [
+            b"stock_class",
+            proposal.action.created_id().as_ref(),
+        ],
+        bump
+    )]
+    pub stock_class: Option<Account<'info, StockClass>>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 16 + 4 + (16 * 32) + 8,
+        seeds = [
+            b"stock_plan",
+            proposal.action.created_id().as_ref(),
+        ],
+        bump
+    )]
+    pub stock_plan: Option<Account<'info, StockPlan>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn initialize_board(
+    ctx: Context<InitializeBoard>,
+    members: Vec<Pubkey>,
+    threshold: u8,
+) -> Result<()> {
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
+
+    let issuer_id = ctx.accounts.issuer.id;
+    let board = &mut ctx.accounts.board;
+    rules::configure_board(board, issuer_id, members, threshold)?;
+
+    emit!(BoardConfigured {
+        envelope: EventEnvelope::new(issuer_id, ctx.accounts.authority.key(), issuer_id)?,
+        members: board.members.clone(),
+        threshold,
+    });
+
+    Ok(())
+}
+
+pub fn create_proposal(
+    ctx: Context<CreateProposal>,
+    id: [u8; 16],
+    action: ProposalAction,
+    stockholder_approval_date: i64,
+) -> Result<()> {
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
+
+    let issuer_id = ctx.accounts.issuer.id;
+    let proposer = ctx.accounts.authority.key();
+    let proposal = &mut ctx.accounts.proposal;
+    proposal.set_inner(Proposal {
+        issuer_id,
+        id,
+        proposer,
+        action,
+        stockholder_approval_date,
+        ..Proposal::default()
+    });
+    rules::create_proposal(proposal, Clock::get()?.unix_timestamp)?;
+
+    emit!(ProposalCreated {
+        envelope: EventEnvelope::new(issuer_id, proposer, id)?,
+        proposal_id: id,
+        action: proposal.action.clone(),
+        stockholder_approval_date,
+    });
+
+    Ok(())
+}
+
+pub fn approve_proposal(ctx: Context<ApproveProposal>) -> Result<()> {
+    let member = ctx.accounts.member.key();
+    let proposal = &mut ctx.accounts.proposal;
+    rules::approve_proposal(
+        proposal,
+        &ctx.accounts.board,
+        member,
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    emit!(ProposalApproved {
+        envelope: EventEnvelope::new(ctx.accounts.issuer.id, member, proposal.id)?,
+        proposal_id: proposal.id,
+        member,
+        approvals: proposal.approvals.len() as u8,
+        board_approval_date: proposal.board_approval_date,
+    });
+
+    Ok(())
+}
+
+/// Performs the approved operation with the same rules and events as its
+/// standalone instruction, then records the execution.
+pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+    let accounts = &mut *ctx.accounts;
+    let issuer_id = accounts.issuer.id;
+    let actor = accounts.authority.key();
+    let proposal = &mut accounts.proposal;
+
+    require!(
+        accounts.stock_class.is_some() == proposal.action.creates_stock_class()
+            && accounts.stock_plan.is_some() == proposal.action.creates_stock_plan(),
+        GovernanceError::AccountMismatch
+    );
+    rules::execute_proposal(proposal, &accounts.board, Clock::get()?.unix_timestamp)?;
+
+    match proposal.action.clone() {
+        ProposalAction::AdjustAuthorizedShares {
+            new_shares_authorized,
+        } => {
+            rules::adjust_authorized_shares(
+                &mut accounts.issuer,
+                &mut accounts.cap_table_summary,
+                new_shares_authorized,
+            );
+
+            emit!(IssuerAuthorizedSharesAdjusted {
+                envelope: EventEnvelope::new(issuer_id, actor, issuer_id)?,
+                new_shares_authorized,
+            });
+        }
+        ProposalAction::CreateStockClass {
+            id,
+            class_type,
+            price_per_share,
+            initial_shares_authorized,
+            terms,
+        } => {
+            let stock_class = accounts
+                .stock_class
+                .as_deref_mut()
+                .ok_or(GovernanceError::AccountMismatch)?;
+            rules::create_stock_class(
+                stock_class,
+                &mut accounts.cap_table_summary,
+                id,
+                class_type,
+                price_per_share,
+                initial_shares_authorized,
+                terms,
+            )?;
+
+            emit!(StockClassCreated {
+                envelope: EventEnvelope::new(issuer_id, actor, id)?,
+                id,
+                class_type: stock_class.class_type.clone(),
+                price_per_share,
+                initial_shares_authorized,
+                terms,
+            });
+        }
+        ProposalAction::CreateStockPlan {
+            id,
+            stock_class_ids,
+            shares_reserved,
+        } => {
+            let stock_plan = accounts
+                .stock_plan
+                .as_deref_mut()
+                .ok_or(GovernanceError::AccountMismatch)?;
+            rules::create_stock_plan(
+                stock_plan,
+                &mut accounts.cap_table_summary,
+                id,
+                stock_class_ids,
+                shares_reserved,
+            )?;
+
+            emit!(StockPlanCreated {
+                envelope: EventEnvelope::new(issuer_id, actor, id)?,
+                id,
+                shares_reserved,
+            });
+        }
+        ProposalAction::UpdateBoard { members, threshold } => {
+            let board = &mut accounts.board;
+            rules::configure_board(board, issuer_id, members, threshold)?;
+
+            emit!(BoardConfigured {
+                envelope: EventEnvelope::new(issuer_id, actor, issuer_id)?,
+                members: board.members.clone(),
+                threshold,
+            });
+        }
+    }
+
+    emit!(ProposalExecuted {
+        envelope: EventEnvelope::new(issuer_id, actor, proposal.id)?,
+        proposal_id: proposal.id,
+        board_approval_date: proposal.board_approval_date,
+        stockholder_approval_date: proposal.stockholder_approval_date,
+    });
+
+    Ok(())
+}
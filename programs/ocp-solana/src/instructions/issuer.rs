@@ -1,6 +1,14 @@
-use crate::errors::*;
+use crate::engine::{
+    liquidation_waterfall, rules, LiquidationDistribution, OptionExercisePrice, WaterfallInput,
+};
+use crate::errors::{GovernanceError, WaterfallError};
+use crate::events::*;
+use crate::instructions::multisig::authorize;
 use crate::state::*;
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::MAX_RETURN_DATA;
+use anchor_lang::Discriminator;
+use std::collections::BTreeSet;
 
 #[derive(Accounts)]
 #[instruction(id: [u8; 16])]
@@ -8,7 +16,7 @@ pub struct InitializeIssuer<'info> {
     #[account(
         init,
         payer = authority,
-        space = 8 + 16 + 8 + 8,
+        space = 8 + 16 + 8 + 8 + 32,
         seeds = [
             b"issuer",
             id.as_ref(),
@@ -16,6 +24,28 @@ pub struct InitializeIssuer<'info> {
         bump
     )]
     pub issuer: Account<'info, Issuer>,
+    #[account(
+        init,
+        payer = authority,
+        space = CapTableSummary::space(0),
+        seeds = [
+            b"cap_table_summary",
+            id.as_ref(),
+        ],
+        bump
+    )]
+    pub cap_table_summary: Account<'info, CapTableSummary>,
+    #[account(
+        init,
+        payer = authority,
+        space = TxLog::SPACE,
+        seeds = [
+            b"tx_log",
+            id.as_ref(),
+        ],
+        bump
+    )]
+    pub tx_log: Account<'info, TxLog>,
     #[account(mut)]
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
@@ -25,7 +55,77 @@ pub struct InitializeIssuer<'info> {
 pub struct AdjustAuthorizedShares<'info> {
     #[account(mut)]
     pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"cap_table_summary", issuer.id.as_ref()],
+        bump
+    )]
+    pub cap_table_summary: Account<'info, CapTableSummary>,
+    /// CHECK: the issuer's board; once it exists the action needs a proposal
+    #[account(
+        seeds = [b"board", issuer.id.as_ref()],
+        bump,
+        constraint = board.data_is_empty() @ GovernanceError::BoardApprovalRequired
+    )]
+    pub board: UncheckedAccount<'info>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetCapTableSummary<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        seeds = [b"cap_table_summary", issuer.id.as_ref()],
+        bump
+    )]
+    pub cap_table_summary: Account<'info, CapTableSummary>,
+}
+
+#[derive(Accounts)]
+pub struct GetLiquidationWaterfall<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        seeds = [b"cap_table_summary", issuer.id.as_ref()],
+        bump
+    )]
+    pub cap_table_summary: Account<'info, CapTableSummary>,
+}
+
+#[derive(Accounts)]
+#[instruction(id: [u8; 16])]
+pub struct SnapshotCapTable<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        seeds = [b"cap_table_summary", issuer.id.as_ref()],
+        bump
+    )]
+    pub cap_table_summary: Account<'info, CapTableSummary>,
+    #[account(
+        seeds = [b"tx_log", issuer.id.as_ref()],
+        bump
+    )]
+    pub tx_log: Account<'info, TxLog>,
+    #[account(
+        init,
+        payer = authority,
+        space = CapTableSnapshot::space(cap_table_summary.stock_classes.len()),
+        seeds = [
+            b"cap_table_snapshot",
+            issuer.id.as_ref(),
+            id.as_ref(),
+        ],
+        bump
+    )]
+    pub snapshot: Account<'info, CapTableSnapshot>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
+    #[account(mut)]
     pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
 pub fn initialize_issuer(
@@ -33,16 +133,20 @@ pub fn initialize_issuer(
     id: [u8; 16],
     initial_shares_authorized: u64,
 ) -> Result<()> {
-    let issuer = &mut ctx.accounts.issuer;
+    rules::initialize_issuer(
+        &mut ctx.accounts.issuer,
+        &mut ctx.accounts.cap_table_summary,
+        id,
+        initial_shares_authorized,
+    )?;
+    ctx.accounts.issuer.authority = ctx.accounts.authority.key();
 
-    require!(
-        issuer.shares_authorized == 0,
-        IssuerError::AlreadyInitialized
-    );
+    ctx.accounts.tx_log.issuer_id = id;
 
-    issuer.id = id;
-    issuer.shares_issued = 0;
-    issuer.shares_authorized = initial_shares_authorized;
+    emit!(IssuerCreated {
+        envelope: EventEnvelope::new(id, ctx.accounts.authority.key(), id)?,
+        initial_shares_authorized,
+    });
 
     msg!("Issuer initialized with id: {:?}", id);
     Ok(())
@@ -52,9 +156,169 @@ pub fn adjust_authorized_shares(
     ctx: Context<AdjustAuthorizedShares>,
     new_shares_authorized: u64,
 ) -> Result<()> {
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
+
     let issuer = &mut ctx.accounts.issuer;
-    issuer.shares_authorized = new_shares_authorized;
+    rules::adjust_authorized_shares(
+        issuer,
+        &mut ctx.accounts.cap_table_summary,
+        new_shares_authorized,
+    );
+
+    emit!(IssuerAuthorizedSharesAdjusted {
+        envelope: EventEnvelope::new(issuer.id, ctx.accounts.authority.key(), issuer.id)?,
+        new_shares_authorized,
+    });
 
     msg!("Adjusted authorized shares to: {}", new_shares_authorized);
     Ok(())
 }
+
+pub fn get_cap_table_summary(ctx: Context<GetCapTableSummary>) -> Result<CapTableSummary> {
+    // `#[account]` derives `Clone`
+    Ok((*ctx.accounts.cap_table_summary).clone())
+}
+
+/// Remaining accounts are every stock class of the issuer and its option and
+/// warrant positions, plus the stock positions to break the result down by
+/// stakeholder. `option_exercise_prices` needs an entry for every option.
+pub fn get_liquidation_waterfall<'info>(
+    ctx: Context<'_, '_, 'info, 'info, GetLiquidationWaterfall<'info>>,
+    exit_value: u64,
+    option_exercise_prices: Vec<OptionExercisePrice>,
+) -> Result<LiquidationDistribution> {
+    let cap_table_summary = &ctx.accounts.cap_table_summary;
+    let mut stock_classes = Vec::new();
+    let mut stock_positions = Vec::new();
+    let mut equity_compensation_positions = Vec::new();
+    let mut warrant_positions = Vec::new();
+    let mut seen = BTreeSet::new();
+
+    for account_info in ctx.remaining_accounts.iter() {
+        if !seen.insert(account_info.key()) {
+            continue;
+        }
+        let discriminator = account_info
+            .try_borrow_data()?
+            .get(..8)
+            .and_then(|data| <[u8; 8]>::try_from(data).ok())
+            .ok_or(WaterfallError::UnsupportedAccount)?;
+
+        if discriminator == StockClass::DISCRIMINATOR {
+            let stock_class = Account::<StockClass>::try_from(account_info)?;
+            require!(
+                cap_table_summary
+                    .stock_classes
+                    .iter()
+                    .any(|summary| summary.stock_class_id == stock_class.id),
+                WaterfallError::UnsupportedAccount
+            );
+            stock_classes.push(stock_class);
+        } else if discriminator == StockActivePosition::DISCRIMINATOR {
+            stock_positions.push(Account::<StockActivePosition>::try_from(account_info)?);
+        } else if discriminator == EquityCompensationActivePosition::DISCRIMINATOR {
+            equity_compensation_positions.push(
+                Account::<EquityCompensationActivePosition>::try_from(account_info)?,
+            );
+        } else if discriminator == WarrantActivePosition::DISCRIMINATOR {
+            warrant_positions.push(Account::<WarrantActivePosition>::try_from(account_info)?);
+        } else {
+            return err!(WaterfallError::UnsupportedAccount);
+        }
+    }
+
+    // Class-level proceeds need every class and every option and warrant
+    require!(
+        stock_classes.len() == cap_table_summary.stock_classes.len(),
+        WaterfallError::MissingStockClass
+    );
+    let options = equity_compensation_positions
+        .iter()
+        .try_fold(0u64, |total, position| total.checked_add(position.quantity))
+        .ok_or(WaterfallError::ArithmeticOverflow)?;
+    let warrants = warrant_positions
+        .iter()
+        .try_fold(0u64, |total, position| total.checked_add(position.quantity))
+        .ok_or(WaterfallError::ArithmeticOverflow)?;
+    require!(
+        options == cap_table_summary.options_outstanding
+            && warrants == cap_table_summary.warrants_outstanding,
+        WaterfallError::IncompletePositions
+    );
+
+    let input = WaterfallInput {
+        stock_classes: stock_classes.iter().map(|account| &**account).collect(),
+        stock_positions: stock_positions.iter().map(|account| &**account).collect(),
+        equity_compensation_positions: equity_compensation_positions
+            .iter()
+            .map(|account| &**account)
+            .collect(),
+        warrant_positions: warrant_positions.iter().map(|account| &**account).collect(),
+        option_exercise_prices: option_exercise_prices
+            .iter()
+            .map(|price| (price.security_id, price.exercise_price))
+            .collect(),
+    };
+    let distribution = liquidation_waterfall(&input, exit_value, Clock::get()?.unix_timestamp)?;
+
+    require!(
+        distribution.try_to_vec()?.len() <= MAX_RETURN_DATA,
+        WaterfallError::ReturnDataTooLarge
+    );
+    Ok(distribution)
+}
+
+/// Copies the summary as it stands. The recorded `TxLog` position orders the
+/// snapshot among logged transactions; it does not cover the unlogged
+/// mutations listed on `CapTableSnapshot::tx_log_sequence`.
+pub fn snapshot_cap_table(ctx: Context<SnapshotCapTable>, id: [u8; 16]) -> Result<()> {
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
+
+    let cap_table_summary = &ctx.accounts.cap_table_summary;
+    let tx_log = &ctx.accounts.tx_log;
+    let issuer_id = ctx.accounts.issuer.id;
+    let authority = ctx.accounts.authority.key();
+    let envelope = EventEnvelope::new(issuer_id, authority, issuer_id)?;
+
+    ctx.accounts.snapshot.set_inner(CapTableSnapshot {
+        issuer_id,
+        id,
+        unix_timestamp: envelope.unix_timestamp,
+        slot: envelope.slot,
+        authority,
+        tx_log_sequence: tx_log.sequence,
+        tx_log_hash: tx_log.last_hash,
+        shares_authorized: cap_table_summary.shares_authorized,
+        stock_classes: cap_table_summary.stock_classes.clone(),
+        options_outstanding: cap_table_summary.options_outstanding,
+        warrants_outstanding: cap_table_summary.warrants_outstanding,
+        plan_pool_reserved: cap_table_summary.plan_pool_reserved,
+        plan_pool_granted: cap_table_summary.plan_pool_granted,
+        plan_pool_available: cap_table_summary.plan_pool_available,
+        convertible_principal: cap_table_summary.convertible_principal,
+    });
+
+    emit!(CapTableSnapshotTaken {
+        envelope,
+        snapshot_id: id,
+        tx_log_sequence: tx_log.sequence,
+        tx_log_hash: tx_log.last_hash,
+    });
+
+    msg!(
+        "Cap table snapshot {:?} taken at sequence {}",
+        id,
+        tx_log.sequence
+    );
+    Ok(())
+}
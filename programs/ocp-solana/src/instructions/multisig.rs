@@ -0,0 +1,297 @@
+use crate::engine::rules;
+use crate::errors::GovernanceError;
+use crate::events::*;
+use crate::state::*;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+
+#[derive(Accounts)]
+pub struct CreateMultisig<'info> {
+    #[account(has_one = authority @ GovernanceError::NotIssuerAuthority)]
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        init,
+        payer = authority,
+        space = Multisig::SPACE,
+        seeds = [b"multisig", issuer.id.as_ref()],
+        bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+    /// CHECK: only its bump is recorded
+    #[account(
+        seeds = [b"multisig_signer", issuer.id.as_ref()],
+        bump
+    )]
+    pub multisig_signer: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Changes the members or threshold; authorized by the multisig itself.
+#[derive(Accounts)]
+pub struct UpdateMultisig<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"multisig", issuer.id.as_ref()],
+        bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(accounts: Vec<TransactionAccount>, data: Vec<u8>)]
+pub struct CreateMultisigTransaction<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"multisig", issuer.id.as_ref()],
+        bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+    #[account(
+        init,
+        payer = proposer,
+        space = MultisigTransaction::space(accounts.len(), data.len()),
+        seeds = [
+            b"multisig_transaction",
+            issuer.id.as_ref(),
+            multisig.nonce.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    pub transaction: Account<'info, MultisigTransaction>,
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveMultisigTransaction<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        seeds = [b"multisig", issuer.id.as_ref()],
+        bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+    #[account(
+        mut,
+        seeds = [
+            b"multisig_transaction",
+            issuer.id.as_ref(),
+            transaction.nonce.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    pub transaction: Account<'info, MultisigTransaction>,
+    pub member: Signer<'info>,
+}
+
+/// The stored instruction's accounts are passed as remaining accounts. The
+/// multisig is read-only here so that an invoked `update_multisig` is not
+/// overwritten on exit.
+#[derive(Accounts)]
+pub struct ExecuteMultisigTransaction<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        seeds = [b"multisig", issuer.id.as_ref()],
+        bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+    #[account(
+        mut,
+        seeds = [
+            b"multisig_transaction",
+            issuer.id.as_ref(),
+            transaction.nonce.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    pub transaction: Account<'info, MultisigTransaction>,
+    /// CHECK: signs the invoked instruction; pays for the accounts it creates
+    #[account(
+        mut,
+        seeds = [b"multisig_signer", issuer.id.as_ref()],
+        bump = multisig.signer_bump
+    )]
+    pub multisig_signer: UncheckedAccount<'info>,
+    pub ocp_program: Program<'info, crate::program::OcpSolana>,
+    pub executor: Signer<'info>,
+}
+
+/// Checks that the issuer authority, or the issuer's multisig once it has
+/// one, authorized the instruction. With a multisig, either `authority` is
+/// the multisig signer PDA, which only an approved `MultisigTransaction` can
+/// sign with, or `authority` and the signers trailing the remaining accounts
+/// include `threshold` members. Returns the remaining accounts before those
+/// co-signers.
+pub fn authorize<'a, 'info>(
+    issuer: &Issuer,
+    multisig: &AccountInfo,
+    authority: &AccountInfo,
+    remaining_accounts: &'a [AccountInfo<'info>],
+) -> Result<&'a [AccountInfo<'info>]> {
+    let cosigner_count = remaining_accounts
+        .iter()
+        .rev()
+        .take_while(|account| account.is_signer)
+        .count();
+    let (accounts, cosigners) =
+        remaining_accounts.split_at(remaining_accounts.len() - cosigner_count);
+
+    if multisig.data_is_empty() {
+        require_keys_eq!(
+            authority.key(),
+            issuer.authority,
+            GovernanceError::NotIssuerAuthority
+        );
+        return Ok(accounts);
+    }
+    require_keys_eq!(
+        *multisig.owner,
+        crate::ID,
+        ErrorCode::AccountOwnedByWrongProgram
+    );
+    let multisig = Multisig::try_deserialize(&mut &multisig.try_borrow_data()?[..])?;
+
+    let multisig_signer = Pubkey::create_program_address(
+        &[b"multisig_signer", &issuer.id, &[multisig.signer_bump]],
+        &crate::ID,
+    )
+    .map_err(|_| ErrorCode::ConstraintSeeds)?;
+    if authority.key() != multisig_signer {
+        let signers: Vec<Pubkey> = core::iter::once(authority.key())
+            .chain(cosigners.iter().map(|account| account.key()))
+            .collect();
+        rules::authorize_multisig(&multisig, &signers)?;
+    }
+
+    Ok(accounts)
+}
+
+pub fn create_multisig(
+    ctx: Context<CreateMultisig>,
+    members: Vec<Pubkey>,
+    threshold: u8,
+) -> Result<()> {
+    let issuer_id = ctx.accounts.issuer.id;
+    let multisig = &mut ctx.accounts.multisig;
+    rules::configure_multisig(multisig, issuer_id, members, threshold)?;
+    multisig.signer_bump = ctx.bumps.multisig_signer;
+
+    emit!(MultisigConfigured {
+        envelope: EventEnvelope::new(issuer_id, ctx.accounts.authority.key(), issuer_id)?,
+        members: multisig.members.clone(),
+        threshold,
+    });
+
+    Ok(())
+}
+
+pub fn update_multisig(
+    ctx: Context<UpdateMultisig>,
+    members: Vec<Pubkey>,
+    threshold: u8,
+) -> Result<()> {
+    let issuer_id = ctx.accounts.issuer.id;
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig.to_account_info(),
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
+
+    let multisig = &mut ctx.accounts.multisig;
+    rules::configure_multisig(multisig, issuer_id, members, threshold)?;
+
+    emit!(MultisigConfigured {
+        envelope: EventEnvelope::new(issuer_id, ctx.accounts.authority.key(), issuer_id)?,
+        members: multisig.members.clone(),
+        threshold,
+    });
+
+    Ok(())
+}
+
+pub fn create_multisig_transaction(
+    ctx: Context<CreateMultisigTransaction>,
+    accounts: Vec<TransactionAccount>,
+    data: Vec<u8>,
+) -> Result<()> {
+    let proposer = ctx.accounts.proposer.key();
+    let transaction = &mut ctx.accounts.transaction;
+    transaction.accounts = accounts;
+    transaction.data = data;
+    rules::create_multisig_transaction(&mut ctx.accounts.multisig, transaction, proposer)?;
+
+    emit!(MultisigTransactionCreated {
+        envelope: EventEnvelope::new(ctx.accounts.issuer.id, proposer, ctx.accounts.issuer.id)?,
+        nonce: transaction.nonce,
+        proposer,
+    });
+
+    Ok(())
+}
+
+pub fn approve_multisig_transaction(ctx: Context<ApproveMultisigTransaction>) -> Result<()> {
+    let member = ctx.accounts.member.key();
+    let transaction = &mut ctx.accounts.transaction;
+    rules::approve_multisig_transaction(transaction, &ctx.accounts.multisig, member)?;
+
+    emit!(MultisigTransactionApproved {
+        envelope: EventEnvelope::new(ctx.accounts.issuer.id, member, ctx.accounts.issuer.id)?,
+        nonce: transaction.nonce,
+        member,
+        approvals: transaction.approvals.len() as u8,
+    });
+
+    Ok(())
+}
+
+/// Invokes the approved instruction with the multisig signer PDA signing.
+pub fn execute_multisig_transaction<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ExecuteMultisigTransaction<'info>>,
+) -> Result<()> {
+    let issuer_id = ctx.accounts.issuer.id;
+    let transaction = &mut ctx.accounts.transaction;
+    rules::execute_multisig_transaction(transaction, &ctx.accounts.multisig)?;
+    // Persist the flag first so the invoked instruction cannot execute the
+    // transaction again
+    transaction.exit(&crate::ID)?;
+
+    emit!(MultisigTransactionExecuted {
+        envelope: EventEnvelope::new(issuer_id, ctx.accounts.executor.key(), issuer_id)?,
+        nonce: transaction.nonce,
+    });
+
+    let instruction = Instruction {
+        program_id: crate::ID,
+        accounts: transaction
+            .accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: account.pubkey,
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data: transaction.data.clone(),
+    };
+    let mut account_infos = ctx.remaining_accounts.to_vec();
+    account_infos.push(ctx.accounts.multisig_signer.to_account_info());
+    account_infos.push(ctx.accounts.ocp_program.to_account_info());
+    invoke_signed(
+        &instruction,
+        &account_infos,
+        &[&[
+            b"multisig_signer",
+            issuer_id.as_ref(),
+            &[ctx.accounts.multisig.signer_bump],
+        ]],
+    )?;
+
+    Ok(())
+}
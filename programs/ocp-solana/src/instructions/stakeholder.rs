@@ -1,4 +1,5 @@
 use crate::events::*;
+use crate::instructions::multisig::authorize;
 use crate::state::*;
 use anchor_lang::prelude::*;
 
@@ -17,21 +18,47 @@ pub struct CreateStakeholder<'info> {
         bump
     )]
     pub stakeholder: Account<'info, Stakeholder>,
+    #[account(
+        init,
+        payer = authority,
+        space = StakeholderHoldings::space(0),
+        seeds = [
+            b"stakeholder_holdings",
+            id.as_ref(),
+        ],
+        bump
+    )]
+    pub stakeholder_holdings: Account<'info, StakeholderHoldings>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
     #[account(mut)]
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
 pub fn create_stakeholder(ctx: Context<CreateStakeholder>, id: [u8; 16]) -> Result<()> {
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
+
     let stakeholder = &mut ctx.accounts.stakeholder;
 
     // Set the stakeholder ID
     stakeholder.id = id;
+    ctx.accounts.stakeholder_holdings.stakeholder_id = id;
 
     // Emit an event
     emit!(StakeholderCreated {
+        envelope: EventEnvelope::new(
+            ctx.accounts.issuer.id,
+            ctx.accounts.authority.key(),
+            stakeholder.id
+        )?,
         id: stakeholder.id,
-        issuer_id: ctx.accounts.issuer.id
     });
 
     msg!("Stakeholder created with id: {:?}", id);
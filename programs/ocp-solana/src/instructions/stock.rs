@@ -1,5 +1,8 @@
+use crate::engine::rules;
 use crate::errors::*;
 use crate::events::*;
+use crate::instructions::multisig::authorize;
+use crate::payload::TxPayload;
 use crate::state::*;
 use anchor_lang::prelude::*;
 
@@ -10,11 +13,32 @@ pub struct IssueStock<'info> {
     pub stock_class: Account<'info, StockClass>,
     #[account(mut)]
     pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"tx_log", issuer.id.as_ref()],
+        bump
+    )]
+    pub tx_log: Account<'info, TxLog>,
+    #[account(
+        mut,
+        seeds = [b"cap_table_summary", issuer.id.as_ref()],
+        bump
+    )]
+    pub cap_table_summary: Account<'info, CapTableSummary>,
     pub stakeholder: Account<'info, Stakeholder>,
+    #[account(
+        mut,
+        seeds = [b"stakeholder_holdings", stakeholder.id.as_ref()],
+        bump,
+        realloc = StakeholderHoldings::space(stakeholder_holdings.holdings.len() + 1),
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub stakeholder_holdings: Account<'info, StakeholderHoldings>,
     #[account(
         init,
         payer = authority,
-        space = 8 + 16 + 16 + 16 + 8 + 8,
+        space = 8 + 16 + 16 + 16 + 8 + 8 + 4,
         seeds = [
             b"stock_position",
             stakeholder.id.as_ref(),
@@ -23,6 +47,91 @@ pub struct IssueStock<'info> {
         bump
     )]
     pub position: Account<'info, StockActivePosition>,
+    #[account(
+        init,
+        payer = authority,
+        space = SecurityRegistration::SPACE,
+        seeds = [b"security", issuer.id.as_ref(), security_id.as_ref()],
+        bump
+    )]
+    pub security_registration: Account<'info, SecurityRegistration>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(resulting_security_id: [u8; 16])]
+pub struct ConvertStock<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"tx_log", issuer.id.as_ref()],
+        bump
+    )]
+    pub tx_log: Account<'info, TxLog>,
+    #[account(
+        mut,
+        seeds = [b"cap_table_summary", issuer.id.as_ref()],
+        bump
+    )]
+    pub cap_table_summary: Account<'info, CapTableSummary>,
+    pub stakeholder: Account<'info, Stakeholder>,
+    #[account(
+        mut,
+        seeds = [b"stakeholder_holdings", stakeholder.id.as_ref()],
+        bump,
+        realloc = StakeholderHoldings::space(stakeholder_holdings.holdings.len() + 1),
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub stakeholder_holdings: Account<'info, StakeholderHoldings>,
+    #[account(
+        mut,
+        constraint = source_stock_class.id == source_position.stock_class_id @ StockError::ConversionClassMismatch
+    )]
+    pub source_stock_class: Account<'info, StockClass>,
+    #[account(
+        mut,
+        constraint = target_stock_class.id == source_stock_class.terms.converts_to_stock_class_id @ StockError::ConversionClassMismatch
+    )]
+    pub target_stock_class: Account<'info, StockClass>,
+    #[account(
+        mut,
+        seeds = [
+            b"stock_position",
+            stakeholder.id.as_ref(),
+            source_position.security_id.as_ref()
+        ],
+        bump
+    )]
+    pub source_position: Account<'info, StockActivePosition>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 16 + 16 + 16 + 8 + 8 + 4,
+        seeds = [
+            b"stock_position",
+            stakeholder.id.as_ref(),
+            resulting_security_id.as_ref()
+        ],
+        bump
+    )]
+    pub resulting_position: Account<'info, StockActivePosition>,
+    #[account(
+        init,
+        payer = authority,
+        space = SecurityRegistration::SPACE,
+        seeds = [b"security", issuer.id.as_ref(), resulting_security_id.as_ref()],
+        bump
+    )]
+    pub security_registration: Account<'info, SecurityRegistration>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
     #[account(mut)]
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
@@ -34,43 +143,147 @@ pub fn issue_stock(
     quantity: u64,
     share_price: u64,
 ) -> Result<()> {
-    require!(quantity > 0, StockError::InvalidQuantity);
-    require!(share_price > 0, StockError::InvalidSharePrice);
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
 
     let position = &mut ctx.accounts.position;
     let stakeholder = &ctx.accounts.stakeholder;
     let stock_class = &mut ctx.accounts.stock_class;
 
-    require!(
-        stock_class.shares_issued + quantity <= stock_class.shares_authorized,
-        StockError::InsufficientShares
-    );
-
-    position.stakeholder_id = stakeholder.id;
-    position.stock_class_id = stock_class.id;
-    position.security_id = security_id;
-    position.quantity = quantity;
-    position.share_price = share_price;
+    rules::issue_stock(
+        stock_class,
+        &mut ctx.accounts.cap_table_summary,
+        position,
+        stakeholder.id,
+        security_id,
+        quantity,
+        share_price,
+    )?;
 
-    stock_class.shares_issued += quantity;
+    ctx.accounts
+        .stakeholder_holdings
+        .add(security_id, PositionType::Stock)?;
+    ctx.accounts.security_registration.register(
+        ctx.accounts.issuer.id,
+        security_id,
+        PositionType::Stock,
+        position.key(),
+    );
 
     // Serialize using the StockIssued event struct
+    let envelope = EventEnvelope::new(
+        ctx.accounts.issuer.id,
+        ctx.accounts.authority.key(),
+        security_id,
+    )?;
     let tx_data = AnchorSerialize::try_to_vec(
         &(StockIssued {
+            envelope,
             stock_class_id: stock_class.id,
             security_id,
             stakeholder_id: stakeholder.id,
             quantity,
             share_price,
-            issuer_id: ctx.accounts.issuer.id,
         }),
     )?;
 
-    emit!(TxCreated {
+    let (sequence, prev_hash) =
+        ctx.accounts
+            .tx_log
+            .append(TxType::StockIssuance, StockIssued::VERSION, &tx_data)?;
+    emit!(TxCreatedV2 {
+        envelope,
         tx_type: TxType::StockIssuance,
+        payload_version: StockIssued::VERSION,
+        tx_data,
+        sequence,
+        prev_hash,
+    });
+
+    Ok(())
+}
+
+pub fn convert_stock(
+    ctx: Context<ConvertStock>,
+    resulting_security_id: [u8; 16],
+    quantity: u64,
+) -> Result<()> {
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
+
+    let source_stock_class = &mut ctx.accounts.source_stock_class;
+    let target_stock_class = &mut ctx.accounts.target_stock_class;
+    let source_position = &mut ctx.accounts.source_position;
+    let resulting_position = &mut ctx.accounts.resulting_position;
+
+    let quantity_received = rules::convert_stock(
+        source_stock_class,
+        target_stock_class,
+        &mut ctx.accounts.cap_table_summary,
+        source_position,
+        resulting_position,
+        resulting_security_id,
+        quantity,
+    )?;
+    let conversion_ratio = source_stock_class.terms.conversion_ratio;
+
+    // Serialize using the StockConverted event struct
+    let envelope = EventEnvelope::new(
+        ctx.accounts.issuer.id,
+        ctx.accounts.authority.key(),
+        source_position.security_id,
+    )?;
+    let tx_data = AnchorSerialize::try_to_vec(
+        &(StockConverted {
+            envelope,
+            security_id: source_position.security_id,
+            resulting_security_id,
+            stakeholder_id: source_position.stakeholder_id,
+            source_stock_class_id: source_stock_class.id,
+            target_stock_class_id: target_stock_class.id,
+            quantity_converted: quantity,
+            quantity_received,
+            conversion_ratio,
+        }),
+    )?;
+
+    let (sequence, prev_hash) =
+        ctx.accounts
+            .tx_log
+            .append(TxType::StockConversion, StockConverted::VERSION, &tx_data)?;
+    emit!(TxCreatedV2 {
+        envelope,
+        tx_type: TxType::StockConversion,
+        payload_version: StockConverted::VERSION,
         tx_data,
-        issuer_id: ctx.accounts.issuer.id,
+        sequence,
+        prev_hash,
     });
 
+    let stakeholder_holdings = &mut ctx.accounts.stakeholder_holdings;
+    stakeholder_holdings.add(resulting_security_id, PositionType::Stock)?;
+    ctx.accounts.security_registration.register(
+        ctx.accounts.issuer.id,
+        resulting_security_id,
+        PositionType::Stock,
+        resulting_position.key(),
+    );
+
+    // Fully converted positions are closed and their rent returned
+    if source_position.quantity == 0 {
+        stakeholder_holdings.remove(source_position.security_id, PositionType::Stock)?;
+        ctx.accounts
+            .source_position
+            .close(ctx.accounts.authority.to_account_info())?;
+    }
+
     Ok(())
 }
@@ -1,4 +1,8 @@
+use crate::engine::rules::{self, ConversionAdjustment};
+use crate::errors::*;
 use crate::events::*;
+use crate::instructions::multisig::authorize;
+use crate::payload::TxPayload;
 use crate::state::*;
 use anchor_lang::prelude::*;
 
@@ -6,10 +10,19 @@ use anchor_lang::prelude::*;
 #[instruction(id: [u8; 16])]
 pub struct CreateStockClass<'info> {
     pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"cap_table_summary", issuer.id.as_ref()],
+        bump,
+        realloc = CapTableSummary::space(cap_table_summary.stock_classes.len() + 1),
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub cap_table_summary: Account<'info, CapTableSummary>,
     #[account(
         init,
         payer = authority,
-        space = 8 + 16 + 40 + 8 + 8 + 8,
+        space = StockClass::SPACE,
         seeds = [
             b"stock_class",
             id.as_ref(),
@@ -17,6 +30,16 @@ pub struct CreateStockClass<'info> {
         bump
     )]
     pub stock_class: Account<'info, StockClass>,
+    /// CHECK: the issuer's board; once it exists the action needs a proposal
+    #[account(
+        seeds = [b"board", issuer.id.as_ref()],
+        bump,
+        constraint = board.data_is_empty() @ GovernanceError::BoardApprovalRequired
+    )]
+    pub board: UncheckedAccount<'info>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
     #[account(mut)]
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
@@ -25,8 +48,93 @@ pub struct CreateStockClass<'info> {
 #[derive(Accounts)]
 pub struct AdjustStockClassShares<'info> {
     pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"cap_table_summary", issuer.id.as_ref()],
+        bump
+    )]
+    pub cap_table_summary: Account<'info, CapTableSummary>,
+    #[account(mut)]
+    pub stock_class: Account<'info, StockClass>,
+    /// CHECK: the issuer's board; once it exists the action needs a proposal
+    #[account(
+        seeds = [b"board", issuer.id.as_ref()],
+        bump,
+        constraint = board.data_is_empty() @ GovernanceError::BoardApprovalRequired
+    )]
+    pub board: UncheckedAccount<'info>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SplitStockClass<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"tx_log", issuer.id.as_ref()],
+        bump
+    )]
+    pub tx_log: Account<'info, TxLog>,
+    #[account(
+        mut,
+        seeds = [b"cap_table_summary", issuer.id.as_ref()],
+        bump
+    )]
+    pub cap_table_summary: Account<'info, CapTableSummary>,
+    #[account(mut)]
+    pub stock_class: Account<'info, StockClass>,
+    /// CHECK: the issuer's board; once it exists the action needs a proposal
+    #[account(
+        seeds = [b"board", issuer.id.as_ref()],
+        bump,
+        constraint = board.data_is_empty() @ GovernanceError::BoardApprovalRequired
+    )]
+    pub board: UncheckedAccount<'info>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
+    pub authority: Signer<'info>,
+}
+
+/// Permissionless crank: brings the `StockActivePosition`s passed as remaining
+/// accounts up to date with the class's recorded splits.
+#[derive(Accounts)]
+pub struct ApplyStockClassSplit<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"cap_table_summary", issuer.id.as_ref()],
+        bump
+    )]
+    pub cap_table_summary: Account<'info, CapTableSummary>,
+    #[account(mut)]
+    pub stock_class: Account<'info, StockClass>,
+}
+
+/// For broad-based weighted average protection, the outstanding shares of every
+/// other stock class in the cap table summary count toward the base.
+#[derive(Accounts)]
+pub struct RecordDilutiveIssuance<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        seeds = [b"cap_table_summary", issuer.id.as_ref()],
+        bump
+    )]
+    pub cap_table_summary: Account<'info, CapTableSummary>,
+    #[account(
+        mut,
+        seeds = [b"tx_log", issuer.id.as_ref()],
+        bump
+    )]
+    pub tx_log: Account<'info, TxLog>,
     #[account(mut)]
     pub stock_class: Account<'info, StockClass>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
     pub authority: Signer<'info>,
 }
 
@@ -36,21 +144,33 @@ pub fn create_stock_class(
     class_type: String,
     price_per_share: u64,
     initial_shares_authorized: u64,
+    terms: StockClassTerms,
 ) -> Result<()> {
-    let stock_class = &mut ctx.accounts.stock_class;
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
 
-    stock_class.id = id;
-    stock_class.class_type = class_type;
-    stock_class.price_per_share = price_per_share;
-    stock_class.shares_issued = 0;
-    stock_class.shares_authorized = initial_shares_authorized;
+    let stock_class = &mut ctx.accounts.stock_class;
+    rules::create_stock_class(
+        stock_class,
+        &mut ctx.accounts.cap_table_summary,
+        id,
+        class_type,
+        price_per_share,
+        initial_shares_authorized,
+        terms,
+    )?;
 
     emit!(StockClassCreated {
+        envelope: EventEnvelope::new(ctx.accounts.issuer.id, ctx.accounts.authority.key(), id)?,
         id,
         class_type: stock_class.class_type.clone(),
         price_per_share,
         initial_shares_authorized,
-        issuer_id: ctx.accounts.issuer.id,
+        terms,
     });
 
     Ok(())
@@ -60,13 +180,194 @@ pub fn adjust_stock_class_shares(
     ctx: Context<AdjustStockClassShares>,
     new_shares_authorized: u64,
 ) -> Result<()> {
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
+
     let stock_class = &mut ctx.accounts.stock_class;
-    stock_class.shares_authorized = new_shares_authorized;
+    rules::adjust_stock_class_shares(
+        stock_class,
+        &mut ctx.accounts.cap_table_summary,
+        new_shares_authorized,
+    )?;
 
     emit!(StockClassSharesAdjusted {
+        envelope: EventEnvelope::new(
+            ctx.accounts.issuer.id,
+            ctx.accounts.authority.key(),
+            stock_class.id
+        )?,
         stock_class_id: stock_class.id,
         new_shares_authorized,
-        issuer_id: ctx.accounts.issuer.id,
+    });
+
+    Ok(())
+}
+
+pub fn split_stock_class(
+    ctx: Context<SplitStockClass>,
+    numerator: u64,
+    denominator: u64,
+    fractional_share_policy: FractionalSharePolicy,
+) -> Result<()> {
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
+
+    // Class totals are restated immediately; positions follow lazily (see apply_stock_class_split)
+    let stock_class = &mut ctx.accounts.stock_class;
+    rules::split_stock_class(
+        stock_class,
+        &mut ctx.accounts.cap_table_summary,
+        numerator,
+        denominator,
+        fractional_share_policy,
+    )?;
+
+    // Serialize using the StockClassSplit event struct
+    let envelope = EventEnvelope::new(
+        ctx.accounts.issuer.id,
+        ctx.accounts.authority.key(),
+        stock_class.id,
+    )?;
+    let tx_data = AnchorSerialize::try_to_vec(
+        &(StockClassSplit {
+            envelope,
+            stock_class_id: stock_class.id,
+            split_epoch: stock_class.split_epoch(),
+            numerator,
+            denominator,
+            fractional_share_policy,
+            new_shares_authorized: stock_class.shares_authorized,
+            new_shares_issued: stock_class.shares_issued,
+            new_price_per_share: stock_class.price_per_share,
+        }),
+    )?;
+
+    let (sequence, prev_hash) =
+        ctx.accounts
+            .tx_log
+            .append(TxType::StockClassSplit, StockClassSplit::VERSION, &tx_data)?;
+    emit!(TxCreatedV2 {
+        envelope,
+        tx_type: TxType::StockClassSplit,
+        payload_version: StockClassSplit::VERSION,
+        tx_data,
+        sequence,
+        prev_hash,
+    });
+
+    Ok(())
+}
+
+pub fn apply_stock_class_split<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ApplyStockClassSplit<'info>>,
+) -> Result<()> {
+    let stock_class = &mut ctx.accounts.stock_class;
+
+    for account_info in ctx.remaining_accounts.iter() {
+        require!(
+            account_info.is_writable,
+            StockClassError::PositionNotWritable
+        );
+
+        let mut position = Account::<StockActivePosition>::try_from(account_info)?;
+        rules::apply_pending_splits(stock_class, &mut position)?;
+        position.exit(&crate::ID)?;
+    }
+
+    ctx.accounts
+        .cap_table_summary
+        .sync_stock_class(stock_class)?;
+
+    Ok(())
+}
+
+pub fn record_dilutive_issuance(
+    ctx: Context<RecordDilutiveIssuance>,
+    new_issue_price: u64,
+    new_shares_issued: u64,
+) -> Result<()> {
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
+
+    let cap_table_summary = &ctx.accounts.cap_table_summary;
+    let stock_class = &mut ctx.accounts.stock_class;
+    let anti_dilution_type = stock_class.terms.anti_dilution_type;
+
+    // Also proves the class belongs to this issuer
+    require!(
+        cap_table_summary
+            .stock_classes
+            .iter()
+            .any(|summary| summary.stock_class_id == stock_class.id),
+        CapTableSummaryError::StockClassNotFound
+    );
+    let other_shares_issued = cap_table_summary
+        .stock_classes
+        .iter()
+        .filter(|summary| summary.stock_class_id != stock_class.id)
+        .try_fold(0u64, |total, summary| {
+            total.checked_add(summary.shares_outstanding)
+        })
+        .ok_or(StockClassError::ArithmeticOverflow)?;
+
+    let ConversionAdjustment {
+        shares_outstanding_basis,
+        previous_conversion_price,
+        new_conversion_price,
+        previous_conversion_ratio,
+        new_conversion_ratio,
+    } = rules::record_dilutive_issuance(
+        stock_class,
+        other_shares_issued,
+        new_issue_price,
+        new_shares_issued,
+    )?;
+
+    // Serialize using the StockClassConversionRatioAdjusted event struct
+    let envelope = EventEnvelope::new(
+        ctx.accounts.issuer.id,
+        ctx.accounts.authority.key(),
+        stock_class.id,
+    )?;
+    let tx_data = AnchorSerialize::try_to_vec(
+        &(StockClassConversionRatioAdjusted {
+            envelope,
+            stock_class_id: stock_class.id,
+            anti_dilution_type,
+            new_issue_price,
+            new_shares_issued,
+            shares_outstanding_basis,
+            previous_conversion_price,
+            new_conversion_price,
+            previous_conversion_ratio,
+            new_conversion_ratio,
+        }),
+    )?;
+
+    let (sequence, prev_hash) = ctx.accounts.tx_log.append(
+        TxType::StockClassConversionRatioAdjustment,
+        StockClassConversionRatioAdjusted::VERSION,
+        &tx_data,
+    )?;
+    emit!(TxCreatedV2 {
+        envelope,
+        tx_type: TxType::StockClassConversionRatioAdjustment,
+        payload_version: StockClassConversionRatioAdjusted::VERSION,
+        tx_data,
+        sequence,
+        prev_hash,
     });
 
     Ok(())
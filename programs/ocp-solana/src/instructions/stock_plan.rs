@@ -1,5 +1,7 @@
-use crate::errors::*;
+use crate::engine::rules;
+use crate::errors::GovernanceError;
 use crate::events::*;
+use crate::instructions::multisig::authorize;
 use crate::state::*;
 use anchor_lang::prelude::*;
 
@@ -7,6 +9,12 @@ use anchor_lang::prelude::*;
 #[instruction(id: [u8; 16], stock_class_ids: Vec<[u8; 16]>)]
 pub struct CreateStockPlan<'info> {
     pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"cap_table_summary", issuer.id.as_ref()],
+        bump
+    )]
+    pub cap_table_summary: Account<'info, CapTableSummary>,
     #[account(
         init,
         payer = authority,
@@ -22,6 +30,16 @@ pub struct CreateStockPlan<'info> {
     )]
     pub stock_plan: Account<'info, StockPlan>,
     pub stock_class: Account<'info, StockClass>,
+    /// CHECK: the issuer's board; once it exists the action needs a proposal
+    #[account(
+        seeds = [b"board", issuer.id.as_ref()],
+        bump,
+        constraint = board.data_is_empty() @ GovernanceError::BoardApprovalRequired
+    )]
+    pub board: UncheckedAccount<'info>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
     #[account(mut)]
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
@@ -30,8 +48,24 @@ pub struct CreateStockPlan<'info> {
 #[derive(Accounts)]
 pub struct AdjustStockPlanShares<'info> {
     pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"cap_table_summary", issuer.id.as_ref()],
+        bump
+    )]
+    pub cap_table_summary: Account<'info, CapTableSummary>,
     #[account(mut)]
     pub stock_plan: Account<'info, StockPlan>,
+    /// CHECK: the issuer's board; once it exists the action needs a proposal
+    #[account(
+        seeds = [b"board", issuer.id.as_ref()],
+        bump,
+        constraint = board.data_is_empty() @ GovernanceError::BoardApprovalRequired
+    )]
+    pub board: UncheckedAccount<'info>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
     pub authority: Signer<'info>,
 }
 
@@ -41,20 +75,25 @@ pub fn create_stock_plan(
     stock_class_ids: Vec<[u8; 16]>,
     shares_reserved: u64,
 ) -> Result<()> {
-    let stock_plan = &mut ctx.accounts.stock_plan;
-    require!(
-        stock_class_ids.len() > 0,
-        StockPlanError::InvalidStockClassCount
-    );
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
 
-    stock_plan.id = id;
-    stock_plan.stock_class_ids = stock_class_ids;
-    stock_plan.shares_reserved = shares_reserved;
+    rules::create_stock_plan(
+        &mut ctx.accounts.stock_plan,
+        &mut ctx.accounts.cap_table_summary,
+        id,
+        stock_class_ids,
+        shares_reserved,
+    )?;
 
     emit!(StockPlanCreated {
+        envelope: EventEnvelope::new(ctx.accounts.issuer.id, ctx.accounts.authority.key(), id)?,
         id,
         shares_reserved,
-        issuer_id: ctx.accounts.issuer.id,
     });
 
     Ok(())
@@ -64,10 +103,26 @@ pub fn adjust_stock_plan_shares(
     ctx: Context<AdjustStockPlanShares>,
     new_shares_reserved: u64,
 ) -> Result<()> {
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
+
     let stock_plan = &mut ctx.accounts.stock_plan;
-    stock_plan.shares_reserved = new_shares_reserved;
+    rules::adjust_stock_plan_shares(
+        stock_plan,
+        &mut ctx.accounts.cap_table_summary,
+        new_shares_reserved,
+    )?;
 
     emit!(StockPlanSharesAdjusted {
+        envelope: EventEnvelope::new(
+            ctx.accounts.issuer.id,
+            ctx.accounts.authority.key(),
+            stock_plan.id
+        )?,
         id: stock_plan.id,
         new_shares_reserved,
     });
@@ -0,0 +1,286 @@
+use crate::engine::rules;
+use crate::errors::VotingError;
+use crate::events::*;
+use crate::instructions::multisig::authorize;
+use crate::state::*;
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use std::collections::BTreeSet;
+
+#[derive(Accounts)]
+pub struct SetStakeholderWallet<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        seeds = [b"stakeholder", stakeholder.id.as_ref()],
+        bump
+    )]
+    pub stakeholder: Account<'info, Stakeholder>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = StakeholderWallet::SPACE,
+        seeds = [
+            b"stakeholder_wallet",
+            issuer.id.as_ref(),
+            stakeholder.id.as_ref(),
+        ],
+        bump
+    )]
+    pub stakeholder_wallet: Account<'info, StakeholderWallet>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(id: [u8; 16])]
+pub struct CreateResolution<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        seeds = [b"cap_table_summary", issuer.id.as_ref()],
+        bump
+    )]
+    pub cap_table_summary: Account<'info, CapTableSummary>,
+    #[account(
+        init,
+        payer = authority,
+        space = Resolution::space(cap_table_summary.stock_classes.len()),
+        seeds = [b"resolution", issuer.id.as_ref(), id.as_ref()],
+        bump
+    )]
+    pub resolution: Account<'info, Resolution>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Permissionless crank; may run again until the record date to pick up
+/// later changes to the stakeholder's holdings, and after it while they are
+/// unchanged since the record date.
+#[derive(Accounts)]
+pub struct RecordVotingPower<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        seeds = [b"resolution", issuer.id.as_ref(), resolution.id.as_ref()],
+        bump
+    )]
+    pub resolution: Account<'info, Resolution>,
+    #[account(
+        seeds = [
+            b"stakeholder_holdings",
+            stakeholder_holdings.stakeholder_id.as_ref(),
+        ],
+        bump
+    )]
+    pub stakeholder_holdings: Account<'info, StakeholderHoldings>,
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        space = VoterRecord::space(resolution.tallies.len()),
+        seeds = [
+            b"voter_record",
+            issuer.id.as_ref(),
+            resolution.id.as_ref(),
+            stakeholder_holdings.stakeholder_id.as_ref(),
+        ],
+        bump
+    )]
+    pub voter_record: Account<'info, VoterRecord>,
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CastVote<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"resolution", issuer.id.as_ref(), resolution.id.as_ref()],
+        bump
+    )]
+    pub resolution: Account<'info, Resolution>,
+    #[account(
+        mut,
+        seeds = [
+            b"voter_record",
+            issuer.id.as_ref(),
+            resolution.id.as_ref(),
+            voter_record.stakeholder_id.as_ref(),
+        ],
+        bump
+    )]
+    pub voter_record: Account<'info, VoterRecord>,
+    #[account(
+        seeds = [
+            b"stakeholder_holdings",
+            voter_record.stakeholder_id.as_ref(),
+        ],
+        bump
+    )]
+    pub stakeholder_holdings: Account<'info, StakeholderHoldings>,
+    #[account(
+        seeds = [
+            b"stakeholder_wallet",
+            issuer.id.as_ref(),
+            voter_record.stakeholder_id.as_ref(),
+        ],
+        bump,
+        constraint = stakeholder_wallet.wallet == wallet.key() @ VotingError::WalletMismatch
+    )]
+    pub stakeholder_wallet: Account<'info, StakeholderWallet>,
+    pub wallet: Signer<'info>,
+}
+
+pub fn set_stakeholder_wallet(ctx: Context<SetStakeholderWallet>, wallet: Pubkey) -> Result<()> {
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
+
+    let issuer_id = ctx.accounts.issuer.id;
+    let stakeholder_id = ctx.accounts.stakeholder.id;
+    ctx.accounts
+        .stakeholder_wallet
+        .set_inner(StakeholderWallet {
+            issuer_id,
+            stakeholder_id,
+            wallet,
+        });
+
+    emit!(StakeholderWalletSet {
+        envelope: EventEnvelope::new(issuer_id, ctx.accounts.authority.key(), stakeholder_id)?,
+        stakeholder_id,
+        wallet,
+    });
+
+    Ok(())
+}
+
+/// Remaining accounts are every stock class of the issuer.
+pub fn create_resolution<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CreateResolution<'info>>,
+    id: [u8; 16],
+    record_date: i64,
+    voting_deadline: i64,
+    separate_class_ids: Vec<[u8; 16]>,
+) -> Result<()> {
+    let remaining_accounts = authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
+
+    let stock_classes = remaining_accounts
+        .iter()
+        .map(Account::<StockClass>::try_from)
+        .collect::<Result<Vec<_>>>()?;
+    let issuer_id = ctx.accounts.issuer.id;
+    let resolution = &mut ctx.accounts.resolution;
+    resolution.set_inner(Resolution {
+        issuer_id,
+        id,
+        record_date,
+        voting_deadline,
+        ..Resolution::default()
+    });
+    rules::create_resolution(
+        resolution,
+        &ctx.accounts.cap_table_summary,
+        &stock_classes
+            .iter()
+            .map(|account| &**account)
+            .collect::<Vec<_>>(),
+        &separate_class_ids,
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    emit!(ResolutionCreated {
+        envelope: EventEnvelope::new(issuer_id, ctx.accounts.authority.key(), id)?,
+        resolution_id: id,
+        record_date,
+        voting_deadline,
+        separate_class_ids,
+    });
+
+    Ok(())
+}
+
+/// Remaining accounts are the stakeholder's stock positions and the stock
+/// classes they belong to, in any order.
+pub fn record_voting_power<'info>(
+    ctx: Context<'_, '_, 'info, 'info, RecordVotingPower<'info>>,
+) -> Result<()> {
+    let mut stock_classes = Vec::new();
+    let mut positions = Vec::new();
+    let mut seen = BTreeSet::new();
+
+    for account_info in ctx.remaining_accounts.iter() {
+        if !seen.insert(account_info.key()) {
+            continue;
+        }
+        let discriminator = account_info
+            .try_borrow_data()?
+            .get(..8)
+            .and_then(|data| <[u8; 8]>::try_from(data).ok())
+            .ok_or(VotingError::UnsupportedAccount)?;
+
+        if discriminator == StockClass::DISCRIMINATOR {
+            stock_classes.push(Account::<StockClass>::try_from(account_info)?);
+        } else if discriminator == StockActivePosition::DISCRIMINATOR {
+            positions.push(Account::<StockActivePosition>::try_from(account_info)?);
+        } else {
+            return err!(VotingError::UnsupportedAccount);
+        }
+    }
+
+    rules::record_voting_power(
+        &ctx.accounts.resolution,
+        &mut ctx.accounts.voter_record,
+        &ctx.accounts.stakeholder_holdings,
+        &stock_classes
+            .iter()
+            .map(|account| &**account)
+            .collect::<Vec<_>>(),
+        &positions
+            .iter()
+            .map(|account| &**account)
+            .collect::<Vec<_>>(),
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    Ok(())
+}
+
+pub fn cast_vote(ctx: Context<CastVote>, choice: VoteChoice) -> Result<()> {
+    let voter_record = &mut ctx.accounts.voter_record;
+    let votes = rules::cast_vote(
+        &mut ctx.accounts.resolution,
+        voter_record,
+        &ctx.accounts.stakeholder_holdings,
+        choice,
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    let resolution_id = ctx.accounts.resolution.id;
+    let wallet = ctx.accounts.wallet.key();
+    emit!(VoteCast {
+        envelope: EventEnvelope::new(ctx.accounts.issuer.id, wallet, resolution_id)?,
+        resolution_id,
+        stakeholder_id: voter_record.stakeholder_id,
+        wallet,
+        choice,
+        votes,
+    });
+
+    Ok(())
+}
@@ -1,18 +1,54 @@
+use crate::engine::rules;
 use crate::errors::*;
 use crate::events::*;
+use crate::instructions::multisig::authorize;
+use crate::payload::TxPayload;
 use crate::state::*;
 use anchor_lang::prelude::*;
 
+// discriminator + stakeholder_id + stock_class_id + security_id + quantity + exercise_price + expiration_date + exercise_trigger + rent_payer
+const WARRANT_POSITION_SPACE: usize = 8 + 16 + 16 + 16 + 8 + 8 + 8 + 1 + 32;
+
 #[derive(Accounts)]
 #[instruction(security_id: [u8; 16], quantity: u64)]
 pub struct IssueWarrant<'info> {
     #[account(mut)]
     pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"tx_log", issuer.id.as_ref()],
+        bump
+    )]
+    pub tx_log: Account<'info, TxLog>,
+    #[account(
+        mut,
+        seeds = [b"cap_table_summary", issuer.id.as_ref()],
+        bump
+    )]
+    pub cap_table_summary: Account<'info, CapTableSummary>,
     pub stakeholder: Account<'info, Stakeholder>,
+    #[account(
+        mut,
+        seeds = [b"stakeholder_holdings", stakeholder.id.as_ref()],
+        bump,
+        realloc = StakeholderHoldings::space(stakeholder_holdings.holdings.len() + 1),
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub stakeholder_holdings: Account<'info, StakeholderHoldings>,
+    /// Must be one of the issuer's classes, since its seeds do not include the issuer
+    #[account(
+        constraint = cap_table_summary
+            .stock_classes
+            .iter()
+            .any(|summary| summary.stock_class_id == stock_class.id)
+            @ WarrantError::UnknownStockClass
+    )]
+    pub stock_class: Account<'info, StockClass>,
     #[account(
         init,
         payer = authority,
-        space = 8 + 16 + 16 + 8, // discriminator + stakeholder_id + security_id + quantity
+        space = WARRANT_POSITION_SPACE,
         seeds = [
             b"warrant_position",
             stakeholder.id.as_ref(),
@@ -21,6 +57,259 @@ pub struct IssueWarrant<'info> {
         bump
     )]
     pub position: Account<'info, WarrantActivePosition>,
+    #[account(
+        init,
+        payer = authority,
+        space = SecurityRegistration::SPACE,
+        seeds = [b"security", issuer.id.as_ref(), security_id.as_ref()],
+        bump
+    )]
+    pub security_registration: Account<'info, SecurityRegistration>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(resulting_security_id: [u8; 16])]
+pub struct ExerciseWarrant<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"tx_log", issuer.id.as_ref()],
+        bump
+    )]
+    pub tx_log: Account<'info, TxLog>,
+    #[account(
+        mut,
+        seeds = [b"cap_table_summary", issuer.id.as_ref()],
+        bump
+    )]
+    pub cap_table_summary: Account<'info, CapTableSummary>,
+    pub stakeholder: Account<'info, Stakeholder>,
+    #[account(
+        mut,
+        seeds = [b"stakeholder_holdings", stakeholder.id.as_ref()],
+        bump,
+        realloc = StakeholderHoldings::space(stakeholder_holdings.holdings.len() + 1),
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub stakeholder_holdings: Account<'info, StakeholderHoldings>,
+    #[account(
+        mut,
+        constraint = stock_class.id == warrant_position.stock_class_id @ WarrantError::StockClassMismatch
+    )]
+    pub stock_class: Account<'info, StockClass>,
+    #[account(
+        mut,
+        seeds = [
+            b"warrant_position",
+            stakeholder.id.as_ref(),
+            warrant_position.security_id.as_ref()
+        ],
+        bump
+    )]
+    pub warrant_position: Account<'info, WarrantActivePosition>,
+    /// Ties the position to the issuer, since its seeds do not include it
+    #[account(
+        seeds = [b"security", issuer.id.as_ref(), warrant_position.security_id.as_ref()],
+        bump,
+        constraint = warrant_registration.position == warrant_position.key() @ WarrantError::PositionNotRegistered
+    )]
+    pub warrant_registration: Account<'info, SecurityRegistration>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 16 + 16 + 16 + 8 + 8 + 4,
+        seeds = [
+            b"stock_position",
+            stakeholder.id.as_ref(),
+            resulting_security_id.as_ref()
+        ],
+        bump
+    )]
+    pub stock_position: Account<'info, StockActivePosition>,
+    #[account(
+        init,
+        payer = authority,
+        space = SecurityRegistration::SPACE,
+        seeds = [b"security", issuer.id.as_ref(), resulting_security_id.as_ref()],
+        bump
+    )]
+    pub security_registration: Account<'info, SecurityRegistration>,
+    /// CHECK: only receives the closed account's lamports; must match the recorded payer
+    #[account(mut, address = warrant_position.rent_payer @ WarrantError::InvalidRentPayer)]
+    pub rent_payer: UncheckedAccount<'info>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelWarrant<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"tx_log", issuer.id.as_ref()],
+        bump
+    )]
+    pub tx_log: Account<'info, TxLog>,
+    #[account(
+        mut,
+        seeds = [b"cap_table_summary", issuer.id.as_ref()],
+        bump
+    )]
+    pub cap_table_summary: Account<'info, CapTableSummary>,
+    pub stakeholder: Account<'info, Stakeholder>,
+    #[account(
+        mut,
+        seeds = [b"stakeholder_holdings", stakeholder.id.as_ref()],
+        bump
+    )]
+    pub stakeholder_holdings: Account<'info, StakeholderHoldings>,
+    #[account(
+        mut,
+        seeds = [
+            b"warrant_position",
+            stakeholder.id.as_ref(),
+            warrant_position.security_id.as_ref()
+        ],
+        bump
+    )]
+    pub warrant_position: Account<'info, WarrantActivePosition>,
+    /// Ties the position to the issuer, since its seeds do not include it
+    #[account(
+        seeds = [b"security", issuer.id.as_ref(), warrant_position.security_id.as_ref()],
+        bump,
+        constraint = security_registration.position == warrant_position.key() @ WarrantError::PositionNotRegistered
+    )]
+    pub security_registration: Account<'info, SecurityRegistration>,
+    /// CHECK: only receives the closed account's lamports; must match the recorded payer
+    #[account(mut, address = warrant_position.rent_payer @ WarrantError::InvalidRentPayer)]
+    pub rent_payer: UncheckedAccount<'info>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+/// Permissionless crank: anyone may close a warrant once it has expired.
+#[derive(Accounts)]
+pub struct ExpireWarrant<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"tx_log", issuer.id.as_ref()],
+        bump
+    )]
+    pub tx_log: Account<'info, TxLog>,
+    #[account(
+        mut,
+        seeds = [b"cap_table_summary", issuer.id.as_ref()],
+        bump
+    )]
+    pub cap_table_summary: Account<'info, CapTableSummary>,
+    #[account(mut, close = rent_payer)]
+    pub warrant_position: Account<'info, WarrantActivePosition>,
+    /// Ties the position to the issuer, since its seeds do not include it
+    #[account(
+        seeds = [b"security", issuer.id.as_ref(), warrant_position.security_id.as_ref()],
+        bump,
+        constraint = security_registration.position == warrant_position.key() @ WarrantError::PositionNotRegistered
+    )]
+    pub security_registration: Account<'info, SecurityRegistration>,
+    #[account(
+        mut,
+        seeds = [b"stakeholder_holdings", warrant_position.stakeholder_id.as_ref()],
+        bump
+    )]
+    pub stakeholder_holdings: Account<'info, StakeholderHoldings>,
+    /// CHECK: only receives the closed account's lamports; must match the recorded payer
+    #[account(mut, address = warrant_position.rent_payer @ WarrantError::InvalidRentPayer)]
+    pub rent_payer: UncheckedAccount<'info>,
+    /// Whoever runs the crank; recorded as the event actor
+    pub cranker: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(resulting_security_id: [u8; 16])]
+pub struct TransferWarrant<'info> {
+    pub issuer: Account<'info, Issuer>,
+    #[account(
+        mut,
+        seeds = [b"tx_log", issuer.id.as_ref()],
+        bump
+    )]
+    pub tx_log: Account<'info, TxLog>,
+    pub stakeholder: Account<'info, Stakeholder>,
+    #[account(
+        mut,
+        seeds = [b"stakeholder_holdings", stakeholder.id.as_ref()],
+        bump
+    )]
+    pub stakeholder_holdings: Account<'info, StakeholderHoldings>,
+    #[account(constraint = new_stakeholder.id != stakeholder.id @ WarrantError::SelfTransfer)]
+    pub new_stakeholder: Account<'info, Stakeholder>,
+    #[account(
+        mut,
+        seeds = [b"stakeholder_holdings", new_stakeholder.id.as_ref()],
+        bump,
+        realloc = StakeholderHoldings::space(new_stakeholder_holdings.holdings.len() + 1),
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub new_stakeholder_holdings: Account<'info, StakeholderHoldings>,
+    #[account(
+        mut,
+        seeds = [
+            b"warrant_position",
+            stakeholder.id.as_ref(),
+            warrant_position.security_id.as_ref()
+        ],
+        bump
+    )]
+    pub warrant_position: Account<'info, WarrantActivePosition>,
+    /// Ties the position to the issuer, since its seeds do not include it
+    #[account(
+        seeds = [b"security", issuer.id.as_ref(), warrant_position.security_id.as_ref()],
+        bump,
+        constraint = warrant_registration.position == warrant_position.key() @ WarrantError::PositionNotRegistered
+    )]
+    pub warrant_registration: Account<'info, SecurityRegistration>,
+    #[account(
+        init,
+        payer = authority,
+        space = WARRANT_POSITION_SPACE,
+        seeds = [
+            b"warrant_position",
+            new_stakeholder.id.as_ref(),
+            resulting_security_id.as_ref()
+        ],
+        bump
+    )]
+    pub new_warrant_position: Account<'info, WarrantActivePosition>,
+    #[account(
+        init,
+        payer = authority,
+        space = SecurityRegistration::SPACE,
+        seeds = [b"security", issuer.id.as_ref(), resulting_security_id.as_ref()],
+        bump
+    )]
+    pub security_registration: Account<'info, SecurityRegistration>,
+    /// CHECK: only receives the closed account's lamports; must match the recorded payer
+    #[account(mut, address = warrant_position.rent_payer @ WarrantError::InvalidRentPayer)]
+    pub rent_payer: UncheckedAccount<'info>,
+    /// CHECK: the issuer's multisig, which may not exist yet
+    #[account(seeds = [b"multisig", issuer.id.as_ref()], bump)]
+    pub multisig: UncheckedAccount<'info>,
     #[account(mut)]
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
@@ -30,30 +319,340 @@ pub fn issue_warrant(
     ctx: Context<IssueWarrant>,
     security_id: [u8; 16],
     quantity: u64,
+    exercise_price: u64,
+    expiration_date: i64,
+    exercise_trigger: WarrantExerciseTrigger,
 ) -> Result<()> {
-    require!(quantity > 0, WarrantError::InvalidQuantity);
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
 
     let position = &mut ctx.accounts.position;
     let stakeholder = &ctx.accounts.stakeholder;
+    let stock_class = &ctx.accounts.stock_class;
 
     position.stakeholder_id = stakeholder.id;
+    position.stock_class_id = stock_class.id;
     position.security_id = security_id;
     position.quantity = quantity;
+    position.exercise_price = exercise_price;
+    position.expiration_date = expiration_date;
+    position.exercise_trigger = exercise_trigger;
+    position.rent_payer = ctx.accounts.authority.key();
+
+    rules::issue_warrant(
+        position,
+        &mut ctx.accounts.cap_table_summary,
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    ctx.accounts
+        .stakeholder_holdings
+        .add(security_id, PositionType::Warrant)?;
+    ctx.accounts.security_registration.register(
+        ctx.accounts.issuer.id,
+        security_id,
+        PositionType::Warrant,
+        position.key(),
+    );
 
     // Serialize the data
+    let envelope = EventEnvelope::new(
+        ctx.accounts.issuer.id,
+        ctx.accounts.authority.key(),
+        security_id,
+    )?;
     let tx_data = AnchorSerialize::try_to_vec(
         &(WarrantIssued {
+            envelope,
             stakeholder_id: stakeholder.id,
+            stock_class_id: stock_class.id,
             security_id,
             quantity,
+            exercise_price,
+            expiration_date,
+            exercise_trigger,
         }),
     )?;
 
-    emit!(TxCreated {
+    let (sequence, prev_hash) =
+        ctx.accounts
+            .tx_log
+            .append(TxType::WarrantIssuance, WarrantIssued::VERSION, &tx_data)?;
+    emit!(TxCreatedV2 {
+        envelope,
         tx_type: TxType::WarrantIssuance,
+        payload_version: WarrantIssued::VERSION,
+        tx_data,
+        sequence,
+        prev_hash,
+    });
+
+    Ok(())
+}
+
+pub fn exercise_warrant(
+    ctx: Context<ExerciseWarrant>,
+    resulting_security_id: [u8; 16],
+    quantity: u64,
+    fair_market_value: Option<u64>,
+) -> Result<()> {
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
+
+    let warrant_position = &mut ctx.accounts.warrant_position;
+    let stock_class = &mut ctx.accounts.stock_class;
+    let stock_position = &mut ctx.accounts.stock_position;
+
+    let shares_received = rules::exercise_warrant(
+        warrant_position,
+        stock_class,
+        stock_position,
+        &mut ctx.accounts.cap_table_summary,
+        resulting_security_id,
+        quantity,
+        fair_market_value,
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    // Serialize using the WarrantExercised event struct
+    let envelope = EventEnvelope::new(
+        ctx.accounts.issuer.id,
+        ctx.accounts.authority.key(),
+        warrant_position.security_id,
+    )?;
+    let tx_data = AnchorSerialize::try_to_vec(
+        &(WarrantExercised {
+            envelope,
+            warrant_security_id: warrant_position.security_id,
+            resulting_stock_security_id: resulting_security_id,
+            stakeholder_id: warrant_position.stakeholder_id,
+            stock_class_id: stock_class.id,
+            quantity,
+            shares_received,
+            exercise_price: warrant_position.exercise_price,
+            fair_market_value,
+        }),
+    )?;
+
+    let (sequence, prev_hash) =
+        ctx.accounts
+            .tx_log
+            .append(TxType::WarrantExercise, WarrantExercised::VERSION, &tx_data)?;
+    emit!(TxCreatedV2 {
+        envelope,
+        tx_type: TxType::WarrantExercise,
+        payload_version: WarrantExercised::VERSION,
+        tx_data,
+        sequence,
+        prev_hash,
+    });
+
+    let stakeholder_holdings = &mut ctx.accounts.stakeholder_holdings;
+    stakeholder_holdings.add(resulting_security_id, PositionType::Stock)?;
+    ctx.accounts.security_registration.register(
+        ctx.accounts.issuer.id,
+        resulting_security_id,
+        PositionType::Stock,
+        stock_position.key(),
+    );
+
+    // Fully exercised warrants are closed and their rent returned
+    if warrant_position.quantity == 0 {
+        stakeholder_holdings.remove(warrant_position.security_id, PositionType::Warrant)?;
+        ctx.accounts
+            .warrant_position
+            .close(ctx.accounts.rent_payer.to_account_info())?;
+    }
+
+    Ok(())
+}
+
+pub fn cancel_warrant(ctx: Context<CancelWarrant>, quantity: u64) -> Result<()> {
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
+
+    let warrant_position = &mut ctx.accounts.warrant_position;
+
+    rules::cancel_warrant(
+        warrant_position,
+        &mut ctx.accounts.cap_table_summary,
+        quantity,
+    )?;
+
+    // Serialize using the WarrantCancelled event struct
+    let envelope = EventEnvelope::new(
+        ctx.accounts.issuer.id,
+        ctx.accounts.authority.key(),
+        warrant_position.security_id,
+    )?;
+    let tx_data = AnchorSerialize::try_to_vec(
+        &(WarrantCancelled {
+            envelope,
+            security_id: warrant_position.security_id,
+            stakeholder_id: warrant_position.stakeholder_id,
+            quantity,
+        }),
+    )?;
+
+    let (sequence, prev_hash) = ctx.accounts.tx_log.append(
+        TxType::WarrantCancellation,
+        WarrantCancelled::VERSION,
+        &tx_data,
+    )?;
+    emit!(TxCreatedV2 {
+        envelope,
+        tx_type: TxType::WarrantCancellation,
+        payload_version: WarrantCancelled::VERSION,
+        tx_data,
+        sequence,
+        prev_hash,
+    });
+
+    if warrant_position.quantity == 0 {
+        ctx.accounts
+            .stakeholder_holdings
+            .remove(warrant_position.security_id, PositionType::Warrant)?;
+        ctx.accounts
+            .warrant_position
+            .close(ctx.accounts.rent_payer.to_account_info())?;
+    }
+
+    Ok(())
+}
+
+pub fn expire_warrant(ctx: Context<ExpireWarrant>) -> Result<()> {
+    let warrant_position = &ctx.accounts.warrant_position;
+
+    rules::expire_warrant(
+        warrant_position,
+        &mut ctx.accounts.cap_table_summary,
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    // Serialize using the WarrantExpired event struct
+    let envelope = EventEnvelope::new(
+        ctx.accounts.issuer.id,
+        ctx.accounts.cranker.key(),
+        warrant_position.security_id,
+    )?;
+    let tx_data = AnchorSerialize::try_to_vec(
+        &(WarrantExpired {
+            envelope,
+            security_id: warrant_position.security_id,
+            stakeholder_id: warrant_position.stakeholder_id,
+            quantity: warrant_position.quantity,
+            expiration_date: warrant_position.expiration_date,
+        }),
+    )?;
+
+    let (sequence, prev_hash) =
+        ctx.accounts
+            .tx_log
+            .append(TxType::WarrantExpiration, WarrantExpired::VERSION, &tx_data)?;
+    emit!(TxCreatedV2 {
+        envelope,
+        tx_type: TxType::WarrantExpiration,
+        payload_version: WarrantExpired::VERSION,
         tx_data,
-        issuer_id: ctx.accounts.issuer.id,
+        sequence,
+        prev_hash,
     });
 
+    ctx.accounts
+        .stakeholder_holdings
+        .remove(warrant_position.security_id, PositionType::Warrant)?;
+
+    Ok(())
+}
+
+pub fn transfer_warrant(
+    ctx: Context<TransferWarrant>,
+    resulting_security_id: [u8; 16],
+    quantity: u64,
+) -> Result<()> {
+    authorize(
+        &ctx.accounts.issuer,
+        &ctx.accounts.multisig,
+        &ctx.accounts.authority,
+        ctx.remaining_accounts,
+    )?;
+
+    let warrant_position = &mut ctx.accounts.warrant_position;
+    let new_warrant_position = &mut ctx.accounts.new_warrant_position;
+    let new_stakeholder = &ctx.accounts.new_stakeholder;
+
+    // The transferee's warrant carries the same terms as the original
+    rules::transfer_warrant(
+        warrant_position,
+        new_warrant_position,
+        new_stakeholder.id,
+        resulting_security_id,
+        quantity,
+        Clock::get()?.unix_timestamp,
+    )?;
+    new_warrant_position.rent_payer = ctx.accounts.authority.key();
+
+    // Serialize using the WarrantTransferred event struct
+    let envelope = EventEnvelope::new(
+        ctx.accounts.issuer.id,
+        ctx.accounts.authority.key(),
+        warrant_position.security_id,
+    )?;
+    let tx_data = AnchorSerialize::try_to_vec(
+        &(WarrantTransferred {
+            envelope,
+            security_id: warrant_position.security_id,
+            resulting_security_id,
+            from_stakeholder_id: warrant_position.stakeholder_id,
+            to_stakeholder_id: new_stakeholder.id,
+            quantity,
+        }),
+    )?;
+
+    let (sequence, prev_hash) = ctx.accounts.tx_log.append(
+        TxType::WarrantTransfer,
+        WarrantTransferred::VERSION,
+        &tx_data,
+    )?;
+    emit!(TxCreatedV2 {
+        envelope,
+        tx_type: TxType::WarrantTransfer,
+        payload_version: WarrantTransferred::VERSION,
+        tx_data,
+        sequence,
+        prev_hash,
+    });
+
+    ctx.accounts
+        .new_stakeholder_holdings
+        .add(resulting_security_id, PositionType::Warrant)?;
+    ctx.accounts.security_registration.register(
+        ctx.accounts.issuer.id,
+        resulting_security_id,
+        PositionType::Warrant,
+        new_warrant_position.key(),
+    );
+
+    if warrant_position.quantity == 0 {
+        ctx.accounts
+            .stakeholder_holdings
+            .remove(warrant_position.security_id, PositionType::Warrant)?;
+        ctx.accounts
+            .warrant_position
+            .close(ctx.accounts.rent_payer.to_account_info())?;
+    }
+
     Ok(())
 }
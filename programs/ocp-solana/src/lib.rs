@@ -1,11 +1,17 @@
 use anchor_lang::prelude::*;
 
+extern crate alloc;
+
+pub mod engine;
 pub mod errors;
 pub mod events;
 pub mod instructions;
+pub mod payload;
 pub mod state;
 
+use engine::{LiquidationDistribution, OptionExercisePrice};
 use instructions::*;
+use state::*;
 
 declare_id!("FejBZZZmyTeqxBLEkbBHiAiHWov7MnTUznNjmi4TyRXR");
 
@@ -28,12 +34,29 @@ pub mod ocp_solana {
         instructions::issuer::adjust_authorized_shares(ctx, new_shares_authorized)
     }
 
+    pub fn get_cap_table_summary(ctx: Context<GetCapTableSummary>) -> Result<CapTableSummary> {
+        instructions::issuer::get_cap_table_summary(ctx)
+    }
+
+    pub fn get_liquidation_waterfall<'info>(
+        ctx: Context<'_, '_, 'info, 'info, GetLiquidationWaterfall<'info>>,
+        exit_value: u64,
+        option_exercise_prices: Vec<OptionExercisePrice>,
+    ) -> Result<LiquidationDistribution> {
+        instructions::issuer::get_liquidation_waterfall(ctx, exit_value, option_exercise_prices)
+    }
+
+    pub fn snapshot_cap_table(ctx: Context<SnapshotCapTable>, id: [u8; 16]) -> Result<()> {
+        instructions::issuer::snapshot_cap_table(ctx, id)
+    }
+
     pub fn create_stock_class(
         ctx: Context<CreateStockClass>,
         id: [u8; 16],
         class_type: String,
         price_per_share: u64,
         initial_shares_authorized: u64,
+        terms: StockClassTerms,
     ) -> Result<()> {
         instructions::stock_class::create_stock_class(
             ctx,
@@ -41,6 +64,7 @@ pub mod ocp_solana {
             class_type,
             price_per_share,
             initial_shares_authorized,
+            terms,
         )
     }
 
@@ -51,6 +75,34 @@ pub mod ocp_solana {
         instructions::stock_class::adjust_stock_class_shares(ctx, new_shares_authorized)
     }
 
+    pub fn split_stock_class(
+        ctx: Context<SplitStockClass>,
+        numerator: u64,
+        denominator: u64,
+        fractional_share_policy: FractionalSharePolicy,
+    ) -> Result<()> {
+        instructions::stock_class::split_stock_class(
+            ctx,
+            numerator,
+            denominator,
+            fractional_share_policy,
+        )
+    }
+
+    pub fn apply_stock_class_split<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ApplyStockClassSplit<'info>>,
+    ) -> Result<()> {
+        instructions::stock_class::apply_stock_class_split(ctx)
+    }
+
+    pub fn record_dilutive_issuance(
+        ctx: Context<RecordDilutiveIssuance>,
+        new_issue_price: u64,
+        new_shares_issued: u64,
+    ) -> Result<()> {
+        instructions::stock_class::record_dilutive_issuance(ctx, new_issue_price, new_shares_issued)
+    }
+
     pub fn create_stakeholder(ctx: Context<CreateStakeholder>, id: [u8; 16]) -> Result<()> {
         instructions::stakeholder::create_stakeholder(ctx, id)
     }
@@ -64,6 +116,14 @@ pub mod ocp_solana {
         instructions::stock::issue_stock(ctx, security_id, quantity, share_price)
     }
 
+    pub fn convert_stock(
+        ctx: Context<ConvertStock>,
+        resulting_security_id: [u8; 16],
+        quantity: u64,
+    ) -> Result<()> {
+        instructions::stock::convert_stock(ctx, resulting_security_id, quantity)
+    }
+
     pub fn create_stock_plan(
         ctx: Context<CreateStockPlan>,
         id: [u8; 16],
@@ -88,6 +148,65 @@ pub mod ocp_solana {
         instructions::convertible::issue_convertible(ctx, security_id, investment_amount)
     }
 
+    pub fn open_financing_round(
+        ctx: Context<OpenFinancingRound>,
+        id: [u8; 16],
+        stock_class_id: [u8; 16],
+        class_type: String,
+        terms: StockClassTerms,
+        pre_money_valuation: u64,
+        target_pool_fraction: u64,
+    ) -> Result<()> {
+        instructions::financing_round::open_financing_round(
+            ctx,
+            id,
+            stock_class_id,
+            class_type,
+            terms,
+            pre_money_valuation,
+            target_pool_fraction,
+        )
+    }
+
+    pub fn add_round_subscription(
+        ctx: Context<AddRoundSubscription>,
+        security_id: [u8; 16],
+        amount: u64,
+    ) -> Result<()> {
+        instructions::financing_round::add_round_subscription(ctx, security_id, amount)
+    }
+
+    pub fn add_round_conversion(
+        ctx: Context<AddRoundConversion>,
+        resulting_security_id: [u8; 16],
+        discount: u64,
+        valuation_cap: u64,
+    ) -> Result<()> {
+        instructions::financing_round::add_round_conversion(
+            ctx,
+            resulting_security_id,
+            discount,
+            valuation_cap,
+        )
+    }
+
+    pub fn cancel_financing_round<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CancelFinancingRound<'info>>,
+    ) -> Result<()> {
+        instructions::financing_round::cancel_financing_round(ctx)
+    }
+
+    pub fn close_financing_round(ctx: Context<CloseFinancingRound>) -> Result<()> {
+        instructions::financing_round::close_financing_round(ctx)
+    }
+
+    pub fn settle_financing_round(
+        ctx: Context<SettleFinancingRound>,
+        security_id: [u8; 16],
+    ) -> Result<()> {
+        instructions::financing_round::settle_financing_round(ctx, security_id)
+    }
+
     pub fn issue_equity_compensation(
         ctx: Context<IssueEquityCompensation>,
         security_id: [u8; 16],
@@ -107,7 +226,139 @@ pub mod ocp_solana {
         ctx: Context<IssueWarrant>,
         security_id: [u8; 16],
         quantity: u64,
+        exercise_price: u64,
+        expiration_date: i64,
+        exercise_trigger: WarrantExerciseTrigger,
     ) -> Result<()> {
-        instructions::warrant::issue_warrant(ctx, security_id, quantity)
+        instructions::warrant::issue_warrant(
+            ctx,
+            security_id,
+            quantity,
+            exercise_price,
+            expiration_date,
+            exercise_trigger,
+        )
+    }
+
+    pub fn exercise_warrant(
+        ctx: Context<ExerciseWarrant>,
+        resulting_security_id: [u8; 16],
+        quantity: u64,
+        fair_market_value: Option<u64>,
+    ) -> Result<()> {
+        instructions::warrant::exercise_warrant(
+            ctx,
+            resulting_security_id,
+            quantity,
+            fair_market_value,
+        )
+    }
+
+    pub fn cancel_warrant(ctx: Context<CancelWarrant>, quantity: u64) -> Result<()> {
+        instructions::warrant::cancel_warrant(ctx, quantity)
+    }
+
+    pub fn expire_warrant(ctx: Context<ExpireWarrant>) -> Result<()> {
+        instructions::warrant::expire_warrant(ctx)
+    }
+
+    pub fn transfer_warrant(
+        ctx: Context<TransferWarrant>,
+        resulting_security_id: [u8; 16],
+        quantity: u64,
+    ) -> Result<()> {
+        instructions::warrant::transfer_warrant(ctx, resulting_security_id, quantity)
+    }
+
+    pub fn initialize_board(
+        ctx: Context<InitializeBoard>,
+        members: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        instructions::governance::initialize_board(ctx, members, threshold)
+    }
+
+    pub fn create_proposal(
+        ctx: Context<CreateProposal>,
+        id: [u8; 16],
+        action: ProposalAction,
+        stockholder_approval_date: i64,
+    ) -> Result<()> {
+        instructions::governance::create_proposal(ctx, id, action, stockholder_approval_date)
+    }
+
+    pub fn approve_proposal(ctx: Context<ApproveProposal>) -> Result<()> {
+        instructions::governance::approve_proposal(ctx)
+    }
+
+    pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+        instructions::governance::execute_proposal(ctx)
+    }
+
+    pub fn create_multisig(
+        ctx: Context<CreateMultisig>,
+        members: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        instructions::multisig::create_multisig(ctx, members, threshold)
+    }
+
+    pub fn update_multisig(
+        ctx: Context<UpdateMultisig>,
+        members: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        instructions::multisig::update_multisig(ctx, members, threshold)
+    }
+
+    pub fn create_multisig_transaction(
+        ctx: Context<CreateMultisigTransaction>,
+        accounts: Vec<TransactionAccount>,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        instructions::multisig::create_multisig_transaction(ctx, accounts, data)
+    }
+
+    pub fn approve_multisig_transaction(ctx: Context<ApproveMultisigTransaction>) -> Result<()> {
+        instructions::multisig::approve_multisig_transaction(ctx)
+    }
+
+    pub fn execute_multisig_transaction<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecuteMultisigTransaction<'info>>,
+    ) -> Result<()> {
+        instructions::multisig::execute_multisig_transaction(ctx)
+    }
+
+    pub fn set_stakeholder_wallet(
+        ctx: Context<SetStakeholderWallet>,
+        wallet: Pubkey,
+    ) -> Result<()> {
+        instructions::voting::set_stakeholder_wallet(ctx, wallet)
+    }
+
+    pub fn create_resolution<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreateResolution<'info>>,
+        id: [u8; 16],
+        record_date: i64,
+        voting_deadline: i64,
+        separate_class_ids: Vec<[u8; 16]>,
+    ) -> Result<()> {
+        instructions::voting::create_resolution(
+            ctx,
+            id,
+            record_date,
+            voting_deadline,
+            separate_class_ids,
+        )
+    }
+
+    pub fn record_voting_power<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RecordVotingPower<'info>>,
+    ) -> Result<()> {
+        instructions::voting::record_voting_power(ctx)
+    }
+
+    pub fn cast_vote(ctx: Context<CastVote>, choice: VoteChoice) -> Result<()> {
+        instructions::voting::cast_vote(ctx, choice)
     }
 }
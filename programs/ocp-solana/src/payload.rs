@@ -0,0 +1,456 @@
+//! Registry of `TxCreated` payload layouts.
+//!
+//! Every `TxCreated` carries a `payload_version` next to its `tx_type`, and
+//! the pair identifies the Borsh layout of `tx_data`. When a payload struct
+//! changes shape its version is bumped here, so consumers can tell old and
+//! new layouts apart instead of silently misdecoding them. The previous
+//! layout stays registered under its version and is upgraded to the current
+//! struct when decoded.
+
+use crate::events::*;
+#[cfg(feature = "decode")]
+use crate::state::WarrantExerciseTrigger;
+#[cfg(feature = "decode")]
+use anchor_lang::prelude::{borsh, AnchorDeserialize};
+
+/// Payload struct serialized into `TxCreated.tx_data` for a transaction type.
+pub trait TxPayload {
+    const TX_TYPE: TxType;
+    const VERSION: u8;
+}
+
+macro_rules! tx_payloads {
+    ($(
+        $tx_type:ident => $payload:ident @ $version:literal
+            $({ $($legacy:ident @ $legacy_version:literal),* $(,)? })?
+    ),* $(,)?) => {
+        $(
+            impl TxPayload for $payload {
+                const TX_TYPE: TxType = TxType::$tx_type;
+                const VERSION: u8 = $version;
+            }
+        )*
+
+        /// Current payload version emitted for `tx_type`, or `None` if the
+        /// program never emits that transaction type.
+        pub fn payload_version(tx_type: TxType) -> Option<u8> {
+            match tx_type {
+                $(TxType::$tx_type => Some($version),)*
+                _ => None,
+            }
+        }
+
+        /// A `TxCreated` payload decoded into the current struct for its
+        /// transaction type, whichever version it was emitted with.
+        #[cfg(feature = "decode")]
+        pub enum DecodedTx {
+            $($tx_type($payload),)*
+        }
+
+        #[cfg(feature = "decode")]
+        impl DecodedTx {
+            pub fn envelope(&self) -> &EventEnvelope {
+                match self {
+                    $(DecodedTx::$tx_type(tx) => &tx.envelope,)*
+                }
+            }
+        }
+
+        /// Decodes the payload of any `TxCreated` according to its
+        /// `(tx_type, payload_version)`. Earlier layouts are upgraded to the
+        /// current one.
+        #[cfg(feature = "decode")]
+        pub fn decode_tx_created(event: &TxCreatedV2) -> Result<DecodedTx, TxDecodeError> {
+            match (event.tx_type, event.payload_version) {
+                $(
+                    (TxType::$tx_type, $version) => {
+                        let payload = $payload::try_from_slice(&event.tx_data)?;
+                        Ok(DecodedTx::$tx_type(payload))
+                    }
+                    $($(
+                        (TxType::$tx_type, $legacy_version) => {
+                            let payload = $legacy::try_from_slice(&event.tx_data)?;
+                            Ok(DecodedTx::$tx_type(payload.upgrade(event.envelope)))
+                        }
+                    )*)?
+                )*
+                (tx_type, version) => Err(TxDecodeError::UnknownPayload { tx_type, version }),
+            }
+        }
+    };
+}
+
+// Version 1 is the first layout emitted for a type. The types the program
+// emitted before every payload started with an `EventEnvelope` are at
+// version 2, with their original layout still registered as version 1.
+tx_payloads! {
+    StockIssuance => StockIssued @ 2 { StockIssuedV1 @ 1 },
+    StockConversion => StockConverted @ 1,
+    StockClassSplit => StockClassSplit @ 1,
+    StockClassConversionRatioAdjustment => StockClassConversionRatioAdjusted @ 1,
+    ConvertibleIssuance => ConvertibleIssued @ 2 { ConvertibleIssuedV1 @ 1 },
+    EquityCompensationIssuance => EquityCompensationIssued @ 2 {
+        EquityCompensationIssuedV1 @ 1,
+    },
+    EquityCompensationExercise => EquityCompensationExercised @ 2 {
+        EquityCompensationExercisedV1 @ 1,
+    },
+    WarrantIssuance => WarrantIssued @ 2 { WarrantIssuedV1 @ 1 },
+    WarrantExercise => WarrantExercised @ 1,
+    WarrantCancellation => WarrantCancelled @ 1,
+    WarrantExpiration => WarrantExpired @ 1,
+    WarrantTransfer => WarrantTransferred @ 1,
+    ConvertibleConversion => ConvertibleConverted @ 1,
+}
+
+/// Version 1 of `StockIssued`, before the envelope replaced `issuer_id`.
+#[cfg(feature = "decode")]
+#[derive(AnchorDeserialize)]
+pub struct StockIssuedV1 {
+    pub stock_class_id: [u8; 16],
+    pub security_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    pub quantity: u64,
+    pub share_price: u64,
+    pub issuer_id: [u8; 16],
+}
+
+#[cfg(feature = "decode")]
+impl StockIssuedV1 {
+    fn upgrade(self, envelope: EventEnvelope) -> StockIssued {
+        StockIssued {
+            envelope: EventEnvelope {
+                issuer_id: self.issuer_id,
+                object_id: self.security_id,
+                ..envelope
+            },
+            stock_class_id: self.stock_class_id,
+            security_id: self.security_id,
+            stakeholder_id: self.stakeholder_id,
+            quantity: self.quantity,
+            share_price: self.share_price,
+        }
+    }
+}
+
+/// Version 1 of `WarrantIssued`, from before warrants recorded their stock
+/// class and exercise terms.
+#[cfg(feature = "decode")]
+#[derive(AnchorDeserialize)]
+pub struct WarrantIssuedV1 {
+    pub stakeholder_id: [u8; 16],
+    pub security_id: [u8; 16],
+    pub quantity: u64,
+}
+
+#[cfg(feature = "decode")]
+impl WarrantIssuedV1 {
+    /// The terms version 1 did not record are left zero, with the default
+    /// trigger.
+    fn upgrade(self, envelope: EventEnvelope) -> WarrantIssued {
+        WarrantIssued {
+            envelope: EventEnvelope {
+                object_id: self.security_id,
+                ..envelope
+            },
+            stakeholder_id: self.stakeholder_id,
+            stock_class_id: [0; 16],
+            security_id: self.security_id,
+            quantity: self.quantity,
+            exercise_price: 0,
+            expiration_date: 0,
+            exercise_trigger: WarrantExerciseTrigger::default(),
+        }
+    }
+}
+
+/// Version 1 payloads that match version 2 apart from the missing envelope.
+/// Upgrading takes the envelope of the `TxCreated` that carried them, with
+/// `object_id` set to the id the handler now puts there.
+macro_rules! envelope_free_payloads {
+    ($(
+        $(#[doc = $doc:literal])*
+        $legacy:ident => $payload:ident by $object_id:ident {
+            $($field:ident: $ty:ty),* $(,)?
+        }
+    )*) => {
+        $(
+            $(#[doc = $doc])*
+            #[cfg(feature = "decode")]
+            #[derive(AnchorDeserialize)]
+            pub struct $legacy {
+                $(pub $field: $ty,)*
+            }
+
+            #[cfg(feature = "decode")]
+            impl $legacy {
+                fn upgrade(self, envelope: EventEnvelope) -> $payload {
+                    $payload {
+                        envelope: EventEnvelope {
+                            object_id: self.$object_id,
+                            ..envelope
+                        },
+                        $($field: self.$field,)*
+                    }
+                }
+            }
+        )*
+    };
+}
+
+envelope_free_payloads! {
+    /// Version 1 of `ConvertibleIssued`
+    ConvertibleIssuedV1 => ConvertibleIssued by security_id {
+        stakeholder_id: [u8; 16],
+        security_id: [u8; 16],
+        investment_amount: u64,
+    }
+    /// Version 1 of `EquityCompensationIssued`
+    EquityCompensationIssuedV1 => EquityCompensationIssued by security_id {
+        security_id: [u8; 16],
+        stakeholder_id: [u8; 16],
+        stock_class_id: [u8; 16],
+        stock_plan_id: [u8; 16],
+        quantity: u64,
+    }
+    /// Version 1 of `EquityCompensationExercised`
+    EquityCompensationExercisedV1 => EquityCompensationExercised by equity_comp_security_id {
+        equity_comp_security_id: [u8; 16],
+        resulting_stock_security_id: [u8; 16],
+        quantity: u64,
+    }
+}
+
+/// The `TxCreated` the program emitted before `TxCreatedV2`: no envelope,
+/// payload version or `TxLog` position.
+#[cfg(feature = "decode")]
+#[derive(AnchorDeserialize)]
+pub struct TxCreatedV1 {
+    pub tx_type: TxType,
+    pub tx_data: Vec<u8>,
+    pub issuer_id: [u8; 16],
+}
+
+#[cfg(feature = "decode")]
+impl anchor_lang::Discriminator for TxCreatedV1 {
+    /// First 8 bytes of sha256("event:TxCreated")
+    const DISCRIMINATOR: [u8; 8] = [1, 188, 68, 198, 61, 225, 163, 78];
+}
+
+#[cfg(feature = "decode")]
+impl TxCreatedV1 {
+    /// Converts to the current layout. Its payload is version 1 of its
+    /// transaction type. It predates the `TxLog`, so `sequence` is zero and
+    /// `prev_hash` all zeros. The timestamp, slot and actor were not recorded
+    /// and are left zero; the object id is taken from the payload when it
+    /// decodes.
+    pub fn upgrade(self) -> TxCreatedV2 {
+        let mut event = TxCreatedV2 {
+            envelope: EventEnvelope {
+                issuer_id: self.issuer_id,
+                unix_timestamp: 0,
+                slot: 0,
+                actor: Default::default(),
+                object_id: [0; 16],
+            },
+            tx_type: self.tx_type,
+            payload_version: 1,
+            tx_data: self.tx_data,
+            sequence: 0,
+            prev_hash: [0; 32],
+        };
+        if let Ok(payload) = decode_tx_created(&event) {
+            event.envelope.object_id = payload.envelope().object_id;
+        }
+        event
+    }
+}
+
+#[cfg(feature = "decode")]
+#[derive(Debug)]
+pub enum TxDecodeError {
+    /// No payload layout is registered for this transaction type and version.
+    UnknownPayload { tx_type: TxType, version: u8 },
+    /// `tx_data` does not match the registered layout.
+    InvalidPayload(std::io::Error),
+}
+
+#[cfg(feature = "decode")]
+impl From<std::io::Error> for TxDecodeError {
+    fn from(error: std::io::Error) -> Self {
+        TxDecodeError::InvalidPayload(error)
+    }
+}
+
+#[cfg(feature = "decode")]
+impl std::fmt::Display for TxDecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TxDecodeError::UnknownPayload { tx_type, version } => write!(
+                f,
+                "no payload registered for {:?} version {}",
+                tx_type, version
+            ),
+            TxDecodeError::InvalidPayload(error) => write!(f, "invalid payload: {}", error),
+        }
+    }
+}
+
+#[cfg(feature = "decode")]
+impl std::error::Error for TxDecodeError {}
+
+#[cfg(all(test, feature = "decode"))]
+mod tests {
+    use super::*;
+    use anchor_lang::prelude::{AnchorSerialize, Pubkey};
+    use anchor_lang::Discriminator;
+
+    fn tx_created(payload_version: u8, tx_data: Vec<u8>) -> TxCreatedV2 {
+        TxCreatedV2 {
+            envelope: EventEnvelope {
+                issuer_id: [1; 16],
+                unix_timestamp: 1_700_000_000,
+                slot: 42,
+                actor: Pubkey::new_unique(),
+                object_id: [3; 16],
+            },
+            tx_type: TxType::StockIssuance,
+            payload_version,
+            tx_data,
+            sequence: 1,
+            prev_hash: [0; 32],
+        }
+    }
+
+    fn stock_issued(event: &TxCreatedV2) -> StockIssued {
+        match decode_tx_created(event) {
+            Ok(DecodedTx::StockIssuance(tx)) => tx,
+            Ok(_) => panic!("decoded as another transaction type"),
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    #[test]
+    fn decodes_every_registered_version_of_a_type() {
+        let mut v1 = Vec::new();
+        [2u8; 16].serialize(&mut v1).unwrap(); // stock_class_id
+        [3u8; 16].serialize(&mut v1).unwrap(); // security_id
+        [4u8; 16].serialize(&mut v1).unwrap(); // stakeholder_id
+        100u64.serialize(&mut v1).unwrap(); // quantity
+        5u64.serialize(&mut v1).unwrap(); // share_price
+        [1u8; 16].serialize(&mut v1).unwrap(); // issuer_id
+        let event = tx_created(1, v1);
+        let from_v1 = stock_issued(&event);
+
+        let v2 = StockIssued {
+            envelope: event.envelope,
+            stock_class_id: [2; 16],
+            security_id: [3; 16],
+            stakeholder_id: [4; 16],
+            quantity: 100,
+            share_price: 5,
+        }
+        .try_to_vec()
+        .unwrap();
+        let from_v2 = stock_issued(&tx_created(2, v2));
+
+        for tx in [from_v1, from_v2] {
+            assert_eq!(tx.envelope, event.envelope);
+            assert_eq!(tx.stock_class_id, [2; 16]);
+            assert_eq!(tx.security_id, [3; 16]);
+            assert_eq!(tx.stakeholder_id, [4; 16]);
+            assert_eq!(tx.quantity, 100);
+            assert_eq!(tx.share_price, 5);
+        }
+    }
+
+    #[test]
+    fn decodes_the_baseline_warrant_issuance() {
+        let mut v1 = Vec::new();
+        [4u8; 16].serialize(&mut v1).unwrap(); // stakeholder_id
+        [5u8; 16].serialize(&mut v1).unwrap(); // security_id
+        100u64.serialize(&mut v1).unwrap(); // quantity
+        let event = TxCreatedV2 {
+            tx_type: TxType::WarrantIssuance,
+            ..tx_created(1, v1)
+        };
+
+        match decode_tx_created(&event) {
+            Ok(DecodedTx::WarrantIssuance(tx)) => {
+                assert_eq!(tx.envelope.object_id, [5; 16]);
+                assert_eq!(tx.stakeholder_id, [4; 16]);
+                assert_eq!(tx.security_id, [5; 16]);
+                assert_eq!(tx.quantity, 100);
+                assert_eq!(tx.stock_class_id, [0; 16]);
+                assert_eq!(tx.exercise_price, 0);
+            }
+            _ => panic!("expected a warrant issuance"),
+        }
+    }
+
+    #[test]
+    fn rejects_an_unregistered_version() {
+        assert!(matches!(
+            decode_tx_created(&tx_created(3, Vec::new())),
+            Err(TxDecodeError::UnknownPayload {
+                tx_type: TxType::StockIssuance,
+                version: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn rejects_a_payload_that_does_not_match_its_version() {
+        let v2 = StockIssued {
+            envelope: tx_created(2, Vec::new()).envelope,
+            stock_class_id: [2; 16],
+            security_id: [3; 16],
+            stakeholder_id: [4; 16],
+            quantity: 100,
+            share_price: 5,
+        }
+        .try_to_vec()
+        .unwrap();
+        assert!(matches!(
+            decode_tx_created(&tx_created(1, v2)),
+            Err(TxDecodeError::InvalidPayload(_))
+        ));
+    }
+
+    #[test]
+    fn legacy_tx_created_keeps_the_original_discriminator() {
+        let hash = anchor_lang::solana_program::hash::hash(b"event:TxCreated");
+        assert_eq!(TxCreatedV1::DISCRIMINATOR, hash.to_bytes()[..8]);
+        assert_ne!(TxCreatedV1::DISCRIMINATOR, TxCreatedV2::DISCRIMINATOR);
+    }
+
+    #[test]
+    fn upgrades_a_legacy_tx_created_and_its_payload() {
+        // Bytes of a `TxCreated` as the program emitted it before the envelope
+        let mut tx_data = Vec::new();
+        [4u8; 16].serialize(&mut tx_data).unwrap(); // stakeholder_id
+        [5u8; 16].serialize(&mut tx_data).unwrap(); // security_id
+        7u64.serialize(&mut tx_data).unwrap(); // investment_amount
+        let mut logged = Vec::new();
+        TxType::ConvertibleIssuance.serialize(&mut logged).unwrap();
+        tx_data.serialize(&mut logged).unwrap();
+        [1u8; 16].serialize(&mut logged).unwrap(); // issuer_id
+
+        let event = TxCreatedV1::try_from_slice(&logged).unwrap().upgrade();
+
+        assert_eq!(event.envelope.issuer_id, [1; 16]);
+        assert_eq!(event.envelope.object_id, [5; 16]);
+        assert_eq!(event.payload_version, 1);
+        assert_eq!(event.sequence, 0);
+        assert_eq!(event.prev_hash, [0; 32]);
+        match decode_tx_created(&event) {
+            Ok(DecodedTx::ConvertibleIssuance(tx)) => {
+                assert_eq!(tx.envelope, event.envelope);
+                assert_eq!(tx.stakeholder_id, [4; 16]);
+                assert_eq!(tx.security_id, [5; 16]);
+                assert_eq!(tx.investment_amount, 7);
+            }
+            _ => panic!("expected a convertible issuance"),
+        }
+    }
+}
@@ -1,4 +1,7 @@
+use crate::errors::{CapTableSummaryError, StakeholderError, TxLogError};
+use crate::events::TxType;
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
 
 #[account]
 #[derive(Default)]
@@ -6,6 +9,95 @@ pub struct Issuer {
     pub id: [u8; 16],
     pub shares_issued: u64,
     pub shares_authorized: u64,
+    /// Signs issuer instructions until a multisig is created
+    pub authority: Pubkey,
+}
+
+/// Maximum number of splits recorded on a stock class over its lifetime
+pub const MAX_STOCK_CLASS_SPLITS: usize = 16;
+
+/// Bytes reserved for a stock class's `class_type`
+pub const MAX_CLASS_TYPE_LEN: usize = 36;
+
+/// Share quantities and prices are fixed-point with 6 decimals
+pub const SHARE_DECIMALS_FACTOR: u64 = 1_000_000;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FractionalSharePolicy {
+    /// Keep fractional shares at full 6-decimal precision
+    #[default]
+    Retain,
+    /// Round each position down to whole shares; fractions are cashed out off-chain
+    RoundDown,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SplitRatio {
+    pub numerator: u64,
+    pub denominator: u64,
+    pub fractional_share_policy: FractionalSharePolicy,
+    /// Pre-split quantity of the positions not yet brought past this split
+    pub pending_quantity: u64,
+}
+
+impl SplitRatio {
+    pub const SIZE: usize = 8 + 8 + 1 + 8;
+
+    /// Scales a share quantity by the ratio, rounding down to the 6-decimal unit
+    pub fn apply_to_quantity(&self, quantity: u64) -> Option<u64> {
+        let scaled = (quantity as u128)
+            .checked_mul(self.numerator as u128)?
+            .checked_div(self.denominator as u128)?;
+        u64::try_from(scaled).ok()
+    }
+
+    /// Scales a per-share price by the inverse ratio, rounding down
+    pub fn apply_to_price(&self, price: u64) -> Option<u64> {
+        let scaled = (price as u128)
+            .checked_mul(self.denominator as u128)?
+            .checked_div(self.numerator as u128)?;
+        u64::try_from(scaled).ok()
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StockClassKind {
+    #[default]
+    Common,
+    Preferred,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AntiDilutionType {
+    #[default]
+    None,
+    FullRatchet,
+    /// Base counts every outstanding share across the issuer's stock classes
+    BroadBasedWeightedAverage,
+    /// Base counts only the protected class's outstanding shares
+    NarrowBasedWeightedAverage,
+}
+
+/// Economic and voting terms of a stock class. Multiples and ratios are fixed-point
+/// with 6 decimals, so `1_000_000` means 1x.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StockClassTerms {
+    pub kind: StockClassKind,
+    /// Higher seniority is paid first in a liquidation; equal values rank pari passu
+    pub seniority: u32,
+    pub liquidation_preference_multiple: u64,
+    pub is_participating: bool,
+    /// Total return cap for participating preferred; zero means uncapped
+    pub participation_cap_multiple: u64,
+    /// Common shares received per share converted
+    pub conversion_ratio: u64,
+    pub converts_to_stock_class_id: [u8; 16],
+    pub votes_per_share: u64,
+    pub anti_dilution_type: AntiDilutionType,
+}
+
+impl StockClassTerms {
+    pub const SIZE: usize = 1 + 4 + 8 + 1 + 8 + 8 + 16 + 8 + 1;
 }
 
 #[account]
@@ -16,6 +108,47 @@ pub struct StockClass {
     pub price_per_share: u64,
     pub shares_issued: u64,
     pub shares_authorized: u64,
+    /// Every split applied to this class, oldest first. Its length is the class split epoch.
+    pub splits: Vec<SplitRatio>,
+    pub terms: StockClassTerms,
+    /// Price at which the class converts; starts at `price_per_share` and only moves
+    /// through splits and anti-dilution adjustments
+    pub conversion_price: u64,
+}
+
+impl StockClass {
+    pub const SPACE: usize = 8
+        + 16
+        + 4
+        + MAX_CLASS_TYPE_LEN
+        + 8
+        + 8
+        + 8
+        + 4
+        + SplitRatio::SIZE * MAX_STOCK_CLASS_SPLITS
+        + StockClassTerms::SIZE
+        + 8;
+
+    pub fn split_epoch(&self) -> u32 {
+        self.splits.len() as u32
+    }
+
+    /// Quantity of the positions not yet brought up to date, restated through
+    /// every later split. Each position rounds on its own, so this only
+    /// estimates their adjusted total until the crank reaches them.
+    pub fn pending_shares(&self) -> Option<u64> {
+        self.splits
+            .iter()
+            .enumerate()
+            .try_fold(0u64, |total, (epoch, split)| {
+                let pending = self.splits[epoch..]
+                    .iter()
+                    .try_fold(split.pending_quantity, |quantity, later| {
+                        later.apply_to_quantity(quantity)
+                    })?;
+                total.checked_add(pending)
+            })
+    }
 }
 
 #[account]
@@ -26,6 +159,27 @@ pub struct StockActivePosition {
     pub security_id: [u8; 16],
     pub quantity: u64,
     pub share_price: u64,
+    /// Number of class splits already reflected in `quantity` and `share_price`
+    pub split_epoch: u32,
+}
+
+impl StockActivePosition {
+    /// Brings the position up to date with every split recorded on its class since
+    /// `split_epoch`. The class's totals are the caller's to restate, see
+    /// `engine::rules::apply_pending_splits`.
+    pub fn apply_pending_splits(&mut self, stock_class: &StockClass) -> Option<()> {
+        for split in stock_class.splits.iter().skip(self.split_epoch as usize) {
+            let quantity = split.apply_to_quantity(self.quantity)?;
+            self.quantity = match split.fractional_share_policy {
+                FractionalSharePolicy::Retain => quantity,
+                FractionalSharePolicy::RoundDown => quantity - quantity % SHARE_DECIMALS_FACTOR,
+            };
+            self.share_price = split.apply_to_price(self.share_price)?;
+        }
+
+        self.split_epoch = stock_class.split_epoch();
+        Some(())
+    }
 }
 
 #[account]
@@ -48,6 +202,8 @@ pub struct ConvertibleActivePosition {
     pub stakeholder_id: [u8; 16],
     pub security_id: [u8; 16],
     pub investment_amount: u64,
+    /// Financing round the note is added to for conversion; zero when none
+    pub round_id: [u8; 16],
 }
 
 #[account]
@@ -58,12 +214,674 @@ pub struct EquityCompensationActivePosition {
     pub stock_plan_id: [u8; 16],
     pub security_id: [u8; 16],
     pub quantity: u64,
+    pub rent_payer: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WarrantExerciseTrigger {
+    #[default]
+    ElectiveAtWill,
+    ElectiveInRange,
+    ElectiveOnCondition,
 }
 
 #[account]
 #[derive(Default)]
 pub struct WarrantActivePosition {
     pub stakeholder_id: [u8; 16],
+    pub stock_class_id: [u8; 16],
     pub security_id: [u8; 16],
     pub quantity: u64,
+    pub exercise_price: u64,
+    pub expiration_date: i64,
+    pub exercise_trigger: WarrantExerciseTrigger,
+    pub rent_payer: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StockClassSummary {
+    pub stock_class_id: [u8; 16],
+    pub shares_authorized: u64,
+    pub shares_outstanding: u64,
+}
+
+impl StockClassSummary {
+    pub const SIZE: usize = 16 + 8 + 8;
+}
+
+/// Running fully-diluted totals for an issuer, kept in sync by every instruction
+/// that changes them.
+#[account]
+#[derive(Default)]
+pub struct CapTableSummary {
+    pub issuer_id: [u8; 16],
+    pub shares_authorized: u64,
+    pub stock_classes: Vec<StockClassSummary>,
+    pub options_outstanding: u64,
+    pub warrants_outstanding: u64,
+    pub plan_pool_reserved: u64,
+    /// Equity compensation granted out of stock plans, exercised or not
+    pub plan_pool_granted: u64,
+    pub plan_pool_available: u64,
+    pub convertible_principal: u64,
+}
+
+impl CapTableSummary {
+    pub fn space(stock_class_count: usize) -> usize {
+        8 + 16 + 8 + 4 + (StockClassSummary::SIZE * stock_class_count) + 8 * 6
+    }
+
+    /// Mirrors a stock class's authorized and issued totals into its summary entry
+    pub fn sync_stock_class(
+        &mut self,
+        stock_class: &StockClass,
+    ) -> core::result::Result<(), CapTableSummaryError> {
+        let summary = self
+            .stock_classes
+            .iter_mut()
+            .find(|summary| summary.stock_class_id == stock_class.id)
+            .ok_or(CapTableSummaryError::StockClassNotFound)?;
+
+        summary.shares_authorized = stock_class.shares_authorized;
+        summary.shares_outstanding = stock_class.shares_issued;
+        Ok(())
+    }
+
+    pub fn shares_outstanding(&self) -> u64 {
+        self.stock_classes.iter().fold(0u64, |total, summary| {
+            total.saturating_add(summary.shares_outstanding)
+        })
+    }
+
+    /// Outstanding shares plus everything that could become shares: options,
+    /// warrants and the unallocated plan pool. Convertibles are excluded as their
+    /// share count is only known at conversion.
+    pub fn fully_diluted_shares(&self) -> u64 {
+        self.shares_outstanding()
+            .saturating_add(self.options_outstanding)
+            .saturating_add(self.warrants_outstanding)
+            .saturating_add(self.plan_pool_available)
+    }
+
+    pub fn sync_plan_pool_available(&mut self) {
+        self.plan_pool_available = self
+            .plan_pool_reserved
+            .saturating_sub(self.plan_pool_granted);
+    }
+}
+
+/// Copy of an issuer's `CapTableSummary` taken at a point in time. Written once
+/// by `snapshot_cap_table` and never modified, so it can back a record date or
+/// an audit without replaying the history.
+#[account]
+#[derive(Default)]
+pub struct CapTableSnapshot {
+    pub issuer_id: [u8; 16],
+    pub id: [u8; 16],
+    pub unix_timestamp: i64,
+    pub slot: u64,
+    /// Signer that took the snapshot
+    pub authority: Pubkey,
+    /// `TxLog` position when the snapshot was taken: the last transaction
+    /// included and the hash chained after it. Stock class and plan creation,
+    /// authorized share adjustments and split cranks are not logged, so the
+    /// totals may include changes that replaying the `TxLog` up to this
+    /// position does not reproduce.
+    pub tx_log_sequence: u64,
+    pub tx_log_hash: [u8; 32],
+    pub shares_authorized: u64,
+    pub stock_classes: Vec<StockClassSummary>,
+    pub options_outstanding: u64,
+    pub warrants_outstanding: u64,
+    pub plan_pool_reserved: u64,
+    pub plan_pool_granted: u64,
+    pub plan_pool_available: u64,
+    pub convertible_principal: u64,
+}
+
+impl CapTableSnapshot {
+    /// The summary's layout plus id, timestamp, slot, authority and `TxLog` position
+    pub fn space(stock_class_count: usize) -> usize {
+        CapTableSummary::space(stock_class_count) + 16 + 8 + 8 + 32 + 8 + 32
+    }
+}
+
+/// Subscriptions and conversions a priced round can hold; bounds the events
+/// `close_financing_round` writes to the transaction log
+pub const MAX_FINANCING_ROUND_ENTRIES: usize = 16;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PricedRoundStatus {
+    #[default]
+    Open,
+    Closed,
+}
+
+/// New money for the round's stock class
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RoundSubscription {
+    pub stakeholder_id: [u8; 16],
+    /// Stock position issued for the subscription
+    pub security_id: [u8; 16],
+    pub amount: u64,
+    /// Set when the round closes
+    pub shares: u64,
+}
+
+impl RoundSubscription {
+    pub const SIZE: usize = 16 + 16 + 8 + 8;
+}
+
+/// Convertible converting into the round's stock class
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RoundConvertible {
+    pub stakeholder_id: [u8; 16],
+    pub security_id: [u8; 16],
+    pub resulting_security_id: [u8; 16],
+    pub principal: u64,
+    /// Discount to the round price, e.g. `200_000` for 20%
+    pub discount: u64,
+    /// Zero for no cap
+    pub valuation_cap: u64,
+    /// Set when the round closes
+    pub conversion_price: u64,
+    /// Set when the round closes
+    pub shares: u64,
+}
+
+impl RoundConvertible {
+    pub const SIZE: usize = 16 + 16 + 16 + 8 * 5;
+}
+
+/// A priced round being assembled. Entries only record intent until
+/// `close_financing_round` prices the round and applies every issuance,
+/// conversion, pool top-up and authorized share increase at once; an open
+/// round is discarded with `cancel_financing_round`. The stock positions of
+/// a closed round are created by `settle_financing_round`, one per entry.
+#[account]
+#[derive(Default)]
+pub struct PricedRound {
+    pub issuer_id: [u8; 16],
+    pub id: [u8; 16],
+    pub status: PricedRoundStatus,
+    /// Stock class created for the round when it closes
+    pub stock_class_id: [u8; 16],
+    pub class_type: String,
+    pub terms: StockClassTerms,
+    pub pre_money_valuation: u64,
+    /// Available plan pool after the round as a fraction of the post-money
+    /// fully diluted shares, e.g. `100_000` for 10%; zero for no top-up
+    pub target_pool_fraction: u64,
+    /// Stock plan topped up; all zeros when there is no top-up
+    pub stock_plan_id: [u8; 16],
+    pub subscriptions: Vec<RoundSubscription>,
+    pub conversions: Vec<RoundConvertible>,
+    /// Set when the round closes
+    pub price_per_share: u64,
+    /// Set when the round closes
+    pub pool_increase: u64,
+    /// Entries whose stock position has been created
+    pub settled: u32,
+}
+
+impl PricedRound {
+    pub fn space(subscription_count: usize, conversion_count: usize) -> usize {
+        8 + 16
+            + 16
+            + 1
+            + 16
+            + 4
+            + MAX_CLASS_TYPE_LEN
+            + StockClassTerms::SIZE
+            + 8
+            + 8
+            + 16
+            + 4
+            + RoundSubscription::SIZE * subscription_count
+            + 4
+            + RoundConvertible::SIZE * conversion_count
+            + 8
+            + 8
+            + 4
+    }
+
+    pub fn entry_count(&self) -> usize {
+        self.subscriptions.len() + self.conversions.len()
+    }
+}
+
+/// Board members an issuer can register, and so approvals a proposal can hold
+pub const MAX_BOARD_MEMBERS: usize = 16;
+
+/// Signers whose approval the issuer's sensitive operations need
+#[account]
+#[derive(Default)]
+pub struct Board {
+    pub issuer_id: [u8; 16],
+    pub members: Vec<Pubkey>,
+    /// Approvals from current members a proposal needs before it executes
+    pub threshold: u8,
+}
+
+impl Board {
+    pub const SPACE: usize = 8 + 16 + 4 + 32 * MAX_BOARD_MEMBERS + 1;
+
+    pub fn is_member(&self, key: &Pubkey) -> bool {
+        self.members.contains(key)
+    }
+}
+
+/// Operation a proposal performs once the board approves it, with the
+/// arguments of the instruction it stands in for
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum ProposalAction {
+    AdjustAuthorizedShares {
+        new_shares_authorized: u64,
+    },
+    CreateStockClass {
+        id: [u8; 16],
+        class_type: String,
+        price_per_share: u64,
+        initial_shares_authorized: u64,
+        terms: StockClassTerms,
+    },
+    CreateStockPlan {
+        id: [u8; 16],
+        stock_class_ids: Vec<[u8; 16]>,
+        shares_reserved: u64,
+    },
+    UpdateBoard {
+        members: Vec<Pubkey>,
+        threshold: u8,
+    },
+}
+
+impl Default for ProposalAction {
+    fn default() -> Self {
+        ProposalAction::AdjustAuthorizedShares {
+            new_shares_authorized: 0,
+        }
+    }
+}
+
+impl ProposalAction {
+    /// Id of the stock class or plan the action creates; all zeros otherwise
+    pub fn created_id(&self) -> [u8; 16] {
+        match self {
+            ProposalAction::CreateStockClass { id, .. }
+            | ProposalAction::CreateStockPlan { id, .. } => *id,
+            _ => [0; 16],
+        }
+    }
+
+    pub fn creates_stock_class(&self) -> bool {
+        matches!(self, ProposalAction::CreateStockClass { .. })
+    }
+
+    pub fn creates_stock_plan(&self) -> bool {
+        matches!(self, ProposalAction::CreateStockPlan { .. })
+    }
+}
+
+/// A pending operation awaiting board approval. The account stays after
+/// execution as the record of who approved it and when.
+#[account]
+#[derive(Default)]
+pub struct Proposal {
+    pub issuer_id: [u8; 16],
+    pub id: [u8; 16],
+    pub proposer: Pubkey,
+    pub action: ProposalAction,
+    /// Board members who approved, in approval order
+    pub approvals: Vec<Pubkey>,
+    /// Unix timestamp at which the approvals reached the threshold; zero
+    /// until then
+    pub board_approval_date: i64,
+    /// Unix timestamp of the stockholder approval obtained for the action;
+    /// zero when none is needed
+    pub stockholder_approval_date: i64,
+    /// Zero until executed
+    pub executed_at: i64,
+}
+
+impl Proposal {
+    pub fn space(action: &ProposalAction) -> usize {
+        8 + 16
+            + 16
+            + 32
+            + AnchorSerialize::try_to_vec(action).map_or(0, |data| data.len())
+            + 4
+            + 32 * MAX_BOARD_MEMBERS
+            + 8
+            + 8
+            + 8
+    }
+}
+
+/// Members an issuer's multisig can have, and so approvals a multisig
+/// transaction can hold
+pub const MAX_MULTISIG_MEMBERS: usize = 16;
+
+/// M-of-N authority over the issuer. Once an issuer has one, its mutating
+/// instructions need `threshold` members among their signers, or must come
+/// from an approved `MultisigTransaction` signed by the multisig signer PDA.
+#[account]
+#[derive(Default)]
+pub struct Multisig {
+    pub issuer_id: [u8; 16],
+    pub members: Vec<Pubkey>,
+    pub threshold: u8,
+    /// Index of the next `MultisigTransaction`, which seeds its address
+    pub nonce: u64,
+    /// Bump of the `[b"multisig_signer", issuer_id]` PDA that signs approved
+    /// transactions
+    pub signer_bump: u8,
+}
+
+impl Multisig {
+    pub const SPACE: usize = 8 + 16 + 4 + 32 * MAX_MULTISIG_MEMBERS + 1 + 8 + 1;
+
+    pub fn is_member(&self, key: &Pubkey) -> bool {
+        self.members.contains(key)
+    }
+}
+
+/// Account meta of an instruction stored for multisig approval
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct TransactionAccount {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl TransactionAccount {
+    pub const SIZE: usize = 32 + 1 + 1;
+}
+
+/// An instruction of this program awaiting the issuer's multisig. Once
+/// approved it is invoked with the multisig signer PDA as its signer.
+#[account]
+#[derive(Default)]
+pub struct MultisigTransaction {
+    pub issuer_id: [u8; 16],
+    pub nonce: u64,
+    pub proposer: Pubkey,
+    pub accounts: Vec<TransactionAccount>,
+    pub data: Vec<u8>,
+    /// Members who approved, in approval order
+    pub approvals: Vec<Pubkey>,
+    pub executed: bool,
+}
+
+impl MultisigTransaction {
+    pub fn space(account_count: usize, data_len: usize) -> usize {
+        8 + 16
+            + 8
+            + 32
+            + 4
+            + TransactionAccount::SIZE * account_count
+            + 4
+            + data_len
+            + 4
+            + 32 * MAX_MULTISIG_MEMBERS
+            + 1
+    }
+}
+
+/// Wallet a stakeholder signs with, such as to vote, registered by the issuer.
+#[account]
+#[derive(Default)]
+pub struct StakeholderWallet {
+    pub issuer_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    pub wallet: Pubkey,
+}
+
+impl StakeholderWallet {
+    pub const SPACE: usize = 8 + 16 + 16 + 32;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VoteChoice {
+    For,
+    Against,
+    Abstain,
+}
+
+/// Votes cast on a resolution by holders of one stock class. Votes are
+/// fixed-point with 6 decimals, like share quantities.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ClassTally {
+    pub stock_class_id: [u8; 16],
+    /// The class's `votes_per_share` when the resolution was created
+    pub votes_per_share: u64,
+    /// Whether the resolution needs this class's approval as a separate class
+    pub separate_vote: bool,
+    pub votes_for: u64,
+    pub votes_against: u64,
+    pub votes_abstain: u64,
+}
+
+impl ClassTally {
+    pub const SIZE: usize = 16 + 8 + 1 + 8 + 8 + 8;
+}
+
+/// A matter put to the issuer's stockholders. Holdings are recorded up to
+/// `record_date`; votes are cast after it until `voting_deadline`.
+#[account]
+#[derive(Default)]
+pub struct Resolution {
+    pub issuer_id: [u8; 16],
+    pub id: [u8; 16],
+    pub record_date: i64,
+    pub voting_deadline: i64,
+    /// One entry per stock class of the issuer when the resolution was created
+    pub tallies: Vec<ClassTally>,
+}
+
+impl Resolution {
+    pub fn space(class_count: usize) -> usize {
+        8 + 16 + 16 + 8 + 8 + 4 + ClassTally::SIZE * class_count
+    }
+}
+
+/// A stakeholder's voting shares on a resolution, as of the last recording,
+/// and the vote cast with them. Only votes if the stakeholder's stock has not
+/// changed between the recording and the record date.
+#[account]
+#[derive(Default)]
+pub struct VoterRecord {
+    pub resolution_id: [u8; 16],
+    pub stakeholder_id: [u8; 16],
+    /// Shares held in each class, in the order of the resolution's tallies
+    pub shares: Vec<u64>,
+    pub recorded_at: i64,
+    pub choice: Option<VoteChoice>,
+}
+
+impl VoterRecord {
+    pub fn space(class_count: usize) -> usize {
+        8 + 16 + 16 + 4 + 8 * class_count + 8 + 1 + 1
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PositionType {
+    Stock,
+    Warrant,
+    Convertible,
+    EquityCompensation,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Holding {
+    pub security_id: [u8; 16],
+    pub position_type: PositionType,
+}
+
+impl Holding {
+    pub const SIZE: usize = 16 + 1;
+}
+
+/// Index of every active position held by a stakeholder, so holdings can be
+/// listed without scanning program accounts.
+#[account]
+#[derive(Default)]
+pub struct StakeholderHoldings {
+    pub stakeholder_id: [u8; 16],
+    /// When a stock holding was last added or removed; voting power recorded
+    /// before then is stale
+    pub stock_changed_at: i64,
+    pub holdings: Vec<Holding>,
+}
+
+impl StakeholderHoldings {
+    pub fn space(holding_count: usize) -> usize {
+        8 + 16 + 8 + 4 + (Holding::SIZE * holding_count)
+    }
+
+    pub fn add(&mut self, security_id: [u8; 16], position_type: PositionType) -> Result<()> {
+        let holding = Holding {
+            security_id,
+            position_type,
+        };
+        require!(
+            !self.holdings.contains(&holding),
+            StakeholderError::DuplicateHolding
+        );
+        self.holdings.push(holding);
+        self.touch(position_type)
+    }
+
+    pub fn remove(&mut self, security_id: [u8; 16], position_type: PositionType) -> Result<()> {
+        let index = self
+            .holdings
+            .iter()
+            .position(|holding| {
+                holding.security_id == security_id && holding.position_type == position_type
+            })
+            .ok_or(StakeholderError::HoldingNotFound)?;
+        self.holdings.swap_remove(index);
+        self.touch(position_type)
+    }
+
+    fn touch(&mut self, position_type: PositionType) -> Result<()> {
+        if position_type == PositionType::Stock {
+            self.stock_changed_at = Clock::get()?.unix_timestamp;
+        }
+        Ok(())
+    }
+}
+
+/// Per-issuer registration of a security id. Created by every instruction
+/// that issues a new security, so a security id can only be used once within
+/// an issuer and always resolves to the position account that holds it.
+#[account]
+pub struct SecurityRegistration {
+    pub issuer_id: [u8; 16],
+    pub security_id: [u8; 16],
+    pub position_type: PositionType,
+    pub position: Pubkey,
+}
+
+impl SecurityRegistration {
+    pub const SPACE: usize = 8 + 16 + 16 + 1 + 32;
+
+    pub fn register(
+        &mut self,
+        issuer_id: [u8; 16],
+        security_id: [u8; 16],
+        position_type: PositionType,
+        position: Pubkey,
+    ) {
+        self.issuer_id = issuer_id;
+        self.security_id = security_id;
+        self.position_type = position_type;
+        self.position = position;
+    }
+}
+
+/// Hash chain over every `TxCreated` emitted for an issuer. Each entry hashes
+/// the previous hash with the transaction type, payload version and payload,
+/// so an indexer that replays the events can detect gaps and verify it has
+/// the complete history.
+#[account]
+#[derive(Default)]
+pub struct TxLog {
+    pub issuer_id: [u8; 16],
+    pub sequence: u64,
+    pub last_hash: [u8; 32],
+}
+
+impl TxLog {
+    pub const SPACE: usize = 8 + 16 + 8 + 32;
+
+    /// Appends a transaction and returns its sequence number together with
+    /// the hash it was chained onto.
+    pub fn append(
+        &mut self,
+        tx_type: TxType,
+        payload_version: u8,
+        tx_data: &[u8],
+    ) -> Result<(u64, [u8; 32])> {
+        let prev_hash = self.last_hash;
+        self.sequence = self
+            .sequence
+            .checked_add(1)
+            .ok_or(TxLogError::SequenceOverflow)?;
+        self.last_hash = Self::chain(&prev_hash, tx_type, payload_version, tx_data);
+        Ok((self.sequence, prev_hash))
+    }
+
+    /// Hash of a transaction chained onto `prev_hash`.
+    pub fn chain(
+        prev_hash: &[u8; 32],
+        tx_type: TxType,
+        payload_version: u8,
+        tx_data: &[u8],
+    ) -> [u8; 32] {
+        hashv(&[prev_hash, &[tx_type as u8], &[payload_version], tx_data]).to_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn serialized_space<T: AnchorSerialize>(account: &T) -> usize {
+        8 + AnchorSerialize::try_to_vec(account).unwrap().len()
+    }
+
+    #[test]
+    fn stock_class_space_fits_a_full_class() {
+        let stock_class = StockClass {
+            class_type: "X".repeat(MAX_CLASS_TYPE_LEN),
+            splits: vec![SplitRatio::default(); MAX_STOCK_CLASS_SPLITS],
+            ..StockClass::default()
+        };
+
+        assert_eq!(serialized_space(&stock_class), StockClass::SPACE);
+    }
+
+    #[test]
+    fn priced_round_space_matches_its_entries() {
+        let round = PricedRound {
+            class_type: "X".repeat(MAX_CLASS_TYPE_LEN),
+            subscriptions: vec![RoundSubscription::default(); 2],
+            conversions: vec![RoundConvertible::default(); 3],
+            ..PricedRound::default()
+        };
+
+        assert_eq!(serialized_space(&round), PricedRound::space(2, 3));
+    }
+
+    #[test]
+    fn tx_log_hash_covers_the_payload_version() {
+        let mut v1 = TxLog::default();
+        let mut v2 = TxLog::default();
+        v1.append(TxType::StockIssuance, 1, &[7; 8]).unwrap();
+        v2.append(TxType::StockIssuance, 2, &[7; 8]).unwrap();
+
+        assert_ne!(v1.last_hash, v2.last_hash);
+    }
 }
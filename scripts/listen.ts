@@ -9,12 +9,38 @@ async function main() {
 
   const listeners: number[] = [];
 
+  // Last TxLog sequence seen per issuer, used to detect missed transactions
+  const lastSequences = new Map<string, number>();
+
   // Listen for TxCreated events
   listeners.push(
-    program.addEventListener("txCreated", (event) => {
+    program.addEventListener("txCreatedV2", (event) => {
+      const issuerId = bytes16ToUuid(event.envelope.issuerId);
+      const sequence = event.sequence.toNumber();
+      const lastSequence = lastSequences.get(issuerId);
+      if (lastSequence !== undefined && sequence !== lastSequence + 1) {
+        console.warn(
+          `⚠️ Missed transactions for issuer ${issuerId}: expected sequence ${
+            lastSequence + 1
+          }, got ${sequence}`
+        );
+      }
+      lastSequences.set(issuerId, sequence);
+
       // Get the event type first
-      const eventType = getTxDataDecodingType(event.txType);
+      const eventType = getTxDataDecodingType(
+        event.txType,
+        event.payloadVersion
+      );
       console.log("🔍 Event Type:", eventType);
+      if (eventType === "unknown") {
+        console.warn(
+          `⚠️ No decoder for ${Object.keys(event.txType)[0]} payload version ${
+            event.payloadVersion
+          }, skipping`
+        );
+        return;
+      }
 
       // Decode the event data using Anchor's decoder
       const decodedData = program.coder.types.decode(
@@ -43,15 +69,29 @@ async function main() {
 
       console.log("📝 Transaction:", {
         type: getOcfTxType(event.txType),
+        ...formatEnvelope(event.envelope),
+        sequence,
+        prevHash: Buffer.from(event.prevHash).toString("hex"),
         data: formattedData,
       });
     })
   );
 
+  // Listen for IssuerCreated events
+  listeners.push(
+    program.addEventListener("issuerCreated", (event) => {
+      console.log("🏢 Issuer:", {
+        ...formatEnvelope(event.envelope),
+        initialSharesAuthorized: event.initialSharesAuthorized.toString(),
+      });
+    })
+  );
+
   // Listen for StakeholderCreated events
   listeners.push(
     program.addEventListener("stakeholderCreated", (event) => {
       console.log("👤 Stakeholder:", {
+        ...formatEnvelope(event.envelope),
         // id: bytes16ToUuid(event.id),
         id: event.id,
       });
@@ -62,6 +102,7 @@ async function main() {
   listeners.push(
     program.addEventListener("stockClassCreated", (event) => {
       console.log("📈 Stock Class:", {
+        ...formatEnvelope(event.envelope),
         // id: bytes16ToUuid(event.id),
         id: event.id,
         classType: event.classType,
@@ -75,6 +116,7 @@ async function main() {
   listeners.push(
     program.addEventListener("stockPlanCreated", (event) => {
       console.log("📋 Stock Plan:", {
+        ...formatEnvelope(event.envelope),
         // id: bytes16ToUuid(event.id),
         id: event.id,
         sharesReserved: event.sharesReserved.toString(),
@@ -82,6 +124,69 @@ async function main() {
     })
   );
 
+  // Listen for CapTableSnapshotTaken events
+  listeners.push(
+    program.addEventListener("capTableSnapshotTaken", (event) => {
+      console.log("📸 Cap Table Snapshot:", {
+        ...formatEnvelope(event.envelope),
+        snapshotId: bytes16ToUuid(event.snapshotId),
+        txLogSequence: event.txLogSequence.toString(),
+        txLogHash: Buffer.from(event.txLogHash).toString("hex"),
+      });
+    })
+  );
+
+  // Listen for FinancingRoundClosed events
+  listeners.push(
+    program.addEventListener("financingRoundClosed", (event) => {
+      console.log("💰 Financing Round Closed:", {
+        ...formatEnvelope(event.envelope),
+        roundId: bytes16ToUuid(event.roundId),
+        stockClassId: bytes16ToUuid(event.stockClassId),
+        pricePerShare: event.pricePerShare.toString(),
+        preMoneyValuation: event.preMoneyValuation.toString(),
+        postMoneyValuation: event.postMoneyValuation.toString(),
+        sharesIssued: event.sharesIssued.toString(),
+        poolIncrease: event.poolIncrease.toString(),
+      });
+    })
+  );
+
+  // Listen for ProposalExecuted events
+  listeners.push(
+    program.addEventListener("proposalExecuted", (event) => {
+      console.log("🗳️ Proposal Executed:", {
+        ...formatEnvelope(event.envelope),
+        proposalId: bytes16ToUuid(event.proposalId),
+        boardApprovalDate: event.boardApprovalDate.toString(),
+        stockholderApprovalDate: event.stockholderApprovalDate.toString(),
+      });
+    })
+  );
+
+  // Listen for MultisigTransactionExecuted events
+  listeners.push(
+    program.addEventListener("multisigTransactionExecuted", (event) => {
+      console.log("🔐 Multisig Transaction Executed:", {
+        ...formatEnvelope(event.envelope),
+        nonce: event.nonce.toString(),
+      });
+    })
+  );
+
+  // Listen for VoteCast events
+  listeners.push(
+    program.addEventListener("voteCast", (event) => {
+      console.log("🗳️ Vote Cast:", {
+        ...formatEnvelope(event.envelope),
+        resolutionId: bytes16ToUuid(event.resolutionId),
+        stakeholderId: bytes16ToUuid(event.stakeholderId),
+        choice: Object.keys(event.choice)[0],
+        votes: event.votes.map((votes) => votes.toString()),
+      });
+    })
+  );
+
   // Handle cleanup
   process.on("SIGINT", async () => {
     console.log("\n🛑 Cleaning up...");
@@ -92,15 +197,37 @@ async function main() {
   console.log("👂 Listening for events... (Press Ctrl+C to exit)");
 }
 
-export function getTxDataDecodingType(txType: any): string {
+function formatEnvelope(envelope: any) {
+  return {
+    issuerId: bytes16ToUuid(envelope.issuerId),
+    objectId: bytes16ToUuid(envelope.objectId),
+    actor: envelope.actor.toString(),
+    timestamp: new Date(envelope.unixTimestamp.toNumber() * 1000).toISOString(),
+    slot: envelope.slot.toString(),
+  };
+}
+
+// Keyed by `${txType}@${payloadVersion}`; mirrors the registry in payload.rs
+export function getTxDataDecodingType(
+  txType: any,
+  payloadVersion: number
+): string {
   const typeMap: { [key: string]: string } = {
-    stockIssuance: "stockIssued",
-    convertibleIssuance: "convertibleIssued",
-    equityCompensationIssuance: "equityCompensationIssued",
-    equityCompensationExercise: "equityCompensationExercised",
-    warrantIssuance: "warrantIssued",
+    "stockIssuance@2": "stockIssued",
+    "convertibleIssuance@2": "convertibleIssued",
+    "equityCompensationIssuance@2": "equityCompensationIssued",
+    "equityCompensationExercise@2": "equityCompensationExercised",
+    "warrantIssuance@2": "warrantIssued",
+    "warrantExercise@2": "warrantExercised",
+    "warrantCancellation@2": "warrantCancelled",
+    "warrantExpiration@2": "warrantExpired",
+    "warrantTransfer@2": "warrantTransferred",
+    "stockClassSplit@2": "stockClassSplit",
+    "stockConversion@2": "stockConverted",
+    "stockClassConversionRatioAdjustment@2": "stockClassConversionRatioAdjusted",
+    "convertibleConversion@1": "convertibleConverted",
   };
-  return typeMap[Object.keys(txType)[0]] || "unknown";
+  return typeMap[`${Object.keys(txType)[0]}@${payloadVersion}`] || "unknown";
 }
 
 function getOcfTxType(txType: any): string {
@@ -110,6 +237,15 @@ function getOcfTxType(txType: any): string {
     equityCompensationIssuance: "TX_EQUITY_COMPENSATION_ISSUANCE",
     equityCompensationExercise: "TX_EQUITY_COMPENSATION_EXERCISE",
     warrantIssuance: "TX_WARRANT_ISSUANCE",
+    warrantExercise: "TX_WARRANT_EXERCISE",
+    warrantCancellation: "TX_WARRANT_CANCELLATION",
+    warrantExpiration: "TX_WARRANT_EXPIRATION",
+    warrantTransfer: "TX_WARRANT_TRANSFER",
+    stockClassSplit: "TX_STOCK_CLASS_SPLIT",
+    stockConversion: "TX_STOCK_CONVERSION",
+    stockClassConversionRatioAdjustment:
+      "TX_STOCK_CLASS_CONVERSION_RATIO_ADJUSTMENT",
+    convertibleConversion: "TX_CONVERTIBLE_CONVERSION",
   };
   return typeMap[Object.keys(txType)[0]] || "unknown";
 }
@@ -146,6 +146,8 @@ export async function exerciseEquityCompensation({
         issuer: issuerPda,
         equityPosition: equityPositionPda,
         stockPosition: stockPositionPda,
+        // The provider issued the option, so it is refunded on full exercise
+        rentPayer: program.provider.publicKey,
         authority: program.provider.publicKey,
       })
       .rpc();
@@ -0,0 +1,458 @@
+import { getProvider, web3 } from "@coral-xyz/anchor";
+import {
+  uuidToBytes16,
+  bytes16ToUuid,
+  stringNumberToBN,
+  getProgram,
+} from "../helpers";
+import { SendTransactionError } from "@solana/web3.js";
+
+function findPda(
+  seeds: (Uint8Array | number[])[],
+  programId: web3.PublicKey
+) {
+  return web3.PublicKey.findProgramAddressSync(
+    seeds.map((seed) => Buffer.from(seed)),
+    programId
+  )[0];
+}
+
+function logTransactionError(error: unknown) {
+  if (error instanceof SendTransactionError) {
+    console.log("Transaction Error Details:");
+    console.log("Message:", error.message);
+    console.log("Logs:", error.logs);
+    console.log("Error:", error.toString());
+  }
+}
+
+export interface OpenFinancingRoundParams {
+  id: string; // UUID
+  issuerId: string; // UUID
+  stockClassId: string; // UUID of the class the round creates
+  classType: string;
+  terms: any; // StockClassTerms as in createStockClass
+  preMoneyValuation: string;
+  targetPoolFraction?: string; // e.g. "0.1" for a 10% pool after the round
+  stockPlanId?: string; // Required with a pool target
+}
+
+// Opens a round that collects subscriptions and conversions until it closes
+export async function openFinancingRound({
+  id,
+  issuerId,
+  stockClassId,
+  classType,
+  terms,
+  preMoneyValuation,
+  targetPoolFraction = "0",
+  stockPlanId,
+}: OpenFinancingRoundParams): Promise<web3.PublicKey> {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+    const idBytes = uuidToBytes16(id);
+    const issuerIdBytes = uuidToBytes16(issuerId);
+
+    const issuerPda = findPda(
+      [Buffer.from("issuer"), issuerIdBytes],
+      program.programId
+    );
+    const roundPda = findPda(
+      [Buffer.from("priced_round"), issuerIdBytes, idBytes],
+      program.programId
+    );
+    const stockPlanPda = stockPlanId
+      ? findPda(
+          [Buffer.from("stock_plan"), uuidToBytes16(stockPlanId)],
+          program.programId
+        )
+      : null;
+
+    const tx = await program.methods
+      .openFinancingRound(
+        idBytes,
+        uuidToBytes16(stockClassId),
+        classType,
+        terms,
+        stringNumberToBN(preMoneyValuation),
+        stringNumberToBN(targetPoolFraction)
+      )
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        stockPlan: stockPlanPda,
+        authority: program.provider.publicKey,
+      })
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx);
+    return roundPda;
+  } catch (error) {
+    logTransactionError(error);
+    throw error;
+  }
+}
+
+// Records new money for a stakeholder; the shares are priced at close
+export async function addRoundSubscription({
+  issuerId,
+  roundId,
+  stakeholderId,
+  securityId,
+  amount,
+}: {
+  issuerId: string;
+  roundId: string;
+  stakeholderId: string;
+  securityId: string;
+  amount: string;
+}) {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+    const issuerIdBytes = uuidToBytes16(issuerId);
+
+    const tx = await program.methods
+      .addRoundSubscription(
+        uuidToBytes16(securityId),
+        stringNumberToBN(amount)
+      )
+      .accounts({
+        // @ts-ignore
+        issuer: findPda(
+          [Buffer.from("issuer"), issuerIdBytes],
+          program.programId
+        ),
+        round: findPda(
+          [
+            Buffer.from("priced_round"),
+            issuerIdBytes,
+            uuidToBytes16(roundId),
+          ],
+          program.programId
+        ),
+        stakeholder: findPda(
+          [Buffer.from("stakeholder"), uuidToBytes16(stakeholderId)],
+          program.programId
+        ),
+        authority: program.provider.publicKey,
+      })
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx);
+  } catch (error) {
+    logTransactionError(error);
+    throw error;
+  }
+}
+
+// Records a convertible that converts into the round's class at close
+export async function addRoundConversion({
+  issuerId,
+  roundId,
+  stakeholderId,
+  securityId,
+  resultingSecurityId,
+  discount = "0",
+  valuationCap = "0",
+}: {
+  issuerId: string;
+  roundId: string;
+  stakeholderId: string;
+  securityId: string; // The convertible
+  resultingSecurityId: string;
+  discount?: string; // e.g. "0.2" for 20%
+  valuationCap?: string; // "0" for no cap
+}) {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+    const issuerIdBytes = uuidToBytes16(issuerId);
+
+    const tx = await program.methods
+      .addRoundConversion(
+        uuidToBytes16(resultingSecurityId),
+        stringNumberToBN(discount),
+        stringNumberToBN(valuationCap)
+      )
+      .accounts({
+        // @ts-ignore
+        issuer: findPda(
+          [Buffer.from("issuer"), issuerIdBytes],
+          program.programId
+        ),
+        round: findPda(
+          [
+            Buffer.from("priced_round"),
+            issuerIdBytes,
+            uuidToBytes16(roundId),
+          ],
+          program.programId
+        ),
+        convertiblePosition: findPda(
+          [
+            Buffer.from("convertible_position"),
+            uuidToBytes16(stakeholderId),
+            uuidToBytes16(securityId),
+          ],
+          program.programId
+        ),
+        authority: program.provider.publicKey,
+      })
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx);
+  } catch (error) {
+    logTransactionError(error);
+    throw error;
+  }
+}
+
+// Applies every issuance, conversion, pool top-up and authorized share
+// amendment of the round in one transaction, or none of them
+export async function closeFinancingRound(
+  issuerId: string,
+  roundId: string
+) {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+    const issuerIdBytes = uuidToBytes16(issuerId);
+    const roundPda = findPda(
+      [
+        Buffer.from("priced_round"),
+        issuerIdBytes,
+        uuidToBytes16(roundId),
+      ],
+      program.programId
+    );
+
+    const round = await program.account.pricedRound.fetch(roundPda);
+    const hasStockPlan = round.stockPlanId.some((byte) => byte !== 0);
+
+    const tx = await program.methods
+      .closeFinancingRound()
+      .accounts({
+        // @ts-ignore
+        issuer: findPda(
+          [Buffer.from("issuer"), issuerIdBytes],
+          program.programId
+        ),
+        round: roundPda,
+        stockClass: findPda(
+          [Buffer.from("stock_class"), Uint8Array.from(round.stockClassId)],
+          program.programId
+        ),
+        stockPlan: hasStockPlan
+          ? findPda(
+              [Buffer.from("stock_plan"), Uint8Array.from(round.stockPlanId)],
+              program.programId
+            )
+          : null,
+        authority: program.provider.publicKey,
+      })
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx);
+    return getFinancingRound(issuerId, roundId);
+  } catch (error) {
+    logTransactionError(error);
+    throw error;
+  }
+}
+
+// Discards an open round, releasing the security ids it reserved and the
+// convertibles it would have converted
+export async function cancelFinancingRound(
+  issuerId: string,
+  roundId: string
+) {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+    const issuerIdBytes = uuidToBytes16(issuerId);
+    const roundPda = findPda(
+      [
+        Buffer.from("priced_round"),
+        issuerIdBytes,
+        uuidToBytes16(roundId),
+      ],
+      program.programId
+    );
+    const round = await program.account.pricedRound.fetch(roundPda);
+
+    const registrations = [
+      ...round.subscriptions.map((subscription) => subscription.securityId),
+      ...round.conversions.map((conversion) => conversion.resultingSecurityId),
+    ].map((securityId) =>
+      findPda(
+        [Buffer.from("security"), issuerIdBytes, Uint8Array.from(securityId)],
+        program.programId
+      )
+    );
+    const convertibles = round.conversions.map((conversion) =>
+      findPda(
+        [
+          Buffer.from("convertible_position"),
+          Uint8Array.from(conversion.stakeholderId),
+          Uint8Array.from(conversion.securityId),
+        ],
+        program.programId
+      )
+    );
+
+    const tx = await program.methods
+      .cancelFinancingRound()
+      .accounts({
+        // @ts-ignore
+        issuer: findPda(
+          [Buffer.from("issuer"), issuerIdBytes],
+          program.programId
+        ),
+        round: roundPda,
+        authority: program.provider.publicKey,
+      })
+      .remainingAccounts(
+        [...registrations, ...convertibles].map((pubkey) => ({
+          pubkey,
+          isSigner: false,
+          isWritable: true,
+        }))
+      )
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx);
+  } catch (error) {
+    logTransactionError(error);
+    throw error;
+  }
+}
+
+// Creates the stock position of every entry of a closed round not yet settled
+export async function settleFinancingRound(
+  issuerId: string,
+  roundId: string
+) {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+    const issuerIdBytes = uuidToBytes16(issuerId);
+    const issuerPda = findPda(
+      [Buffer.from("issuer"), issuerIdBytes],
+      program.programId
+    );
+    const roundPda = findPda(
+      [
+        Buffer.from("priced_round"),
+        issuerIdBytes,
+        uuidToBytes16(roundId),
+      ],
+      program.programId
+    );
+    const round = await program.account.pricedRound.fetch(roundPda);
+
+    const entries = [
+      ...round.subscriptions.map((subscription) => ({
+        stakeholderId: subscription.stakeholderId,
+        securityId: subscription.securityId,
+        convertibleSecurityId: null,
+      })),
+      ...round.conversions.map((conversion) => ({
+        stakeholderId: conversion.stakeholderId,
+        securityId: conversion.resultingSecurityId,
+        convertibleSecurityId: conversion.securityId,
+      })),
+    ];
+
+    for (const {
+      stakeholderId,
+      securityId,
+      convertibleSecurityId,
+    } of entries) {
+      const positionPda = findPda(
+        [
+          Buffer.from("stock_position"),
+          Uint8Array.from(stakeholderId),
+          Uint8Array.from(securityId),
+        ],
+        program.programId
+      );
+      if (await provider.connection.getAccountInfo(positionPda)) {
+        continue;
+      }
+
+      const tx = await program.methods
+        .settleFinancingRound(securityId)
+        .accounts({
+          // @ts-ignore
+          issuer: issuerPda,
+          round: roundPda,
+          stakeholder: findPda(
+            [Buffer.from("stakeholder"), Uint8Array.from(stakeholderId)],
+            program.programId
+          ),
+          convertiblePosition: convertibleSecurityId
+            ? findPda(
+                [
+                  Buffer.from("convertible_position"),
+                  Uint8Array.from(stakeholderId),
+                  Uint8Array.from(convertibleSecurityId),
+                ],
+                program.programId
+              )
+            : null,
+          authority: program.provider.publicKey,
+        })
+        .rpc();
+      await provider.connection.confirmTransaction(tx);
+    }
+  } catch (error) {
+    logTransactionError(error);
+    throw error;
+  }
+}
+
+export async function getFinancingRound(issuerId: string, roundId: string) {
+  try {
+    const { program } = getProgram();
+    const round = await program.account.pricedRound.fetch(
+      findPda(
+        [
+          Buffer.from("priced_round"),
+          uuidToBytes16(issuerId),
+          uuidToBytes16(roundId),
+        ],
+        program.programId
+      )
+    );
+
+    return {
+      roundId: bytes16ToUuid(round.id),
+      status: Object.keys(round.status)[0],
+      stockClassId: bytes16ToUuid(round.stockClassId),
+      preMoneyValuation: round.preMoneyValuation.toString(),
+      targetPoolFraction: round.targetPoolFraction.toString(),
+      pricePerShare: round.pricePerShare.toString(),
+      poolIncrease: round.poolIncrease.toString(),
+      subscriptions: round.subscriptions.map((subscription) => ({
+        stakeholderId: bytes16ToUuid(subscription.stakeholderId),
+        securityId: bytes16ToUuid(subscription.securityId),
+        amount: subscription.amount.toString(),
+        shares: subscription.shares.toString(),
+      })),
+      conversions: round.conversions.map((conversion) => ({
+        stakeholderId: bytes16ToUuid(conversion.stakeholderId),
+        securityId: bytes16ToUuid(conversion.securityId),
+        resultingSecurityId: bytes16ToUuid(conversion.resultingSecurityId),
+        principal: conversion.principal.toString(),
+        conversionPrice: conversion.conversionPrice.toString(),
+        shares: conversion.shares.toString(),
+      })),
+      settled: round.settled,
+    };
+  } catch (error) {
+    console.error("Error fetching financing round:", error);
+    throw error;
+  }
+}
@@ -0,0 +1,217 @@
+import { getProvider, web3 } from "@coral-xyz/anchor";
+import { uuidToBytes16, bytes16ToUuid, getProgram } from "../helpers";
+import { SendTransactionError } from "@solana/web3.js";
+import BN from "bn.js";
+
+function findPda(
+  seeds: (Uint8Array | number[])[],
+  programId: web3.PublicKey
+) {
+  return web3.PublicKey.findProgramAddressSync(
+    seeds.map((seed) => Buffer.from(seed)),
+    programId
+  )[0];
+}
+
+function logTransactionError(error: unknown) {
+  if (error instanceof SendTransactionError) {
+    console.log("Transaction Error Details:");
+    console.log("Message:", error.message);
+    console.log("Logs:", error.logs);
+    console.log("Error:", error.toString());
+  }
+}
+
+// Registers the board members whose approval sensitive operations need
+export async function initializeBoard({
+  issuerId,
+  members,
+  threshold,
+}: {
+  issuerId: string;
+  members: string[]; // Base58 public keys
+  threshold: number;
+}): Promise<web3.PublicKey> {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+    const issuerIdBytes = uuidToBytes16(issuerId);
+
+    const tx = await program.methods
+      .initializeBoard(
+        members.map((member) => new web3.PublicKey(member)),
+        threshold
+      )
+      .accounts({
+        // @ts-ignore
+        issuer: findPda(
+          [Buffer.from("issuer"), issuerIdBytes],
+          program.programId
+        ),
+        authority: program.provider.publicKey,
+      })
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx);
+    return findPda([Buffer.from("board"), issuerIdBytes], program.programId);
+  } catch (error) {
+    logTransactionError(error);
+    throw error;
+  }
+}
+
+// `action` takes the IDL's ProposalAction shape, e.g.
+// { adjustAuthorizedShares: { newSharesAuthorized } }
+export async function createProposal({
+  id,
+  issuerId,
+  action,
+  stockholderApprovalDate = 0,
+}: {
+  id: string;
+  issuerId: string;
+  action: any;
+  stockholderApprovalDate?: number; // Unix timestamp; 0 when not required
+}): Promise<web3.PublicKey> {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+    const idBytes = uuidToBytes16(id);
+    const issuerIdBytes = uuidToBytes16(issuerId);
+
+    const tx = await program.methods
+      .createProposal(idBytes, action, new BN(stockholderApprovalDate))
+      .accounts({
+        // @ts-ignore
+        issuer: findPda(
+          [Buffer.from("issuer"), issuerIdBytes],
+          program.programId
+        ),
+        authority: program.provider.publicKey,
+      })
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx);
+    return findPda(
+      [Buffer.from("proposal"), issuerIdBytes, idBytes],
+      program.programId
+    );
+  } catch (error) {
+    logTransactionError(error);
+    throw error;
+  }
+}
+
+// Approves a proposal as the given board member
+export async function approveProposal({
+  issuerId,
+  proposalId,
+  member,
+}: {
+  issuerId: string;
+  proposalId: string;
+  member: web3.Keypair;
+}) {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+    const issuerIdBytes = uuidToBytes16(issuerId);
+
+    const tx = await program.methods
+      .approveProposal()
+      .accounts({
+        // @ts-ignore
+        issuer: findPda(
+          [Buffer.from("issuer"), issuerIdBytes],
+          program.programId
+        ),
+        proposal: findPda(
+          [Buffer.from("proposal"), issuerIdBytes, uuidToBytes16(proposalId)],
+          program.programId
+        ),
+        member: member.publicKey,
+      })
+      .signers([member])
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx);
+  } catch (error) {
+    logTransactionError(error);
+    throw error;
+  }
+}
+
+// Performs an approved proposal's operation
+export async function executeProposal(issuerId: string, proposalId: string) {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+    const issuerIdBytes = uuidToBytes16(issuerId);
+    const proposalPda = findPda(
+      [Buffer.from("proposal"), issuerIdBytes, uuidToBytes16(proposalId)],
+      program.programId
+    );
+
+    const { action } = await program.account.proposal.fetch(proposalPda);
+
+    const tx = await program.methods
+      .executeProposal()
+      .accounts({
+        // @ts-ignore
+        issuer: findPda(
+          [Buffer.from("issuer"), issuerIdBytes],
+          program.programId
+        ),
+        proposal: proposalPda,
+        stockClass: action.createStockClass
+          ? findPda(
+              [Buffer.from("stock_class"), action.createStockClass.id],
+              program.programId
+            )
+          : null,
+        stockPlan: action.createStockPlan
+          ? findPda(
+              [Buffer.from("stock_plan"), action.createStockPlan.id],
+              program.programId
+            )
+          : null,
+        authority: program.provider.publicKey,
+      })
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx);
+    return getProposal(issuerId, proposalId);
+  } catch (error) {
+    logTransactionError(error);
+    throw error;
+  }
+}
+
+export async function getProposal(issuerId: string, proposalId: string) {
+  try {
+    const { program } = getProgram();
+    const proposal = await program.account.proposal.fetch(
+      findPda(
+        [
+          Buffer.from("proposal"),
+          uuidToBytes16(issuerId),
+          uuidToBytes16(proposalId),
+        ],
+        program.programId
+      )
+    );
+
+    return {
+      proposalId: bytes16ToUuid(proposal.id),
+      proposer: proposal.proposer.toString(),
+      action: Object.keys(proposal.action)[0],
+      approvals: proposal.approvals.map((member) => member.toString()),
+      boardApprovalDate: proposal.boardApprovalDate.toNumber(),
+      stockholderApprovalDate: proposal.stockholderApprovalDate.toNumber(),
+      executedAt: proposal.executedAt.toNumber(),
+    };
+  } catch (error) {
+    console.error("Error fetching proposal:", error);
+    throw error;
+  }
+}
@@ -1,11 +1,13 @@
 import { AnchorProvider, web3 } from "@coral-xyz/anchor";
 import {
   uuidToBytes16,
+  bytes16ToUuid,
   stringNumberToBN,
   getProgram,
   getProvider,
 } from "../helpers";
 import { SendTransactionError } from "@solana/web3.js";
+import BN from "bn.js";
 
 export interface CreateIssuerParams {
   id: string; // UUID
@@ -68,3 +70,244 @@ export async function getIssuer(issuerPda: string) {
     throw error;
   }
 }
+
+// Reads the issuer's running cap table totals through the program's return data
+export async function getCapTableSummary(issuerId: string) {
+  try {
+    const { program } = getProgram();
+    const issuerIdBytes = uuidToBytes16(issuerId);
+
+    const [issuerPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("issuer"), Buffer.from(issuerIdBytes)],
+      program.programId
+    );
+
+    const summary = await program.methods
+      .getCapTableSummary()
+      .accounts({ issuer: issuerPda })
+      .view();
+
+    const sharesOutstanding = summary.stockClasses.reduce(
+      (total, stockClass) => total.add(stockClass.sharesOutstanding),
+      new BN(0)
+    );
+
+    return {
+      sharesAuthorized: summary.sharesAuthorized.toString(),
+      stockClasses: summary.stockClasses.map((stockClass) => ({
+        stockClassId: bytes16ToUuid(stockClass.stockClassId),
+        sharesAuthorized: stockClass.sharesAuthorized.toString(),
+        sharesOutstanding: stockClass.sharesOutstanding.toString(),
+      })),
+      optionsOutstanding: summary.optionsOutstanding.toString(),
+      warrantsOutstanding: summary.warrantsOutstanding.toString(),
+      planPoolReserved: summary.planPoolReserved.toString(),
+      planPoolAvailable: summary.planPoolAvailable.toString(),
+      convertiblePrincipal: summary.convertiblePrincipal.toString(),
+      fullyDilutedShares: sharesOutstanding
+        .add(summary.optionsOutstanding)
+        .add(summary.warrantsOutstanding)
+        .add(summary.planPoolAvailable)
+        .toString(),
+    };
+  } catch (error) {
+    console.error("Error fetching cap table summary:", error);
+    throw error;
+  }
+}
+
+export interface LiquidationWaterfallParams {
+  issuerId: string; // UUID
+  exitValue: string; // String number like "25000000"
+  // Every option and warrant position, plus the stock positions to break the
+  // result down by stakeholder
+  positions: string[];
+  // Option security id (UUID) to exercise price, required for every option;
+  // the program does not record it
+  optionExercisePrices?: Record<string, string>;
+}
+
+// Splits an exit value over the issuer's stock classes and stakeholders
+// through the program's return data
+export async function getLiquidationWaterfall({
+  issuerId,
+  exitValue,
+  positions,
+  optionExercisePrices = {},
+}: LiquidationWaterfallParams) {
+  try {
+    const { program } = getProgram();
+    const issuerIdBytes = uuidToBytes16(issuerId);
+
+    const [issuerPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("issuer"), Buffer.from(issuerIdBytes)],
+      program.programId
+    );
+
+    const { stockClasses } = await getCapTableSummary(issuerId);
+    const stockClassPdas = await Promise.all(
+      stockClasses.map(async ({ stockClassId }) => {
+        const [pda] = await web3.PublicKey.findProgramAddress(
+          [
+            Buffer.from("stock_class"),
+            Buffer.from(uuidToBytes16(stockClassId)),
+          ],
+          program.programId
+        );
+        return pda;
+      })
+    );
+
+    const distribution = await program.methods
+      .getLiquidationWaterfall(
+        stringNumberToBN(exitValue),
+        Object.entries(optionExercisePrices).map(
+          ([securityId, exercisePrice]) => ({
+            securityId: uuidToBytes16(securityId),
+            exercisePrice: stringNumberToBN(exercisePrice),
+          })
+        )
+      )
+      .accounts({ issuer: issuerPda })
+      .remainingAccounts(
+        [
+          ...stockClassPdas,
+          ...positions.map((position) => new web3.PublicKey(position)),
+        ].map((pubkey) => ({ pubkey, isWritable: false, isSigner: false }))
+      )
+      .view();
+
+    return {
+      exitValue: distribution.exitValue.toString(),
+      exerciseProceeds: distribution.exerciseProceeds.toString(),
+      classes: distribution.classes.map((stockClass) => ({
+        stockClassId: bytes16ToUuid(stockClass.stockClassId),
+        converted: stockClass.converted,
+        shares: stockClass.shares.toString(),
+        preference: stockClass.preference.toString(),
+        participation: stockClass.participation.toString(),
+      })),
+      stakeholders: distribution.stakeholders.map((stakeholder) => ({
+        stakeholderId: bytes16ToUuid(stakeholder.stakeholderId),
+        proceeds: stakeholder.proceeds.toString(),
+      })),
+      unallocated: distribution.unallocated.toString(),
+    };
+  } catch (error) {
+    console.error("Error computing liquidation waterfall:", error);
+    throw error;
+  }
+}
+
+// Records the issuer's current cap table totals in an immutable snapshot
+export async function snapshotCapTable(issuerId: string, snapshotId: string) {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+    const issuerIdBytes = uuidToBytes16(issuerId);
+    const snapshotIdBytes = uuidToBytes16(snapshotId);
+
+    const [issuerPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("issuer"), Buffer.from(issuerIdBytes)],
+      program.programId
+    );
+    const [snapshotPda] = await web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("cap_table_snapshot"),
+        Buffer.from(issuerIdBytes),
+        Buffer.from(snapshotIdBytes),
+      ],
+      program.programId
+    );
+
+    const tx = await program.methods
+      .snapshotCapTable(snapshotIdBytes)
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        authority: program.provider.publicKey,
+      })
+      .rpc();
+
+    const confirmedTx = await provider.connection.confirmTransaction(tx);
+    const slot = confirmedTx.context.slot;
+    return { publicKey: snapshotPda.toString(), slot };
+  } catch (error) {
+    console.error("Error taking cap table snapshot:", error);
+    throw error;
+  }
+}
+
+export async function getCapTableSnapshot(
+  issuerId: string,
+  snapshotId: string
+) {
+  try {
+    const { program } = getProgram();
+    const [snapshotPda] = await web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("cap_table_snapshot"),
+        Buffer.from(uuidToBytes16(issuerId)),
+        Buffer.from(uuidToBytes16(snapshotId)),
+      ],
+      program.programId
+    );
+
+    const snapshot = await program.account.capTableSnapshot.fetch(snapshotPda);
+
+    return {
+      snapshotId: bytes16ToUuid(snapshot.id),
+      unixTimestamp: snapshot.unixTimestamp.toNumber(),
+      slot: snapshot.slot.toNumber(),
+      authority: snapshot.authority.toString(),
+      txLogSequence: snapshot.txLogSequence.toString(),
+      txLogHash: Buffer.from(snapshot.txLogHash).toString("hex"),
+      sharesAuthorized: snapshot.sharesAuthorized.toString(),
+      stockClasses: snapshot.stockClasses.map((stockClass) => ({
+        stockClassId: bytes16ToUuid(stockClass.stockClassId),
+        sharesAuthorized: stockClass.sharesAuthorized.toString(),
+        sharesOutstanding: stockClass.sharesOutstanding.toString(),
+      })),
+      optionsOutstanding: snapshot.optionsOutstanding.toString(),
+      warrantsOutstanding: snapshot.warrantsOutstanding.toString(),
+      planPoolReserved: snapshot.planPoolReserved.toString(),
+      planPoolGranted: snapshot.planPoolGranted.toString(),
+      planPoolAvailable: snapshot.planPoolAvailable.toString(),
+      convertiblePrincipal: snapshot.convertiblePrincipal.toString(),
+    };
+  } catch (error) {
+    console.error("Error fetching cap table snapshot:", error);
+    throw error;
+  }
+}
+
+// Resolves a security id to the position account registered for it
+export async function resolveSecurity(issuerId: string, securityId: string) {
+  try {
+    const { program } = getProgram();
+    const issuerIdBytes = uuidToBytes16(issuerId);
+    const securityIdBytes = uuidToBytes16(securityId);
+
+    const [registrationPda] = await web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("security"),
+        Buffer.from(issuerIdBytes),
+        Buffer.from(securityIdBytes),
+      ],
+      program.programId
+    );
+
+    const registration = await program.account.securityRegistration.fetch(
+      registrationPda
+    );
+
+    return {
+      securityId: bytes16ToUuid(registration.securityId),
+      positionType: Object.keys(registration.positionType)[0],
+      position: registration.position.toString(),
+    };
+  } catch (error) {
+    console.error("Error resolving security id:", error);
+    throw error;
+  }
+}
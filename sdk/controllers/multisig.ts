@@ -0,0 +1,246 @@
+import { getProvider, web3 } from "@coral-xyz/anchor";
+import { uuidToBytes16, getProgram } from "../helpers";
+import { SendTransactionError } from "@solana/web3.js";
+import BN from "bn.js";
+
+function findPda(
+  seeds: (Uint8Array | number[])[],
+  programId: web3.PublicKey
+) {
+  return web3.PublicKey.findProgramAddressSync(
+    seeds.map((seed) => Buffer.from(seed)),
+    programId
+  )[0];
+}
+
+function transactionPda(
+  issuerIdBytes: Uint8Array | number[],
+  nonce: BN,
+  programId: web3.PublicKey
+) {
+  return findPda(
+    [
+      Buffer.from("multisig_transaction"),
+      issuerIdBytes,
+      nonce.toArrayLike(Buffer, "le", 8),
+    ],
+    programId
+  );
+}
+
+function logTransactionError(error: unknown) {
+  if (error instanceof SendTransactionError) {
+    console.log("Transaction Error Details:");
+    console.log("Message:", error.message);
+    console.log("Logs:", error.logs);
+    console.log("Error:", error.toString());
+  }
+}
+
+// The multisig signer PDA, used as `authority` in instructions stored for
+// approval
+export function getMultisigSigner(issuerId: string): web3.PublicKey {
+  const { program } = getProgram();
+  return findPda(
+    [Buffer.from("multisig_signer"), uuidToBytes16(issuerId)],
+    program.programId
+  );
+}
+
+// Makes the issuer authority an M-of-N set; only the current issuer authority
+// can create it. Once created, issuer instructions need `threshold` member
+// signatures.
+export async function createMultisig({
+  issuerId,
+  members,
+  threshold,
+}: {
+  issuerId: string;
+  members: string[]; // Base58 public keys
+  threshold: number;
+}): Promise<web3.PublicKey> {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+    const issuerIdBytes = uuidToBytes16(issuerId);
+
+    const tx = await program.methods
+      .createMultisig(
+        members.map((member) => new web3.PublicKey(member)),
+        threshold
+      )
+      .accounts({
+        // @ts-ignore
+        issuer: findPda(
+          [Buffer.from("issuer"), issuerIdBytes],
+          program.programId
+        ),
+        authority: program.provider.publicKey,
+      })
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx);
+    return findPda([Buffer.from("multisig"), issuerIdBytes], program.programId);
+  } catch (error) {
+    logTransactionError(error);
+    throw error;
+  }
+}
+
+// Stores `instruction` for members to approve; the proposer's approval is
+// counted. Returns the transaction's nonce.
+export async function createMultisigTransaction({
+  issuerId,
+  instruction,
+  proposer,
+}: {
+  issuerId: string;
+  instruction: web3.TransactionInstruction;
+  proposer: web3.Keypair;
+}): Promise<number> {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+    const issuerIdBytes = uuidToBytes16(issuerId);
+    const multisigPda = findPda(
+      [Buffer.from("multisig"), issuerIdBytes],
+      program.programId
+    );
+    const { nonce } = await program.account.multisig.fetch(multisigPda);
+
+    const tx = await program.methods
+      .createMultisigTransaction(
+        instruction.keys.map((key) => ({
+          pubkey: key.pubkey,
+          isSigner: key.isSigner,
+          isWritable: key.isWritable,
+        })),
+        instruction.data
+      )
+      .accounts({
+        // @ts-ignore
+        issuer: findPda(
+          [Buffer.from("issuer"), issuerIdBytes],
+          program.programId
+        ),
+        transaction: transactionPda(issuerIdBytes, nonce, program.programId),
+        proposer: proposer.publicKey,
+      })
+      .signers([proposer])
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx);
+    return nonce.toNumber();
+  } catch (error) {
+    logTransactionError(error);
+    throw error;
+  }
+}
+
+// Approves a stored transaction as the given multisig member
+export async function approveMultisigTransaction({
+  issuerId,
+  nonce,
+  member,
+}: {
+  issuerId: string;
+  nonce: number;
+  member: web3.Keypair;
+}) {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+    const issuerIdBytes = uuidToBytes16(issuerId);
+
+    const tx = await program.methods
+      .approveMultisigTransaction()
+      .accounts({
+        // @ts-ignore
+        issuer: findPda(
+          [Buffer.from("issuer"), issuerIdBytes],
+          program.programId
+        ),
+        transaction: transactionPda(
+          issuerIdBytes,
+          new BN(nonce),
+          program.programId
+        ),
+        member: member.publicKey,
+      })
+      .signers([member])
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx);
+  } catch (error) {
+    logTransactionError(error);
+    throw error;
+  }
+}
+
+// Invokes an approved transaction. The multisig signer pays for accounts the
+// stored instruction creates, so fund it first.
+export async function executeMultisigTransaction(
+  issuerId: string,
+  nonce: number
+) {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+    const issuerIdBytes = uuidToBytes16(issuerId);
+    const transactionAddress = transactionPda(
+      issuerIdBytes,
+      new BN(nonce),
+      program.programId
+    );
+    const { accounts } = await program.account.multisigTransaction.fetch(
+      transactionAddress
+    );
+
+    const tx = await program.methods
+      .executeMultisigTransaction()
+      .accounts({
+        // @ts-ignore
+        issuer: findPda(
+          [Buffer.from("issuer"), issuerIdBytes],
+          program.programId
+        ),
+        transaction: transactionAddress,
+        ocpProgram: program.programId,
+        executor: program.provider.publicKey,
+      })
+      .remainingAccounts(
+        accounts.map((account) => ({
+          pubkey: account.pubkey,
+          isSigner: false,
+          isWritable: account.isWritable,
+        }))
+      )
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx);
+  } catch (error) {
+    logTransactionError(error);
+    throw error;
+  }
+}
+
+export async function getMultisig(issuerId: string) {
+  try {
+    const { program } = getProgram();
+    const multisig = await program.account.multisig.fetch(
+      findPda(
+        [Buffer.from("multisig"), uuidToBytes16(issuerId)],
+        program.programId
+      )
+    );
+
+    return {
+      members: multisig.members.map((member) => member.toString()),
+      threshold: multisig.threshold,
+      nonce: multisig.nonce.toNumber(),
+    };
+  } catch (error) {
+    console.error("Error fetching multisig:", error);
+    throw error;
+  }
+}
@@ -1,5 +1,5 @@
 import { getProvider, web3 } from "@coral-xyz/anchor";
-import { uuidToBytes16, getProgram } from "../helpers";
+import { uuidToBytes16, bytes16ToUuid, getProgram } from "../helpers";
 import { SendTransactionError } from "@solana/web3.js";
 
 export async function createStakeholder({
@@ -62,3 +62,27 @@ export async function getStakeholder(stakeholderPda: web3.PublicKey) {
     throw error;
   }
 }
+
+// Lists every active security held by the stakeholder with its position type
+export async function getStakeholderHoldings(stakeholderId: string) {
+  try {
+    const { program } = getProgram();
+    const stakeholderIdBytes = uuidToBytes16(stakeholderId);
+
+    const [holdingsPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("stakeholder_holdings"), Buffer.from(stakeholderIdBytes)],
+      program.programId
+    );
+
+    const stakeholderHoldings =
+      await program.account.stakeholderHoldings.fetch(holdingsPda);
+
+    return stakeholderHoldings.holdings.map((holding) => ({
+      securityId: bytes16ToUuid(holding.securityId),
+      positionType: Object.keys(holding.positionType)[0],
+    }));
+  } catch (error) {
+    console.error("Error fetching stakeholder holdings:", error);
+    throw error;
+  }
+}
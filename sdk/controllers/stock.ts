@@ -76,6 +76,101 @@ export async function issueStock({
   }
 }
 
+export async function convertStock({
+  issuerId,
+  securityId,
+  resultingSecurityId,
+  sourceStockClassId,
+  targetStockClassId,
+  stakeholderId,
+  quantity,
+}: {
+  issuerId: string;
+  securityId: string;
+  resultingSecurityId: string;
+  sourceStockClassId: string;
+  targetStockClassId: string;
+  stakeholderId: string;
+  quantity: string;
+}): Promise<web3.PublicKey> {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+
+    const issuerIdBytes = uuidToBytes16(issuerId);
+    const securityIdBytes = uuidToBytes16(securityId);
+    const resultingSecurityIdBytes = uuidToBytes16(resultingSecurityId);
+    const sourceStockClassIdBytes = uuidToBytes16(sourceStockClassId);
+    const targetStockClassIdBytes = uuidToBytes16(targetStockClassId);
+    const stakeholderIdBytes = uuidToBytes16(stakeholderId);
+    const quantityBN = stringNumberToBN(quantity);
+
+    // Find PDAs
+    const [issuerPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("issuer"), Buffer.from(issuerIdBytes)],
+      program.programId
+    );
+
+    const [sourceStockClassPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("stock_class"), Buffer.from(sourceStockClassIdBytes)],
+      program.programId
+    );
+
+    const [targetStockClassPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("stock_class"), Buffer.from(targetStockClassIdBytes)],
+      program.programId
+    );
+
+    const [stakeholderPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("stakeholder"), Buffer.from(stakeholderIdBytes)],
+      program.programId
+    );
+
+    const [sourcePositionPda] = await web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("stock_position"),
+        Buffer.from(stakeholderIdBytes),
+        Buffer.from(securityIdBytes),
+      ],
+      program.programId
+    );
+
+    const [resultingPositionPda] = await web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("stock_position"),
+        Buffer.from(stakeholderIdBytes),
+        Buffer.from(resultingSecurityIdBytes),
+      ],
+      program.programId
+    );
+
+    const tx = await program.methods
+      .convertStock(resultingSecurityIdBytes, quantityBN)
+      .accounts({
+        issuer: issuerPda,
+        stakeholder: stakeholderPda,
+        sourceStockClass: sourceStockClassPda,
+        targetStockClass: targetStockClassPda,
+        // @ts-ignore
+        sourcePosition: sourcePositionPda,
+        resultingPosition: resultingPositionPda,
+        authority: program.provider.publicKey,
+      })
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx);
+    return resultingPositionPda;
+  } catch (error) {
+    if (error instanceof SendTransactionError) {
+      console.log("Transaction Error Details:");
+      console.log("Message:", error.message);
+      console.log("Logs:", error.logs);
+      console.log("Error:", error.toString());
+    }
+    throw error;
+  }
+}
+
 export async function getStockPosition(positionPda: web3.PublicKey) {
   try {
     const { program } = getProgram();
@@ -1,6 +1,43 @@
 import { getProvider, web3 } from "@coral-xyz/anchor";
 import { uuidToBytes16, stringNumberToBN, getProgram } from "../helpers";
 import { SendTransactionError } from "@solana/web3.js";
+import BN from "bn.js";
+
+export type StockClassTermsInput = {
+  kind: "common" | "preferred";
+  seniority?: number;
+  liquidationPreferenceMultiple?: string; // e.g. "1" for 1x
+  isParticipating?: boolean;
+  participationCapMultiple?: string; // "0" for uncapped
+  conversionRatio?: string; // Common shares per share converted
+  convertsToStockClassId?: string;
+  votesPerShare?: string;
+  antiDilutionType?:
+    | "none"
+    | "fullRatchet"
+    | "broadBasedWeightedAverage"
+    | "narrowBasedWeightedAverage";
+};
+
+function toStockClassTerms(terms: StockClassTermsInput) {
+  return {
+    kind: { [terms.kind]: {} } as any,
+    seniority: terms.seniority ?? 0,
+    liquidationPreferenceMultiple: stringNumberToBN(
+      terms.liquidationPreferenceMultiple ?? "0"
+    ),
+    isParticipating: terms.isParticipating ?? false,
+    participationCapMultiple: stringNumberToBN(
+      terms.participationCapMultiple ?? "0"
+    ),
+    conversionRatio: stringNumberToBN(terms.conversionRatio ?? "0"),
+    convertsToStockClassId: terms.convertsToStockClassId
+      ? uuidToBytes16(terms.convertsToStockClassId)
+      : Array(16).fill(0),
+    votesPerShare: stringNumberToBN(terms.votesPerShare ?? "1"),
+    antiDilutionType: { [terms.antiDilutionType ?? "none"]: {} } as any,
+  };
+}
 
 export async function createStockClass({
   id,
@@ -8,12 +45,14 @@ export async function createStockClass({
   classType,
   pricePerShare,
   sharesAuthorized,
+  terms = { kind: "common" },
 }: {
   id: string;
   issuerId: string;
   classType: string;
   pricePerShare: string;
   sharesAuthorized: string;
+  terms?: StockClassTermsInput;
 }): Promise<web3.PublicKey> {
   try {
     const { program } = getProgram();
@@ -36,7 +75,13 @@ export async function createStockClass({
     );
 
     const tx = await program.methods
-      .createStockClass(idBytes, classType, pricePerShareBN, sharesAuthorizedBN)
+      .createStockClass(
+        idBytes,
+        classType,
+        pricePerShareBN,
+        sharesAuthorizedBN,
+        toStockClassTerms(terms)
+      )
       .accounts({
         // @ts-ignore
         issuer: issuerPda,
@@ -60,6 +105,173 @@ export async function createStockClass({
   }
 }
 
+export async function splitStockClass({
+  id,
+  issuerId,
+  numerator,
+  denominator,
+  roundDownFractionalShares = false,
+}: {
+  id: string;
+  issuerId: string;
+  numerator: number;
+  denominator: number;
+  roundDownFractionalShares?: boolean;
+}): Promise<web3.PublicKey> {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+
+    const idBytes = uuidToBytes16(id);
+    const issuerIdBytes = uuidToBytes16(issuerId);
+
+    // Find PDAs
+    const [issuerPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("issuer"), Buffer.from(issuerIdBytes)],
+      program.programId
+    );
+
+    const [stockClassPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("stock_class"), Buffer.from(idBytes)],
+      program.programId
+    );
+
+    const tx = await program.methods
+      .splitStockClass(
+        new BN(numerator),
+        new BN(denominator),
+        roundDownFractionalShares ? { roundDown: {} } : { retain: {} }
+      )
+      .accounts({
+        issuer: issuerPda,
+        stockClass: stockClassPda,
+        authority: program.provider.publicKey,
+      })
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx);
+    return stockClassPda;
+  } catch (error) {
+    if (error instanceof SendTransactionError) {
+      console.log("Transaction Error Details:");
+      console.log("Message:", error.message);
+      console.log("Logs:", error.logs);
+      console.log("Error:", error.toString());
+    }
+    throw error;
+  }
+}
+
+// Brings the given stock positions up to date with the class's splits
+export async function applyStockClassSplit({
+  id,
+  issuerId,
+  positionPdas,
+}: {
+  id: string;
+  issuerId: string;
+  positionPdas: web3.PublicKey[];
+}): Promise<void> {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+
+    const idBytes = uuidToBytes16(id);
+    const issuerIdBytes = uuidToBytes16(issuerId);
+
+    // Find PDAs
+    const [issuerPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("issuer"), Buffer.from(issuerIdBytes)],
+      program.programId
+    );
+
+    const [stockClassPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("stock_class"), Buffer.from(idBytes)],
+      program.programId
+    );
+
+    const tx = await program.methods
+      .applyStockClassSplit()
+      .accounts({
+        issuer: issuerPda,
+        stockClass: stockClassPda,
+      })
+      .remainingAccounts(
+        positionPdas.map((pubkey) => ({
+          pubkey,
+          isWritable: true,
+          isSigner: false,
+        }))
+      )
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx);
+  } catch (error) {
+    if (error instanceof SendTransactionError) {
+      console.log("Transaction Error Details:");
+      console.log("Message:", error.message);
+      console.log("Logs:", error.logs);
+      console.log("Error:", error.toString());
+    }
+    throw error;
+  }
+}
+
+// Broad-based weighted average takes the outstanding share base from the
+// issuer's cap table summary
+export async function recordDilutiveIssuance({
+  id,
+  issuerId,
+  newIssuePrice,
+  newSharesIssued,
+}: {
+  id: string;
+  issuerId: string;
+  newIssuePrice: string;
+  newSharesIssued: string;
+}): Promise<web3.PublicKey> {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+
+    const idBytes = uuidToBytes16(id);
+    const issuerIdBytes = uuidToBytes16(issuerId);
+    const newIssuePriceBN = stringNumberToBN(newIssuePrice);
+    const newSharesIssuedBN = stringNumberToBN(newSharesIssued);
+
+    // Find PDAs
+    const [issuerPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("issuer"), Buffer.from(issuerIdBytes)],
+      program.programId
+    );
+
+    const [stockClassPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("stock_class"), Buffer.from(idBytes)],
+      program.programId
+    );
+
+    const tx = await program.methods
+      .recordDilutiveIssuance(newIssuePriceBN, newSharesIssuedBN)
+      .accounts({
+        issuer: issuerPda,
+        stockClass: stockClassPda,
+        authority: program.provider.publicKey,
+      })
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx);
+    return stockClassPda;
+  } catch (error) {
+    if (error instanceof SendTransactionError) {
+      console.log("Transaction Error Details:");
+      console.log("Message:", error.message);
+      console.log("Logs:", error.logs);
+      console.log("Error:", error.toString());
+    }
+    throw error;
+  }
+}
+
 export async function getStockClass(stockClassPda: web3.PublicKey) {
   try {
     const { program } = getProgram();
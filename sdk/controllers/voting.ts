@@ -0,0 +1,306 @@
+import { getProvider, web3 } from "@coral-xyz/anchor";
+import { uuidToBytes16, bytes16ToUuid, getProgram } from "../helpers";
+import { SendTransactionError } from "@solana/web3.js";
+import BN from "bn.js";
+
+function findPda(
+  seeds: (Uint8Array | number[])[],
+  programId: web3.PublicKey
+) {
+  return web3.PublicKey.findProgramAddressSync(
+    seeds.map((seed) => Buffer.from(seed)),
+    programId
+  )[0];
+}
+
+function logTransactionError(error: unknown) {
+  if (error instanceof SendTransactionError) {
+    console.log("Transaction Error Details:");
+    console.log("Message:", error.message);
+    console.log("Logs:", error.logs);
+    console.log("Error:", error.toString());
+  }
+}
+
+const readonly = (pubkey: web3.PublicKey) => ({
+  pubkey,
+  isSigner: false,
+  isWritable: false,
+});
+
+// Registers the wallet a stakeholder signs votes with
+export async function setStakeholderWallet({
+  issuerId,
+  stakeholderId,
+  wallet,
+}: {
+  issuerId: string;
+  stakeholderId: string;
+  wallet: string; // Base58 public key
+}) {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+
+    const tx = await program.methods
+      .setStakeholderWallet(new web3.PublicKey(wallet))
+      .accounts({
+        // @ts-ignore
+        issuer: findPda(
+          [Buffer.from("issuer"), uuidToBytes16(issuerId)],
+          program.programId
+        ),
+        stakeholder: findPda(
+          [Buffer.from("stakeholder"), uuidToBytes16(stakeholderId)],
+          program.programId
+        ),
+        authority: program.provider.publicKey,
+      })
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx);
+  } catch (error) {
+    logTransactionError(error);
+    throw error;
+  }
+}
+
+// Puts a resolution to the stockholders. `stockClassIds` must list every
+// stock class of the issuer.
+export async function createResolution({
+  id,
+  issuerId,
+  recordDate,
+  votingDeadline,
+  separateClassIds = [],
+  stockClassIds,
+}: {
+  id: string;
+  issuerId: string;
+  recordDate: number; // Unix timestamp
+  votingDeadline: number; // Unix timestamp
+  separateClassIds?: string[];
+  stockClassIds: string[];
+}): Promise<web3.PublicKey> {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+    const idBytes = uuidToBytes16(id);
+    const issuerIdBytes = uuidToBytes16(issuerId);
+
+    const tx = await program.methods
+      .createResolution(
+        idBytes,
+        new BN(recordDate),
+        new BN(votingDeadline),
+        separateClassIds.map(uuidToBytes16)
+      )
+      .accounts({
+        // @ts-ignore
+        issuer: findPda(
+          [Buffer.from("issuer"), issuerIdBytes],
+          program.programId
+        ),
+        authority: program.provider.publicKey,
+      })
+      .remainingAccounts(
+        stockClassIds.map((stockClassId) =>
+          readonly(
+            findPda(
+              [Buffer.from("stock_class"), uuidToBytes16(stockClassId)],
+              program.programId
+            )
+          )
+        )
+      )
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx);
+    return findPda(
+      [Buffer.from("resolution"), issuerIdBytes, idBytes],
+      program.programId
+    );
+  } catch (error) {
+    logTransactionError(error);
+    throw error;
+  }
+}
+
+// Records a stakeholder's stock positions before the record date. Run it
+// again if their holdings change before then.
+export async function recordVotingPower({
+  issuerId,
+  resolutionId,
+  stakeholderId,
+}: {
+  issuerId: string;
+  resolutionId: string;
+  stakeholderId: string;
+}) {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+    const issuerIdBytes = uuidToBytes16(issuerId);
+    const resolutionIdBytes = uuidToBytes16(resolutionId);
+    const stakeholderIdBytes = uuidToBytes16(stakeholderId);
+    const holdingsPda = findPda(
+      [Buffer.from("stakeholder_holdings"), stakeholderIdBytes],
+      program.programId
+    );
+
+    const { holdings } = await program.account.stakeholderHoldings.fetch(
+      holdingsPda
+    );
+    const positionPdas = holdings
+      .filter((holding) => holding.positionType.stock)
+      .map((holding) =>
+        findPda(
+          [
+            Buffer.from("stock_position"),
+            stakeholderIdBytes,
+            holding.securityId,
+          ],
+          program.programId
+        )
+      );
+    const positions =
+      await program.account.stockActivePosition.fetchMultiple(positionPdas);
+    const stockClassPdas = positions.map((position) =>
+      findPda(
+        [Buffer.from("stock_class"), position.stockClassId],
+        program.programId
+      )
+    );
+
+    const tx = await program.methods
+      .recordVotingPower()
+      .accounts({
+        // @ts-ignore
+        issuer: findPda(
+          [Buffer.from("issuer"), issuerIdBytes],
+          program.programId
+        ),
+        resolution: findPda(
+          [Buffer.from("resolution"), issuerIdBytes, resolutionIdBytes],
+          program.programId
+        ),
+        stakeholderHoldings: holdingsPda,
+        voterRecord: findPda(
+          [
+            Buffer.from("voter_record"),
+            issuerIdBytes,
+            resolutionIdBytes,
+            stakeholderIdBytes,
+          ],
+          program.programId
+        ),
+        cranker: program.provider.publicKey,
+      })
+      .remainingAccounts([...stockClassPdas, ...positionPdas].map(readonly))
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx);
+  } catch (error) {
+    logTransactionError(error);
+    throw error;
+  }
+}
+
+// Votes as the stakeholder, signed by their registered wallet
+export async function castVote({
+  issuerId,
+  resolutionId,
+  stakeholderId,
+  choice,
+  wallet,
+}: {
+  issuerId: string;
+  resolutionId: string;
+  stakeholderId: string;
+  choice: "for" | "against" | "abstain";
+  wallet: web3.Keypair;
+}) {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+    const issuerIdBytes = uuidToBytes16(issuerId);
+    const resolutionIdBytes = uuidToBytes16(resolutionId);
+    const stakeholderIdBytes = uuidToBytes16(stakeholderId);
+
+    const tx = await program.methods
+      // @ts-ignore
+      .castVote({ [choice]: {} })
+      .accounts({
+        // @ts-ignore
+        issuer: findPda(
+          [Buffer.from("issuer"), issuerIdBytes],
+          program.programId
+        ),
+        resolution: findPda(
+          [Buffer.from("resolution"), issuerIdBytes, resolutionIdBytes],
+          program.programId
+        ),
+        voterRecord: findPda(
+          [
+            Buffer.from("voter_record"),
+            issuerIdBytes,
+            resolutionIdBytes,
+            stakeholderIdBytes,
+          ],
+          program.programId
+        ),
+        stakeholderHoldings: findPda(
+          [Buffer.from("stakeholder_holdings"), stakeholderIdBytes],
+          program.programId
+        ),
+        stakeholderWallet: findPda(
+          [
+            Buffer.from("stakeholder_wallet"),
+            issuerIdBytes,
+            stakeholderIdBytes,
+          ],
+          program.programId
+        ),
+        wallet: wallet.publicKey,
+      })
+      .signers([wallet])
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx);
+  } catch (error) {
+    logTransactionError(error);
+    throw error;
+  }
+}
+
+export async function getResolution(issuerId: string, resolutionId: string) {
+  try {
+    const { program } = getProgram();
+    const resolution = await program.account.resolution.fetch(
+      findPda(
+        [
+          Buffer.from("resolution"),
+          uuidToBytes16(issuerId),
+          uuidToBytes16(resolutionId),
+        ],
+        program.programId
+      )
+    );
+
+    return {
+      resolutionId: bytes16ToUuid(resolution.id),
+      recordDate: resolution.recordDate.toNumber(),
+      votingDeadline: resolution.votingDeadline.toNumber(),
+      tallies: resolution.tallies.map((tally) => ({
+        stockClassId: bytes16ToUuid(tally.stockClassId),
+        separateVote: tally.separateVote,
+        votesFor: tally.votesFor.toString(),
+        votesAgainst: tally.votesAgainst.toString(),
+        votesAbstain: tally.votesAbstain.toString(),
+      })),
+    };
+  } catch (error) {
+    console.error("Error fetching resolution:", error);
+    throw error;
+  }
+}
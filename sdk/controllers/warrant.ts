@@ -1,8 +1,201 @@
 import { getProvider, web3 } from "@coral-xyz/anchor";
 import { uuidToBytes16, stringNumberToBN, getProgram } from "../helpers";
 import { SendTransactionError } from "@solana/web3.js";
+import BN from "bn.js";
+
+export type WarrantExerciseTrigger =
+  | "electiveAtWill"
+  | "electiveInRange"
+  | "electiveOnCondition";
 
 export async function issueWarrant({
+  issuerId,
+  securityId,
+  stakeholderId,
+  stockClassId,
+  quantity,
+  exercisePrice,
+  expirationDate,
+  exerciseTrigger = "electiveAtWill",
+}: {
+  issuerId: string;
+  securityId: string;
+  stakeholderId: string;
+  stockClassId: string;
+  quantity: string;
+  exercisePrice: string;
+  expirationDate: number; // Unix timestamp in seconds
+  exerciseTrigger?: WarrantExerciseTrigger;
+}): Promise<web3.PublicKey> {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+
+    const issuerIdBytes = uuidToBytes16(issuerId);
+    const securityIdBytes = uuidToBytes16(securityId);
+    const stakeholderIdBytes = uuidToBytes16(stakeholderId);
+    const stockClassIdBytes = uuidToBytes16(stockClassId);
+    const quantityBN = stringNumberToBN(quantity);
+    const exercisePriceBN = stringNumberToBN(exercisePrice);
+
+    // Find PDAs
+    const [issuerPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("issuer"), Buffer.from(issuerIdBytes)],
+      program.programId
+    );
+
+    const [stakeholderPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("stakeholder"), Buffer.from(stakeholderIdBytes)],
+      program.programId
+    );
+
+    const [stockClassPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("stock_class"), Buffer.from(stockClassIdBytes)],
+      program.programId
+    );
+
+    const [warrantPositionPda] = await web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("warrant_position"),
+        Buffer.from(stakeholderIdBytes),
+        Buffer.from(securityIdBytes),
+      ],
+      program.programId
+    );
+
+    const tx = await program.methods
+      .issueWarrant(
+        securityIdBytes,
+        quantityBN,
+        exercisePriceBN,
+        new BN(expirationDate),
+        { [exerciseTrigger]: {} } as any
+      )
+      .accounts({
+        issuer: issuerPda,
+        stakeholder: stakeholderPda,
+        stockClass: stockClassPda,
+        authority: program.provider.publicKey,
+      })
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx);
+    return warrantPositionPda;
+  } catch (error) {
+    if (error instanceof SendTransactionError) {
+      console.log("Transaction Error Details:");
+      console.log("Message:", error.message);
+      console.log("Logs:", error.logs);
+      console.log("Error:", error.toString());
+    }
+    throw error;
+  }
+}
+
+export async function exerciseWarrant({
+  issuerId,
+  warrantSecurityId,
+  resultingStockSecurityId,
+  stockClassId,
+  stakeholderId,
+  quantity,
+  fairMarketValue,
+}: {
+  issuerId: string;
+  warrantSecurityId: string;
+  resultingStockSecurityId: string;
+  stockClassId: string;
+  stakeholderId: string;
+  quantity: string;
+  fairMarketValue?: string; // Set for cashless (net) exercise
+}): Promise<web3.PublicKey> {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+
+    const issuerIdBytes = uuidToBytes16(issuerId);
+    const warrantSecurityIdBytes = uuidToBytes16(warrantSecurityId);
+    const resultingStockSecurityIdBytes = uuidToBytes16(
+      resultingStockSecurityId
+    );
+    const stockClassIdBytes = uuidToBytes16(stockClassId);
+    const stakeholderIdBytes = uuidToBytes16(stakeholderId);
+    const quantityBN = stringNumberToBN(quantity);
+    const fairMarketValueBN = fairMarketValue
+      ? stringNumberToBN(fairMarketValue)
+      : null;
+
+    // Find PDAs
+    const [issuerPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("issuer"), Buffer.from(issuerIdBytes)],
+      program.programId
+    );
+
+    const [stakeholderPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("stakeholder"), Buffer.from(stakeholderIdBytes)],
+      program.programId
+    );
+
+    const [stockClassPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("stock_class"), Buffer.from(stockClassIdBytes)],
+      program.programId
+    );
+
+    const [warrantPositionPda] = await web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("warrant_position"),
+        Buffer.from(stakeholderIdBytes),
+        Buffer.from(warrantSecurityIdBytes),
+      ],
+      program.programId
+    );
+
+    const [stockPositionPda] = await web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("stock_position"),
+        Buffer.from(stakeholderIdBytes),
+        Buffer.from(resultingStockSecurityIdBytes),
+      ],
+      program.programId
+    );
+
+    // Rent is refunded to whoever paid for the warrant account if it closes
+    const { rentPayer } = await program.account.warrantActivePosition.fetch(
+      warrantPositionPda
+    );
+
+    const tx = await program.methods
+      .exerciseWarrant(
+        resultingStockSecurityIdBytes,
+        quantityBN,
+        fairMarketValueBN
+      )
+      .accounts({
+        issuer: issuerPda,
+        stakeholder: stakeholderPda,
+        stockClass: stockClassPda,
+        // @ts-ignore
+        warrantPosition: warrantPositionPda,
+        stockPosition: stockPositionPda,
+        rentPayer,
+        authority: program.provider.publicKey,
+      })
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx);
+    return stockPositionPda;
+  } catch (error) {
+    if (error instanceof SendTransactionError) {
+      console.log("Transaction Error Details:");
+      console.log("Message:", error.message);
+      console.log("Logs:", error.logs);
+      console.log("Error:", error.toString());
+    }
+    throw error;
+  }
+}
+
+export async function cancelWarrant({
   issuerId,
   securityId,
   stakeholderId,
@@ -42,11 +235,19 @@ export async function issueWarrant({
       program.programId
     );
 
+    // Rent is refunded to whoever paid for the warrant account if it closes
+    const { rentPayer } = await program.account.warrantActivePosition.fetch(
+      warrantPositionPda
+    );
+
     const tx = await program.methods
-      .issueWarrant(securityIdBytes, quantityBN)
+      .cancelWarrant(quantityBN)
       .accounts({
         issuer: issuerPda,
         stakeholder: stakeholderPda,
+        // @ts-ignore
+        warrantPosition: warrantPositionPda,
+        rentPayer,
         authority: program.provider.publicKey,
       })
       .rpc();
@@ -64,6 +265,158 @@ export async function issueWarrant({
   }
 }
 
+export async function expireWarrant({
+  issuerId,
+  securityId,
+  stakeholderId,
+}: {
+  issuerId: string;
+  securityId: string;
+  stakeholderId: string;
+}): Promise<web3.PublicKey> {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+
+    const issuerIdBytes = uuidToBytes16(issuerId);
+    const securityIdBytes = uuidToBytes16(securityId);
+    const stakeholderIdBytes = uuidToBytes16(stakeholderId);
+
+    // Find PDAs
+    const [issuerPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("issuer"), Buffer.from(issuerIdBytes)],
+      program.programId
+    );
+
+    const [warrantPositionPda] = await web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("warrant_position"),
+        Buffer.from(stakeholderIdBytes),
+        Buffer.from(securityIdBytes),
+      ],
+      program.programId
+    );
+
+    // Rent is refunded to whoever paid for the warrant account
+    const position = await program.account.warrantActivePosition.fetch(
+      warrantPositionPda
+    );
+
+    const tx = await program.methods
+      .expireWarrant()
+      .accounts({
+        issuer: issuerPda,
+        warrantPosition: warrantPositionPda,
+        rentPayer: position.rentPayer,
+        cranker: program.provider.publicKey,
+      })
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx);
+    return warrantPositionPda;
+  } catch (error) {
+    if (error instanceof SendTransactionError) {
+      console.log("Transaction Error Details:");
+      console.log("Message:", error.message);
+      console.log("Logs:", error.logs);
+      console.log("Error:", error.toString());
+    }
+    throw error;
+  }
+}
+
+export async function transferWarrant({
+  issuerId,
+  securityId,
+  resultingSecurityId,
+  stakeholderId,
+  newStakeholderId,
+  quantity,
+}: {
+  issuerId: string;
+  securityId: string;
+  resultingSecurityId: string;
+  stakeholderId: string;
+  newStakeholderId: string;
+  quantity: string;
+}): Promise<web3.PublicKey> {
+  try {
+    const { program } = getProgram();
+    const provider = getProvider();
+
+    const issuerIdBytes = uuidToBytes16(issuerId);
+    const securityIdBytes = uuidToBytes16(securityId);
+    const resultingSecurityIdBytes = uuidToBytes16(resultingSecurityId);
+    const stakeholderIdBytes = uuidToBytes16(stakeholderId);
+    const newStakeholderIdBytes = uuidToBytes16(newStakeholderId);
+    const quantityBN = stringNumberToBN(quantity);
+
+    // Find PDAs
+    const [issuerPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("issuer"), Buffer.from(issuerIdBytes)],
+      program.programId
+    );
+
+    const [stakeholderPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("stakeholder"), Buffer.from(stakeholderIdBytes)],
+      program.programId
+    );
+
+    const [newStakeholderPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("stakeholder"), Buffer.from(newStakeholderIdBytes)],
+      program.programId
+    );
+
+    const [warrantPositionPda] = await web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("warrant_position"),
+        Buffer.from(stakeholderIdBytes),
+        Buffer.from(securityIdBytes),
+      ],
+      program.programId
+    );
+
+    const [newWarrantPositionPda] = await web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("warrant_position"),
+        Buffer.from(newStakeholderIdBytes),
+        Buffer.from(resultingSecurityIdBytes),
+      ],
+      program.programId
+    );
+
+    // Rent is refunded to whoever paid for the warrant account if it closes
+    const { rentPayer } = await program.account.warrantActivePosition.fetch(
+      warrantPositionPda
+    );
+
+    const tx = await program.methods
+      .transferWarrant(resultingSecurityIdBytes, quantityBN)
+      .accounts({
+        issuer: issuerPda,
+        stakeholder: stakeholderPda,
+        newStakeholder: newStakeholderPda,
+        // @ts-ignore
+        warrantPosition: warrantPositionPda,
+        newWarrantPosition: newWarrantPositionPda,
+        rentPayer,
+        authority: program.provider.publicKey,
+      })
+      .rpc();
+
+    await provider.connection.confirmTransaction(tx);
+    return newWarrantPositionPda;
+  } catch (error) {
+    if (error instanceof SendTransactionError) {
+      console.log("Transaction Error Details:");
+      console.log("Message:", error.message);
+      console.log("Logs:", error.logs);
+      console.log("Error:", error.toString());
+    }
+    throw error;
+  }
+}
+
 export async function getWarrantPosition(positionPda: web3.PublicKey) {
   try {
     const { program } = getProgram();
@@ -74,8 +427,13 @@ export async function getWarrantPosition(positionPda: web3.PublicKey) {
     // Convert raw data to readable format
     const decodedPosition = {
       stakeholderId: Buffer.from(position.stakeholderId).toString("hex"),
+      stockClassId: Buffer.from(position.stockClassId).toString("hex"),
       securityId: Buffer.from(position.securityId).toString("hex"),
       quantity: position.quantity.toString(),
+      exercisePrice: position.exercisePrice.toString(),
+      expirationDate: position.expirationDate.toNumber(),
+      exerciseTrigger: Object.keys(position.exerciseTrigger)[0],
+      rentPayer: position.rentPayer.toString(),
     };
 
     console.log("Warrant position decoded data:", decodedPosition);
@@ -173,7 +173,7 @@ describe("Convertible Tests", () => {
     );
 
     const eventPromise = new Promise((resolve, reject) => {
-      const listener = program.addEventListener("txCreated", (event) => {
+      const listener = program.addEventListener("txCreatedV2", (event) => {
         program.removeEventListener(listener);
         resolve(event);
       });
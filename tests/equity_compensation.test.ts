@@ -2,7 +2,7 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { OcpSolana } from "../target/types/ocp_solana";
 import { expect } from "chai";
-import { isTxType } from "./helpers";
+import { isTxType, commonStockClassTerms } from "./helpers";
 
 describe("Equity Compensation Tests", () => {
   const provider = anchor.AnchorProvider.env();
@@ -77,7 +77,8 @@ describe("Equity Compensation Tests", () => {
         Array.from(stockClassId),
         "COMMON",
         sharePrice,
-        initialShares
+        initialShares,
+        commonStockClassTerms()
       )
       .accounts({
         issuer: issuerPda,
@@ -194,16 +195,32 @@ describe("Equity Compensation Tests", () => {
         issuer: issuerPda,
         equityPosition: equityPositionPda,
         stockPosition: stockPositionPda,
+        rentPayer: authority.publicKey,
         authority: authority.publicKey,
       })
       .rpc();
 
-    // Verify final equity position quantity
+    // A fully exercised option is closed and leaves the holdings
     const finalPosition =
-      await program.account.equityCompensationActivePosition.fetch(
+      await program.account.equityCompensationActivePosition.fetchNullable(
         equityPositionPda
       );
-    expect(finalPosition.quantity.eq(new anchor.BN(0))).to.be.true;
+    expect(finalPosition).to.be.null;
+
+    const [holdingsPda] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("stakeholder_holdings"), Buffer.from(stakeholderId)],
+      program.programId
+    );
+    const holdings = await program.account.stakeholderHoldings.fetch(
+      holdingsPda
+    );
+    expect(
+      holdings.holdings.some((holding) =>
+        Buffer.from(holding.securityId).equals(
+          Buffer.from(equityCompSecurityId)
+        )
+      )
+    ).to.be.false;
   });
 
   it("Fails when attempting to exercise with mismatched quantities", async () => {
@@ -268,6 +285,7 @@ describe("Equity Compensation Tests", () => {
           issuer: issuerPda,
           equityPosition: equityPositionPda,
           stockPosition: stockPositionPda,
+          rentPayer: authority.publicKey,
           authority: authority.publicKey,
         })
         .rpc();
@@ -292,7 +310,7 @@ describe("Equity Compensation Tests", () => {
     );
 
     const eventPromise = new Promise((resolve, reject) => {
-      const listener = program.addEventListener("txCreated", (event) => {
+      const listener = program.addEventListener("txCreatedV2", (event) => {
         program.removeEventListener(listener);
         resolve(event);
       });
@@ -395,7 +413,7 @@ describe("Equity Compensation Tests", () => {
 
     // Now set up event listener for exercise
     const eventPromise = new Promise((resolve, reject) => {
-      const listener = program.addEventListener("txCreated", (event) => {
+      const listener = program.addEventListener("txCreatedV2", (event) => {
         program.removeEventListener(listener);
         resolve(event);
       });
@@ -414,6 +432,7 @@ describe("Equity Compensation Tests", () => {
         equityPosition: equityPositionPda,
         // @ts-ignore
         stockPosition: stockPositionPda,
+        rentPayer: authority.publicKey,
         authority: authority.publicKey,
       })
       .rpc();
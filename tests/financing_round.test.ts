@@ -0,0 +1,417 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { OcpSolana } from "../target/types/ocp_solana";
+import { expect } from "chai";
+import { commonStockClassTerms, preferredStockClassTerms } from "./helpers";
+
+describe("Financing Round Tests", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.OcpSolana as Program<OcpSolana>;
+  const authority = provider.wallet;
+
+  // Test data
+  const issuerId = new Uint8Array(16).fill(80);
+  const commonId = new Uint8Array(16).fill(81);
+  const seriesAId = new Uint8Array(16).fill(82);
+  const founderId = new Uint8Array(16).fill(83);
+  const investorId = new Uint8Array(16).fill(84);
+  const safeHolderId = new Uint8Array(16).fill(85);
+  const founderSecurityId = new Uint8Array(16).fill(86);
+  const safeSecurityId = new Uint8Array(16).fill(87);
+  const investorSecurityId = new Uint8Array(16).fill(88);
+  const conversionSecurityId = new Uint8Array(16).fill(89);
+  const roundId = new Uint8Array(16).fill(90);
+  const emptyRoundId = new Uint8Array(16).fill(91);
+  const emptyRoundClassId = new Uint8Array(16).fill(92);
+  const otherRoundId = new Uint8Array(16).fill(93);
+  const otherRoundClassId = new Uint8Array(16).fill(94);
+  const otherConversionSecurityId = new Uint8Array(16).fill(95);
+  const otherIssuerId = new Uint8Array(16).fill(144);
+  const otherIssuerRoundId = new Uint8Array(16).fill(145);
+
+  // 8M common shares, a $1M SAFE at a 20% discount and $8M cap, and $2.5M
+  // of new money at a $10M pre-money
+  const commonQuantity = new anchor.BN("8000000000000");
+  const safeAmount = new anchor.BN("1000000000000");
+  const investmentAmount = new anchor.BN("2500000000000");
+  const preMoneyValuation = new anchor.BN("10000000000000");
+
+  // The SAFE converts at the discount: 1.09375 * 0.8 = 0.875 per share
+  const pricePerShare = 1093750;
+  const conversionPrice = 875000;
+  const investorShares = new anchor.BN("2285714285714");
+  const conversionShares = new anchor.BN("1142857142857");
+
+  let issuerPda: anchor.web3.PublicKey;
+  let roundPda: anchor.web3.PublicKey;
+
+  const pda = (seeds: (string | Uint8Array)[]) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      seeds.map((seed) => Buffer.from(seed)),
+      program.programId
+    )[0];
+
+  const openRound = (id: Uint8Array, stockClassId: Uint8Array) =>
+    program.methods
+      .openFinancingRound(
+        Array.from(id),
+        Array.from(stockClassId),
+        "SERIES_A",
+        preferredStockClassTerms(commonId),
+        preMoneyValuation,
+        new anchor.BN(0)
+      )
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        stockPlan: null,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+  const closeRound = (id: Uint8Array, stockClassId: Uint8Array) =>
+    program.methods
+      .closeFinancingRound()
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        round: pda(["priced_round", issuerId, id]),
+        stockClass: pda(["stock_class", stockClassId]),
+        stockPlan: null,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+  before(async () => {
+    issuerPda = pda(["issuer", issuerId]);
+    roundPda = pda(["priced_round", issuerId, roundId]);
+
+    await program.methods
+      .initializeIssuer(Array.from(issuerId), new anchor.BN("10000000000000"))
+      .accounts({ authority: authority.publicKey })
+      .rpc();
+
+    await program.methods
+      .createStockClass(
+        Array.from(commonId),
+        "COMMON",
+        new anchor.BN(1000000),
+        commonQuantity,
+        commonStockClassTerms()
+      )
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    for (const stakeholderId of [founderId, investorId, safeHolderId]) {
+      await program.methods
+        .createStakeholder(Array.from(stakeholderId))
+        .accounts({
+          // @ts-ignore
+          issuer: issuerPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    }
+
+    await program.methods
+      .issueStock(
+        Array.from(founderSecurityId),
+        commonQuantity,
+        new anchor.BN(1000000)
+      )
+      .accounts({
+        stockClass: pda(["stock_class", commonId]),
+        issuer: issuerPda,
+        stakeholder: pda(["stakeholder", founderId]),
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    await program.methods
+      .issueConvertible(Array.from(safeSecurityId), safeAmount)
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        stakeholder: pda(["stakeholder", safeHolderId]),
+        authority: authority.publicKey,
+      })
+      .rpc();
+  });
+
+  it("Records subscriptions and conversions on an open round", async () => {
+    await openRound(roundId, seriesAId);
+
+    await program.methods
+      .addRoundSubscription(Array.from(investorSecurityId), investmentAmount)
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        round: roundPda,
+        stakeholder: pda(["stakeholder", investorId]),
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    await program.methods
+      .addRoundConversion(
+        Array.from(conversionSecurityId),
+        new anchor.BN(200000),
+        new anchor.BN("8000000000000")
+      )
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        round: roundPda,
+        convertiblePosition: pda([
+          "convertible_position",
+          safeHolderId,
+          safeSecurityId,
+        ]),
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    const round = await program.account.pricedRound.fetch(roundPda);
+    expect(round.status).to.deep.equal({ open: {} });
+    expect(round.subscriptions.length).to.equal(1);
+    expect(round.conversions.length).to.equal(1);
+
+    // Nothing is applied until the round closes
+    const stockClass = await program.account.stockClass.fetchNullable(
+      pda(["stock_class", seriesAId])
+    );
+    expect(stockClass).to.be.null;
+  });
+
+  it("Fails to add a security id that is already registered", async () => {
+    try {
+      await program.methods
+        .addRoundSubscription(Array.from(founderSecurityId), investmentAmount)
+        .accounts({
+          // @ts-ignore
+          issuer: issuerPda,
+          round: roundPda,
+          stakeholder: pda(["stakeholder", investorId]),
+          authority: authority.publicKey,
+        })
+        .rpc();
+      expect.fail("Should have thrown an error");
+    } catch (error) {
+      expect(error.logs.join("\n")).to.include("already in use");
+    }
+  });
+
+  it("Fails to add a convertible another round will convert", async () => {
+    const otherRoundPda = pda(["priced_round", issuerId, otherRoundId]);
+    await openRound(otherRoundId, otherRoundClassId);
+
+    try {
+      await program.methods
+        .addRoundConversion(
+          Array.from(otherConversionSecurityId),
+          new anchor.BN(0),
+          new anchor.BN(0)
+        )
+        .accounts({
+          // @ts-ignore
+          issuer: issuerPda,
+          round: otherRoundPda,
+          convertiblePosition: pda([
+            "convertible_position",
+            safeHolderId,
+            safeSecurityId,
+          ]),
+          authority: authority.publicKey,
+        })
+        .rpc();
+      expect.fail("Should have thrown an error");
+    } catch (error) {
+      expect(error.toString()).to.include("ConvertibleInRound");
+    }
+
+    await program.methods
+      .cancelFinancingRound()
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        round: otherRoundPda,
+        authority: authority.publicKey,
+      })
+      .rpc();
+  });
+
+  it("Fails to add another issuer's convertible", async () => {
+    const otherIssuerPda = pda(["issuer", otherIssuerId]);
+    const otherIssuerRoundPda = pda([
+      "priced_round",
+      otherIssuerId,
+      otherIssuerRoundId,
+    ]);
+
+    await program.methods
+      .initializeIssuer(
+        Array.from(otherIssuerId),
+        new anchor.BN("10000000000000")
+      )
+      .accounts({ authority: authority.publicKey })
+      .rpc();
+
+    await program.methods
+      .openFinancingRound(
+        Array.from(otherIssuerRoundId),
+        Array.from(otherRoundClassId),
+        "SERIES_A",
+        preferredStockClassTerms(commonId),
+        preMoneyValuation,
+        new anchor.BN(0)
+      )
+      .accounts({
+        // @ts-ignore
+        issuer: otherIssuerPda,
+        stockPlan: null,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    try {
+      await program.methods
+        .addRoundConversion(
+          Array.from(otherConversionSecurityId),
+          new anchor.BN(0),
+          new anchor.BN(0)
+        )
+        .accounts({
+          // @ts-ignore
+          issuer: otherIssuerPda,
+          round: otherIssuerRoundPda,
+          convertiblePosition: pda([
+            "convertible_position",
+            safeHolderId,
+            safeSecurityId,
+          ]),
+          authority: authority.publicKey,
+        })
+        .rpc();
+      expect.fail("Should have thrown an error");
+    } catch (error) {
+      // The SAFE is registered under the first issuer only
+      expect(error.toString()).to.include("AccountNotInitialized");
+    }
+
+    const round = await program.account.pricedRound.fetch(otherIssuerRoundPda);
+    expect(round.conversions.length).to.equal(0);
+  });
+
+  it("Applies the whole round when it closes", async () => {
+    await closeRound(roundId, seriesAId);
+
+    const round = await program.account.pricedRound.fetch(roundPda);
+    expect(round.status).to.deep.equal({ closed: {} });
+    expect(round.pricePerShare.toNumber()).to.equal(pricePerShare);
+    expect(round.subscriptions[0].shares.eq(investorShares)).to.be.true;
+    expect(round.conversions[0].conversionPrice.toNumber()).to.equal(
+      conversionPrice
+    );
+    expect(round.conversions[0].shares.eq(conversionShares)).to.be.true;
+
+    const newShares = investorShares.add(conversionShares);
+    const stockClass = await program.account.stockClass.fetch(
+      pda(["stock_class", seriesAId])
+    );
+    expect(stockClass.sharesIssued.eq(newShares)).to.be.true;
+    expect(stockClass.sharesAuthorized.eq(newShares)).to.be.true;
+
+    // Authorized shares are raised to cover the new class
+    const issuer = await program.account.issuer.fetch(issuerPda);
+    expect(issuer.sharesAuthorized.eq(commonQuantity.add(newShares))).to.be
+      .true;
+
+    const summary = await program.methods
+      .getCapTableSummary()
+      .accounts({ issuer: issuerPda })
+      .view();
+    expect(summary.convertiblePrincipal.toNumber()).to.equal(0);
+  });
+
+  it("Settles each entry into a stock position", async () => {
+    for (const [stakeholderId, securityId, convertiblePosition] of [
+      [investorId, investorSecurityId, null],
+      [
+        safeHolderId,
+        conversionSecurityId,
+        pda(["convertible_position", safeHolderId, safeSecurityId]),
+      ],
+    ] as const) {
+      await program.methods
+        .settleFinancingRound(Array.from(securityId))
+        .accounts({
+          // @ts-ignore
+          issuer: issuerPda,
+          round: roundPda,
+          stakeholder: pda(["stakeholder", stakeholderId]),
+          convertiblePosition,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    }
+
+    const investorPosition = await program.account.stockActivePosition.fetch(
+      pda(["stock_position", investorId, investorSecurityId])
+    );
+    expect(investorPosition.quantity.eq(investorShares)).to.be.true;
+    expect(investorPosition.sharePrice.toNumber()).to.equal(pricePerShare);
+
+    const conversionPosition = await program.account.stockActivePosition.fetch(
+      pda(["stock_position", safeHolderId, conversionSecurityId])
+    );
+    expect(conversionPosition.quantity.eq(conversionShares)).to.be.true;
+    expect(conversionPosition.sharePrice.toNumber()).to.equal(conversionPrice);
+
+    // The SAFE is gone once its shares exist
+    const convertible =
+      await program.account.convertibleActivePosition.fetchNullable(
+        pda(["convertible_position", safeHolderId, safeSecurityId])
+      );
+    expect(convertible).to.be.null;
+
+    const round = await program.account.pricedRound.fetch(roundPda);
+    expect(round.settled).to.equal(2);
+  });
+
+  it("Leaves the cap table untouched when closing fails", async () => {
+    await openRound(emptyRoundId, emptyRoundClassId);
+
+    try {
+      await closeRound(emptyRoundId, emptyRoundClassId);
+      expect.fail("Should have thrown an error");
+    } catch (error) {
+      expect(error.toString()).to.include("NoSubscriptions");
+    }
+
+    const stockClass = await program.account.stockClass.fetchNullable(
+      pda(["stock_class", emptyRoundClassId])
+    );
+    expect(stockClass).to.be.null;
+
+    await program.methods
+      .cancelFinancingRound()
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        round: pda(["priced_round", issuerId, emptyRoundId]),
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    const round = await program.account.pricedRound.fetchNullable(
+      pda(["priced_round", issuerId, emptyRoundId])
+    );
+    expect(round).to.be.null;
+  });
+});
@@ -0,0 +1,322 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { OcpSolana } from "../target/types/ocp_solana";
+import { expect } from "chai";
+import { commonStockClassTerms } from "./helpers";
+
+describe("Governance Tests", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.OcpSolana as Program<OcpSolana>;
+  const authority = provider.wallet;
+
+  // Test data
+  const issuerId = new Uint8Array(16).fill(110);
+  const authorizedSharesProposalId = new Uint8Array(16).fill(111);
+  const stockClassProposalId = new Uint8Array(16).fill(112);
+  const stockClassId = new Uint8Array(16).fill(113);
+  const directStockClassId = new Uint8Array(16).fill(114);
+  const directStockPlanId = new Uint8Array(16).fill(115);
+  const stockPlanProposalId = new Uint8Array(16).fill(146);
+  const stockPlanId = new Uint8Array(16).fill(147);
+  const roundId = new Uint8Array(16).fill(148);
+  const roundStockClassId = new Uint8Array(16).fill(149);
+
+  const members = [
+    anchor.web3.Keypair.generate(),
+    anchor.web3.Keypair.generate(),
+    anchor.web3.Keypair.generate(),
+  ];
+  const outsider = anchor.web3.Keypair.generate();
+  const stockholderApprovalDate = new anchor.BN(1700000000);
+
+  let issuerPda: anchor.web3.PublicKey;
+
+  const pda = (seeds: (string | Uint8Array)[]) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      seeds.map((seed) => Buffer.from(seed)),
+      program.programId
+    )[0];
+
+  const approve = (proposalId: Uint8Array, member: anchor.web3.Keypair) =>
+    program.methods
+      .approveProposal()
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        proposal: pda(["proposal", issuerId, proposalId]),
+        member: member.publicKey,
+      })
+      .signers([member])
+      .rpc();
+
+  const execute = (
+    proposalId: Uint8Array,
+    stockClass: anchor.web3.PublicKey | null = null,
+    stockPlan: anchor.web3.PublicKey | null = null
+  ) =>
+    program.methods
+      .executeProposal()
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        proposal: pda(["proposal", issuerId, proposalId]),
+        stockClass,
+        stockPlan,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+  before(async () => {
+    issuerPda = pda(["issuer", issuerId]);
+
+    await program.methods
+      .initializeIssuer(Array.from(issuerId), new anchor.BN(1000000000))
+      .accounts({ authority: authority.publicKey })
+      .rpc();
+
+    // Two of three members must approve
+    await program.methods
+      .initializeBoard(members.map((member) => member.publicKey), 2)
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    await program.methods
+      .createProposal(
+        Array.from(authorizedSharesProposalId),
+        {
+          adjustAuthorizedShares: {
+            newSharesAuthorized: new anchor.BN(2000000000),
+          },
+        },
+        stockholderApprovalDate
+      )
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        authority: authority.publicKey,
+      })
+      .rpc();
+  });
+
+  it("Fails to execute before the threshold is reached", async () => {
+    await approve(authorizedSharesProposalId, members[0]);
+
+    try {
+      await execute(authorizedSharesProposalId);
+      expect.fail("Should have thrown an error");
+    } catch (error) {
+      expect(error.toString()).to.include("NotApproved");
+    }
+  });
+
+  it("Rejects approvals from outside the board", async () => {
+    try {
+      await approve(authorizedSharesProposalId, outsider);
+      expect.fail("Should have thrown an error");
+    } catch (error) {
+      expect(error.toString()).to.include("NotBoardMember");
+    }
+  });
+
+  it("Rejects a second approval from the same member", async () => {
+    try {
+      await approve(authorizedSharesProposalId, members[0]);
+      expect.fail("Should have thrown an error");
+    } catch (error) {
+      expect(error.toString()).to.include("AlreadyApproved");
+    }
+  });
+
+  it("Executes once approved and records the approval dates", async () => {
+    await approve(authorizedSharesProposalId, members[1]);
+
+    let proposal = await program.account.proposal.fetch(
+      pda(["proposal", issuerId, authorizedSharesProposalId])
+    );
+    expect(proposal.boardApprovalDate.toNumber()).to.be.greaterThan(0);
+
+    let executed;
+    const listener = program.addEventListener("proposalExecuted", (event) => {
+      executed = event;
+    });
+    await execute(authorizedSharesProposalId);
+    await new Promise((resolve) => setTimeout(resolve, 1000));
+    await program.removeEventListener(listener);
+
+    const issuer = await program.account.issuer.fetch(issuerPda);
+    expect(issuer.sharesAuthorized.toNumber()).to.equal(2000000000);
+
+    proposal = await program.account.proposal.fetch(
+      pda(["proposal", issuerId, authorizedSharesProposalId])
+    );
+    expect(proposal.executedAt.toNumber()).to.be.greaterThan(0);
+    expect(executed.boardApprovalDate.eq(proposal.boardApprovalDate)).to.be
+      .true;
+    expect(executed.stockholderApprovalDate.eq(stockholderApprovalDate)).to.be
+      .true;
+  });
+
+  it("Fails to execute a proposal twice", async () => {
+    try {
+      await execute(authorizedSharesProposalId);
+      expect.fail("Should have thrown an error");
+    } catch (error) {
+      expect(error.toString()).to.include("AlreadyExecuted");
+    }
+  });
+
+  it("Creates a stock class through a proposal", async () => {
+    await program.methods
+      .createProposal(
+        Array.from(stockClassProposalId),
+        {
+          createStockClass: {
+            id: Array.from(stockClassId),
+            classType: "COMMON",
+            pricePerShare: new anchor.BN(1000000),
+            initialSharesAuthorized: new anchor.BN(500000000),
+            terms: commonStockClassTerms(),
+          },
+        },
+        new anchor.BN(0)
+      )
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    await approve(stockClassProposalId, members[1]);
+    await approve(stockClassProposalId, members[2]);
+    await execute(stockClassProposalId, pda(["stock_class", stockClassId]));
+
+    const stockClass = await program.account.stockClass.fetch(
+      pda(["stock_class", stockClassId])
+    );
+    expect(stockClass.sharesAuthorized.toNumber()).to.equal(500000000);
+  });
+
+  it("Creates a stock plan through a proposal", async () => {
+    await program.methods
+      .createProposal(
+        Array.from(stockPlanProposalId),
+        {
+          createStockPlan: {
+            id: Array.from(stockPlanId),
+            stockClassIds: [Array.from(stockClassId)],
+            sharesReserved: new anchor.BN(100000000),
+          },
+        },
+        new anchor.BN(0)
+      )
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    await approve(stockPlanProposalId, members[0]);
+    await approve(stockPlanProposalId, members[1]);
+    await execute(stockPlanProposalId, null, pda(["stock_plan", stockPlanId]));
+
+    const stockPlan = await program.account.stockPlan.fetch(
+      pda(["stock_plan", stockPlanId])
+    );
+    expect(stockPlan.sharesReserved.toNumber()).to.equal(100000000);
+  });
+
+  it("Rejects board actions called directly once a board exists", async () => {
+    const accounts = {
+      issuer: issuerPda,
+      authority: authority.publicKey,
+    };
+    const direct = [
+      program.methods
+        .adjustAuthorizedShares(new anchor.BN(3000000000))
+        .accounts(accounts),
+      program.methods
+        .createStockClass(
+          Array.from(directStockClassId),
+          "COMMON",
+          new anchor.BN(1000000),
+          new anchor.BN(500000000),
+          commonStockClassTerms()
+        )
+        // @ts-ignore
+        .accounts(accounts),
+      program.methods
+        .createStockPlan(
+          Array.from(directStockPlanId),
+          [Array.from(stockClassId)],
+          new anchor.BN(100000000)
+        )
+        .accounts({
+          ...accounts,
+          // @ts-ignore
+          stockClass: pda(["stock_class", stockClassId]),
+        }),
+      program.methods
+        .adjustStockClassShares(new anchor.BN(600000000))
+        .accounts({
+          ...accounts,
+          stockClass: pda(["stock_class", stockClassId]),
+        }),
+      program.methods
+        .splitStockClass(new anchor.BN(2), new anchor.BN(1), { retain: {} })
+        .accounts({
+          ...accounts,
+          stockClass: pda(["stock_class", stockClassId]),
+        }),
+      program.methods
+        .adjustStockPlanShares(new anchor.BN(200000000))
+        .accounts({
+          ...accounts,
+          stockPlan: pda(["stock_plan", stockPlanId]),
+        }),
+      program.methods
+        .closeFinancingRound()
+        .accounts({
+          ...accounts,
+          // @ts-ignore
+          round: pda(["priced_round", issuerId, roundId]),
+          stockClass: pda(["stock_class", roundStockClassId]),
+          stockPlan: null,
+        }),
+    ];
+
+    // Opening a round changes nothing yet; closing it applies the round
+    await program.methods
+      .openFinancingRound(
+        Array.from(roundId),
+        Array.from(roundStockClassId),
+        "SERIES_A",
+        commonStockClassTerms(),
+        new anchor.BN(1000000000),
+        new anchor.BN(0)
+      )
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        stockPlan: null,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    for (const builder of direct) {
+      try {
+        await builder.rpc();
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error.toString()).to.include("BoardApprovalRequired");
+      }
+    }
+  });
+});
@@ -1,11 +1,52 @@
+import BN from "bn.js";
+
 export const TxTypes = {
   WarrantIssuance: { warrantIssuance: {} },
   StockIssuance: { stockIssuance: {} },
   ConvertibleIssuance: { convertibleIssuance: {} },
   EquityCompensationIssuance: { equityCompensationIssuance: {} },
   EquityCompensationExercise: { equityCompensationExercise: {} },
+  WarrantExercise: { warrantExercise: {} },
+  WarrantCancellation: { warrantCancellation: {} },
+  WarrantExpiration: { warrantExpiration: {} },
+  WarrantTransfer: { warrantTransfer: {} },
+  StockClassSplit: { stockClassSplit: {} },
+  StockConversion: { stockConversion: {} },
+  StockClassConversionRatioAdjustment: {
+    stockClassConversionRatioAdjustment: {},
+  },
+  ConvertibleConversion: { convertibleConversion: {} },
 } as const;
 
+// Default terms for a one-vote-per-share common class
+export const commonStockClassTerms = () => ({
+  kind: { common: {} },
+  seniority: 0,
+  liquidationPreferenceMultiple: new BN(0),
+  isParticipating: false,
+  participationCapMultiple: new BN(0),
+  conversionRatio: new BN(0),
+  convertsToStockClassId: Array(16).fill(0),
+  votesPerShare: new BN(1000000),
+  antiDilutionType: { none: {} },
+});
+
+// 1x non-participating preferred converting 1:1 into the given common class
+export const preferredStockClassTerms = (
+  commonStockClassId: Uint8Array,
+  antiDilutionType: object = { none: {} }
+) => ({
+  kind: { preferred: {} },
+  seniority: 1,
+  liquidationPreferenceMultiple: new BN(1000000),
+  isParticipating: false,
+  participationCapMultiple: new BN(0),
+  conversionRatio: new BN(1000000),
+  convertsToStockClassId: Array.from(commonStockClassId),
+  votesPerShare: new BN(1000000),
+  antiDilutionType,
+});
+
 // Type guard
 export function isTxType(event: any, type: keyof typeof TxTypes): boolean {
   return JSON.stringify(event.txType) === JSON.stringify(TxTypes[type]);
@@ -2,6 +2,7 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { OcpSolana } from "../target/types/ocp_solana";
 import { expect } from "chai";
+import { commonStockClassTerms } from "./helpers";
 
 describe("Issuer Program Tests", () => {
   // Configure the client
@@ -75,6 +76,183 @@ describe("Issuer Program Tests", () => {
     }
   });
 
+  it("Emits an event envelope when adjusting authorized shares", async () => {
+    const eventPromise = new Promise((resolve, reject) => {
+      const listener = program.addEventListener(
+        "issuerAuthorizedSharesAdjusted",
+        (event) => {
+          program.removeEventListener(listener);
+          resolve(event);
+        }
+      );
+
+      setTimeout(() => {
+        program.removeEventListener(listener);
+        reject(new Error("Timeout waiting for event"));
+      }, 30000);
+    });
+
+    await program.methods
+      .adjustAuthorizedShares(newSharesAuthorized)
+      .accounts({
+        issuer: issuerPda,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    const event = (await eventPromise) as any;
+    expect(event.newSharesAuthorized.eq(newSharesAuthorized)).to.be.true;
+    expect(Buffer.from(event.envelope.issuerId).equals(Buffer.from(testId))).to
+      .be.true;
+    expect(Buffer.from(event.envelope.objectId).equals(Buffer.from(testId))).to
+      .be.true;
+    expect(event.envelope.actor.equals(authority.publicKey)).to.be.true;
+    expect(event.envelope.unixTimestamp.toNumber()).to.be.greaterThan(0);
+  });
+
+  it("Creates and maintains the cap table summary", async () => {
+    const [capTableSummaryPda] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("cap_table_summary"), Buffer.from(testId)],
+      program.programId
+    );
+
+    const summaryAccount = await program.account.capTableSummary.fetch(
+      capTableSummaryPda
+    );
+    expect(Buffer.from(summaryAccount.issuerId).equals(Buffer.from(testId))).to
+      .be.true;
+    expect(summaryAccount.sharesAuthorized.eq(newSharesAuthorized)).to.be.true;
+
+    // Add a stock class and issue into it, then read the totals back
+    const stockClassId = new Uint8Array(16).fill(6);
+    const stakeholderId = new Uint8Array(16).fill(7);
+    const securityId = new Uint8Array(16).fill(8);
+    const quantity = new anchor.BN(250000);
+
+    const [stockClassPda] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("stock_class"), Buffer.from(stockClassId)],
+      program.programId
+    );
+    const [stakeholderPda] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("stakeholder"), Buffer.from(stakeholderId)],
+      program.programId
+    );
+
+    await program.methods
+      .createStockClass(
+        Array.from(stockClassId),
+        "COMMON",
+        new anchor.BN(1000000),
+        initialShares,
+        commonStockClassTerms()
+      )
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    await program.methods
+      .createStakeholder(Array.from(stakeholderId))
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    await program.methods
+      .issueStock(Array.from(securityId), quantity, new anchor.BN(1000000))
+      .accounts({
+        stockClass: stockClassPda,
+        issuer: issuerPda,
+        stakeholder: stakeholderPda,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    const summary = await program.methods
+      .getCapTableSummary()
+      .accounts({ issuer: issuerPda })
+      .view();
+
+    expect(summary.stockClasses.length).to.equal(1);
+    expect(
+      Buffer.from(summary.stockClasses[0].stockClassId).equals(
+        Buffer.from(stockClassId)
+      )
+    ).to.be.true;
+    expect(summary.stockClasses[0].sharesAuthorized.eq(initialShares)).to.be
+      .true;
+    expect(summary.stockClasses[0].sharesOutstanding.eq(quantity)).to.be.true;
+    expect(summary.optionsOutstanding.eq(new anchor.BN(0))).to.be.true;
+  });
+
+  it("Takes an immutable cap table snapshot", async () => {
+    const snapshotId = new Uint8Array(16).fill(9);
+    const [snapshotPda] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("cap_table_snapshot"),
+        Buffer.from(testId),
+        Buffer.from(snapshotId),
+      ],
+      program.programId
+    );
+    const [txLogPda] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("tx_log"), Buffer.from(testId)],
+      program.programId
+    );
+
+    await program.methods
+      .snapshotCapTable(Array.from(snapshotId))
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    const snapshot = await program.account.capTableSnapshot.fetch(snapshotPda);
+    const summary = await program.methods
+      .getCapTableSummary()
+      .accounts({ issuer: issuerPda })
+      .view();
+    const txLog = await program.account.txLog.fetch(txLogPda);
+
+    expect(Buffer.from(snapshot.issuerId).equals(Buffer.from(testId))).to.be
+      .true;
+    expect(Buffer.from(snapshot.id).equals(Buffer.from(snapshotId))).to.be.true;
+    expect(snapshot.authority.equals(authority.publicKey)).to.be.true;
+    expect(snapshot.unixTimestamp.toNumber()).to.be.greaterThan(0);
+    expect(snapshot.txLogSequence.eq(txLog.sequence)).to.be.true;
+    expect(
+      Buffer.from(snapshot.txLogHash).equals(Buffer.from(txLog.lastHash))
+    ).to.be.true;
+    expect(snapshot.sharesAuthorized.eq(summary.sharesAuthorized)).to.be.true;
+    expect(snapshot.stockClasses.length).to.equal(summary.stockClasses.length);
+    expect(
+      snapshot.stockClasses[0].sharesOutstanding.eq(
+        summary.stockClasses[0].sharesOutstanding
+      )
+    ).to.be.true;
+
+    // A snapshot id can only be used once
+    try {
+      await program.methods
+        .snapshotCapTable(Array.from(snapshotId))
+        .accounts({
+          // @ts-ignore
+          issuer: issuerPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+      expect.fail("Should have thrown an error");
+    } catch (error) {
+      expect(error.logs.join("\n")).to.include("already in use");
+    }
+  });
+
   it("Fails to initialize already initialized issuer", async () => {
     try {
       // Find PDA for issuer
@@ -0,0 +1,233 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { OcpSolana } from "../target/types/ocp_solana";
+import { expect } from "chai";
+
+describe("Multisig Tests", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.OcpSolana as Program<OcpSolana>;
+  const authority = provider.wallet;
+
+  // Test data
+  const issuerId = new Uint8Array(16).fill(120);
+  const cosignedStakeholderId = new Uint8Array(16).fill(121);
+  const storedStakeholderId = new Uint8Array(16).fill(122);
+
+  // The provider wallet and two others; any two must sign
+  const cosigner = anchor.web3.Keypair.generate();
+  const thirdMember = anchor.web3.Keypair.generate();
+  const outsider = anchor.web3.Keypair.generate();
+
+  let issuerPda: anchor.web3.PublicKey;
+  let signerPda: anchor.web3.PublicKey;
+
+  const pda = (seeds: (string | Uint8Array | Buffer)[]) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      seeds.map((seed) => Buffer.from(seed)),
+      program.programId
+    )[0];
+
+  const transactionPda = (nonce: number) =>
+    pda([
+      "multisig_transaction",
+      issuerId,
+      new anchor.BN(nonce).toArrayLike(Buffer, "le", 8),
+    ]);
+
+  const createStakeholder = (
+    id: Uint8Array,
+    signer: anchor.web3.PublicKey = authority.publicKey
+  ) =>
+    program.methods.createStakeholder(Array.from(id)).accounts({
+      // @ts-ignore
+      issuer: issuerPda,
+      authority: signer,
+    });
+
+  const createMultisig = (signer: anchor.web3.PublicKey) =>
+    program.methods
+      .createMultisig(
+        [authority.publicKey, cosigner.publicKey, thirdMember.publicKey],
+        2
+      )
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        authority: signer,
+      });
+
+  before(async () => {
+    issuerPda = pda(["issuer", issuerId]);
+    signerPda = pda(["multisig_signer", issuerId]);
+
+    await program.methods
+      .initializeIssuer(Array.from(issuerId), new anchor.BN(1000000000))
+      .accounts({ authority: authority.publicKey })
+      .rpc();
+
+    // The outsider pays rent for the accounts it tries to create
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        anchor.web3.SystemProgram.transfer({
+          fromPubkey: authority.publicKey,
+          toPubkey: outsider.publicKey,
+          lamports: anchor.web3.LAMPORTS_PER_SOL,
+        })
+      )
+    );
+  });
+
+  it("Rejects issuer instructions from others before a multisig", async () => {
+    try {
+      await createStakeholder(cosignedStakeholderId, outsider.publicKey)
+        .signers([outsider])
+        .rpc();
+      expect.fail("Should have thrown an error");
+    } catch (error) {
+      expect(error.toString()).to.include("NotIssuerAuthority");
+    }
+  });
+
+  it("Rejects a multisig created by others", async () => {
+    try {
+      await createMultisig(outsider.publicKey).signers([outsider]).rpc();
+      expect.fail("Should have thrown an error");
+    } catch (error) {
+      expect(error.toString()).to.include("NotIssuerAuthority");
+    }
+  });
+
+  it("Creates a multisig as the issuer authority", async () => {
+    await createMultisig(authority.publicKey).rpc();
+
+    const multisig = await program.account.multisig.fetch(
+      pda(["multisig", issuerId])
+    );
+    expect(multisig.threshold).to.equal(2);
+  });
+
+  it("Rejects issuer instructions with a single signature", async () => {
+    try {
+      await createStakeholder(cosignedStakeholderId).rpc();
+      expect.fail("Should have thrown an error");
+    } catch (error) {
+      expect(error.toString()).to.include("MultisigUnauthorized");
+    }
+  });
+
+  it("Rejects signatures from outside the multisig", async () => {
+    try {
+      await createStakeholder(cosignedStakeholderId)
+        .remainingAccounts([
+          { pubkey: outsider.publicKey, isSigner: true, isWritable: false },
+        ])
+        .signers([outsider])
+        .rpc();
+      expect.fail("Should have thrown an error");
+    } catch (error) {
+      expect(error.toString()).to.include("MultisigUnauthorized");
+    }
+  });
+
+  it("Accepts instructions co-signed in one transaction", async () => {
+    await createStakeholder(cosignedStakeholderId)
+      .remainingAccounts([
+        { pubkey: cosigner.publicKey, isSigner: true, isWritable: false },
+      ])
+      .signers([cosigner])
+      .rpc();
+
+    const stakeholder = await program.account.stakeholder.fetch(
+      pda(["stakeholder", cosignedStakeholderId])
+    );
+    expect(stakeholder.id).to.deep.equal(Array.from(cosignedStakeholderId));
+  });
+
+  it("Executes a stored transaction once approved", async () => {
+    // The multisig signer authorizes and pays for the stored instruction
+    const instruction = await createStakeholder(
+      storedStakeholderId,
+      signerPda
+    ).instruction();
+
+    await program.methods
+      .createMultisigTransaction(
+        instruction.keys.map((key) => ({
+          pubkey: key.pubkey,
+          isSigner: key.isSigner,
+          isWritable: key.isWritable,
+        })),
+        instruction.data
+      )
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        transaction: transactionPda(0),
+        proposer: authority.publicKey,
+      })
+      .rpc();
+
+    const execute = () =>
+      program.methods
+        .executeMultisigTransaction()
+        .accounts({
+          // @ts-ignore
+          issuer: issuerPda,
+          transaction: transactionPda(0),
+          ocpProgram: program.programId,
+          executor: authority.publicKey,
+        })
+        .remainingAccounts(
+          instruction.keys.map((key) => ({ ...key, isSigner: false }))
+        )
+        .rpc();
+
+    try {
+      await execute();
+      expect.fail("Should have thrown an error");
+    } catch (error) {
+      expect(error.toString()).to.include("NotApproved");
+    }
+
+    await program.methods
+      .approveMultisigTransaction()
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        transaction: transactionPda(0),
+        member: cosigner.publicKey,
+      })
+      .signers([cosigner])
+      .rpc();
+
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        anchor.web3.SystemProgram.transfer({
+          fromPubkey: authority.publicKey,
+          toPubkey: signerPda,
+          lamports: anchor.web3.LAMPORTS_PER_SOL,
+        })
+      )
+    );
+    await execute();
+
+    const stakeholder = await program.account.stakeholder.fetch(
+      pda(["stakeholder", storedStakeholderId])
+    );
+    expect(stakeholder.id).to.deep.equal(Array.from(storedStakeholderId));
+
+    const multisig = await program.account.multisig.fetch(
+      pda(["multisig", issuerId])
+    );
+    expect(multisig.nonce.toNumber()).to.equal(1);
+
+    try {
+      await execute();
+      expect.fail("Should have thrown an error");
+    } catch (error) {
+      expect(error.toString()).to.include("AlreadyExecuted");
+    }
+  });
+});
@@ -100,6 +100,42 @@ describe("Stakeholder Tests", () => {
     }
   });
 
+  it("Indexes the stakeholder's holdings as securities are issued", async () => {
+    const securityId = new Uint8Array(16).fill(9);
+    const [holdingsPda] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("stakeholder_holdings"), Buffer.from(stakeholderId)],
+      program.programId
+    );
+    const [stakeholderPda] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("stakeholder"), Buffer.from(stakeholderId)],
+      program.programId
+    );
+
+    // Created empty alongside the stakeholder
+    let holdings = await program.account.stakeholderHoldings.fetch(holdingsPda);
+    expect(Buffer.from(holdings.stakeholderId).equals(Buffer.from(stakeholderId)))
+      .to.be.true;
+    expect(holdings.holdings).to.have.lengthOf(0);
+
+    await program.methods
+      .issueConvertible(Array.from(securityId), new anchor.BN(5000000))
+      .accounts({
+        issuer: issuerPda,
+        stakeholder: stakeholderPda,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    holdings = await program.account.stakeholderHoldings.fetch(holdingsPda);
+    expect(holdings.holdings).to.have.lengthOf(1);
+    expect(
+      Buffer.from(holdings.holdings[0].securityId).equals(Buffer.from(securityId))
+    ).to.be.true;
+    expect(holdings.holdings[0].positionType).to.deep.equal({
+      convertible: {},
+    });
+  });
+
   it("Can fetch all stakeholders", async () => {
     try {
       // Fetch all stakeholder accounts
@@ -2,7 +2,15 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { OcpSolana } from "../target/types/ocp_solana";
 import { expect } from "chai";
-import { isTxType } from "./helpers";
+import { createHash } from "crypto";
+import {
+  isTxType,
+  commonStockClassTerms,
+  preferredStockClassTerms,
+} from "./helpers";
+
+// Borsh index of TxType::StockIssuance
+const TX_TYPE_STOCK_ISSUANCE = 5;
 
 describe("Stock Tests", () => {
   const provider = anchor.AnchorProvider.env();
@@ -54,7 +62,8 @@ describe("Stock Tests", () => {
         Array.from(stockClassId),
         "COMMON",
         sharePrice,
-        initialShares
+        initialShares,
+        commonStockClassTerms()
       )
       .accounts({
         authority: authority.publicKey,
@@ -131,6 +140,62 @@ describe("Stock Tests", () => {
     expect(stockClass.sharesIssued.eq(issuanceQuantity)).to.be.true;
   });
 
+  it("Registers the security id and rejects reusing it", async () => {
+    const [registrationPda] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("security"), Buffer.from(issuerId), Buffer.from(securityId)],
+      program.programId
+    );
+    const [positionPda] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("stock_position"),
+        Buffer.from(stakeholderId),
+        Buffer.from(securityId),
+      ],
+      program.programId
+    );
+
+    const registration = await program.account.securityRegistration.fetch(
+      registrationPda
+    );
+    expect(Buffer.from(registration.issuerId).equals(Buffer.from(issuerId))).to
+      .be.true;
+    expect(registration.positionType).to.deep.equal({ stock: {} });
+    expect(registration.position.equals(positionPda)).to.be.true;
+
+    // Same security id issued to a different stakeholder
+    const otherStakeholderId = new Uint8Array(16).fill(60);
+    await program.methods
+      .createStakeholder(Array.from(otherStakeholderId))
+      .accounts({
+        authority: authority.publicKey,
+        // @ts-ignore
+        issuer: issuerPda,
+      })
+      .rpc();
+    const [otherStakeholderPda] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("stakeholder"), Buffer.from(otherStakeholderId)],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .issueStock(Array.from(securityId), issuanceQuantity, sharePrice)
+        .accounts({
+          stockClass: stockClassPda,
+          issuer: issuerPda,
+          stakeholder: otherStakeholderPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      expect.fail("Should have thrown an error");
+    } catch (error) {
+      expect(error).to.be.instanceOf(Error);
+      // The security registration PDA already exists for this issuer
+      expect(error.logs.join("\n")).to.include("already in use");
+    }
+  });
+
   it("Fails when attempting to issue stock with non-existent stock class", async () => {
     // Generate PDA for a stock class that doesn't exist
     const nonExistentStockClassId = new Uint8Array(16).fill(98);
@@ -286,8 +351,14 @@ describe("Stock Tests", () => {
       ],
       program.programId
     );
+    const [txLogPda] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("tx_log"), Buffer.from(issuerId)],
+      program.programId
+    );
+    const txLogBefore = await program.account.txLog.fetch(txLogPda);
+
     const eventPromise = new Promise((resolve, reject) => {
-      const listener = program.addEventListener("txCreated", (event) => {
+      const listener = program.addEventListener("txCreatedV2", (event) => {
         program.removeEventListener(listener);
         resolve(event);
       });
@@ -314,6 +385,12 @@ describe("Stock Tests", () => {
     const event = (await eventPromise) as any;
 
     expect(isTxType(event, "StockIssuance")).to.be.true;
+    expect(event.payloadVersion).to.equal(2);
+    expect(Buffer.from(event.envelope.issuerId).equals(Buffer.from(issuerId)))
+      .to.be.true;
+    expect(Buffer.from(event.envelope.objectId).equals(Buffer.from(securityId)))
+      .to.be.true;
+    expect(event.envelope.actor.equals(authority.publicKey)).to.be.true;
 
     const decodedData = program.coder.types.decode("stockIssued", event.txData);
 
@@ -327,5 +404,188 @@ describe("Stock Tests", () => {
     ).to.be.true;
     expect(decodedData.quantity.eq(issuanceQuantity)).to.be.true;
     expect(decodedData.sharePrice.eq(sharePrice)).to.be.true;
+
+    // The event is chained onto the issuer's TxLog
+    const txLogAfter = await program.account.txLog.fetch(txLogPda);
+    expect(event.sequence.eq(txLogBefore.sequence.addn(1))).to.be.true;
+    expect(txLogAfter.sequence.eq(event.sequence)).to.be.true;
+    expect(Buffer.from(event.prevHash).equals(Buffer.from(txLogBefore.lastHash)))
+      .to.be.true;
+
+    const expectedHash = createHash("sha256")
+      .update(Buffer.from(event.prevHash))
+      .update(Buffer.from([TX_TYPE_STOCK_ISSUANCE]))
+      .update(Buffer.from([event.payloadVersion]))
+      .update(Buffer.from(event.txData))
+      .digest();
+    expect(Buffer.from(txLogAfter.lastHash).equals(expectedHash)).to.be.true;
+  });
+
+  describe("Stock conversion", () => {
+    const preferredClassId = new Uint8Array(16).fill(16);
+    const preferredSecurityId = new Uint8Array(16).fill(17);
+    const resultingSecurityId = new Uint8Array(16).fill(18);
+    const preferredQuantity = new anchor.BN(50000);
+
+    let preferredClassPda: anchor.web3.PublicKey;
+    let preferredPositionPda: anchor.web3.PublicKey;
+    let resultingPositionPda: anchor.web3.PublicKey;
+
+    before(async () => {
+      [preferredClassPda] = await anchor.web3.PublicKey.findProgramAddress(
+        [Buffer.from("stock_class"), Buffer.from(preferredClassId)],
+        program.programId
+      );
+
+      [preferredPositionPda] = await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("stock_position"),
+          Buffer.from(stakeholderId),
+          Buffer.from(preferredSecurityId),
+        ],
+        program.programId
+      );
+
+      [resultingPositionPda] = await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("stock_position"),
+          Buffer.from(stakeholderId),
+          Buffer.from(resultingSecurityId),
+        ],
+        program.programId
+      );
+
+      // Preferred class converting 1:1 into the common class
+      await program.methods
+        .createStockClass(
+          Array.from(preferredClassId),
+          "PREFERRED",
+          sharePrice,
+          initialShares,
+          preferredStockClassTerms(stockClassId)
+        )
+        .accounts({
+          authority: authority.publicKey,
+          // @ts-ignore
+          issuer: issuerPda,
+        })
+        .rpc();
+
+      await program.methods
+        .issueStock(Array.from(preferredSecurityId), preferredQuantity, sharePrice)
+        .accounts({
+          stockClass: preferredClassPda,
+          issuer: issuerPda,
+          stakeholder: stakeholderPda,
+          // @ts-ignore
+          position: preferredPositionPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    });
+
+    it("Fails to convert common stock", async () => {
+      const [commonPositionPda] = await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("stock_position"),
+          Buffer.from(stakeholderId),
+          Buffer.from(securityId),
+        ],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .convertStock(Array.from(resultingSecurityId), issuanceQuantity)
+          .accounts({
+            issuer: issuerPda,
+            stakeholder: stakeholderPda,
+            sourceStockClass: stockClassPda,
+            targetStockClass: stockClassPda,
+            // @ts-ignore
+            sourcePosition: commonPositionPda,
+            resultingPosition: resultingPositionPda,
+            authority: authority.publicKey,
+          })
+          .rpc();
+
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        // Common classes carry no conversion target
+        expect(error).to.be.instanceOf(Error);
+        expect(error.toString()).to.include("ConversionClassMismatch");
+      }
+    });
+
+    it("Converts preferred stock into common and emits TxCreated event", async () => {
+      const eventPromise = new Promise((resolve, reject) => {
+        const listener = program.addEventListener("txCreatedV2", (event) => {
+          program.removeEventListener(listener);
+          resolve(event);
+        });
+
+        setTimeout(() => {
+          program.removeEventListener(listener);
+          reject(new Error("Timeout waiting for event"));
+        }, 30000);
+      });
+
+      const commonBefore = await program.account.stockClass.fetch(
+        stockClassPda
+      );
+
+      await program.methods
+        .convertStock(Array.from(resultingSecurityId), preferredQuantity)
+        .accounts({
+          issuer: issuerPda,
+          stakeholder: stakeholderPda,
+          sourceStockClass: preferredClassPda,
+          targetStockClass: stockClassPda,
+          // @ts-ignore
+          sourcePosition: preferredPositionPda,
+          resultingPosition: resultingPositionPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      const event = (await eventPromise) as any;
+      expect(isTxType(event, "StockConversion")).to.be.true;
+
+      const decodedData = program.coder.types.decode(
+        "stockConverted",
+        event.txData
+      );
+      expect(decodedData.quantityConverted.eq(preferredQuantity)).to.be.true;
+      expect(decodedData.quantityReceived.eq(preferredQuantity)).to.be.true;
+
+      // Fully converted preferred position is closed
+      const preferredPosition =
+        await program.account.stockActivePosition.fetchNullable(
+          preferredPositionPda
+        );
+      expect(preferredPosition).to.be.null;
+
+      const resultingPosition = await program.account.stockActivePosition.fetch(
+        resultingPositionPda
+      );
+      expect(
+        Buffer.from(resultingPosition.stockClassId).equals(
+          Buffer.from(stockClassId)
+        )
+      ).to.be.true;
+      expect(resultingPosition.quantity.eq(preferredQuantity)).to.be.true;
+
+      const preferredClass = await program.account.stockClass.fetch(
+        preferredClassPda
+      );
+      expect(preferredClass.sharesIssued.eq(new anchor.BN(0))).to.be.true;
+
+      const commonAfter = await program.account.stockClass.fetch(stockClassPda);
+      expect(
+        commonAfter.sharesIssued.eq(
+          commonBefore.sharesIssued.add(preferredQuantity)
+        )
+      ).to.be.true;
+    });
   });
 });
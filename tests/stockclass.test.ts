@@ -2,6 +2,11 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { OcpSolana } from "../target/types/ocp_solana";
 import { expect } from "chai";
+import {
+  isTxType,
+  commonStockClassTerms,
+  preferredStockClassTerms,
+} from "./helpers";
 
 describe("Stock Class Tests", () => {
   const provider = anchor.AnchorProvider.env();
@@ -54,7 +59,8 @@ describe("Stock Class Tests", () => {
           Array.from(stockClassId),
           classType,
           pricePerShare,
-          initialShares
+          initialShares,
+          commonStockClassTerms()
         )
         .accounts({
           issuer: issuerPda,
@@ -97,7 +103,8 @@ describe("Stock Class Tests", () => {
           Array.from(preferredId),
           preferredClassType,
           pricePerShare,
-          initialShares
+          initialShares,
+          preferredStockClassTerms(stockClassId)
         )
         .accounts({
           issuer: issuerPda,
@@ -114,6 +121,12 @@ describe("Stock Class Tests", () => {
       expect(Buffer.from(stockClassAccount.id).equals(Buffer.from(preferredId)))
         .to.be.true;
       expect(stockClassAccount.classType).to.equal(preferredClassType);
+      expect(stockClassAccount.terms.kind).to.deep.equal({ preferred: {} });
+      expect(
+        Buffer.from(stockClassAccount.terms.convertsToStockClassId).equals(
+          Buffer.from(stockClassId)
+        )
+      ).to.be.true;
       expect(stockClassAccount.sharesIssued.eq(new anchor.BN(0))).to.be.true;
       expect(stockClassAccount.sharesAuthorized.eq(initialShares)).to.be.true;
     } catch (error) {
@@ -152,4 +165,351 @@ describe("Stock Class Tests", () => {
       throw error;
     }
   });
+
+  describe("Stock class splits", () => {
+    const stakeholderId = new Uint8Array(16).fill(33);
+    const securityId = new Uint8Array(16).fill(34);
+    const issuanceQuantity = new anchor.BN(100000);
+
+    let stockClassPda: anchor.web3.PublicKey;
+    let positionPda: anchor.web3.PublicKey;
+
+    before(async () => {
+      [stockClassPda] = await anchor.web3.PublicKey.findProgramAddress(
+        [Buffer.from("stock_class"), Buffer.from(stockClassId)],
+        program.programId
+      );
+
+      const [stakeholderPda] = await anchor.web3.PublicKey.findProgramAddress(
+        [Buffer.from("stakeholder"), Buffer.from(stakeholderId)],
+        program.programId
+      );
+
+      [positionPda] = await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("stock_position"),
+          Buffer.from(stakeholderId),
+          Buffer.from(securityId),
+        ],
+        program.programId
+      );
+
+      await program.methods
+        .createStakeholder(Array.from(stakeholderId))
+        .accounts({
+          issuer: issuerPda,
+          // @ts-ignore
+          stakeholder: stakeholderPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      await program.methods
+        .issueStock(Array.from(securityId), issuanceQuantity, pricePerShare)
+        .accounts({
+          stockClass: stockClassPda,
+          issuer: issuerPda,
+          stakeholder: stakeholderPda,
+          // @ts-ignore
+          position: positionPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    });
+
+    it("Splits a stock class 10:1 and emits TxCreated event", async () => {
+      const eventPromise = new Promise((resolve, reject) => {
+        const listener = program.addEventListener("txCreatedV2", (event) => {
+          program.removeEventListener(listener);
+          resolve(event);
+        });
+
+        setTimeout(() => {
+          program.removeEventListener(listener);
+          reject(new Error("Timeout waiting for event"));
+        }, 30000);
+      });
+
+      await program.methods
+        .splitStockClass(new anchor.BN(10), new anchor.BN(1), { retain: {} })
+        .accounts({
+          issuer: issuerPda,
+          stockClass: stockClassPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      const event = (await eventPromise) as any;
+      expect(isTxType(event, "StockClassSplit")).to.be.true;
+
+      const decodedData = program.coder.types.decode(
+        "stockClassSplit",
+        event.txData
+      );
+      expect(decodedData.splitEpoch).to.equal(1);
+
+      const stockClass = await program.account.stockClass.fetch(stockClassPda);
+      expect(stockClass.sharesAuthorized.eq(newSharesAuthorized.muln(10))).to
+        .be.true;
+      expect(stockClass.sharesIssued.eq(issuanceQuantity.muln(10))).to.be.true;
+      expect(stockClass.pricePerShare.eq(pricePerShare.divn(10))).to.be.true;
+      expect(stockClass.splits.length).to.equal(1);
+
+      // Positions are untouched until the split is applied
+      const position = await program.account.stockActivePosition.fetch(
+        positionPda
+      );
+      expect(position.quantity.eq(issuanceQuantity)).to.be.true;
+      expect(position.splitEpoch).to.equal(0);
+    });
+
+    it("Applies the split to positions via the crank", async () => {
+      await program.methods
+        .applyStockClassSplit()
+        .accounts({
+          issuer: issuerPda,
+          stockClass: stockClassPda,
+        })
+        .remainingAccounts([
+          { pubkey: positionPda, isWritable: true, isSigner: false },
+        ])
+        .rpc();
+
+      const position = await program.account.stockActivePosition.fetch(
+        positionPda
+      );
+      expect(position.quantity.eq(issuanceQuantity.muln(10))).to.be.true;
+      expect(position.sharePrice.eq(pricePerShare.divn(10))).to.be.true;
+      expect(position.splitEpoch).to.equal(1);
+
+      // Once every position is up to date the class total is their sum
+      const stockClass = await program.account.stockClass.fetch(stockClassPda);
+      expect(stockClass.sharesIssued.eq(position.quantity)).to.be.true;
+      expect(stockClass.splits[0].pendingQuantity.eqn(0)).to.be.true;
+    });
+
+    it("Fails to split with a 1:1 ratio", async () => {
+      try {
+        await program.methods
+          .splitStockClass(new anchor.BN(1), new anchor.BN(1), { retain: {} })
+          .accounts({
+            issuer: issuerPda,
+            stockClass: stockClassPda,
+            authority: authority.publicKey,
+          })
+          .rpc();
+
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error).to.be.instanceOf(Error);
+        expect(error.toString()).to.include("InvalidSplitRatio");
+      }
+    });
+  });
+
+  describe("Anti-dilution adjustments", () => {
+    const weightedClassId = new Uint8Array(16).fill(36);
+    const ratchetClassId = new Uint8Array(16).fill(37);
+    const stakeholderId = new Uint8Array(16).fill(38);
+    const securityId = new Uint8Array(16).fill(39);
+    const preferredShares = new anchor.BN(1000000);
+
+    let weightedClassPda: anchor.web3.PublicKey;
+    let ratchetClassPda: anchor.web3.PublicKey;
+
+    before(async () => {
+      [weightedClassPda] = await anchor.web3.PublicKey.findProgramAddress(
+        [Buffer.from("stock_class"), Buffer.from(weightedClassId)],
+        program.programId
+      );
+      [ratchetClassPda] = await anchor.web3.PublicKey.findProgramAddress(
+        [Buffer.from("stock_class"), Buffer.from(ratchetClassId)],
+        program.programId
+      );
+
+      await program.methods
+        .createStockClass(
+          Array.from(weightedClassId),
+          "PREFERRED",
+          pricePerShare,
+          initialShares,
+          preferredStockClassTerms(stockClassId, {
+            narrowBasedWeightedAverage: {},
+          })
+        )
+        .accounts({
+          issuer: issuerPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      await program.methods
+        .createStockClass(
+          Array.from(ratchetClassId),
+          "PREFERRED",
+          pricePerShare,
+          initialShares,
+          preferredStockClassTerms(stockClassId, { fullRatchet: {} })
+        )
+        .accounts({
+          issuer: issuerPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      // Outstanding preferred shares form the narrow-based weighting
+      const [stakeholderPda] = await anchor.web3.PublicKey.findProgramAddress(
+        [Buffer.from("stakeholder"), Buffer.from(stakeholderId)],
+        program.programId
+      );
+
+      await program.methods
+        .createStakeholder(Array.from(stakeholderId))
+        .accounts({
+          issuer: issuerPda,
+          // @ts-ignore
+          stakeholder: stakeholderPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      await program.methods
+        .issueStock(Array.from(securityId), preferredShares, pricePerShare)
+        .accounts({
+          stockClass: weightedClassPda,
+          issuer: issuerPda,
+          stakeholder: stakeholderPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    });
+
+    it("Applies narrow-based weighted average on a down round", async () => {
+      const eventPromise = new Promise((resolve, reject) => {
+        const listener = program.addEventListener("txCreatedV2", (event) => {
+          program.removeEventListener(listener);
+          resolve(event);
+        });
+
+        setTimeout(() => {
+          program.removeEventListener(listener);
+          reject(new Error("Timeout waiting for event"));
+        }, 30000);
+      });
+
+      await program.methods
+        .recordDilutiveIssuance(new anchor.BN(500000), new anchor.BN(1000000))
+        .accounts({
+          issuer: issuerPda,
+          stockClass: weightedClassPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      const event = (await eventPromise) as any;
+      expect(isTxType(event, "StockClassConversionRatioAdjustment")).to.be
+        .true;
+
+      // (1.00 * 1,000,000 + 0.50 * 1,000,000) / 2,000,000 = 0.75
+      const stockClass = await program.account.stockClass.fetch(
+        weightedClassPda
+      );
+      expect(stockClass.conversionPrice.eq(new anchor.BN(750000))).to.be.true;
+      expect(stockClass.terms.conversionRatio.eq(new anchor.BN(1333333))).to.be
+        .true;
+    });
+
+    it("Bases broad-based weighted average on the summary", async () => {
+      const broadClassId = new Uint8Array(16).fill(140);
+      const [broadClassPda] = await anchor.web3.PublicKey.findProgramAddress(
+        [Buffer.from("stock_class"), Buffer.from(broadClassId)],
+        program.programId
+      );
+      const [capTableSummaryPda] =
+        await anchor.web3.PublicKey.findProgramAddress(
+          [Buffer.from("cap_table_summary"), Buffer.from(issuerId)],
+          program.programId
+        );
+
+      await program.methods
+        .createStockClass(
+          Array.from(broadClassId),
+          "PREFERRED",
+          pricePerShare,
+          initialShares,
+          preferredStockClassTerms(stockClassId, {
+            broadBasedWeightedAverage: {},
+          })
+        )
+        .accounts({
+          issuer: issuerPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      // The new class has nothing issued, so the base is every other class
+      const summary = await program.account.capTableSummary.fetch(
+        capTableSummaryPda
+      );
+      const basis = summary.stockClasses.reduce(
+        (total, stockClass) => total.add(stockClass.sharesOutstanding),
+        new anchor.BN(0)
+      );
+      expect(basis.gt(new anchor.BN(0))).to.be.true;
+
+      const newIssuePrice = new anchor.BN(400000);
+      const newShares = new anchor.BN(1000);
+      await program.methods
+        .recordDilutiveIssuance(newIssuePrice, newShares)
+        .accounts({
+          issuer: issuerPda,
+          stockClass: broadClassPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      const expected = pricePerShare
+        .mul(basis)
+        .add(newIssuePrice.mul(newShares))
+        .div(basis.add(newShares));
+      const stockClass = await program.account.stockClass.fetch(broadClassPda);
+      expect(stockClass.conversionPrice.eq(expected)).to.be.true;
+    });
+
+    it("Applies full ratchet on a down round", async () => {
+      await program.methods
+        .recordDilutiveIssuance(new anchor.BN(400000), new anchor.BN(1000))
+        .accounts({
+          issuer: issuerPda,
+          stockClass: ratchetClassPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      const stockClass = await program.account.stockClass.fetch(
+        ratchetClassPda
+      );
+      expect(stockClass.conversionPrice.eq(new anchor.BN(400000))).to.be.true;
+      expect(stockClass.terms.conversionRatio.eq(new anchor.BN(2500000))).to.be
+        .true;
+    });
+
+    it("Fails when the issuance price is not below the conversion price", async () => {
+      try {
+        await program.methods
+          .recordDilutiveIssuance(pricePerShare, new anchor.BN(1000))
+          .accounts({
+            issuer: issuerPda,
+            stockClass: ratchetClassPda,
+            authority: authority.publicKey,
+          })
+          .rpc();
+
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error).to.be.instanceOf(Error);
+        expect(error.toString()).to.include("NotDilutive");
+      }
+    });
+  });
 });
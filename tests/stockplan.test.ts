@@ -2,6 +2,7 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { OcpSolana } from "../target/types/ocp_solana";
 import { expect } from "chai";
+import { commonStockClassTerms } from "./helpers";
 
 describe("Stock Plan Tests", () => {
   const provider = anchor.AnchorProvider.env();
@@ -57,7 +58,8 @@ describe("Stock Plan Tests", () => {
         Array.from(stockClassId1),
         "COMMON",
         new anchor.BN(1000000),
-        sharesReserved
+        sharesReserved,
+        commonStockClassTerms()
       )
       .accounts({
         issuer: issuerPda,
@@ -74,7 +76,8 @@ describe("Stock Plan Tests", () => {
         Array.from(stockClassId2),
         "COMMON",
         new anchor.BN(1000000),
-        sharesReserved
+        sharesReserved,
+        commonStockClassTerms()
       )
       .accounts({
         issuer: issuerPda,
@@ -0,0 +1,314 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { OcpSolana } from "../target/types/ocp_solana";
+import { expect } from "chai";
+import { commonStockClassTerms, preferredStockClassTerms } from "./helpers";
+
+describe("Voting Tests", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.OcpSolana as Program<OcpSolana>;
+  const authority = provider.wallet;
+
+  // Test data
+  const issuerId = new Uint8Array(16).fill(130);
+  const commonId = new Uint8Array(16).fill(131);
+  const preferredId = new Uint8Array(16).fill(132);
+  const founderId = new Uint8Array(16).fill(133);
+  const investorId = new Uint8Array(16).fill(134);
+  const founderSecurityId = new Uint8Array(16).fill(135);
+  const investorSecurityId = new Uint8Array(16).fill(136);
+  const resolutionId = new Uint8Array(16).fill(137);
+  const laterFounderSecurityId = new Uint8Array(16).fill(151);
+  const laterInvestorSecurityId = new Uint8Array(16).fill(152);
+
+  // 1,000 common shares at one vote each and 500 preferred shares at two
+  const founderShares = new anchor.BN(1000000000);
+  const laterFounderShares = new anchor.BN(200000000);
+  const investorShares = new anchor.BN(500000000);
+  const founderWallet = anchor.web3.Keypair.generate();
+  const investorWallet = anchor.web3.Keypair.generate();
+
+  let issuerPda: anchor.web3.PublicKey;
+  let resolutionPda: anchor.web3.PublicKey;
+
+  const pda = (seeds: (string | Uint8Array)[]) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      seeds.map((seed) => Buffer.from(seed)),
+      program.programId
+    )[0];
+
+  const readonly = (pubkey: anchor.web3.PublicKey) => ({
+    pubkey,
+    isSigner: false,
+    isWritable: false,
+  });
+
+  const recordVotingPower = (
+    stakeholderId: Uint8Array,
+    securityIds: Uint8Array[],
+    stockClassId: Uint8Array
+  ) =>
+    program.methods
+      .recordVotingPower()
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        resolution: resolutionPda,
+        stakeholderHoldings: pda(["stakeholder_holdings", stakeholderId]),
+        voterRecord: pda([
+          "voter_record",
+          issuerId,
+          resolutionId,
+          stakeholderId,
+        ]),
+        cranker: authority.publicKey,
+      })
+      .remainingAccounts([
+        readonly(pda(["stock_class", stockClassId])),
+        ...securityIds.map((securityId) =>
+          readonly(pda(["stock_position", stakeholderId, securityId]))
+        ),
+      ])
+      .rpc();
+
+  const issueStock = (
+    stakeholderId: Uint8Array,
+    securityId: Uint8Array,
+    stockClassId: Uint8Array,
+    quantity: anchor.BN
+  ) =>
+    program.methods
+      .issueStock(Array.from(securityId), quantity, new anchor.BN(1000000))
+      .accounts({
+        stockClass: pda(["stock_class", stockClassId]),
+        issuer: issuerPda,
+        stakeholder: pda(["stakeholder", stakeholderId]),
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+  const castVote = (
+    stakeholderId: Uint8Array,
+    wallet: anchor.web3.Keypair,
+    choice: object
+  ) =>
+    program.methods
+      // @ts-ignore
+      .castVote(choice)
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        resolution: resolutionPda,
+        voterRecord: pda([
+          "voter_record",
+          issuerId,
+          resolutionId,
+          stakeholderId,
+        ]),
+        stakeholderHoldings: pda(["stakeholder_holdings", stakeholderId]),
+        stakeholderWallet: pda(["stakeholder_wallet", issuerId, stakeholderId]),
+        wallet: wallet.publicKey,
+      })
+      .signers([wallet])
+      .rpc();
+
+  before(async () => {
+    issuerPda = pda(["issuer", issuerId]);
+    resolutionPda = pda(["resolution", issuerId, resolutionId]);
+
+    await program.methods
+      .initializeIssuer(Array.from(issuerId), new anchor.BN("10000000000"))
+      .accounts({ authority: authority.publicKey })
+      .rpc();
+
+    for (const [id, classType, terms] of [
+      [commonId, "COMMON", commonStockClassTerms()],
+      [
+        preferredId,
+        "PREFERRED",
+        {
+          ...preferredStockClassTerms(commonId),
+          votesPerShare: new anchor.BN(2000000),
+        },
+      ],
+    ] as const) {
+      await program.methods
+        .createStockClass(
+          Array.from(id),
+          classType,
+          new anchor.BN(1000000),
+          new anchor.BN("5000000000"),
+          terms
+        )
+        .accounts({
+          // @ts-ignore
+          issuer: issuerPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    }
+
+    for (const [stakeholderId, securityId, stockClassId, quantity, wallet] of [
+      [founderId, founderSecurityId, commonId, founderShares, founderWallet],
+      [
+        investorId,
+        investorSecurityId,
+        preferredId,
+        investorShares,
+        investorWallet,
+      ],
+    ] as const) {
+      await program.methods
+        .createStakeholder(Array.from(stakeholderId))
+        .accounts({
+          // @ts-ignore
+          issuer: issuerPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      await issueStock(stakeholderId, securityId, stockClassId, quantity);
+
+      await program.methods
+        .setStakeholderWallet(wallet.publicKey)
+        .accounts({
+          // @ts-ignore
+          issuer: issuerPda,
+          stakeholder: pda(["stakeholder", stakeholderId]),
+          authority: authority.publicKey,
+        })
+        .rpc();
+    }
+
+    // The preferred must also approve as a separate class
+    const now = Math.floor(Date.now() / 1000);
+    await program.methods
+      .createResolution(
+        Array.from(resolutionId),
+        new anchor.BN(now + 10),
+        new anchor.BN(now + 3600),
+        [Array.from(preferredId)]
+      )
+      .accounts({
+        // @ts-ignore
+        issuer: issuerPda,
+        authority: authority.publicKey,
+      })
+      .remainingAccounts([
+        readonly(pda(["stock_class", commonId])),
+        readonly(pda(["stock_class", preferredId])),
+      ])
+      .rpc();
+  });
+
+  it("Records holdings up to the record date", async () => {
+    await recordVotingPower(founderId, [founderSecurityId], commonId);
+    await recordVotingPower(investorId, [investorSecurityId], preferredId);
+
+    const voterRecord = await program.account.voterRecord.fetch(
+      pda(["voter_record", issuerId, resolutionId, investorId])
+    );
+    expect(voterRecord.shares.map((shares) => shares.toString())).to.deep.equal(
+      ["0", investorShares.toString()]
+    );
+  });
+
+  it("Rejects votes before the record date", async () => {
+    try {
+      await castVote(founderId, founderWallet, { for: {} });
+      expect.fail("Should have thrown an error");
+    } catch (error) {
+      expect(error.toString()).to.include("VotingClosed");
+    }
+  });
+
+  it("Rejects a vote recorded before the holder's stock changed", async () => {
+    // Counting the old record would count these shares twice if they had
+    // moved from another stakeholder
+    await issueStock(
+      founderId,
+      laterFounderSecurityId,
+      commonId,
+      laterFounderShares
+    );
+    await new Promise((resolve) => setTimeout(resolve, 11000));
+
+    try {
+      await castVote(founderId, founderWallet, { for: {} });
+      expect.fail("Should have thrown an error");
+    } catch (error) {
+      expect(error.toString()).to.include("StaleVoterRecord");
+    }
+  });
+
+  it("Records holdings unchanged since the record date", async () => {
+    await recordVotingPower(
+      founderId,
+      [founderSecurityId, laterFounderSecurityId],
+      commonId
+    );
+    await recordVotingPower(investorId, [investorSecurityId], preferredId);
+
+    const voterRecord = await program.account.voterRecord.fetch(
+      pda(["voter_record", issuerId, resolutionId, founderId])
+    );
+    expect(voterRecord.shares.map((shares) => shares.toString())).to.deep.equal(
+      [founderShares.add(laterFounderShares).toString(), "0"]
+    );
+  });
+
+  it("Fails to record holdings changed after the record date", async () => {
+    await issueStock(
+      investorId,
+      laterInvestorSecurityId,
+      preferredId,
+      investorShares
+    );
+
+    try {
+      await recordVotingPower(
+        investorId,
+        [investorSecurityId, laterInvestorSecurityId],
+        preferredId
+      );
+      expect.fail("Should have thrown an error");
+    } catch (error) {
+      expect(error.toString()).to.include("RecordDatePassed");
+    }
+  });
+
+  it("Rejects votes not signed by the stakeholder's wallet", async () => {
+    try {
+      await castVote(founderId, investorWallet, { for: {} });
+      expect.fail("Should have thrown an error");
+    } catch (error) {
+      expect(error.toString()).to.include("WalletMismatch");
+    }
+  });
+
+  it("Tallies votes by class weighted by votes per share", async () => {
+    await castVote(founderId, founderWallet, { for: {} });
+    await castVote(investorId, investorWallet, { against: {} });
+
+    const resolution = await program.account.resolution.fetch(resolutionPda);
+    const [common, preferred] = resolution.tallies;
+    expect(common.separateVote).to.be.false;
+    expect(common.votesFor.eq(founderShares.add(laterFounderShares))).to.be
+      .true;
+    expect(common.votesAgainst.toNumber()).to.equal(0);
+    expect(preferred.separateVote).to.be.true;
+    expect(preferred.votesFor.toNumber()).to.equal(0);
+    expect(preferred.votesAgainst.eq(investorShares.muln(2))).to.be.true;
+  });
+
+  it("Rejects a second vote", async () => {
+    try {
+      await castVote(founderId, founderWallet, { against: {} });
+      expect.fail("Should have thrown an error");
+    } catch (error) {
+      expect(error.toString()).to.include("AlreadyVoted");
+    }
+  });
+});
@@ -2,7 +2,7 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { OcpSolana } from "../target/types/ocp_solana";
 import { expect } from "chai";
-import { TxTypes, isTxType } from "./helpers";
+import { TxTypes, isTxType, commonStockClassTerms } from "./helpers";
 
 describe("Warrant Tests", () => {
   const provider = anchor.AnchorProvider.env();
@@ -15,10 +15,17 @@ describe("Warrant Tests", () => {
   const issuerId = new Uint8Array(16).fill(40);
   const stakeholderId = new Uint8Array(16).fill(41);
   const securityId = new Uint8Array(16).fill(42);
+  const stockClassId = new Uint8Array(16).fill(44);
   const quantity = new anchor.BN(100000);
+  const exercisePrice = new anchor.BN(1000000); // 1 USDC
+  const expirationDate = new anchor.BN(
+    Math.floor(Date.now() / 1000) + 365 * 24 * 60 * 60
+  );
+  const exerciseTrigger = { electiveAtWill: {} };
 
   let issuerPda: anchor.web3.PublicKey;
   let stakeholderPda: anchor.web3.PublicKey;
+  let stockClassPda: anchor.web3.PublicKey;
 
   before(async () => {
     // Find PDAs
@@ -54,11 +61,34 @@ describe("Warrant Tests", () => {
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .rpc();
+
+    // Create stock class the warrant exercises into
+    [stockClassPda] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("stock_class"), Buffer.from(stockClassId)],
+      program.programId
+    );
+
+    await program.methods
+      .createStockClass(
+        Array.from(stockClassId),
+        "COMMON",
+        exercisePrice,
+        new anchor.BN(1000000),
+        commonStockClassTerms()
+      )
+      .accounts({
+        issuer: issuerPda,
+        // @ts-ignore
+        stockClass: stockClassPda,
+        authority: authority.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
   });
 
   it("Issues warrant and emits TxCreated event", async () => {
     const eventPromise = new Promise((resolve, reject) => {
-      const listener = program.addEventListener("txCreated", (event) => {
+      const listener = program.addEventListener("txCreatedV2", (event) => {
         program.removeEventListener(listener);
         resolve(event);
       });
@@ -80,10 +110,17 @@ describe("Warrant Tests", () => {
 
     // Issue the warrant
     await program.methods
-      .issueWarrant(Array.from(securityId), quantity)
+      .issueWarrant(
+        Array.from(securityId),
+        quantity,
+        exercisePrice,
+        expirationDate,
+        exerciseTrigger
+      )
       .accounts({
         issuer: issuerPda,
         stakeholder: stakeholderPda,
+        stockClass: stockClassPda,
         // @ts-ignore
         position: positionPda,
         authority: authority.publicKey,
@@ -110,6 +147,19 @@ describe("Warrant Tests", () => {
     expect(Buffer.from(decodedData.securityId).equals(Buffer.from(securityId)))
       .to.be.true;
     expect(decodedData.quantity.eq(quantity)).to.be.true;
+    expect(
+      Buffer.from(decodedData.stockClassId).equals(Buffer.from(stockClassId))
+    ).to.be.true;
+    expect(decodedData.exercisePrice.eq(exercisePrice)).to.be.true;
+    expect(decodedData.expirationDate.eq(expirationDate)).to.be.true;
+
+    // Verify the stored warrant terms
+    const position = await program.account.warrantActivePosition.fetch(
+      positionPda
+    );
+    expect(position.exercisePrice.eq(exercisePrice)).to.be.true;
+    expect(position.expirationDate.eq(expirationDate)).to.be.true;
+    expect(position.exerciseTrigger).to.deep.equal(exerciseTrigger);
   });
 
   it("Fails when attempting to issue warrant with zero quantity", async () => {
@@ -125,10 +175,17 @@ describe("Warrant Tests", () => {
 
     try {
       await program.methods
-        .issueWarrant(Array.from(newSecurityId), new anchor.BN(0))
+        .issueWarrant(
+          Array.from(newSecurityId),
+          new anchor.BN(0),
+          exercisePrice,
+          expirationDate,
+          exerciseTrigger
+        )
         .accounts({
           issuer: issuerPda,
           stakeholder: stakeholderPda,
+          stockClass: stockClassPda,
           // @ts-ignore
           position: positionPda,
           authority: authority.publicKey,
@@ -142,4 +199,574 @@ describe("Warrant Tests", () => {
       expect(error.toString()).to.include("InvalidQuantity");
     }
   });
+
+  it("Fails when attempting to issue warrant with past expiration date", async () => {
+    const newSecurityId = new Uint8Array(16).fill(45);
+    const [positionPda] = await anchor.web3.PublicKey.findProgramAddress(
+      [
+        Buffer.from("warrant_position"),
+        Buffer.from(stakeholderId),
+        Buffer.from(newSecurityId),
+      ],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .issueWarrant(
+          Array.from(newSecurityId),
+          quantity,
+          exercisePrice,
+          new anchor.BN(Math.floor(Date.now() / 1000) - 60),
+          exerciseTrigger
+        )
+        .accounts({
+          issuer: issuerPda,
+          stakeholder: stakeholderPda,
+          stockClass: stockClassPda,
+          // @ts-ignore
+          position: positionPda,
+          authority: authority.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+      expect.fail("Should have thrown an error");
+    } catch (error) {
+      expect(error).to.be.instanceOf(Error);
+      expect(error.toString()).to.include("InvalidExpirationDate");
+    }
+  });
+
+  describe("Warrant exercise", () => {
+    const warrantPositionSeeds = () => [
+      Buffer.from("warrant_position"),
+      Buffer.from(stakeholderId),
+      Buffer.from(securityId),
+    ];
+
+    const stockPositionPdaFor = async (resultingSecurityId: Uint8Array) => {
+      const [pda] = await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("stock_position"),
+          Buffer.from(stakeholderId),
+          Buffer.from(resultingSecurityId),
+        ],
+        program.programId
+      );
+      return pda;
+    };
+
+    it("Exercises warrant for cash and emits TxCreated event", async () => {
+      const resultingSecurityId = new Uint8Array(16).fill(46);
+      const exerciseQuantity = new anchor.BN(40000);
+      const [warrantPositionPda] =
+        await anchor.web3.PublicKey.findProgramAddress(
+          warrantPositionSeeds(),
+          program.programId
+        );
+      const stockPositionPda = await stockPositionPdaFor(resultingSecurityId);
+
+      const eventPromise = new Promise((resolve, reject) => {
+        const listener = program.addEventListener("txCreatedV2", (event) => {
+          program.removeEventListener(listener);
+          resolve(event);
+        });
+
+        setTimeout(() => {
+          program.removeEventListener(listener);
+          reject(new Error("Timeout waiting for event"));
+        }, 30000);
+      });
+
+      await program.methods
+        .exerciseWarrant(Array.from(resultingSecurityId), exerciseQuantity, null)
+        .accounts({
+          issuer: issuerPda,
+          stakeholder: stakeholderPda,
+          stockClass: stockClassPda,
+          // @ts-ignore
+          warrantPosition: warrantPositionPda,
+          stockPosition: stockPositionPda,
+          rentPayer: authority.publicKey,
+          authority: authority.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+      const event = (await eventPromise) as any;
+      expect(isTxType(event, "WarrantExercise")).to.be.true;
+
+      const decodedData = program.coder.types.decode(
+        "warrantExercised",
+        event.txData
+      );
+      expect(decodedData.quantity.eq(exerciseQuantity)).to.be.true;
+      expect(decodedData.sharesReceived.eq(exerciseQuantity)).to.be.true;
+      expect(decodedData.fairMarketValue).to.be.null;
+
+      const stockPosition = await program.account.stockActivePosition.fetch(
+        stockPositionPda
+      );
+      expect(stockPosition.quantity.eq(exerciseQuantity)).to.be.true;
+      expect(
+        Buffer.from(stockPosition.stockClassId).equals(Buffer.from(stockClassId))
+      ).to.be.true;
+
+      const warrantPosition = await program.account.warrantActivePosition.fetch(
+        warrantPositionPda
+      );
+      expect(warrantPosition.quantity.eq(quantity.sub(exerciseQuantity))).to.be
+        .true;
+    });
+
+    it("Fails net exercise when fair market value is below exercise price", async () => {
+      const resultingSecurityId = new Uint8Array(16).fill(47);
+      const [warrantPositionPda] =
+        await anchor.web3.PublicKey.findProgramAddress(
+          warrantPositionSeeds(),
+          program.programId
+        );
+
+      try {
+        await program.methods
+          .exerciseWarrant(
+            Array.from(resultingSecurityId),
+            new anchor.BN(1000),
+            new anchor.BN(500000)
+          )
+          .accounts({
+            issuer: issuerPda,
+            stakeholder: stakeholderPda,
+            stockClass: stockClassPda,
+            // @ts-ignore
+            warrantPosition: warrantPositionPda,
+            stockPosition: await stockPositionPdaFor(resultingSecurityId),
+            rentPayer: authority.publicKey,
+            authority: authority.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .rpc();
+
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error).to.be.instanceOf(Error);
+        expect(error.toString()).to.include("NotInTheMoney");
+      }
+    });
+
+    it("Net exercises remaining warrants and closes the position", async () => {
+      const resultingSecurityId = new Uint8Array(16).fill(48);
+      const remaining = new anchor.BN(60000);
+      const fairMarketValue = new anchor.BN(4000000); // 4 USDC
+      const [warrantPositionPda] =
+        await anchor.web3.PublicKey.findProgramAddress(
+          warrantPositionSeeds(),
+          program.programId
+        );
+      const stockPositionPda = await stockPositionPdaFor(resultingSecurityId);
+
+      await program.methods
+        .exerciseWarrant(Array.from(resultingSecurityId), remaining, fairMarketValue)
+        .accounts({
+          issuer: issuerPda,
+          stakeholder: stakeholderPda,
+          stockClass: stockClassPda,
+          // @ts-ignore
+          warrantPosition: warrantPositionPda,
+          stockPosition: stockPositionPda,
+          rentPayer: authority.publicKey,
+          authority: authority.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+      // 60,000 * (4 - 1) / 4 = 45,000 shares
+      const stockPosition = await program.account.stockActivePosition.fetch(
+        stockPositionPda
+      );
+      expect(stockPosition.quantity.eq(new anchor.BN(45000))).to.be.true;
+
+      const warrantPosition =
+        await program.account.warrantActivePosition.fetchNullable(
+          warrantPositionPda
+        );
+      expect(warrantPosition).to.be.null;
+    });
+  });
+
+  describe("Warrant cancellation, expiry and transfer", () => {
+    const warrantSecurityId = new Uint8Array(16).fill(49);
+    const newStakeholderId = new Uint8Array(16).fill(50);
+    const transferredSecurityId = new Uint8Array(16).fill(51);
+    const shortLivedSecurityId = new Uint8Array(16).fill(52);
+    const guardedSecurityId = new Uint8Array(16).fill(53);
+    const otherIssuerId = new Uint8Array(16).fill(141);
+
+    let newStakeholderPda: anchor.web3.PublicKey;
+    let warrantPositionPda: anchor.web3.PublicKey;
+    let transferredPositionPda: anchor.web3.PublicKey;
+    let otherIssuerPda: anchor.web3.PublicKey;
+
+    const issue = async (
+      id: Uint8Array,
+      expiry: anchor.BN
+    ): Promise<anchor.web3.PublicKey> => {
+      const [positionPda] = await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("warrant_position"),
+          Buffer.from(stakeholderId),
+          Buffer.from(id),
+        ],
+        program.programId
+      );
+
+      await program.methods
+        .issueWarrant(
+          Array.from(id),
+          quantity,
+          exercisePrice,
+          expiry,
+          exerciseTrigger
+        )
+        .accounts({
+          issuer: issuerPda,
+          stakeholder: stakeholderPda,
+          stockClass: stockClassPda,
+          // @ts-ignore
+          position: positionPda,
+          authority: authority.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+      return positionPda;
+    };
+
+    before(async () => {
+      [newStakeholderPda] = await anchor.web3.PublicKey.findProgramAddress(
+        [Buffer.from("stakeholder"), Buffer.from(newStakeholderId)],
+        program.programId
+      );
+
+      await program.methods
+        .createStakeholder(Array.from(newStakeholderId))
+        .accounts({
+          issuer: issuerPda,
+          // @ts-ignore
+          stakeholder: newStakeholderPda,
+          authority: authority.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+      warrantPositionPda = await issue(warrantSecurityId, expirationDate);
+
+      // A second issuer whose authority must not reach the first issuer's warrants
+      [otherIssuerPda] = await anchor.web3.PublicKey.findProgramAddress(
+        [Buffer.from("issuer"), Buffer.from(otherIssuerId)],
+        program.programId
+      );
+
+      await program.methods
+        .initializeIssuer(Array.from(otherIssuerId), new anchor.BN(1000000))
+        .accounts({
+          // @ts-ignore
+          issuer: otherIssuerPda,
+          authority: authority.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+      [transferredPositionPda] = await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("warrant_position"),
+          Buffer.from(newStakeholderId),
+          Buffer.from(transferredSecurityId),
+        ],
+        program.programId
+      );
+    });
+
+    it("Transfers part of a warrant to a new stakeholder", async () => {
+      const transferQuantity = new anchor.BN(30000);
+
+      await program.methods
+        .transferWarrant(Array.from(transferredSecurityId), transferQuantity)
+        .accounts({
+          issuer: issuerPda,
+          stakeholder: stakeholderPda,
+          newStakeholder: newStakeholderPda,
+          // @ts-ignore
+          warrantPosition: warrantPositionPda,
+          newWarrantPosition: transferredPositionPda,
+          rentPayer: authority.publicKey,
+          authority: authority.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+      const source = await program.account.warrantActivePosition.fetch(
+        warrantPositionPda
+      );
+      expect(source.quantity.eq(quantity.sub(transferQuantity))).to.be.true;
+
+      const transferred = await program.account.warrantActivePosition.fetch(
+        transferredPositionPda
+      );
+      expect(
+        Buffer.from(transferred.stakeholderId).equals(
+          Buffer.from(newStakeholderId)
+        )
+      ).to.be.true;
+      expect(transferred.quantity.eq(transferQuantity)).to.be.true;
+      expect(transferred.exercisePrice.eq(exercisePrice)).to.be.true;
+      expect(transferred.expirationDate.eq(expirationDate)).to.be.true;
+    });
+
+    it("Cancels the remaining warrant and closes the position", async () => {
+      const eventPromise = new Promise((resolve, reject) => {
+        const listener = program.addEventListener("txCreatedV2", (event) => {
+          program.removeEventListener(listener);
+          resolve(event);
+        });
+
+        setTimeout(() => {
+          program.removeEventListener(listener);
+          reject(new Error("Timeout waiting for event"));
+        }, 30000);
+      });
+
+      await program.methods
+        .cancelWarrant(new anchor.BN(70000))
+        .accounts({
+          issuer: issuerPda,
+          stakeholder: stakeholderPda,
+          // @ts-ignore
+          warrantPosition: warrantPositionPda,
+          rentPayer: authority.publicKey,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      const event = (await eventPromise) as any;
+      expect(isTxType(event, "WarrantCancellation")).to.be.true;
+
+      const position =
+        await program.account.warrantActivePosition.fetchNullable(
+          warrantPositionPda
+        );
+      expect(position).to.be.null;
+    });
+
+    it("Fails to expire a warrant before its expiration date", async () => {
+      try {
+        await program.methods
+          .expireWarrant()
+          .accounts({
+            issuer: issuerPda,
+            warrantPosition: transferredPositionPda,
+            rentPayer: authority.publicKey,
+            cranker: authority.publicKey,
+          })
+          .rpc();
+
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error).to.be.instanceOf(Error);
+        expect(error.toString()).to.include("WarrantNotExpired");
+      }
+    });
+
+    it("Expires a warrant past its expiration date and refunds rent", async () => {
+      const positionPda = await issue(
+        shortLivedSecurityId,
+        new anchor.BN(Math.floor(Date.now() / 1000) + 2)
+      );
+
+      // Wait for the warrant to lapse
+      await new Promise((resolve) => setTimeout(resolve, 5000));
+
+      // Any signer may crank the expiry; rent goes back to the original payer
+      await program.methods
+        .expireWarrant()
+        .accounts({
+          issuer: issuerPda,
+          warrantPosition: positionPda,
+          rentPayer: authority.publicKey,
+          cranker: authority.publicKey,
+        })
+        .rpc();
+
+      const position =
+        await program.account.warrantActivePosition.fetchNullable(positionPda);
+      expect(position).to.be.null;
+    });
+
+    it("Fails to issue a warrant on another issuer's stock class", async () => {
+      const foreignSecurityId = new Uint8Array(16).fill(150);
+      const [positionPda] = await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("warrant_position"),
+          Buffer.from(stakeholderId),
+          Buffer.from(foreignSecurityId),
+        ],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .issueWarrant(
+            Array.from(foreignSecurityId),
+            quantity,
+            exercisePrice,
+            expirationDate,
+            exerciseTrigger
+          )
+          .accounts({
+            issuer: otherIssuerPda,
+            stakeholder: stakeholderPda,
+            stockClass: stockClassPda,
+            // @ts-ignore
+            position: positionPda,
+            authority: authority.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .rpc();
+
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error).to.be.instanceOf(Error);
+        expect(error.toString()).to.include("UnknownStockClass");
+      }
+
+      const position =
+        await program.account.warrantActivePosition.fetchNullable(positionPda);
+      expect(position).to.be.null;
+    });
+
+    it("Fails to cancel another issuer's warrant", async () => {
+      const positionPda = await issue(guardedSecurityId, expirationDate);
+
+      try {
+        await program.methods
+          .cancelWarrant(quantity)
+          .accounts({
+            issuer: otherIssuerPda,
+            stakeholder: stakeholderPda,
+            // @ts-ignore
+            warrantPosition: positionPda,
+            rentPayer: authority.publicKey,
+            authority: authority.publicKey,
+          })
+          .rpc();
+
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error).to.be.instanceOf(Error);
+        // The security is registered under the first issuer only
+        expect(error.toString()).to.include("AccountNotInitialized");
+      }
+
+      const position = await program.account.warrantActivePosition.fetch(
+        positionPda
+      );
+      expect(position.quantity.eq(quantity)).to.be.true;
+    });
+
+    it("Fails to transfer another issuer's warrant", async () => {
+      const [positionPda] = await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("warrant_position"),
+          Buffer.from(stakeholderId),
+          Buffer.from(guardedSecurityId),
+        ],
+        program.programId
+      );
+      const resultingSecurityId = new Uint8Array(16).fill(142);
+      const [resultingPositionPda] =
+        await anchor.web3.PublicKey.findProgramAddress(
+          [
+            Buffer.from("warrant_position"),
+            Buffer.from(newStakeholderId),
+            Buffer.from(resultingSecurityId),
+          ],
+          program.programId
+        );
+
+      try {
+        await program.methods
+          .transferWarrant(Array.from(resultingSecurityId), quantity)
+          .accounts({
+            issuer: otherIssuerPda,
+            stakeholder: stakeholderPda,
+            newStakeholder: newStakeholderPda,
+            // @ts-ignore
+            warrantPosition: positionPda,
+            newWarrantPosition: resultingPositionPda,
+            rentPayer: authority.publicKey,
+            authority: authority.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .rpc();
+
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error).to.be.instanceOf(Error);
+        expect(error.toString()).to.include("AccountNotInitialized");
+      }
+
+      const position = await program.account.warrantActivePosition.fetch(
+        positionPda
+      );
+      expect(position.quantity.eq(quantity)).to.be.true;
+    });
+
+    it("Fails to exercise another issuer's warrant", async () => {
+      const [positionPda] = await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("warrant_position"),
+          Buffer.from(stakeholderId),
+          Buffer.from(guardedSecurityId),
+        ],
+        program.programId
+      );
+      const resultingSecurityId = new Uint8Array(16).fill(143);
+      const [stockPositionPda] = await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from("stock_position"),
+          Buffer.from(stakeholderId),
+          Buffer.from(resultingSecurityId),
+        ],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .exerciseWarrant(Array.from(resultingSecurityId), quantity, null)
+          .accounts({
+            issuer: otherIssuerPda,
+            stakeholder: stakeholderPda,
+            stockClass: stockClassPda,
+            // @ts-ignore
+            warrantPosition: positionPda,
+            stockPosition: stockPositionPda,
+            rentPayer: authority.publicKey,
+            authority: authority.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .rpc();
+
+        expect.fail("Should have thrown an error");
+      } catch (error) {
+        expect(error).to.be.instanceOf(Error);
+        expect(error.toString()).to.include("AccountNotInitialized");
+      }
+
+      const position = await program.account.warrantActivePosition.fetch(
+        positionPda
+      );
+      expect(position.quantity.eq(quantity)).to.be.true;
+    });
+  });
 });
@@ -0,0 +1,142 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { OcpSolana } from "../target/types/ocp_solana";
+import { expect } from "chai";
+import { commonStockClassTerms, preferredStockClassTerms } from "./helpers";
+
+describe("Liquidation Waterfall Tests", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.OcpSolana as Program<OcpSolana>;
+  const authority = provider.wallet;
+
+  // Test data
+  const issuerId = new Uint8Array(16).fill(70);
+  const commonId = new Uint8Array(16).fill(71);
+  const preferredId = new Uint8Array(16).fill(72);
+  const founderId = new Uint8Array(16).fill(73);
+  const investorId = new Uint8Array(16).fill(74);
+  const commonSecurityId = new Uint8Array(16).fill(75);
+  const preferredSecurityId = new Uint8Array(16).fill(76);
+
+  const pricePerShare = new anchor.BN(1000000);
+  const commonQuantity = new anchor.BN(8000000);
+  const preferredQuantity = new anchor.BN(2000000);
+
+  let issuerPda: anchor.web3.PublicKey;
+  let remainingAccounts: anchor.web3.AccountMeta[];
+
+  const pda = (seeds: (string | Uint8Array)[]) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      seeds.map((seed) => Buffer.from(seed)),
+      program.programId
+    )[0];
+
+  before(async () => {
+    issuerPda = pda(["issuer", issuerId]);
+
+    await program.methods
+      .initializeIssuer(Array.from(issuerId), new anchor.BN(100000000))
+      .accounts({ authority: authority.publicKey })
+      .rpc();
+
+    for (const [id, classType, terms] of [
+      [commonId, "COMMON", commonStockClassTerms()],
+      [preferredId, "SERIES_A", preferredStockClassTerms(commonId)],
+    ] as const) {
+      await program.methods
+        .createStockClass(
+          Array.from(id),
+          classType,
+          pricePerShare,
+          new anchor.BN(50000000),
+          terms
+        )
+        .accounts({
+          // @ts-ignore
+          issuer: issuerPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+    }
+
+    for (const [stakeholderId, stockClassId, securityId, quantity] of [
+      [founderId, commonId, commonSecurityId, commonQuantity],
+      [investorId, preferredId, preferredSecurityId, preferredQuantity],
+    ] as const) {
+      await program.methods
+        .createStakeholder(Array.from(stakeholderId))
+        .accounts({
+          // @ts-ignore
+          issuer: issuerPda,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      await program.methods
+        .issueStock(Array.from(securityId), quantity, pricePerShare)
+        .accounts({
+          stockClass: pda(["stock_class", stockClassId]),
+          issuer: issuerPda,
+          stakeholder: pda(["stakeholder", stakeholderId]),
+          authority: authority.publicKey,
+        })
+        .rpc();
+    }
+
+    remainingAccounts = [
+      pda(["stock_class", commonId]),
+      pda(["stock_class", preferredId]),
+      pda(["stock_position", founderId, commonSecurityId]),
+      pda(["stock_position", investorId, preferredSecurityId]),
+    ].map((pubkey) => ({ pubkey, isWritable: false, isSigner: false }));
+  });
+
+  const waterfall = (exitValue: number) =>
+    program.methods
+      .getLiquidationWaterfall(new anchor.BN(exitValue), [])
+      .accounts({ issuer: issuerPda })
+      .remainingAccounts(remainingAccounts)
+      .view();
+
+  const proceedsOf = (distribution: any, stakeholderId: Uint8Array) =>
+    distribution.stakeholders
+      .find((entry) =>
+        Buffer.from(entry.stakeholderId).equals(Buffer.from(stakeholderId))
+      )
+      .proceeds.toNumber();
+
+  it("Pays the preference first at a low exit", async () => {
+    const distribution = await waterfall(5000000);
+
+    expect(distribution.classes[1].converted).to.be.false;
+    expect(distribution.classes[1].preference.toNumber()).to.equal(2000000);
+    expect(proceedsOf(distribution, investorId)).to.equal(2000000);
+    expect(proceedsOf(distribution, founderId)).to.equal(3000000);
+  });
+
+  it("Converts preferred when converting pays more", async () => {
+    const distribution = await waterfall(20000000);
+
+    expect(distribution.classes[1].converted).to.be.true;
+    expect(distribution.classes[1].preference.toNumber()).to.equal(0);
+    expect(proceedsOf(distribution, investorId)).to.equal(4000000);
+    expect(proceedsOf(distribution, founderId)).to.equal(16000000);
+  });
+
+  it("Fails without every stock class of the issuer", async () => {
+    try {
+      await program.methods
+        .getLiquidationWaterfall(new anchor.BN(5000000), [])
+        .accounts({ issuer: issuerPda })
+        .remainingAccounts(remainingAccounts.slice(1))
+        .view();
+      expect.fail("Should have thrown an error");
+    } catch (error) {
+      expect(error.simulationResponse.logs.join("\n")).to.include(
+        "MissingStockClass"
+      );
+    }
+  });
+});